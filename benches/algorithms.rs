@@ -0,0 +1,70 @@
+//! Compares the fitting algorithms over the number of points r, dimensions M and clusters k, and
+//! times building the trees separately from the fit loop.
+//!
+//! Run with `cargo bench --features datasets`, optionally followed by `-- <filter>` to only run
+//! the benchmarks whose name matches the filter, for example `-- "m=16 k=4"` or `-- build`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use kmeans::datasets::gaussian_blobs;
+use kmeans::{Algorithm, BallTree, Dataset, KMeans, Point, Tree};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+/// Numbers of points to fit.
+const R: [usize; 2] = [10_000, 100_000];
+
+/// Number of timed runs per benchmark; a fit of 100,000 points takes too long for the default.
+const SAMPLE_SIZE: usize = 10;
+
+/// Maximum number of iterations per fit, so that every algorithm does the same amount of work.
+const MAX_ITER: usize = 20;
+
+const ALGORITHMS: [Algorithm; 7] = [Algorithm::Naive, Algorithm::Simple, Algorithm::Blacklist, Algorithm::Elkan, Algorithm::Hamerly, Algorithm::BallTree, Algorithm::Auto];
+
+/// `r` points in blobs around random centers, as many as the clusters that are fitted.
+fn blobs<const K: usize, const M: usize>(r: usize) -> Vec<Point<M>> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let centers: Vec<Point<M>> = (0..K).map(|_| Point([(); M].map(|_| rng.gen_range(-10.0..10.0)))).collect();
+    gaussian_blobs(r, &centers, 1.0, 0).0
+}
+
+/// Time building the mrkd-tree and the ball tree.
+fn bench_build<const K: usize, const M: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("build m={} k={}", M, K));
+    group.sample_size(SAMPLE_SIZE);
+    for r in R {
+        let points = blobs::<K, M>(r);
+        let weights = vec![1.0; r];
+        let mut rng = StdRng::seed_from_u64(0);
+        group.throughput(Throughput::Elements(r as u64));
+        group.bench_with_input(BenchmarkId::new("mrkd-tree", r), &points, |b, points| {
+            b.iter(|| Tree::with_leaf_size(points, &weights, 8, &mut rng))
+        });
+        group.bench_with_input(BenchmarkId::new("ball tree", r), &points, |b, points| {
+            b.iter(|| BallTree::new(points))
+        });
+    }
+    group.finish();
+}
+
+/// Time the fit loop of every algorithm. The points are fitted as a [`Dataset`], whose mrkd-tree
+/// is built once beforehand; only [`Algorithm::BallTree`] builds its tree in every fit.
+fn bench_fit<const K: usize, const M: usize>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("fit m={} k={}", M, K));
+    group.sample_size(SAMPLE_SIZE);
+    for r in R {
+        let dataset = Dataset::new(blobs::<K, M>(r)).unwrap();
+        group.throughput(Throughput::Elements(r as u64));
+        for algorithm in ALGORITHMS {
+            let builder = KMeans::<K, M>::builder().algorithm(algorithm).random_state(0).max_iter(MAX_ITER);
+            group.bench_with_input(BenchmarkId::new(format!("{:?}", algorithm), r), &dataset, |b, dataset| {
+                b.iter(|| builder.fit_dataset(dataset).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(build, bench_build::<4, 2>, bench_build::<16, 2>, bench_build::<4, 8>, bench_build::<16, 8>, bench_build::<4, 16>, bench_build::<16, 16>);
+criterion_group!(fit, bench_fit::<4, 2>, bench_fit::<16, 2>, bench_fit::<4, 8>, bench_fit::<16, 8>, bench_fit::<4, 16>, bench_fit::<16, 16>);
+criterion_main!(build, fit);
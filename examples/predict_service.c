@@ -0,0 +1,76 @@
+/*
+ * A minimal prediction service over the C API: fits a model on the rows of m values in a CSV
+ * file, then answers every line of standard input, a row of m comma-separated values, with the
+ * index of its cluster.
+ *
+ * Build and run with:
+ *
+ *     cargo build --release --features ffi
+ *     cc -I include examples/predict_service.c -L target/release -lkmeans -o predict_service
+ *     LD_LIBRARY_PATH=target/release ./predict_service data.csv 2 8 < queries.csv
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "kmeans.h"
+
+/* Parse up to m comma-separated values of a line, returning the number parsed. */
+static size_t parse_row(char *line, double *row, size_t m) {
+    size_t d = 0;
+    for (char *field = strtok(line, ",\n"); field != NULL && d < m; field = strtok(NULL, ",\n")) {
+        row[d++] = strtod(field, NULL);
+    }
+    return d;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 4) {
+        fprintf(stderr, "usage: %s data.csv m k < queries.csv\n", argv[0]);
+        return 2;
+    }
+    size_t m = strtoul(argv[2], NULL, 10);
+    size_t k = strtoul(argv[3], NULL, 10);
+    FILE *file = fopen(argv[1], "r");
+    if (file == NULL || m == 0) {
+        fprintf(stderr, "cannot read %s with %zu columns\n", argv[1], m);
+        return 1;
+    }
+
+    size_t n = 0, capacity = 1024;
+    double *data = malloc(capacity * m * sizeof(double));
+    char line[4096];
+    while (fgets(line, sizeof line, file) != NULL) {
+        if (n == capacity) {
+            capacity *= 2;
+            data = realloc(data, capacity * m * sizeof(double));
+        }
+        if (parse_row(line, &data[n * m], m) == m) {
+            n++;
+        }
+    }
+    fclose(file);
+
+    KMeansModel *model = kmeans_fit(data, n, m, k, 0);
+    free(data);
+    if (model == NULL) {
+        fprintf(stderr, "cannot make %zu clusters out of %zu rows\n", k, n);
+        return 1;
+    }
+
+    double *row = malloc(m * sizeof(double));
+    size_t label;
+    while (fgets(line, sizeof line, stdin) != NULL) {
+        if (parse_row(line, row, m) == m && kmeans_predict(model, row, 1, m, &label) == 0) {
+            printf("%zu\n", label);
+        } else {
+            printf("error\n");
+        }
+        fflush(stdout);
+    }
+
+    free(row);
+    kmeans_free(model);
+    return 0;
+}
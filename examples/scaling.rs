@@ -0,0 +1,29 @@
+//! Times the Naive and Simple algorithms with an increasing number of threads.
+//!
+//! Run with `cargo run --release --features rayon --example scaling`.
+
+use kmeans::{KMeans, Algorithm, Point};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::time::Instant;
+
+const K: usize = 8;
+const M: usize = 4;
+const R: usize = 1_000_000;
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let points: Vec<Point<M>> = (0..R).map(|_| Point([(); M].map(|_| rng.gen::<f64>()))).collect();
+
+    let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut threads = 1;
+    while threads <= max_threads {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        for algorithm in [Algorithm::Naive, Algorithm::Simple] {
+            let now = Instant::now();
+            pool.install(|| KMeans::<K, M>::builder().algorithm(algorithm).random_state(0).max_iter(20).fit(&points).unwrap());
+            println!("{:?}, {} threads: {:?}", algorithm, threads, now.elapsed());
+        }
+        threads *= 2;
+    }
+}
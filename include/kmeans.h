@@ -0,0 +1,50 @@
+#ifndef KMEANS_H
+#define KMEANS_H
+
+/* Generated with cbindgen from src/ffi.rs; do not edit by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// A fitted model, owned by the caller until it is passed to [`kmeans_free`].
+typedef struct KMeansModel KMeansModel;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Fit k clusters on the `n` rows of `m` values in `data`, with a pre-determined random state.
+// Returns NULL if `data` is NULL, `m` is 0, there are fewer than `k` distinct rows or `k` is 0, or a value
+// is NaN or infinite.
+//
+// # Safety
+//
+// `data` must point to `n * m` readable values.
+KMeansModel *kmeans_fit(const double *data, size_t n, size_t m, size_t k, uint64_t seed);
+
+// Write the index of the closest center of each of the `n` rows of `m` values in `data` to
+// `labels`. Returns 0 on success, or -1 if an argument is NULL, `m` differs from the number of
+// dimensions of the model, or a value is NaN or infinite, in which case `labels` is not written.
+//
+// # Safety
+//
+// `model` must have been returned by [`kmeans_fit`] and not freed, `data` must point to `n * m`
+// readable values and `labels` to `n` writable values.
+int32_t kmeans_predict(const KMeansModel *model,
+                       const double *data,
+                       size_t n,
+                       size_t m,
+                       size_t *labels);
+
+// Free a model returned by [`kmeans_fit`]. Does nothing if `model` is NULL.
+//
+// # Safety
+//
+// `model` must have been returned by [`kmeans_fit`] and not freed yet.
+void kmeans_free(KMeansModel *model);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* KMEANS_H */
@@ -0,0 +1,279 @@
+//! End-to-end applications of the clustering algorithms, from a file format to a result. They are
+//! thin layers over the rest of the crate, and show how its parts fit together.
+
+use crate::data::{Encoding, FrequencyEncoder};
+use crate::error::ClusterError;
+use crate::dynamic::{DynKMeans, DynPoint};
+use std::convert::TryInto;
+use std::io::{self, Read};
+
+/// The result of [`segment_customers`].
+pub struct Segmentation {
+    /// The names of the columns, from the header of the CSV.
+    pub columns: Vec<String>,
+    /// The index of the segment that each row belongs to.
+    pub segments: Vec<usize>,
+    /// The mean of every column per segment, in the units of the CSV. Categorical columns are
+    /// given as the mean frequency of their categories.
+    pub centers: Vec<Vec<f64>>,
+    /// The number of rows per segment.
+    pub sizes: Vec<usize>
+}
+
+/// Split the customers in a CSV with a header row into `k` segments. Numeric columns are used as
+/// they are, and other columns are encoded with a [`FrequencyEncoder`]. Columns are standardized
+/// before clustering, so that their units do not matter.
+///
+/// Values are separated by commas and cannot be quoted.
+///
+/// Time complexity: O(i * r * k * M)
+///
+/// # Errors
+///
+/// If `k` is 0 or larger than the number of rows.
+pub fn segment_customers(mut csv: impl Read, k: usize, random_state: u64) -> io::Result<Segmentation> {
+    let mut text = String::new();
+    csv.read_to_string(&mut text)?;
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+    let columns: Vec<String> = lines.next()
+        .ok_or_else(|| invalid_data("the CSV has no header"))?
+        .split(',')
+        .map(|name| name.trim().to_owned())
+        .collect();
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').map(str::trim).collect()).collect();
+    if let Option::Some(row) = rows.iter().position(|row| row.len() != columns.len()) {
+        return Err(invalid_data(format!("row {} has {} values, but the header has {}", row, rows[row].len(), columns.len())))
+    }
+
+    // Encode every column as numbers
+    let r = rows.len();
+    let m = columns.len();
+    let mut values = vec![vec![0.0; m]; r];
+    for d in 0..m {
+        let column: Vec<&str> = rows.iter().map(|row| row[d]).collect();
+        let numeric: Result<Vec<f64>, _> = column.iter().map(|value| value.parse::<f64>()).collect();
+        let column = numeric.unwrap_or_else(|_| FrequencyEncoder::fit(&column, Encoding::Frequency).transform_many(&column));
+        for (row, value) in values.iter_mut().zip(column) {
+            row[d] = value;
+        }
+    }
+
+    // Standardize the columns, leaving constant columns at 0
+    let mut means = vec![0.0; m];
+    let mut deviations = vec![0.0; m];
+    for d in 0..m {
+        means[d] = values.iter().map(|row| row[d]).sum::<f64>() / r as f64;
+        deviations[d] = (values.iter().map(|row| (row[d] - means[d]).powi(2)).sum::<f64>() / r as f64).sqrt();
+    }
+    let points: Vec<DynPoint> = values.iter().map(|row| {
+        DynPoint((0..m).map(|d| if deviations[d] > 0.0 { (row[d] - means[d]) / deviations[d] } else { 0.0 }).collect())
+    }).collect();
+
+    // Describe the segments in the original units
+    let model = DynKMeans::fit_with_random_state(&points, k, random_state).map_err(invalid_input)?;
+    let mut centers = vec![vec![0.0; m]; k];
+    let mut sizes = vec![0; k];
+    for (row, &c) in values.iter().zip(&model.point_centers) {
+        for d in 0..m {
+            centers[c][d] += row[d];
+        }
+        sizes[c] += 1;
+    }
+    for (center, &size) in centers.iter_mut().zip(&sizes) {
+        for x in center.iter_mut() {
+            *x /= size.max(1) as f64;
+        }
+    }
+
+    Ok(Segmentation { columns, segments: model.point_centers, centers, sizes })
+}
+
+/// The result of [`compress_embeddings`].
+pub struct Codebook {
+    /// The codewords that the embeddings are replaced by.
+    pub codewords: Vec<DynPoint>,
+    /// The index of the codeword of each embedding.
+    pub codes: Vec<usize>,
+    /// The sum of the squared distances of the embeddings to their codewords, divided by the sum
+    /// of their squared norms.
+    pub relative_error: f64
+}
+
+/// Compress the embeddings in a 2-dimensional `.npy` array of `f4` or `f8` values by vector
+/// quantization: every row is replaced by the closest of `codebook_size` codewords.
+///
+/// Time complexity: O(i * r * k * M)
+///
+/// # Errors
+///
+/// If `codebook_size` is 0 or larger than the number of rows.
+pub fn compress_embeddings(npy: impl Read, codebook_size: usize, random_state: u64) -> io::Result<Codebook> {
+    let embeddings = read_npy(npy)?;
+    let model = DynKMeans::fit_with_random_state(&embeddings, codebook_size, random_state).map_err(invalid_input)?;
+
+    let error: f64 = embeddings.iter()
+        .zip(&model.point_centers)
+        .map(|(embedding, &c)| embedding.squared_distance(&model.centers[c]))
+        .sum();
+    let norm: f64 = embeddings.iter().map(|embedding| embedding.0.iter().map(|x| x * x).sum::<f64>()).sum();
+    let relative_error = if norm > 0.0 { error / norm } else { 0.0 };
+
+    Ok(Codebook { codewords: model.centers, codes: model.point_centers, relative_error })
+}
+
+/// Read a 2-dimensional array of little-endian `f4` or `f8` values in C order from the `.npy`
+/// format of NumPy.
+fn read_npy(mut npy: impl Read) -> io::Result<Vec<DynPoint>> {
+    let mut bytes = Vec::new();
+    npy.read_to_end(&mut bytes)?;
+    if bytes.len() < 10 || &bytes[..6] != b"\x93NUMPY" {
+        return Err(invalid_data("not an .npy file"))
+    }
+
+    // Version 1 has a 2-byte header length, later versions a 4-byte one
+    let (header_start, header_len) = match bytes[6] {
+        1 => (10, u16::from_le_bytes([bytes[8], bytes[9]]) as usize),
+        _ if bytes.len() >= 12 => (12, u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize),
+        _ => return Err(invalid_data("truncated .npy header"))
+    };
+    let data = bytes.get(header_start + header_len..).ok_or_else(|| invalid_data("truncated .npy header"))?;
+    let header = String::from_utf8_lossy(&bytes[header_start..header_start + header_len]);
+
+    let size = if header.contains("'<f4'") {
+        4
+    } else if header.contains("'<f8'") {
+        8
+    } else {
+        return Err(invalid_data("only little-endian f4 and f8 arrays are supported"))
+    };
+    if header.contains("'fortran_order': True") {
+        return Err(invalid_data("only arrays in C order are supported"))
+    }
+    let shape: Vec<usize> = header.split("'shape':")
+        .nth(1)
+        .and_then(|shape| shape.split(['(', ')']).nth(1))
+        .ok_or_else(|| invalid_data("the .npy header has no shape"))?
+        .split(',')
+        .filter(|n| !n.trim().is_empty())
+        .map(|n| n.trim().parse().map_err(|_| invalid_data("invalid shape in the .npy header")))
+        .collect::<io::Result<_>>()?;
+    let (r, m) = match shape[..] {
+        [r, m] => (r, m),
+        _ => return Err(invalid_data("only 2-dimensional arrays are supported"))
+    };
+    if data.len() < r * m * size {
+        return Err(invalid_data("truncated .npy data"))
+    }
+
+    let values: Vec<f64> = data[..r * m * size].chunks_exact(size).map(|chunk| match size {
+        4 => f32::from_le_bytes(chunk.try_into().unwrap()) as f64,
+        _ => f64::from_le_bytes(chunk.try_into().unwrap())
+    }).collect();
+    Ok(values.chunks(m.max(1)).take(r).map(|row| DynPoint(row.to_vec())).collect())
+}
+
+/// The result of [`quantize_image`].
+#[cfg(feature = "png")]
+pub struct Quantization {
+    /// The colors of the quantized image.
+    pub palette: Vec<[u8; 3]>,
+    /// The mean silhouette coefficient of a sample of the pixels, see
+    /// [`crate::metrics::sampled_silhouette_score`], or NaN if the image has a single color.
+    pub silhouette: f64
+}
+
+/// Reduce the colors of a PNG image to a palette of `colors` colors, found by clustering the
+/// pixels in RGB space, and write the result to `output` as an indexed PNG image. Transparency is
+/// dropped.
+///
+/// Time complexity: O(i * r * k)
+///
+/// # Errors
+///
+/// If `colors` is 0, larger than 256 or larger than the number of pixels.
+#[cfg(feature = "png")]
+pub fn quantize_image(png: impl Read, colors: usize, output: impl io::Write, random_state: u64) -> io::Result<Quantization> {
+    use crate::metrics::sampled_silhouette_score;
+    use crate::point::Point;
+    use crate::sweep::Sweep;
+
+    if colors > 256 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "an indexed PNG image has at most 256 colors"))
+    }
+
+    // Decode the image to 8-bit channels
+    let mut decoder = png::Decoder::new(png);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let channels = info.color_type.samples();
+    let pixels: Vec<Point<3>> = buffer[..info.buffer_size()].chunks_exact(channels).map(|pixel| match pixel.len() {
+        // Grayscale, with or without alpha
+        1 | 2 => Point([pixel[0] as f64; 3]),
+        _ => Point([pixel[0] as f64, pixel[1] as f64, pixel[2] as f64])
+    }).collect();
+
+    let model = Sweep::new([colors]).random_state(random_state).fit(&pixels).map_err(invalid_input)?.remove(0);
+    let palette: Vec<[u8; 3]> = model.centers.iter().map(|center| center.0.map(|x| x.round() as u8)).collect();
+    let used = (0..colors).filter(|&c| model.point_centers.contains(&c)).count();
+    let silhouette = if used >= 2 {
+        sampled_silhouette_score(&pixels, &model.point_centers, 1000, random_state)
+    } else {
+        f64::NAN
+    };
+
+    // Encode the indexed image
+    let mut encoder = png::Encoder::new(output, info.width, info.height);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette.concat());
+    let mut writer = encoder.write_header()?;
+    let indices: Vec<u8> = model.point_centers.iter().map(|&c| c as u8).collect();
+    writer.write_image_data(&indices)?;
+    writer.finish()?;
+
+    Ok(Quantization { palette, silhouette })
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn invalid_input(error: ClusterError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_customers_csv() {
+        let csv = "age,spend,region\n20,100,north\n22,110,north\n60,900,south\n62,950,south\n";
+        let segmentation = segment_customers(csv.as_bytes(), 2, 0).unwrap();
+        assert_eq!(segmentation.columns, ["age", "spend", "region"]);
+        assert_eq!(segmentation.segments[0], segmentation.segments[1]);
+        assert_ne!(segmentation.segments[0], segmentation.segments[2]);
+        assert_eq!(segmentation.centers[segmentation.segments[0]], [21.0, 105.0, 0.5]);
+        assert_eq!(segmentation.sizes, [2, 2]);
+    }
+
+    #[test]
+    fn compress_embeddings_npy() {
+        let header = "{'descr': '<f4', 'fortran_order': False, 'shape': (4, 2), }";
+        let header = format!("{:<117}\n", header);
+        let mut npy = b"\x93NUMPY\x01\x00".to_vec();
+        npy.extend((header.len() as u16).to_le_bytes());
+        npy.extend(header.bytes());
+        for x in [1.0f32, 0.0, 1.0, 0.0, 0.0, 2.0, 0.0, 2.0] {
+            npy.extend(x.to_le_bytes());
+        }
+
+        let codebook = compress_embeddings(&npy[..], 2, 0).unwrap();
+        assert_eq!(codebook.codes[0], codebook.codes[1]);
+        assert_ne!(codebook.codes[0], codebook.codes[2]);
+        assert_eq!(codebook.codewords[codebook.codes[2]], DynPoint(vec![0.0, 2.0]));
+        assert_eq!(codebook.relative_error, 0.0);
+    }
+}
@@ -0,0 +1,364 @@
+use crate::index::SpatialIndex;
+use crate::mrkd::DEFAULT_LEAF_SIZE;
+use crate::point::Point;
+use crate::quickselect::{median, split};
+use crate::seeding::SeedSequence;
+use crate::summation::{CenterSums, CompensatedPoint, CompensatedSum};
+use rand::Rng;
+use std::ops::Deref;
+
+/// A ball tree (Omohundro, 1989): every node bounds its points with a ball around their mean,
+/// and caches their center of mass like the mrkd-tree (Pelleg & Moore, 1999). The bounds of a
+/// kd-tree become loose above roughly 15 dimensions, as the hyper-rectangles of the nodes stay
+/// wide in most dimensions; a ball only depends on the distances of the points to its center, so
+/// it keeps pruning on high-dimensional data that lies close to a lower-dimensional subspace.
+///
+/// Nodes are split at the median of the dimension with the largest spread, and stored as an arena
+/// like [`crate::Tree`].
+///
+/// # References
+///
+/// Omohundro, S. M. (1989). Five balltree construction algorithms. International Computer Science
+///     Institute Technical Report TR-89-063.
+///
+/// Pelleg, D., & Moore, A. (1999). Accelerating exact k-means algorithms with geometric reasoning.
+///     Proceedings of the Fifth ACM SIGKDD International Conference
+///     on Knowledge Discovery and Data Mining, 277–281. <https://doi.org/10.1145/312129.312248>
+#[derive(PartialEq, Debug)]
+pub struct BallTree<const M: usize> {
+    /// The nodes, starting with the root. The left child of a non-leaf node directly follows it.
+    nodes: Vec<BallNode<M>>,
+    /// The points of the leaves, in the order of the leaves
+    points: Vec<Point<M>>,
+    /// The weights of the points
+    weights: Vec<f64>,
+    /// The indices of the points in the points the tree was built from
+    indices: Vec<usize>
+}
+
+#[derive(PartialEq, Debug)]
+pub struct BallNode<const M: usize> {
+    /// Center of the ball: the unweighted mean of contained points
+    pub center: Point<M>,
+
+    /// Radius of the ball: the largest distance of a contained point to its center
+    pub radius: f64,
+
+    /// Number of points in contained leaf nodes
+    pub number_of_points: usize,
+
+    /// Total weight of contained points
+    pub weight: f64,
+
+    /// Weighted center of mass of contained points
+    pub center_of_mass: Point<M>,
+
+    /// The difference between the weighted sum of the contained points and the center of mass
+    /// times the weight, which is lost to rounding, see [`BallNode::add_to`]
+    compensation: Point<M>,
+
+    /// Node information
+    kind: BallNodeKind
+}
+
+#[derive(PartialEq, Debug)]
+enum BallNodeKind {
+    NonLeaf {
+        /// index of the left child
+        l: usize,
+        /// index of the right child
+        r: usize
+    },
+    /// The range of the points of the leaf in the points of the tree
+    Leaf(usize, usize)
+}
+
+/// A node of a [`BallTree`], through which its children or points can be reached.
+#[derive(Clone, Copy)]
+pub struct BallNodeRef<'a, const M: usize> {
+    tree: &'a BallTree<M>,
+    index: usize
+}
+
+/// The contents of a node: two child nodes, or the points of a leaf node, with their weights and
+/// positions in the points of the tree.
+pub enum BallChildren<'a, const M: usize> {
+    NonLeaf(BallNodeRef<'a, M>, BallNodeRef<'a, M>),
+    Leaf(&'a [Point<M>], &'a [f64], usize)
+}
+
+impl<'a, const M: usize> BallNodeRef<'a, M> {
+    /// The child nodes, or the points of a leaf node.
+    pub fn children(&self) -> BallChildren<'a, M> {
+        match self.tree.nodes[self.index].kind {
+            BallNodeKind::NonLeaf { l, r } => BallChildren::NonLeaf(self.tree.node(l), self.tree.node(r)),
+            BallNodeKind::Leaf(start, end) => BallChildren::Leaf(&self.tree.points[start..end], &self.tree.weights[start..end], start)
+        }
+    }
+
+    /// The smallest distance from `point` to a point in the ball, 0 if it is inside.
+    fn distance(&self, point: &Point<M>) -> f64 {
+        (point.distance(&self.center) - self.radius).max(0.0)
+    }
+}
+
+impl<const M: usize> BallNode<M> {
+    /// Add the weighted sum of the contained points to `sums` as center `k`, like
+    /// [`crate::mrkd::Node::add_to`].
+    ///
+    /// Time complexity: O(M)
+    pub(crate) fn add_to<const K: usize>(&self, sums: &mut CenterSums<K, M>, k: usize) {
+        sums.sums[k].add_with_error(&(self.center_of_mass * self.weight), &self.compensation);
+        sums.counts[k].add(self.weight);
+    }
+}
+
+impl<'a, const M: usize> Deref for BallNodeRef<'a, M> {
+    type Target = BallNode<M>;
+
+    fn deref(&self) -> &BallNode<M> {
+        &self.tree.nodes[self.index]
+    }
+}
+
+/// A node that is still to be built: the range of its points in the index buffer, and the
+/// non-leaf node it is the left or right child of.
+struct Task {
+    start: usize,
+    end: usize,
+    parent: Option<(usize, bool)>
+}
+
+impl<const M: usize> BallTree<M> {
+    /// Build a tree over `points`, with a fixed random state.
+    ///
+    /// Time complexity: O(r * log(r) * M)
+    pub fn new(points: &[Point<M>]) -> Self {
+        Self::with_leaf_size(points, &vec![1.0; points.len()], DEFAULT_LEAF_SIZE, &mut SeedSequence::new(0).rng())
+    }
+
+    /// Build a tree where each point has a weight in the center of mass, splitting nodes until they
+    /// have at most `max_leaf_size` points, using `rng` to pick the pivots while splitting.
+    ///
+    /// Time complexity: O(r * log(r) * M)
+    pub fn with_leaf_size(points: &[Point<M>], weights: &[f64], max_leaf_size: usize, rng: &mut impl Rng) -> Self {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        let nodes = Self::build(points, weights, &mut order, max_leaf_size.max(1), rng);
+        Self {
+            nodes,
+            points: order.iter().map(|&i| points[i]).collect(),
+            weights: order.iter().map(|&i| weights[i]).collect(),
+            indices: order
+        }
+    }
+
+    /// The root node.
+    pub(crate) fn root(&self) -> BallNodeRef<'_, M> {
+        self.node(0)
+    }
+
+    fn node(&self, index: usize) -> BallNodeRef<'_, M> {
+        BallNodeRef { tree: self, index }
+    }
+
+    /// Build the nodes over the points at the indices in `order`, reordering it so that the points
+    /// of every node are contiguous, depth-first with an explicit stack.
+    fn build(points: &[Point<M>], weights: &[f64], order: &mut [usize], max_leaf_size: usize, rng: &mut impl Rng) -> Vec<BallNode<M>> {
+        let mut nodes: Vec<BallNode<M>> = Vec::with_capacity(2 * order.len().div_ceil(max_leaf_size));
+        let mut tasks = vec![Task { start: 0, end: order.len(), parent: Option::None }];
+
+        while let Option::Some(Task { start, end, parent }) = tasks.pop() {
+            // Link the node to its parent
+            let index = nodes.len();
+            if let Option::Some((parent, left)) = parent {
+                if let BallNodeKind::NonLeaf { l, r } = &mut nodes[parent].kind {
+                    *(if left { l } else { r }) = index;
+                }
+            }
+
+            // Determine the bounding ball and the cached center of mass
+            let indices = &mut order[start..end];
+            let mut weight = CompensatedSum::default();
+            let mut center = Point::default();
+            let mut sum = CompensatedPoint::default();
+            for &i in indices.iter() {
+                center += points[i];
+                sum.add(&(points[i] * weights[i]));
+                weight.add(weights[i]);
+            }
+            center /= indices.len() as f64;
+            let weight = weight.value();
+            let center_of_mass = sum.value() / weight;
+            let radius = indices.iter().map(|&i| points[i].distance(&center)).fold(0.0, f64::max);
+            let mut node = BallNode {
+                center,
+                radius,
+                number_of_points: indices.len(),
+                weight,
+                center_of_mass,
+                compensation: sum.difference(&(center_of_mass * weight)),
+                kind: BallNodeKind::Leaf(start, end)
+            };
+
+            // If few enough points remain, make a leaf node
+            if indices.len() <= max_leaf_size {
+                nodes.push(node);
+                continue;
+            }
+
+            // Else, split at the median of the dimension in which the points are spread most
+            let d = (0..M).map(|d| {
+                let (min, max) = indices.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &i| {
+                    (min.min(points[i].0[d]), max.max(points[i].0[d]))
+                });
+                (d, max - min)
+            }).fold((0, f64::NEG_INFINITY), |best, spread| if spread.1 > best.1 { spread } else { best }).0;
+            let v = median(points, indices, d, rng);
+            let mid = start + split(points, indices, d, v);
+            node.kind = BallNodeKind::NonLeaf { l: 0, r: 0 };
+            nodes.push(node);
+
+            // Build the left child first, then the right one
+            tasks.push(Task { start: mid, end, parent: Option::Some((index, false)) });
+            tasks.push(Task { start, end: mid, parent: Option::Some((index, true)) });
+        }
+
+        nodes
+    }
+
+    /// The number of points in the tree.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Whether the tree has no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The point in the tree closest to `point`, and its distance.
+    ///
+    /// Time complexity: O(log(r) * M) on average
+    ///
+    /// # Panics
+    ///
+    /// If the tree has no points.
+    pub fn nearest(&self, point: &Point<M>) -> (Point<M>, f64) {
+        let (j, d) = self.k_nearest_positions(point, 1)[0];
+        (self.points[j], d)
+    }
+
+    /// The indices of the `n` points in the tree closest to `point`, in the points the tree was
+    /// built from, with their distances, in ascending order of distance.
+    ///
+    /// Time complexity: O(log(r) * n * M) on average
+    pub fn k_nearest(&self, point: &Point<M>, n: usize) -> Vec<(usize, f64)> {
+        self.k_nearest_positions(point, n).into_iter().map(|(j, d)| (self.indices[j], d)).collect()
+    }
+
+    fn k_nearest_positions(&self, point: &Point<M>, n: usize) -> Vec<(usize, f64)> {
+        let mut nearest = Vec::with_capacity(n + 1);
+        Self::k_nearest_search(self.root(), point, n, &mut nearest);
+        nearest
+    }
+
+    /// Indices of the points in the tree within distance `radius` of `point`, in no particular
+    /// order.
+    ///
+    /// Time complexity: O(log(r) + n * M) on average for n points within the radius
+    pub fn within_radius(&self, point: &Point<M>, radius: f64) -> Vec<usize> {
+        let mut indices = Vec::new();
+        self.radius_search(self.root(), point, radius, &mut indices);
+        indices
+    }
+
+    fn radius_search(&self, node: BallNodeRef<M>, point: &Point<M>, radius: f64, indices: &mut Vec<usize>) {
+        // Skip balls that are entirely outside the radius
+        if node.distance(point) > radius {
+            return;
+        }
+
+        match node.children() {
+            BallChildren::NonLeaf(l, r) => {
+                self.radius_search(l, point, radius, indices);
+                self.radius_search(r, point, radius, indices);
+            },
+            BallChildren::Leaf(points, _, start) => {
+                for (j, leaf_point) in points.iter().enumerate() {
+                    if leaf_point.distance(point) <= radius {
+                        indices.push(self.indices[start + j]);
+                    }
+                }
+            }
+        }
+    }
+
+    fn k_nearest_search(node: BallNodeRef<M>, point: &Point<M>, n: usize, nearest: &mut Vec<(usize, f64)>) {
+        // Skip balls that cannot contain a point closer than the current n-th nearest
+        if nearest.len() == n && nearest.last().is_none_or(|&(_, max)| node.distance(point) >= max) {
+            return;
+        }
+
+        match node.children() {
+            BallChildren::NonLeaf(l, r) => {
+                // Visit the child whose center is closest first
+                let (first, second) = if point.squared_distance(&l.center) <= point.squared_distance(&r.center) { (l, r) } else { (r, l) };
+                Self::k_nearest_search(first, point, n, nearest);
+                Self::k_nearest_search(second, point, n, nearest);
+            },
+            BallChildren::Leaf(points, _, start) => {
+                for (j, leaf_point) in points.iter().enumerate() {
+                    let d = leaf_point.distance(point);
+                    let index = nearest.partition_point(|&(_, x)| x <= d);
+                    if index < n {
+                        nearest.insert(index, (start + j, d));
+                        nearest.truncate(n);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<const M: usize> SpatialIndex<M> for BallTree<M> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn nearest(&self, point: &Point<M>) -> (Point<M>, f64) {
+        self.nearest(point)
+    }
+
+    fn k_nearest(&self, point: &Point<M>, n: usize) -> Vec<(usize, f64)> {
+        self.k_nearest(point, n)
+    }
+
+    fn within_radius(&self, point: &Point<M>, radius: f64) -> Vec<usize> {
+        self.within_radius(point, radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_neighbors() {
+        let points: Vec<Point<20>> = (0..200)
+            .map(|i| Point(std::array::from_fn(|d| ((i * (d + 3) + d * d) % 17) as f64 + 0.01 * i as f64)))
+            .collect();
+        let tree = BallTree::new(&points);
+        let query = Point([6.5; 20]);
+
+        let mut expected: Vec<(usize, f64)> = points.iter().map(|point| point.distance(&query)).enumerate().collect();
+        expected.sort_by(|a, b| a.1.total_cmp(&b.1));
+        assert_eq!(tree.k_nearest(&query, 7), expected[..7]);
+        assert_eq!(tree.nearest(&query), (points[expected[0].0], expected[0].1));
+
+        let mut within = tree.within_radius(&query, expected[19].1);
+        within.sort();
+        let mut expected: Vec<usize> = expected[..20].iter().map(|&(i, _)| i).collect();
+        expected.sort();
+        assert_eq!(within, expected);
+    }
+}
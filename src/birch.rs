@@ -0,0 +1,308 @@
+use crate::builder::KMeansBuilder;
+use crate::clusterer::KMeans;
+use crate::error::{check_point_rows, ClusterError};
+use crate::estimator::Fit;
+use crate::metric::Euclidean;
+use crate::point::Point;
+
+/// The number of runs of k-means on the subclusters.
+const N_INIT: usize = 10;
+
+/// The summary of a set of points that BIRCH keeps instead of the points: their number, their sum
+/// and the sum of their squared norms. Clustering features of disjoint sets are added to merge
+/// them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClusteringFeature<const M: usize> {
+    /// The number of points.
+    pub n: f64,
+    /// The sum of the points.
+    pub linear_sum: Point<M>,
+    /// The sum of the squared norms of the points.
+    pub squared_sum: f64
+}
+
+impl<const M: usize> ClusteringFeature<M> {
+    fn empty() -> Self {
+        Self { n: 0.0, linear_sum: Point::default(), squared_sum: 0.0 }
+    }
+
+    fn of(point: &Point<M>) -> Self {
+        Self { n: 1.0, linear_sum: *point, squared_sum: point.dot(point) }
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.n += other.n;
+        self.linear_sum += other.linear_sum;
+        self.squared_sum += other.squared_sum;
+    }
+
+    /// The mean of the points.
+    ///
+    /// Time complexity: O(M)
+    pub fn centroid(&self) -> Point<M> {
+        self.linear_sum / self.n
+    }
+
+    /// The root mean squared distance of the points to their centroid.
+    ///
+    /// Time complexity: O(M)
+    pub fn radius(&self) -> f64 {
+        let centroid = self.centroid();
+        (self.squared_sum / self.n - centroid.dot(&centroid)).max(0.0).sqrt()
+    }
+}
+
+/// A node of the CF-tree: the clustering features of its subclusters, and for non-leaf nodes the
+/// child node that each of them summarizes.
+struct Node<const M: usize> {
+    entries: Vec<(ClusteringFeature<M>, Option<usize>)>
+}
+
+impl<const M: usize> Node<M> {
+    fn total(&self) -> ClusteringFeature<M> {
+        let mut total = ClusteringFeature::empty();
+        for (cf, _) in &self.entries {
+            total.add(cf);
+        }
+        total
+    }
+
+    /// The entry with the centroid closest to `point`.
+    fn closest(&self, point: &Point<M>) -> Option<usize> {
+        (0..self.entries.len()).min_by(|&a, &b| {
+            let d = |i: usize| self.entries[i].0.centroid().squared_distance(point);
+            d(a).total_cmp(&d(b))
+        })
+    }
+}
+
+/// Implements BIRCH (Zhang et al., 1996), which clusters a stream of points in a single pass.
+/// The points are summarized in a height-balanced tree of [`ClusteringFeature`]s: every point is
+/// added to the closest subcluster in a leaf, as long as its radius stays within the threshold,
+/// and otherwise starts a new subcluster. Nodes with more entries than the branching factor are
+/// split in two around their two farthest entries. The subclusters are finally clustered with
+/// k-means, weighted by their sizes.
+///
+/// The memory use depends on the threshold and the spread of the points, rather than on the
+/// number of points; the threshold is not increased automatically when the tree grows.
+///
+/// # References
+///
+/// Zhang, T., Ramakrishnan, R., & Livny, M. (1996). BIRCH: An efficient data clustering method
+///     for very large databases. ACM SIGMOD Record, 25(2), 103–114.
+///     <https://doi.org/10.1145/235968.233324>
+pub struct Birch<const M: usize> {
+    threshold: f64,
+    branching_factor: usize,
+    nodes: Vec<Node<M>>,
+    root: usize
+}
+
+impl<const M: usize> Birch<M> {
+    /// Start an empty tree, with subclusters of at most radius `threshold` and nodes of at most
+    /// `branching_factor` entries.
+    ///
+    /// # Panics
+    ///
+    /// If the branching factor is smaller than 2.
+    pub fn new(threshold: f64, branching_factor: usize) -> Self {
+        assert!(branching_factor >= 2, "the branching factor must be at least 2");
+        Self { threshold, branching_factor, nodes: vec![Node { entries: Vec::new() }], root: 0 }
+    }
+
+    /// Add a point to the tree.
+    ///
+    /// Time complexity: O(B * M * log_B(s)) for branching factor B and s subclusters
+    pub fn insert(&mut self, point: &Point<M>) {
+        let cf = ClusteringFeature::of(point);
+        if let Option::Some(sibling) = self.insert_into(self.root, &cf) {
+            // Grow the tree by a level when the root splits
+            let entries = vec![
+                (self.nodes[self.root].total(), Option::Some(self.root)),
+                (self.nodes[sibling].total(), Option::Some(sibling))
+            ];
+            self.nodes.push(Node { entries });
+            self.root = self.nodes.len() - 1;
+        }
+    }
+
+    /// Add `cf` to the subtree at `node`, and return the new sibling of `node` if it was split.
+    fn insert_into(&mut self, node: usize, cf: &ClusteringFeature<M>) -> Option<usize> {
+        let point = cf.centroid();
+        match self.nodes[node].closest(&point) {
+            Option::Some(i) => match self.nodes[node].entries[i].1 {
+                // Descend into the closest child, and add the entry of its new sibling
+                Option::Some(child) => {
+                    let sibling = self.insert_into(child, cf);
+                    let entries = &mut self.nodes[node].entries;
+                    entries[i].0.add(cf);
+                    if let Option::Some(sibling) = sibling {
+                        let total = self.nodes[child].total();
+                        let sibling_total = self.nodes[sibling].total();
+                        let entries = &mut self.nodes[node].entries;
+                        entries[i].0 = total;
+                        entries.push((sibling_total, Option::Some(sibling)));
+                    }
+                },
+                // Absorb the point into the closest subcluster of a leaf if it stays small enough
+                None => {
+                    let mut merged = self.nodes[node].entries[i].0;
+                    merged.add(cf);
+                    if merged.radius() <= self.threshold {
+                        self.nodes[node].entries[i].0 = merged;
+                    } else {
+                        self.nodes[node].entries.push((*cf, Option::None));
+                    }
+                }
+            },
+            None => self.nodes[node].entries.push((*cf, Option::None))
+        }
+
+        if self.nodes[node].entries.len() > self.branching_factor {
+            Option::Some(self.split(node))
+        } else {
+            Option::None
+        }
+    }
+
+    /// Move the entries of `node` closer to the farther of its two farthest entries to a new node.
+    ///
+    /// Time complexity: O(B^2 * M)
+    fn split(&mut self, node: usize) -> usize {
+        let entries = std::mem::take(&mut self.nodes[node].entries);
+        let centroids: Vec<Point<M>> = entries.iter().map(|(cf, _)| cf.centroid()).collect();
+        let mut seeds = (0, 1);
+        let mut max_d = f64::NEG_INFINITY;
+        for a in 0..centroids.len() {
+            for b in a + 1..centroids.len() {
+                let d = centroids[a].squared_distance(&centroids[b]);
+                if d > max_d {
+                    max_d = d;
+                    seeds = (a, b);
+                }
+            }
+        }
+
+        let (mut kept, mut moved) = (Vec::new(), Vec::new());
+        for (i, entry) in entries.into_iter().enumerate() {
+            let to_kept = i == seeds.0 || i != seeds.1
+                && centroids[i].squared_distance(&centroids[seeds.0]) <= centroids[i].squared_distance(&centroids[seeds.1]);
+            if to_kept { kept.push(entry) } else { moved.push(entry) }
+        }
+        self.nodes[node].entries = kept;
+        self.nodes.push(Node { entries: moved });
+        self.nodes.len() - 1
+    }
+
+    /// The clustering features of the subclusters in the leaves.
+    ///
+    /// Time complexity: O(s) for s subclusters
+    pub fn subclusters(&self) -> Vec<ClusteringFeature<M>> {
+        let mut subclusters = Vec::new();
+        let mut stack = vec![self.root];
+        while let Option::Some(node) = stack.pop() {
+            for (cf, child) in &self.nodes[node].entries {
+                match child {
+                    Option::Some(child) => stack.push(*child),
+                    None => subclusters.push(*cf)
+                }
+            }
+        }
+        subclusters
+    }
+
+    /// Get k clusters of the subclusters, weighted by their sizes, keeping the best of 10 runs,
+    /// as there are far fewer subclusters than points. The [`KMeans::point_centers`] of the model
+    /// are those of the subclusters, in the order of [`Birch::subclusters`]. Use
+    /// [`KMeansBuilder::fit_weighted`] on the subclusters to fit with other settings.
+    ///
+    /// Time complexity: O(i * s * k * M) for i iterations over s subclusters
+    pub fn finish<const K: usize>(&self) -> Result<KMeans<K, M>, ClusterError> {
+        self.finish_with(KMeansBuilder::new().n_init(N_INIT))
+    }
+
+    /// Get k clusters of the subclusters with a pre-determined random state.
+    pub fn finish_with_random_state<const K: usize>(&self, random_state: u64) -> Result<KMeans<K, M>, ClusterError> {
+        self.finish_with(KMeansBuilder::new().n_init(N_INIT).random_state(random_state))
+    }
+
+    fn finish_with<const K: usize>(&self, builder: KMeansBuilder<K, M>) -> Result<KMeans<K, M>, ClusterError> {
+        let subclusters = self.subclusters();
+        let centroids: Vec<Point<M>> = subclusters.iter().map(ClusteringFeature::centroid).collect();
+        let weights: Vec<f64> = subclusters.iter().map(|cf| cf.n).collect();
+        builder.fit_weighted(&centroids, &weights)
+    }
+}
+
+/// The parameters of a [`Birch`] tree and its final clustering, to cluster points in a single
+/// pass through the [`Fit`] trait. Unlike [`Birch::finish`], the fitted model assigns the points
+/// themselves to the centers, rather than the subclusters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BirchParams<const K: usize> {
+    threshold: f64,
+    branching_factor: usize,
+    random_state: Option<u64>
+}
+
+impl<const K: usize> BirchParams<K> {
+    /// Build a tree with subclusters of at most radius `threshold` and nodes of at most
+    /// `branching_factor` entries.
+    ///
+    /// # Panics
+    ///
+    /// If the branching factor is smaller than 2, when fitting.
+    pub fn new(threshold: f64, branching_factor: usize) -> Self {
+        Self { threshold, branching_factor, random_state: Option::None }
+    }
+
+    /// Cluster the subclusters with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for BirchParams<K> {
+    type Model = KMeans<K, M>;
+
+    /// Time complexity: O(r * B * M * log_B(s) + i * s * k * M + r * k * M)
+    fn fit(&self, points: &[Point<M>]) -> Result<KMeans<K, M>, ClusterError> {
+        check_point_rows(points.iter().map(|point| point.0), M, K)?;
+        let mut birch = Birch::new(self.threshold, self.branching_factor);
+        for point in points {
+            birch.insert(point);
+        }
+        let mut model = match self.random_state {
+            Option::Some(random_state) => birch.finish_with_random_state(random_state)?,
+            None => birch.finish()?
+        };
+        model.point_centers = model.predict_many(points);
+        Ok(model.with_metric(points, Euclidean))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::datasets::gaussian_blobs;
+    use crate::metrics::match_labels;
+    use super::*;
+
+    #[test]
+    fn finish_with_random_state() {
+        let centers = [Point([0.0, 0.0]), Point([20.0, 0.0]), Point([0.0, 20.0])];
+        let (points, labels) = gaussian_blobs(3000, &centers, 1.0, 0);
+        let mut birch = Birch::new(1.0, 8);
+        for point in &points {
+            birch.insert(point);
+        }
+
+        let subclusters = birch.subclusters();
+        assert!(subclusters.len() < points.len() / 10);
+        assert_eq!(subclusters.iter().map(|cf| cf.n).sum::<f64>(), 3000.0);
+
+        let model = birch.finish_with_random_state::<3>(0).unwrap();
+        let predicted: Vec<usize> = points.iter().map(|point| model.predict(point)).collect();
+        let mapping = match_labels(&labels, &predicted);
+        assert!(predicted.iter().zip(&labels).all(|(&k, &label)| mapping[k] == label));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::point::Point;
+use crate::summation::CenterSums;
+
+/// State of Elkan's algorithm (Elkan, 2003), which maintains an upper bound on the distance of every
+/// point to its center and a lower bound on its distance to every other center. Using the triangle
+/// inequality, most distance computations can be skipped, regardless of the number of dimensions.
+///
+/// # References
+///
+/// Elkan, C. (2003). Using the triangle inequality to accelerate k-means. Proceedings of the
+///     Twentieth International Conference on Machine Learning, 147–153.
+pub(crate) struct Elkan<const K: usize, const M: usize> {
+    /// The index of the center that each point belongs to.
+    pub labels: Vec<usize>,
+    /// Upper bound on the distance of each point to its center.
+    upper: Vec<f64>,
+    /// Lower bound on the distance of each point to each center.
+    lower: Vec<[f64; K]>,
+    /// Whether the upper bound of each point may be loose.
+    stale: Vec<bool>,
+    /// The centers of the previous step.
+    previous: Option<[Point<M>; K]>
+}
+
+impl<const K: usize, const M: usize> Elkan<K, M> {
+    pub fn new(r: usize) -> Self {
+        Self {
+            labels: vec![0; r],
+            upper: vec![f64::INFINITY; r],
+            lower: vec![[0.0; K]; r],
+            stale: vec![true; r],
+            previous: Option::None
+        }
+    }
+
+    /// Assign the points to `centers` and return the weighted sums and the total weights of the
+    /// points per center.
+    ///
+    /// Time complexity: worst case O(r * k * M + k^2 * M)
+    pub fn step(&mut self, points: &[Point<M>], weights: &[f64], centers: &[Point<M>; K]) -> ([Point<M>; K], [f64; K]) {
+        match self.previous {
+            // Assign every point the first time
+            None => self.initialize(points, centers),
+            Option::Some(previous) => {
+                // Move the bounds along with the centers
+                let shifts = center_shifts(&previous, centers);
+                for i in 0..points.len() {
+                    for (lower, shift) in self.lower[i].iter_mut().zip(&shifts) {
+                        *lower = (*lower - shift).max(0.0);
+                    }
+                    self.upper[i] += shifts[self.labels[i]];
+                    self.stale[i] = true;
+                }
+                self.assign(points, centers);
+            }
+        }
+        self.previous = Option::Some(*centers);
+        weighted_sums(points, weights, &self.labels)
+    }
+
+    fn initialize(&mut self, points: &[Point<M>], centers: &[Point<M>; K]) {
+        for (i, point) in points.iter().enumerate() {
+            for (k, center) in centers.iter().enumerate() {
+                let d = point.distance(center);
+                self.lower[i][k] = d;
+                if d < self.upper[i] {
+                    self.upper[i] = d;
+                    self.labels[i] = k;
+                }
+            }
+            self.stale[i] = false;
+        }
+    }
+
+    fn assign(&mut self, points: &[Point<M>], centers: &[Point<M>; K]) {
+        let distances = center_distances(centers);
+        let separation = separations(&distances);
+
+        for (i, point) in points.iter().enumerate() {
+            // Skip points that are closer to their center than halfway to any other center
+            if self.upper[i] <= separation[self.labels[i]] {
+                continue;
+            }
+
+            for k in 0..K {
+                let c = self.labels[i];
+                if k == c || self.upper[i] <= self.lower[i][k] || self.upper[i] <= 0.5 * distances[c][k] {
+                    continue;
+                }
+
+                // Tighten the upper bound before computing the distance to the other center
+                if self.stale[i] {
+                    self.upper[i] = point.distance(&centers[c]);
+                    self.lower[i][c] = self.upper[i];
+                    self.stale[i] = false;
+                    if self.upper[i] <= self.lower[i][k] || self.upper[i] <= 0.5 * distances[c][k] {
+                        continue;
+                    }
+                }
+
+                let d = point.distance(&centers[k]);
+                self.lower[i][k] = d;
+                if d < self.upper[i] {
+                    self.upper[i] = d;
+                    self.labels[i] = k;
+                }
+            }
+        }
+    }
+}
+
+/// State of Hamerly's algorithm (Hamerly, 2010), which maintains an upper bound on the distance of
+/// every point to its center, but only a single lower bound on its distance to all other centers.
+/// This prunes less than Elkan's algorithm, but needs O(r) instead of O(r * k) memory for the
+/// bounds and less work per point, which often makes it faster for a moderate number of clusters.
+///
+/// # References
+///
+/// Hamerly, G. (2010). Making k-means even faster. Proceedings of the 2010 SIAM International
+///     Conference on Data Mining, 130–140. <https://doi.org/10.1137/1.9781611972801.12>
+pub(crate) struct Hamerly<const K: usize, const M: usize> {
+    /// The index of the center that each point belongs to.
+    pub labels: Vec<usize>,
+    /// Upper bound on the distance of each point to its center.
+    upper: Vec<f64>,
+    /// Lower bound on the distance of each point to the closest other center.
+    lower: Vec<f64>,
+    /// The centers of the previous step.
+    previous: Option<[Point<M>; K]>
+}
+
+impl<const K: usize, const M: usize> Hamerly<K, M> {
+    pub fn new(r: usize) -> Self {
+        Self {
+            labels: vec![0; r],
+            upper: vec![f64::INFINITY; r],
+            lower: vec![0.0; r],
+            previous: Option::None
+        }
+    }
+
+    /// Assign the points to `centers` and return the weighted sums and the total weights of the
+    /// points per center.
+    ///
+    /// Time complexity: worst case O(r * k * M + k^2 * M)
+    pub fn step(&mut self, points: &[Point<M>], weights: &[f64], centers: &[Point<M>; K]) -> ([Point<M>; K], [f64; K]) {
+        match self.previous {
+            // Assign every point the first time
+            None => {
+                for (i, point) in points.iter().enumerate() {
+                    self.assign_point(i, point, centers);
+                }
+            },
+            Option::Some(previous) => {
+                // Move the bounds along with the centers: the closest other center moved at most
+                // as far as the center that moved farthest, apart from the own center
+                let shifts = center_shifts(&previous, centers);
+                let farthest = (0..K).max_by(|&a, &b| shifts[a].total_cmp(&shifts[b])).unwrap();
+                let second = (0..K).filter(|&k| k != farthest).map(|k| shifts[k]).fold(0.0, f64::max);
+                for i in 0..points.len() {
+                    let c = self.labels[i];
+                    self.upper[i] += shifts[c];
+                    self.lower[i] = (self.lower[i] - if c == farthest { second } else { shifts[farthest] }).max(0.0);
+                }
+                self.assign(points, centers);
+            }
+        }
+        self.previous = Option::Some(*centers);
+        weighted_sums(points, weights, &self.labels)
+    }
+
+    fn assign(&mut self, points: &[Point<M>], centers: &[Point<M>; K]) {
+        let separation = separations(&center_distances(centers));
+
+        for (i, point) in points.iter().enumerate() {
+            // Skip points that are closer to their center than to any other center could be
+            let c = self.labels[i];
+            let bound = separation[c].max(self.lower[i]);
+            if self.upper[i] <= bound {
+                continue;
+            }
+
+            // Tighten the upper bound before computing the distances to the other centers
+            self.upper[i] = point.distance(&centers[c]);
+            if self.upper[i] <= bound {
+                continue;
+            }
+            self.assign_point(i, point, centers);
+        }
+    }
+
+    /// Compute the distances of a point to every center, and set its label to the closest
+    /// center, and its bounds to the distances to the closest and second closest center.
+    ///
+    /// Time complexity: O(k * M)
+    fn assign_point(&mut self, i: usize, point: &Point<M>, centers: &[Point<M>; K]) {
+        let (mut closest, mut second) = (f64::INFINITY, f64::INFINITY);
+        for (k, center) in centers.iter().enumerate() {
+            let d = point.distance(center);
+            if d < closest {
+                second = closest;
+                closest = d;
+                self.labels[i] = k;
+            } else if d < second {
+                second = d;
+            }
+        }
+        self.upper[i] = closest;
+        self.lower[i] = second;
+    }
+}
+
+/// The weighted sums and the total weights of the points per center.
+///
+/// Time complexity: O(r * M)
+pub(crate) fn weighted_sums<const K: usize, const M: usize>(points: &[Point<M>], weights: &[f64], labels: &[usize]) -> ([Point<M>; K], [f64; K]) {
+    let mut sums = CenterSums::default();
+    for ((point, &w), &k) in points.iter().zip(weights).zip(labels) {
+        sums.add(k, point, w);
+    }
+    sums.values()
+}
+
+/// Distance that each center moved.
+///
+/// Time complexity: O(k * M)
+pub(crate) fn center_shifts<const K: usize, const M: usize>(previous: &[Point<M>; K], centers: &[Point<M>; K]) -> [f64; K] {
+    std::array::from_fn(|k| previous[k].distance(&centers[k]))
+}
+
+/// Distances between every pair of centers.
+///
+/// Time complexity: O(k^2 * M)
+pub(crate) fn center_distances<const K: usize, const M: usize>(centers: &[Point<M>; K]) -> [[f64; K]; K] {
+    let mut distances = [[0.0; K]; K];
+    for a in 0..K {
+        for (b, center) in centers.iter().enumerate().skip(a + 1) {
+            let d = centers[a].distance(center);
+            distances[a][b] = d;
+            distances[b][a] = d;
+        }
+    }
+    distances
+}
+
+/// Half the distance from each center to the closest other center: points closer than that to
+/// their center cannot be closer to another center.
+///
+/// Time complexity: O(k^2)
+pub(crate) fn separations<const K: usize>(distances: &[[f64; K]; K]) -> [f64; K] {
+    let mut separation = [f64::INFINITY; K];
+    for (a, separation) in separation.iter_mut().enumerate() {
+        for (b, distance) in distances[a].iter().enumerate() {
+            if a != b {
+                *separation = separation.min(0.5 * distance);
+            }
+        }
+    }
+    separation
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn elkan_step() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([1.0, 0.0]),
+            Point([9.0, 0.0]),
+            Point([10.0, 0.0])
+        ];
+        let mut elkan = Elkan::<2, 2>::new(points.len());
+        let weights = [1.0; 4];
+        elkan.step(&points, &weights, &[Point([0.0, 0.0]), Point([1.0, 0.0])]);
+        assert_eq!(elkan.labels, vec![0, 1, 1, 1]);
+        let (sums, counts) = elkan.step(&points, &weights, &[Point([0.0, 0.0]), Point([20.0 / 3.0, 0.0])]);
+        assert_eq!(elkan.labels, vec![0, 0, 1, 1]);
+        assert_eq!(sums, [Point([1.0, 0.0]), Point([19.0, 0.0])]);
+        assert_eq!(counts, [2.0, 2.0]);
+    }
+
+    #[test]
+    fn hamerly_step() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([1.0, 0.0]),
+            Point([9.0, 0.0]),
+            Point([10.0, 0.0])
+        ];
+        let mut hamerly = Hamerly::<2, 2>::new(points.len());
+        let weights = [1.0; 4];
+        hamerly.step(&points, &weights, &[Point([0.0, 0.0]), Point([1.0, 0.0])]);
+        assert_eq!(hamerly.labels, vec![0, 1, 1, 1]);
+        assert_eq!(hamerly.lower, vec![1.0, 1.0, 9.0, 10.0]);
+        let (sums, counts) = hamerly.step(&points, &weights, &[Point([0.0, 0.0]), Point([20.0 / 3.0, 0.0])]);
+        assert_eq!(hamerly.labels, vec![0, 0, 1, 1]);
+        assert_eq!(sums, [Point([1.0, 0.0]), Point([19.0, 0.0])]);
+        assert_eq!(counts, [2.0, 2.0]);
+    }
+}
@@ -0,0 +1,759 @@
+use crate::clusterer::{Algorithm, EmptyClusterPolicy, IterationState, KMeans, KMeansIter, Observer};
+use crate::dataset::Dataset;
+use crate::error::{check_centers, check_cluster_sizes, check_points, ClusterError};
+use crate::estimator::Fit;
+use crate::initialization::Initialization;
+use crate::memory::{estimate_memory, MemoryBudgetExceeded, MemoryPolicy};
+use crate::metric::{Euclidean, Metric};
+use crate::mrkd::{Tree, DEFAULT_LEAF_SIZE};
+use crate::parallelism::Parallelism;
+use crate::point::Point;
+use crate::preprocess::{handle_missing, MissingPolicy};
+use std::borrow::Cow;
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// A group of dimensions that should together contribute `weight` to the distance between points,
+/// regardless of how many dimensions are in the group.
+///
+/// Each dimension in the group contributes `weight / dimensions.len()` to the squared Euclidean
+/// distance, so that, for example, 3 geographical features are not drowned out by 300 embedding
+/// features. Dimensions that are not in any group form a group of their own with weight 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureGroup {
+    /// The indices of the dimensions in the group.
+    pub dimensions: Vec<usize>,
+    /// The total weight of the group.
+    pub weight: f64
+}
+
+/// Configures and fits a [`KMeans`] model.
+#[derive(Clone, Debug)]
+pub struct KMeansBuilder<const K: usize, const M: usize, D = Euclidean> {
+    pub(crate) algorithm: Algorithm,
+    pub(crate) metric: D,
+    pub(crate) initialization: Initialization,
+    pub(crate) initial_centers: Option<[Point<M>; K]>,
+    pub(crate) random_state: Option<u64>,
+    pub(crate) n_init: usize,
+    pub(crate) max_iter: usize,
+    pub(crate) tol: f64,
+    pub(crate) approximation: f64,
+    pub(crate) max_node_movement: f64,
+    pub(crate) cluster_sizes: Option<(usize, usize)>,
+    pub(crate) max_leaf_size: usize,
+    pub(crate) empty_cluster_policy: EmptyClusterPolicy,
+    pub(crate) missing_policy: MissingPolicy,
+    pub(crate) feature_groups: Vec<FeatureGroup>,
+    pub(crate) dimension_weights: [f64; M],
+    pub(crate) memory_budget: Option<(usize, MemoryPolicy)>,
+    pub(crate) time_budget: Option<Duration>,
+    pub(crate) cancellation: Option<Arc<AtomicBool>>,
+    pub(crate) record_history: bool,
+    pub(crate) record_diagnostics: bool,
+    pub(crate) sort_centers: bool,
+    pub(crate) feature_names: Option<Vec<String>>,
+    pub(crate) parallelism: Parallelism,
+    #[cfg(feature = "gpu")]
+    pub(crate) gpu: bool
+}
+
+impl<const K: usize, const M: usize, D: Metric> Default for KMeansBuilder<K, M, D> {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::Simple,
+            metric: D::default(),
+            initialization: Initialization::Random,
+            initial_centers: Option::None,
+            random_state: Option::None,
+            n_init: 1,
+            max_iter: 300,
+            tol: 0.0,
+            approximation: 0.0,
+            max_node_movement: 0.0,
+            cluster_sizes: Option::None,
+            max_leaf_size: DEFAULT_LEAF_SIZE,
+            empty_cluster_policy: EmptyClusterPolicy::Keep,
+            missing_policy: MissingPolicy::Error,
+            feature_groups: Vec::new(),
+            dimension_weights: [1.0; M],
+            memory_budget: Option::None,
+            time_budget: Option::None,
+            cancellation: Option::None,
+            record_history: false,
+            record_diagnostics: false,
+            sort_centers: false,
+            feature_names: Option::None,
+            parallelism: Parallelism::Default,
+            #[cfg(feature = "gpu")]
+            gpu: false
+        }
+    }
+}
+
+impl<const K: usize, const M: usize, D: Metric> KMeansBuilder<K, M, D> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the algorithm used for fitting. Defaults to [`Algorithm::Simple`].
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Set the [`Metric`] used to assign points to centers. Defaults to [`Euclidean`].
+    ///
+    /// For metrics that do not order centers the same way as Euclidean distance, the algorithm
+    /// falls back to [`Algorithm::Naive`].
+    pub fn metric<E: Metric>(self, metric: E) -> KMeansBuilder<K, M, E> {
+        KMeansBuilder {
+            algorithm: self.algorithm,
+            metric,
+            initialization: self.initialization,
+            initial_centers: self.initial_centers,
+            random_state: self.random_state,
+            n_init: self.n_init,
+            max_iter: self.max_iter,
+            tol: self.tol,
+            approximation: self.approximation,
+            max_node_movement: self.max_node_movement,
+            cluster_sizes: self.cluster_sizes,
+            max_leaf_size: self.max_leaf_size,
+            empty_cluster_policy: self.empty_cluster_policy,
+            missing_policy: self.missing_policy,
+            feature_groups: self.feature_groups,
+            dimension_weights: self.dimension_weights,
+            memory_budget: self.memory_budget,
+            time_budget: self.time_budget,
+            cancellation: self.cancellation,
+            record_history: self.record_history,
+            record_diagnostics: self.record_diagnostics,
+            sort_centers: self.sort_centers,
+            feature_names: self.feature_names,
+            parallelism: self.parallelism,
+            #[cfg(feature = "gpu")]
+            gpu: self.gpu
+        }
+    }
+
+    /// Set the method used to pick the initial centers. Defaults to [`Initialization::Random`].
+    pub fn initialization(mut self, initialization: Initialization) -> Self {
+        self.initialization = initialization;
+        self
+    }
+
+    /// Start fitting from `centers` instead of picking initial centers, for example to warm-start
+    /// from a previous model. Since every run would start from the same centers, `n_init` is
+    /// ignored.
+    pub fn initial_centers(mut self, centers: [Point<M>; K]) -> Self {
+        self.initial_centers = Option::Some(centers);
+        self
+    }
+
+    /// Like [`KMeansBuilder::initial_centers`], for centers whose shape is only known at runtime,
+    /// for example when reproducing published results from a file. Returns an error if there are
+    /// not k rows, or if a row does not have M values or has NaN or infinite values.
+    pub fn try_initial_center_rows<R: AsRef<[f64]>>(self, rows: &[R]) -> Result<Self, ClusterError> {
+        check_centers(rows.iter().map(AsRef::as_ref), K, M)?;
+        Ok(self.initial_centers(std::array::from_fn(|k| Point(std::array::from_fn(|d| rows[k].as_ref()[d])))))
+    }
+
+    /// Set a pre-determined random state. Every restart and every parallel task draws from its
+    /// own random stream derived from it, see [`crate::seeding::SeedSequence`], so the fitted model
+    /// is bit-identical regardless of the number of threads.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+
+    /// Set the number of runs with different initial centers, of which the run with the lowest
+    /// inertia is kept. Defaults to 1.
+    pub fn n_init(mut self, n_init: usize) -> Self {
+        self.n_init = n_init.max(1);
+        self
+    }
+
+    /// Set the maximum number of iterations. Defaults to 300.
+    pub fn max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Set the convergence tolerance: fitting stops once no center moves more than `tol`.
+    /// Defaults to 0, i.e. fitting stops once the centers no longer change.
+    pub fn tol(mut self, tol: f64) -> Self {
+        self.tol = tol;
+        self
+    }
+
+    /// Fit approximately, trading accuracy for speed with the distortion that the mrkd-tree
+    /// computes from its cached statistics (Pelleg & Moore, 1999). A node of the tree is assigned
+    /// to the center closest to its hyper-rectangle as a whole once that provably increases the
+    /// distortion of its points by at most a fraction `epsilon`, and fitting stops once an
+    /// iteration decreases the distortion by at most a fraction `epsilon`. The distortion of
+    /// every iteration is then at most a factor 1 / (1 - `epsilon`) above the exact one.
+    ///
+    /// Defaults to 0, i.e. exact fitting. Only used by [`Algorithm::Simple`] and
+    /// [`Algorithm::Blacklist`], and values should be between 0 and 1.
+    pub fn approximation(mut self, epsilon: f64) -> Self {
+        self.approximation = epsilon;
+        self
+    }
+
+    /// Fit approximately by no longer descending the mrkd-tree into a node once its points can
+    /// move no center by more than `epsilon`, to first order, when they are all assigned to the
+    /// center closest to its hyper-rectangle. The movement is estimated from the weight of every
+    /// cluster in the previous iteration, so the first iteration is exact. This speeds up
+    /// iterations on large trees the most, and [`IterationState::distortion_error`] bounds how
+    /// much the distortion of an iteration is increased by it.
+    ///
+    /// Defaults to 0, i.e. exact fitting. Only used by [`Algorithm::Simple`] and
+    /// [`Algorithm::Blacklist`].
+    pub fn max_node_movement(mut self, epsilon: f64) -> Self {
+        self.max_node_movement = epsilon;
+        self
+    }
+
+    /// Constrain every cluster to between `min` and `max` points, for example to get clusters of
+    /// roughly equal size for territory design. Instead of to the closest center, the points are
+    /// then assigned by solving a min-cost flow problem in every iteration, see
+    /// [`crate::CapacitatedKMeans`], which always uses [`Algorithm::Naive`]. Fitting returns
+    /// [`ClusterError::InfeasibleClusterSizes`] if the points cannot be divided this way.
+    ///
+    /// [`KMeans::predict`] still assigns new points to the closest center.
+    ///
+    /// Time complexity: O(r * k^2 * log(r * k)) per iteration
+    pub fn cluster_sizes(mut self, min: usize, max: usize) -> Self {
+        self.cluster_sizes = Option::Some((min, max));
+        self
+    }
+
+    /// Split the nodes of the mrkd-tree or ball tree until they have at most `max_leaf_size`
+    /// points. Larger leaves make the tree smaller and faster to build, but their points are
+    /// assigned one by one. Defaults to 8; values below 1 are treated as 1. Only used by
+    /// [`Algorithm::Simple`], [`Algorithm::Blacklist`] and [`Algorithm::BallTree`].
+    pub fn max_leaf_size(mut self, max_leaf_size: usize) -> Self {
+        self.max_leaf_size = max_leaf_size;
+        self
+    }
+
+    /// Limit the wall-clock time spent fitting to roughly `budget`. Fitting stops at the iteration
+    /// boundary closest to the deadline, and the best model so far is returned with
+    /// [`KMeans::truncated`] set. At least one iteration is always performed.
+    pub fn time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Option::Some(budget);
+        self
+    }
+
+    /// Stop fitting once `token` is set, for example from another thread when the user aborts a
+    /// fit. The token is checked once per iteration; the run in progress stops at the next
+    /// iteration boundary, no further runs are started, and the best model so far is returned with
+    /// [`KMeans::truncated`] set. At least one iteration is always performed.
+    pub fn cancellation_token(mut self, token: Arc<AtomicBool>) -> Self {
+        self.cancellation = Option::Some(token);
+        self
+    }
+
+    /// Whether the fit was cancelled through the cancellation token.
+    pub(crate) fn cancelled(&self) -> bool {
+        self.cancellation.as_ref().is_some_and(|token| token.load(Ordering::Relaxed))
+    }
+
+    /// Set the threads that fitting may use, see [`Parallelism`]. Applies to all fits of the
+    /// builder except [`KMeansBuilder::iter`], which runs on the thread that advances it. Defaults
+    /// to [`Parallelism::Default`].
+    pub fn parallelism(mut self, parallelism: Parallelism) -> Self {
+        self.parallelism = parallelism;
+        self
+    }
+
+    /// Assign the points to the centers on the GPU, with the `gpu` feature, for
+    /// [`Algorithm::Naive`] and [`Algorithm::Elkan`], which then computes all distances instead of
+    /// skipping them with bounds. The points are uploaded once per run, and the sums per center
+    /// are still computed on the CPU. The GPU computes the distances in `f32`, so the centers can
+    /// differ slightly from a fit on the CPU when points are almost equally close to two centers;
+    /// the final labels are computed on the CPU. Without a suitable GPU, with other metrics than
+    /// (squared) Euclidean distance or with [`KMeansBuilder::cluster_sizes`], the fit runs on the
+    /// CPU. Defaults to false.
+    #[cfg(feature = "gpu")]
+    pub fn gpu(mut self, gpu: bool) -> Self {
+        self.gpu = gpu;
+        self
+    }
+
+    /// Record the centers after every iteration in [`KMeans::history`], for example to visualize
+    /// the fit or to find centers that oscillate. Defaults to false.
+    pub fn record_history(mut self, record_history: bool) -> Self {
+        self.record_history = record_history;
+        self
+    }
+
+    /// Record statistics on the mrkd-tree and how its nodes were handled in every iteration in
+    /// [`KMeans::diagnostics`], to see whether the tree helps on the data. Defaults to false.
+    pub fn record_diagnostics(mut self, record_diagnostics: bool) -> Self {
+        self.record_diagnostics = record_diagnostics;
+        self
+    }
+
+    /// Sort the centers of the fitted model lexicographically, see [`KMeans::sort_centers`], such
+    /// that the labels do not depend on the order in which the centers were initialized. Defaults
+    /// to false.
+    pub fn sort_centers(mut self, sort_centers: bool) -> Self {
+        self.sort_centers = sort_centers;
+        self
+    }
+
+    /// Attach names to the dimensions of the fitted model, see [`KMeans::with_feature_names`].
+    ///
+    /// # Panics
+    ///
+    /// If the number of names differs from M.
+    pub fn feature_names<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        assert_eq!(names.len(), M, "every dimension needs a name");
+        self.feature_names = Option::Some(names);
+        self
+    }
+
+    /// Set what happens to centers that no point is assigned to. Defaults to
+    /// [`EmptyClusterPolicy::Keep`].
+    pub fn empty_cluster_policy(mut self, empty_cluster_policy: EmptyClusterPolicy) -> Self {
+        self.empty_cluster_policy = empty_cluster_policy;
+        self
+    }
+
+    /// Set what happens to points with missing (NaN) coordinates. Defaults to
+    /// [`MissingPolicy::Error`].
+    pub fn missing_policy(mut self, missing_policy: MissingPolicy) -> Self {
+        self.missing_policy = missing_policy;
+        self
+    }
+
+    /// Declare a [`FeatureGroup`] of `dimensions` with a total weight of `weight`.
+    ///
+    /// # Panics
+    ///
+    /// If a dimension is out of range, already part of another group, or if the weight is negative.
+    pub fn feature_group(mut self, dimensions: impl IntoIterator<Item = usize>, weight: f64) -> Self {
+        let dimensions: Vec<usize> = dimensions.into_iter().collect();
+        assert!(weight >= 0.0, "feature group weight must be non-negative");
+        for &d in &dimensions {
+            assert!(d < M, "feature group dimension {} out of range", d);
+            assert!(
+                self.feature_groups.iter().all(|group| !group.dimensions.contains(&d)),
+                "dimension {} is part of multiple feature groups", d
+            );
+        }
+        self.feature_groups.push(FeatureGroup { dimensions, weight });
+        self
+    }
+
+    /// Weigh every dimension in the squared Euclidean distance, so that some features count more
+    /// toward the distance than others. This is equivalent to scaling dimension `d` by the square
+    /// root of `weights[d]`, which is how the points are fitted: the distances to
+    /// hyper-rectangles and the domination checks of the accelerated algorithms therefore use the
+    /// same weights as the distances between points. The weights multiply those of the
+    /// [`FeatureGroup`]s, and default to 1.
+    ///
+    /// # Panics
+    ///
+    /// If a weight is negative or not finite.
+    pub fn dimension_weights(mut self, weights: [f64; M]) -> Self {
+        assert!(weights.iter().all(|&w| w.is_finite() && w >= 0.0), "dimension weights must be finite and non-negative");
+        self.dimension_weights = weights;
+        self
+    }
+
+    /// Limit the predicted peak memory use of a fit to `bytes`, as estimated by
+    /// [`crate::estimate_memory`]. When the budget would be exceeded, `policy` decides whether to
+    /// refuse to fit or to fall back to a cheaper algorithm.
+    pub fn memory_budget(mut self, bytes: usize, policy: MemoryPolicy) -> Self {
+        self.memory_budget = Option::Some((bytes, policy));
+        self
+    }
+
+    /// The algorithm that will be used to fit `r` points within the memory budget, with
+    /// [`Algorithm::Auto`] resolved.
+    ///
+    /// Time complexity: O(1)
+    pub fn budgeted_algorithm(&self, r: usize) -> Result<Algorithm, MemoryBudgetExceeded> {
+        // Metrics without the geometric properties of Euclidean distance and constrained cluster
+        // sizes always use Lloyd's algorithm
+        let algorithm = if D::EUCLIDEAN_ORDER && self.cluster_sizes.is_none() {
+            self.algorithm.resolve(r, M, K)
+        } else {
+            Algorithm::Naive
+        };
+        let (budget, policy) = match self.memory_budget {
+            Option::Some(memory_budget) => memory_budget,
+            None => return Ok(algorithm)
+        };
+
+        let estimate = estimate_memory(r, K, M, algorithm).total();
+        if estimate <= budget {
+            return Ok(algorithm)
+        }
+
+        let fallback = estimate_memory(r, K, M, Algorithm::Naive).total();
+        if policy == MemoryPolicy::Downgrade && fallback <= budget {
+            Ok(Algorithm::Naive)
+        } else {
+            Err(MemoryBudgetExceeded { estimate: estimate.min(fallback), budget })
+        }
+    }
+
+    /// The weight of each dimension in the squared Euclidean distance, combining the
+    /// [`FeatureGroup`]s and the [`KMeansBuilder::dimension_weights`].
+    ///
+    /// Time complexity: O(M)
+    pub fn feature_weights(&self) -> [f64; M] {
+        let mut weights = self.dimension_weights;
+        for group in &self.feature_groups {
+            for &d in &group.dimensions {
+                weights[d] *= group.weight / group.dimensions.len() as f64;
+            }
+        }
+        weights
+    }
+
+    /// Get k clusters based on `points`, and the distance of each point to every center, see
+    /// [`KMeans::transform`], or an error for the reasons of [`KMeansBuilder::fit`].
+    pub fn fit_transform(&self, points: &[Point<M>]) -> Result<(KMeans<K, M, D>, Vec<[f64; K]>), ClusterError> {
+        let model = self.fit(points)?;
+        let distances = model.transform(points);
+        Ok((model, distances))
+    }
+
+    /// Get k clusters based on `points`, or an error if they or the initial centers have NaN or
+    /// infinite coordinates, there are fewer than k distinct points, or the fit would exceed the
+    /// memory budget.
+    pub fn fit(&self, points: &[Point<M>]) -> Result<KMeans<K, M, D>, ClusterError> {
+        self.fit_weighted(points, &vec![1.0; points.len()])
+    }
+
+    /// Get k clusters based on weighted `points`, or an error if the number of weights differs
+    /// from the number of points, a weight is negative or not finite, or for the reasons of
+    /// [`KMeansBuilder::fit`]. Missing coordinates are handled first, according to the
+    /// [`MissingPolicy`].
+    pub fn fit_weighted(&self, points: &[Point<M>], weights: &[f64]) -> Result<KMeans<K, M, D>, ClusterError> {
+        self.fit_observed(points, weights, Option::None, Option::None)
+    }
+
+    /// Get k clusters based on `points`, calling `callback` after every iteration, or an error for
+    /// the reasons of [`KMeansBuilder::fit`].
+    pub fn fit_with_callback(&self, points: &[Point<M>], mut callback: impl FnMut(&IterationState<K, M>) -> ControlFlow<()> + Send) -> Result<KMeans<K, M, D>, ClusterError> {
+        self.fit_observed(points, &vec![1.0; points.len()], Option::Some(&mut callback), Option::None)
+    }
+
+    /// Get k clusters based on the points of `dataset`, reusing its mrkd-tree, or an error if there
+    /// are fewer than k distinct points or the fit would exceed the memory budget.
+    pub fn fit_dataset(&self, dataset: &Dataset<M>) -> Result<KMeans<K, M, D>, ClusterError> {
+        self.fit_observed(&dataset.points, &dataset.weights, Option::None, Option::Some(&dataset.tree))
+    }
+
+    /// Start fitting a single run on `points`, performing one iteration every time the returned
+    /// iterator is advanced, or return an error for the reasons of [`KMeansBuilder::fit`]. The run
+    /// is initialized like the first run of [`KMeansBuilder::fit`]; the number of runs, time
+    /// budget and cancellation token are not used.
+    pub fn iter<'a>(&'a self, points: &'a [Point<M>]) -> Result<KMeansIter<'a, K, M, D>, ClusterError> {
+        let ones = vec![1.0; points.len()];
+        let (handled, weights) = handle_missing(points, &ones, self.missing_policy);
+        check_points(&handled, &weights, K)?;
+        check_cluster_sizes(handled.len(), K, self.cluster_sizes)?;
+        if let Option::Some(centers) = &self.initial_centers {
+            check_centers(centers.iter().map(|center| &center.0[..]), K, M)?;
+        }
+        let weights = Cow::Owned(weights.into_owned());
+        // Only keep a copy of the points if missing values were handled
+        let points = match handled {
+            Cow::Borrowed(_) => Cow::Borrowed(points),
+            Cow::Owned(handled) => Cow::Owned(handled)
+        };
+        let algorithm = self.budgeted_algorithm(points.len())?;
+        let config = if algorithm == self.algorithm { Cow::Borrowed(self) } else { Cow::Owned(self.clone().algorithm(algorithm)) };
+        Ok(KMeansIter::new(points, weights, config))
+    }
+
+    fn fit_observed(&self, points: &[Point<M>], weights: &[f64], observer: Observer<K, M>, tree: Option<&Tree<M>>) -> Result<KMeans<K, M, D>, ClusterError> {
+        self.parallelism.install(|| self.fit_installed(points, weights, observer, tree))
+    }
+
+    fn fit_installed(&self, points: &[Point<M>], weights: &[f64], observer: Observer<K, M>, tree: Option<&Tree<M>>) -> Result<KMeans<K, M, D>, ClusterError> {
+        let (points, weights) = handle_missing(points, weights, self.missing_policy);
+        check_points(&points, &weights, K)?;
+        check_cluster_sizes(points.len(), K, self.cluster_sizes)?;
+        if let Option::Some(centers) = &self.initial_centers {
+            check_centers(centers.iter().map(|center| &center.0[..]), K, M)?;
+        }
+        let algorithm = self.budgeted_algorithm(points.len())?;
+        let mut model = if algorithm == self.algorithm {
+            KMeans::new(&points, &weights, self, observer, tree)
+        } else {
+            KMeans::new(&points, &weights, &self.clone().algorithm(algorithm), observer, tree)
+        };
+        model.feature_names = self.feature_names.clone();
+        if self.sort_centers {
+            model = model.sort_centers();
+        }
+        Ok(model)
+    }
+}
+
+impl<const K: usize, const M: usize, D: Metric> Fit<M> for KMeansBuilder<K, M, D> {
+    type Model = KMeans<K, M, D>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<KMeans<K, M, D>, ClusterError> {
+        self.fit(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::match_labels;
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn feature_weights() {
+        let builder = KMeansBuilder::<2, 4>::new()
+            .feature_group(0..3, 1.0)
+            .feature_group([3], 0.5);
+        assert_eq!(builder.feature_weights(), [1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0, 0.5]);
+        assert_eq!(builder.dimension_weights([3.0, 1.0, 1.0, 4.0]).feature_weights(), [1.0, 1.0 / 3.0, 1.0 / 3.0, 2.0]);
+    }
+
+    #[test]
+    fn fit_dimension_weights() {
+        // The accelerated algorithms find the same clusters as Lloyd's algorithm in the weighted space
+        let points: Vec<Point<2>> = (0..100).map(|i| Point([(i % 10) as f64, (i * 7 % 23) as f64])).collect();
+        let builder = KMeansBuilder::<3, 2>::new().random_state(0).dimension_weights([25.0, 0.04]);
+        let naive = builder.clone().algorithm(Algorithm::Naive).fit(&points).unwrap();
+        for algorithm in [Algorithm::Simple, Algorithm::Blacklist, Algorithm::Elkan] {
+            let model = builder.clone().algorithm(algorithm).fit(&points).unwrap();
+            assert_eq!(model.point_centers, naive.point_centers);
+            assert_eq!(model.feature_weights, [25.0, 0.04]);
+        }
+    }
+
+    #[test]
+    fn ties_and_sort_centers() {
+        // The point without weight ends up equally close to both centers
+        let points = [Point([-1.0]), Point([1.0]), Point([2.0]), Point([3.0]), Point([5.0])];
+        let weights = [1.0, 1.0, 0.0, 1.0, 1.0];
+        let builder = KMeansBuilder::<2, 1>::new().initial_centers([Point([5.0]), Point([-1.0])]);
+        for algorithm in [Algorithm::Naive, Algorithm::Simple, Algorithm::Blacklist, Algorithm::Elkan, Algorithm::Hamerly] {
+            let model = builder.clone().algorithm(algorithm).fit_weighted(&points, &weights).unwrap();
+            assert_eq!(model.centers, [Point([4.0]), Point([0.0])]);
+            assert_eq!(model.point_centers, vec![1, 1, 0, 0, 0]);
+        }
+
+        let model = builder.sort_centers(true).record_history(true).fit_weighted(&points, &weights).unwrap();
+        assert_eq!(model.centers, [Point([0.0]), Point([4.0])]);
+        assert_eq!(model.point_centers, vec![0, 0, 1, 1, 1]);
+        assert_eq!(model.history.unwrap().centers[0], [Point([-1.0]), Point([5.0])]);
+    }
+
+    #[test]
+    fn record_diagnostics() {
+        let points: Vec<Point<2>> = (0..1000).map(|i| Point([(i % 40) as f64 + if i % 2 == 0 { 100.0 } else { 0.0 }, (i % 7) as f64])).collect();
+        let builder = KMeansBuilder::<2, 2>::new().random_state(0).record_diagnostics(true);
+        let model = builder.clone().algorithm(Algorithm::Simple).fit(&points).unwrap();
+        let diagnostics = model.diagnostics.unwrap();
+        assert_eq!(diagnostics.algorithm, Algorithm::Simple);
+        assert!(diagnostics.tree_depth > 0 && diagnostics.tree_leaves > 1);
+        assert_eq!(diagnostics.tree_nodes, 2 * diagnostics.tree_leaves - 1);
+        assert_eq!(diagnostics.iterations.len(), model.iterations);
+        for nodes in &diagnostics.iterations {
+            // Every visited node is owned, descended into or a leaf
+            assert_eq!(nodes.owned + nodes.leaves, nodes.descended + 1);
+        }
+        // Once the clusters separate, whole subtrees are owned
+        assert!(diagnostics.iterations.last().unwrap().points < points.len());
+
+        let diagnostics = builder.algorithm(Algorithm::Naive).fit(&points).unwrap().diagnostics.unwrap();
+        assert_eq!((diagnostics.tree_nodes, diagnostics.iterations.len()), (0, 0));
+    }
+
+    #[test]
+    fn fit_feature_groups() {
+        // Without weighting, the spread in the second dimension dominates
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.1, 10.0]),
+            Point([1.0, 0.0]),
+            Point([1.1, 10.0])
+        ];
+        let model = KMeansBuilder::<2, 2>::new()
+            .algorithm(Algorithm::Naive)
+            .random_state(0)
+            .feature_group([1], 0.0)
+            .fit(&points).unwrap();
+        assert_eq!(model.point_centers[0], model.point_centers[1]);
+        assert_eq!(model.point_centers[2], model.point_centers[3]);
+        assert_ne!(model.point_centers[0], model.point_centers[2]);
+    }
+
+    #[test]
+    fn memory_budget() {
+        let budget = estimate_memory(1000, 2, 2, Algorithm::Naive).total();
+        let builder = KMeansBuilder::<2, 2>::new().algorithm(Algorithm::Simple);
+        assert_eq!(builder.clone().memory_budget(budget, MemoryPolicy::Downgrade).budgeted_algorithm(1000), Ok(Algorithm::Naive));
+        assert!(builder.clone().memory_budget(budget, MemoryPolicy::Reject).budgeted_algorithm(1000).is_err());
+        assert!(builder.memory_budget(budget - 1, MemoryPolicy::Downgrade).budgeted_algorithm(1000).is_err());
+    }
+
+    #[test]
+    fn fit_with_callback() {
+        let points: Vec<Point<2>> = (0..20).map(|i| Point([i as f64, (i * i % 7) as f64])).collect();
+        let builder = KMeansBuilder::<3, 2>::new().random_state(0).n_init(2);
+
+        let mut states = Vec::new();
+        let model = builder.fit_with_callback(&points, |state| {
+            states.push(state.clone());
+            ControlFlow::Continue(())
+        }).unwrap();
+        assert_eq!(model.centers, builder.fit(&points).unwrap().centers);
+        assert_eq!(states.iter().filter(|state| state.run == 0).count(), builder.clone().n_init(1).fit(&points).unwrap().iterations);
+        assert_eq!(states.last().unwrap().max_shift, 0.0);
+        assert!(states.windows(2).all(|pair| pair[1].run > pair[0].run || pair[1].inertia <= pair[0].inertia));
+
+        // Stop every run after the first iteration
+        let model = builder.fit_with_callback(&points, |_| ControlFlow::Break(())).unwrap();
+        assert_eq!(model.iterations, 1);
+    }
+
+    #[test]
+    fn iter() {
+        let points: Vec<Point<2>> = (0..20).map(|i| Point([i as f64, (i * i % 7) as f64])).collect();
+        for algorithm in [Algorithm::Naive, Algorithm::Simple, Algorithm::Elkan] {
+            let builder = KMeansBuilder::<3, 2>::new().algorithm(algorithm).random_state(0);
+            let model = builder.fit(&points).unwrap();
+
+            let mut iter = builder.iter(&points).unwrap();
+            let states: Vec<IterationState<3, 2>> = iter.by_ref().collect();
+            assert_eq!(states.len(), model.iterations);
+            assert_eq!(states.last().unwrap().inertia, model.inertia());
+            let fitted = iter.into_model();
+            assert_eq!(fitted.centers, model.centers);
+            assert_eq!(fitted.point_centers, model.point_centers);
+        }
+
+        // Stop after two iterations
+        let builder = KMeansBuilder::<3, 2>::new().random_state(0);
+        let mut iter = builder.iter(&points).unwrap();
+        let second = iter.nth(1).unwrap();
+        assert_eq!(iter.centers(), &second.centers);
+        assert_eq!(iter.into_model().iterations, 2);
+
+        // The initial centers are checked like for a fit
+        let builder = KMeansBuilder::<3, 2>::new().initial_centers([points[0], Point([0.0, f64::NAN]), points[2]]);
+        assert_eq!(builder.iter(&points).err(), Option::Some(ClusterError::NonFiniteCenter { center: 1, dimension: 1 }));
+    }
+
+    #[test]
+    fn approximation() {
+        let points: Vec<Point<2>> = (0..200).map(|i| Point([(i % 20) as f64 + (i / 20) as f64 * 0.1, (i * i % 13) as f64])).collect();
+        let builder = KMeansBuilder::<4, 2>::new().algorithm(Algorithm::Blacklist).random_state(0);
+
+        // The distortion of an iteration is the inertia of the centers before it
+        let states: Vec<IterationState<4, 2>> = builder.iter(&points).unwrap().collect();
+        for pair in states.windows(2) {
+            assert!((pair[1].distortion.unwrap() - pair[0].inertia).abs() < 1e-6);
+        }
+
+        // Approximate fitting stops earlier with a bounded distortion
+        let exact = builder.fit(&points).unwrap();
+        let approximate = builder.clone().approximation(0.1).fit(&points).unwrap();
+        assert!(approximate.converged);
+        assert!(approximate.iterations <= exact.iterations);
+        let states: Vec<IterationState<4, 2>> = builder.clone().approximation(0.1).iter(&points).unwrap().collect();
+        assert!(states.windows(2).all(|pair| pair[1].distortion.unwrap() <= pair[0].inertia / 0.9 + 1e-6));
+
+        // The distortion is bounded from both sides when limiting the movement of the centers
+        let states: Vec<IterationState<4, 2>> = builder.max_node_movement(0.5).iter(&points).unwrap().collect();
+        assert_eq!(states[0].distortion_error, Option::Some(0.0));
+        for pair in states.windows(2) {
+            let (distortion, error) = (pair[1].distortion.unwrap(), pair[1].distortion_error.unwrap());
+            assert!(distortion >= pair[0].inertia - 1e-6 && distortion - error <= pair[0].inertia + 1e-6);
+        }
+        assert!(states.iter().any(|state| state.distortion_error > Option::Some(0.0)));
+    }
+
+    #[test]
+    fn cluster_sizes() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.1, 0.0]),
+            Point([0.2, 0.0]),
+            Point([0.3, 0.0]),
+            Point([10.0, 0.0]),
+            Point([10.1, 0.0])
+        ];
+        let model = KMeansBuilder::<2, 2>::new().random_state(0).cluster_sizes(3, 3).fit(&points).unwrap();
+        let expected = [0, 0, 0, 1, 1, 1];
+        let mapping = match_labels(&expected, &model.point_centers);
+        assert_eq!(model.point_centers.iter().map(|&k| mapping[k]).collect::<Vec<_>>(), expected);
+
+        let builder = KMeansBuilder::<2, 2>::new().cluster_sizes(4, 5);
+        assert_eq!(builder.fit(&points).err(), Option::Some(ClusterError::InfeasibleClusterSizes { points: 6, clusters: 2, min: 4, max: 5 }));
+    }
+
+    #[test]
+    fn cluster_sizes_scale() {
+        use rand::{Rng, SeedableRng};
+        use rand::rngs::StdRng;
+        use std::time::{Duration, Instant};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let points: Vec<Point<2>> = (0..4000).map(|_| Point([rng.gen::<f64>(), rng.gen::<f64>()])).collect();
+        let start = Instant::now();
+        let model = KMeansBuilder::<8, 2>::new().random_state(0).max_iter(10).cluster_sizes(500, 500).fit(&points).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(model.cluster_sizes(), [500; 8]);
+    }
+
+    #[test]
+    fn try_initial_center_rows() {
+        let builder = KMeansBuilder::<2, 2>::new();
+        assert_eq!(builder.clone().try_initial_center_rows(&[[0.0, 0.0]]).err(), Option::Some(ClusterError::CenterCountMismatch { expected: 2, found: 1 }));
+        assert_eq!(builder.clone().try_initial_center_rows(&[vec![0.0, 0.0], vec![1.0]]).err(), Option::Some(ClusterError::CenterDimensionMismatch { center: 1, expected: 2, found: 1 }));
+        let builder = builder.try_initial_center_rows(&[[0.0, 0.0], [4.0, 0.0]]).unwrap();
+        assert_eq!(builder.initial_centers, Option::Some([Point([0.0, 0.0]), Point([4.0, 0.0])]));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallelism() {
+        let points: Vec<Point<2>> = (0..100).map(|i| Point([(i % 10) as f64, (i / 10) as f64])).collect();
+        let pool = Arc::new(rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap());
+        let reference = KMeansBuilder::<3, 2>::new().random_state(0).fit(&points).unwrap();
+        for (parallelism, threads) in [(Parallelism::Sequential, 1), (Parallelism::Threads(3), 3), (Parallelism::Pool(pool), 2)] {
+            let builder = KMeansBuilder::<3, 2>::new().random_state(0).parallelism(parallelism);
+            let mut used = 0;
+            let model = builder.fit_with_callback(&points, |_| {
+                used = rayon::current_num_threads();
+                ControlFlow::Continue(())
+            }).unwrap();
+            assert_eq!(used, threads);
+            assert_eq!(model.centers, reference.centers);
+        }
+    }
+
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn gpu() {
+        // Without a GPU, the fit runs on the CPU, and with one, the clusters are well separated
+        let points: Vec<Point<2>> = (0..1000).map(|i| Point([(i % 4 * 100 + i % 7) as f64, (i % 3) as f64])).collect();
+        for algorithm in [Algorithm::Naive, Algorithm::Elkan] {
+            let reference = KMeansBuilder::<4, 2>::new().algorithm(algorithm).random_state(0).fit(&points).unwrap();
+            let model = KMeansBuilder::<4, 2>::new().algorithm(algorithm).random_state(0).gpu(true).fit(&points).unwrap();
+            assert_eq!(model.labels(), reference.labels());
+            for (center, expected) in model.centers.iter().zip(&reference.centers) {
+                assert!(center.distance(expected) < 1e-9);
+            }
+        }
+    }
+}
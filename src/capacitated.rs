@@ -0,0 +1,186 @@
+use crate::centers::Centers;
+use crate::error::{check_points, ClusterError};
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::flow::transport;
+use crate::initialization::random_points;
+use crate::point::Point;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Maximum number of iterations.
+const MAX_ITER: usize = 300;
+
+/// Implements capacitated k-means, where every point has a demand and every cluster a capacity
+/// that the total demand of its points should not exceed. Each iteration assigns the points by
+/// solving a transportation problem (Mulvey & Beck, 1984) minimizing the demand-weighted squared
+/// distances, and moves the centers to the demand-weighted means of their points.
+///
+/// The transportation problem may split the demand of a point over multiple clusters; such points
+/// are assigned to the cluster receiving the largest share. At most k - 1 points are split, so the
+/// capacities may be exceeded by at most that many points.
+///
+/// # References
+///
+/// Mulvey, J. M., & Beck, M. P. (1984). Solving capacitated clustering problems. European Journal
+///     of Operational Research, 18(3), 339–348. <https://doi.org/10.1016/0377-2217(84)90155-3>
+pub struct CapacitatedKMeans<const K: usize, const M: usize> {
+    /// The coordinates of the k means.
+    pub centers: [Point<M>; K],
+    /// The index of the center that each point belongs to.
+    pub point_centers: Vec<usize>,
+    /// The total demand of the points in each cluster.
+    pub loads: [f64; K]
+}
+
+impl<const K: usize, const M: usize> CapacitatedKMeans<K, M> {
+    /// Get k clusters based on `points` with the given `demands` and `capacities`, or an error if
+    /// the number of demands differs from the number of points, a demand is negative, NaN or
+    /// infinite, the points have NaN or infinite coordinates, there are fewer than k distinct
+    /// points, or the total capacity is smaller than the total demand.
+    pub fn fit(points: &[Point<M>], demands: &[f64], capacities: [f64; K]) -> Result<Self, ClusterError> {
+        Self::new(points, demands, capacities, Option::None)
+    }
+
+    /// Get k clusters based on `points` with a pre-determined random state, or an error, see
+    /// [`CapacitatedKMeans::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], demands: &[f64], capacities: [f64; K], random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, demands, capacities, Option::Some(random_state))
+    }
+
+    fn new(points: &[Point<M>], demands: &[f64], capacities: [f64; K], random_state: Option<u64>) -> Result<Self, ClusterError> {
+        check_points(points, demands, K)?;
+        let demand = demands.iter().sum::<f64>();
+        let capacity = capacities.iter().sum::<f64>();
+        if capacity.is_nan() || capacity < demand {
+            return Err(ClusterError::InsufficientCapacity { demand, capacity })
+        }
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        let mut centers = random_points::<K, M>(points, &mut rng);
+        let mut point_centers = vec![usize::MAX; points.len()];
+
+        for _ in 0..MAX_ITER {
+            // Assign the points by solving the transportation problem
+            let cost: Vec<Vec<f64>> = points.iter()
+                .map(|point| centers.iter().map(|center| point.squared_distance(center)).collect())
+                .collect();
+            let flow = transport(demands, &capacities, &cost);
+
+            let mut different = false;
+            for (i, shares) in flow.iter().enumerate() {
+                let k = (0..K).max_by(|&a, &b| shares[a].total_cmp(&shares[b])).unwrap_or(0);
+                if point_centers[i] != k {
+                    point_centers[i] = k;
+                    different = true;
+                }
+            }
+
+            // Move the centers to the demand-weighted means, keeping empty centers in place
+            let mut sums = [Point::default(); K];
+            let mut totals = [0.0; K];
+            for ((point, &demand), &k) in points.iter().zip(demands).zip(&point_centers) {
+                for d in 0..M {
+                    sums[k].0[d] += point.0[d] * demand;
+                }
+                totals[k] += demand;
+            }
+            for k in 0..K {
+                if totals[k] > 0.0 {
+                    centers[k] = Point(sums[k].0.map(|x| x / totals[k]));
+                }
+            }
+
+            if !different {
+                break;
+            }
+        }
+
+        let mut loads = [0.0; K];
+        for (&demand, &k) in demands.iter().zip(&point_centers) {
+            loads[k] += demand;
+        }
+
+        Ok(CapacitatedKMeans { centers, point_centers, loads })
+    }
+}
+
+/// The parameters of [`CapacitatedKMeans::fit`], to fit it through the [`Fit`] trait: the
+/// capacities of the clusters and the demands of the points it will be fitted on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapacitatedKMeansParams<const K: usize> {
+    demands: Vec<f64>,
+    capacities: [f64; K],
+    random_state: Option<u64>
+}
+
+impl<const K: usize> CapacitatedKMeansParams<K> {
+    /// Fit points with `demands` into clusters with `capacities`. Fitting fails unless there is a
+    /// demand for every point.
+    pub fn new(demands: Vec<f64>, capacities: [f64; K]) -> Self {
+        Self { demands, capacities, random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for CapacitatedKMeansParams<K> {
+    type Model = CapacitatedKMeans<K, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<CapacitatedKMeans<K, M>, ClusterError> {
+        CapacitatedKMeans::new(points, &self.demands, self.capacities, self.random_state)
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for CapacitatedKMeans<K, M> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.iter().map(|&k| Option::Some(k)).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.to_vec())
+    }
+}
+
+impl<const K: usize, const M: usize> Predict<M> for CapacitatedKMeans<K, M> {
+    /// The closest center, regardless of the remaining capacity of its cluster.
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        Option::Some(Centers::new(self.centers).closest(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.1, 0.0]),
+            Point([0.2, 0.0]),
+            Point([10.0, 0.0])
+        ];
+        let model = CapacitatedKMeans::<2, 2>::fit_with_random_state(&points, &[1.0; 4], [2.0, 2.0], 0).unwrap();
+        assert_eq!(model.loads, [2.0, 2.0]);
+        assert_eq!(model.point_centers[0], model.point_centers[1]);
+        assert_eq!(model.point_centers[2], model.point_centers[3]);
+
+        let invalid = [Point([0.0, 0.0]), Point([f64::NAN, 0.0])];
+        assert_eq!(CapacitatedKMeans::<2, 2>::fit(&invalid, &[1.0; 2], [2.0, 2.0]).err(), Option::Some(ClusterError::NonFinite { point: 1, dimension: 0 }));
+        assert_eq!(CapacitatedKMeans::<2, 2>::fit(&points, &[1.0; 4], [1.0, 2.0]).err(), Option::Some(ClusterError::InsufficientCapacity { demand: 4.0, capacity: 3.0 }));
+    }
+}
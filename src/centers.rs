@@ -1,7 +1,11 @@
+use crate::ball_tree::{BallChildren, BallNodeRef, BallTree};
+use crate::clusterer::NodeCounts;
+use crate::geometry;
+use crate::metric::{Metric, SquaredEuclidean};
 use crate::point::Point;
 use crate::hyper_rectangle::HyperRectangle;
-use crate::mrkd::{Tree, Node};
-use std::ops::Deref;
+use crate::mrkd::{Children, NodeRef, Tree};
+use crate::summation::CenterSums;
 
 /// A representation of the set of clusters C used in Section 3 of (Pelleg & Moore, 1999).
 ///
@@ -12,6 +16,42 @@ use std::ops::Deref;
 ///     on Knowledge Discovery and Data Mining, 277–281. <https://doi.org/10.1145/312129.312248>
 pub struct Centers<const K: usize, const M: usize> (pub [Point<M>; K]);
 
+/// The result of Update(h, C).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Update<const K: usize, const M: usize> {
+    /// The weighted sum of the points assigned to each center
+    pub sums: [Point<M>; K],
+    /// The total weight of the points assigned to each center
+    pub counts: [f64; K],
+    /// The distortion of the points with respect to the current centers, see
+    /// [`crate::mrkd::Node::distortion`]
+    pub distortion: f64,
+    /// An upper bound on how much `distortion` exceeds the exact distortion, which is 0 unless
+    /// nodes were assigned approximately
+    pub error: f64,
+    /// How the nodes of the tree were handled
+    pub nodes: NodeCounts
+}
+
+/// When a node of the tree in which more than one candidate remains is still assigned to the
+/// closest candidate as a whole, see [`Centers::update_approximate`]. The default never does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pruning<const K: usize> {
+    /// The largest increase of the distortion of a node, as a fraction of its distortion
+    pub distortion: f64,
+    /// The largest movement of a candidate caused by a node, to first order
+    pub movement: f64,
+    /// The total weight of the points of every center in the previous iteration, which determines
+    /// how far a node can move it
+    pub weights: [f64; K]
+}
+
+impl<const K: usize> Default for Pruning<K> {
+    fn default() -> Self {
+        Self { distortion: 0.0, movement: 0.0, weights: [0.0; K] }
+    }
+}
+
 impl<const K: usize, const M: usize> Centers<K, M> {
     pub fn new(centers: [Point<M>; K]) -> Self {
         Self(centers)
@@ -20,120 +60,369 @@ impl<const K: usize, const M: usize> Centers<K, M> {
     /// Update(h, C) as defined in Section 3.1 (p. 280)
     ///
     /// Time complexity: worst case O(r * k * M)
-    pub fn update(&self, tree: &Tree<M>) -> ([Point<M>; K], [usize; K]) {
-        let mut centers = [(); K].map(|_| Point::<M>::default());
-        let mut counts = [0; K];
-
-        match tree.node.deref() {
-            // If the node is not a leaf node, check if the hyper-rectangle has an owner
-            Node::NonLeaf(node) => {
-                match self.owner(&tree.h) {
-                    // If it does, update the centers according to the cached info in the node
-                    Some(k) => {
-                        centers[k] = centers[k] + tree.center_of_mass * tree.number_of_points;
-                        counts[k] = counts[k] + tree.number_of_points;
-                    },
-                    // Else, descend in the child nodes
-                    None => {
-                        let (centers_l, counts_l) = self.update(&node.l);
-                        let (centers_r, counts_r) = self.update(&node.r);
-                        for k in 0..K {
-                            centers[k] = centers[k] + centers_l[k] + centers_r[k];
-                            counts[k] = counts_l[k] + counts_r[k];
-                        }
-                    }
-                };
+    /// Returns the weighted sums and the total weights of the points per center, and their
+    /// distortion.
+    pub fn update(&self, tree: &Tree<M>) -> Update<K, M> {
+        let mut nodes = NodeCounts::default();
+        let mut sums = CenterSums::default();
+        let distortion = self.update_node(tree.root(), &mut sums, &mut nodes);
+        let (sums, counts) = sums.values();
+        Update { sums, counts, distortion, error: 0.0, nodes }
+    }
+
+    fn update_node(&self, tree: NodeRef<M>, sums: &mut CenterSums<K, M>, nodes: &mut NodeCounts) -> f64 {
+        // If the node has more than one point, check if the hyper-rectangle has an owner
+        if tree.number_of_points > 1 {
+            if let Option::Some(k) = self.owner(&tree.h) {
+                // If it does, update the centers according to the cached info in the node
+                tree.add_to(sums, k);
+                nodes.owned += 1;
+                return tree.distortion(&self.0[k])
+            }
+        }
+
+        match tree.children() {
+            // Else, descend in the child nodes
+            Children::NonLeaf(l, r) => {
+                nodes.descended += 1;
+                self.update_node(l, sums, nodes) + self.update_node(r, sums, nodes)
             },
-            // If the node is a leaf node, update the centers as normal
-            Node::Leaf(point) => {
-                let k = self.closest(point);
-                centers[k] = centers[k] + point.clone();
-                counts[k] = counts[k] + 1;
+            // Or, in a leaf node, update the centers as normal
+            Children::Leaf(leaf) => {
+                nodes.leaves += 1;
+                nodes.points += leaf.points.len();
+                let mut distortion = 0.0;
+                for (point, w, _) in leaf.iter() {
+                    let k = self.closest(point);
+                    sums.add(k, point, w);
+                    distortion += point.squared_distance(&self.0[k]) * w;
+                }
+                distortion
             }
+        }
+    }
+
+    /// Update(h, C) using the blacklisting algorithm described in Section 4 (p. 280): the centers
+    /// that are dominated by the center closest to a hyper-rectangle are removed from the list of
+    /// candidates passed down the tree, so that every node only considers the remaining candidates.
+    ///
+    /// Time complexity: worst case O(r * k * M), but typically far fewer candidates remain per node
+    pub fn update_blacklist(&self, tree: &Tree<M>) -> Update<K, M> {
+        self.update_approximate(tree, &Pruning::default())
+    }
+
+    /// [`Centers::update_blacklist`], but a node in which more than one candidate remains is still
+    /// assigned to the closest candidate as a whole if either:
+    ///
+    /// - that increases the distortion by at most a fraction `pruning.distortion` of the
+    ///   distortion of the node with respect to that candidate. The increase is bounded by the
+    ///   weight of the node times the difference between the squared distance of the candidate to
+    ///   the farthest corner of the hyper-rectangle and its squared distance to the
+    ///   hyper-rectangle, so for a fraction below 1, the distortion is at most a factor
+    ///   1 / (1 - fraction) larger than the exact distortion.
+    /// - that moves none of the remaining candidates by more than `pruning.movement`, to first
+    ///   order: the weight of the node times the distance from a candidate to the farthest corner,
+    ///   divided by the weight of the candidate in the previous iteration.
+    ///
+    /// The increases of the distortion of these nodes are summed in [`Update::error`].
+    ///
+    /// Time complexity: worst case O(r * k * M), and fewer nodes are visited as the pruning grows
+    pub fn update_approximate(&self, tree: &Tree<M>, pruning: &Pruning<K>) -> Update<K, M> {
+        let mut update = Update { sums: [Point::<M>::default(); K], counts: [0.0; K], distortion: 0.0, error: 0.0, nodes: NodeCounts::default() };
+        let mut sums = CenterSums::default();
+        let candidates: [usize; K] = std::array::from_fn(|k| k);
+        self.blacklist(tree.root(), &candidates, pruning, &mut sums, &mut update);
+        (update.sums, update.counts) = sums.values();
+        update
+    }
+
+    fn blacklist(&self, tree: NodeRef<M>, candidates: &[usize], pruning: &Pruning<K>, sums: &mut CenterSums<K, M>, update: &mut Update<K, M>) {
+        let mut remaining = [0; K];
+        let mut n = 0;
+        let candidates = if tree.number_of_points > 1 {
+            // Find the candidate closest to the hyper-rectangle
+            let mut min_d = f64::INFINITY;
+            let mut c1 = candidates[0];
+            for &c in candidates {
+                let d = tree.h.squared_distance(&self.0[c]);
+                if d < min_d {
+                    min_d = d;
+                    c1 = c;
+                }
+            }
+
+            // Remove the candidates it dominates
+            for &c2 in candidates {
+                if c2 == c1 || !self.dominates(c1, c2, &tree.h) {
+                    remaining[n] = c2;
+                    n += 1;
+                }
+            }
+
+            // If a single candidate remains, it owns every point in the node, and otherwise the
+            // closest candidate may still be close enough to the owners of the points
+            let error = if n == 1 {
+                Option::Some(0.0)
+            } else if pruning.distortion > 0.0 || pruning.movement > 0.0 {
+                let max_d = tree.h.farthest(&self.0[c1]).squared_distance(&self.0[c1]);
+                let error = tree.weight * (max_d - min_d);
+                let movement = remaining[..n].iter()
+                    .map(|&c| match pruning.weights[c] {
+                        w if w > 0.0 => tree.weight * tree.h.farthest(&self.0[c]).distance(&self.0[c]) / w,
+                        _ => f64::INFINITY
+                    })
+                    .fold(0.0, f64::max);
+                let pruned = pruning.distortion > 0.0 && error <= pruning.distortion * tree.distortion(&self.0[c1])
+                    || pruning.movement > 0.0 && movement <= pruning.movement;
+                if pruned { Option::Some(error) } else { Option::None }
+            } else {
+                Option::None
+            };
+            if let Option::Some(error) = error {
+                tree.add_to(sums, c1);
+                update.distortion += tree.distortion(&self.0[c1]);
+                update.error += error;
+                update.nodes.owned += 1;
+                return;
+            }
+            &remaining[..n]
+        } else {
+            candidates
         };
 
-        (centers, counts)
+        match tree.children() {
+            // Else, descend in the child nodes with the remaining candidates
+            Children::NonLeaf(l, r) => {
+                update.nodes.descended += 1;
+                self.blacklist(l, candidates, pruning, sums, update);
+                self.blacklist(r, candidates, pruning, sums, update);
+            },
+            // Or, in a leaf node, update the closest candidate as normal
+            Children::Leaf(leaf) => {
+                update.nodes.leaves += 1;
+                update.nodes.points += leaf.points.len();
+                for (point, w, _) in leaf.iter() {
+                    let mut min_d = f64::INFINITY;
+                    let mut min_c = candidates[0];
+                    for &c in candidates {
+                        let d = point.squared_distance(&self.0[c]);
+                        if d < min_d {
+                            min_d = d;
+                            min_c = c;
+                        }
+                    }
+                    sums.add(min_c, point, w);
+                    update.distortion += min_d * w;
+                }
+            }
+        }
     }
 
-    /// Closest center to a point. In this case, no special action is taken when multiple centers
-    /// are equally close.
+    /// The index of the center closest to every point in `tree` under `metric`, in the order of
+    /// the points the tree was built from. This uses the blacklisting of
+    /// [`Centers::update_blacklist`] to assign every hyper-rectangle in which a single candidate
+    /// dominates the others as a whole, and gives the same result as [`Centers::closest_by`] for
+    /// each point.
     ///
-    /// Time complexity: O(k * M)
-    pub fn closest(&self, point: &Point<M>) -> usize {
-        let mut min_d = f64::INFINITY;
-        let mut min_c = 0;
+    /// Time complexity: worst case O(r * k * M), but typically far fewer candidates remain per node
+    ///
+    /// # Panics
+    ///
+    /// If `metric` is not separable.
+    pub fn assign_blacklist<D: Metric>(&self, tree: &Tree<M>, metric: &D) -> Vec<usize> {
+        let mut labels = vec![0; tree.len()];
+        let candidates: [usize; K] = std::array::from_fn(|k| k);
+        self.assign_node(tree.root(), metric, &candidates, &mut labels);
+        labels
+    }
 
-        for k in 0..K {
-            let d = point.distance(&self.0[k]);
-            if d < min_d {
-                min_d = d;
-                min_c = k;
+    fn assign_node<D: Metric>(&self, tree: NodeRef<M>, metric: &D, candidates: &[usize], labels: &mut [usize]) {
+        let mut remaining = [0; K];
+        let mut n = 0;
+        let candidates = if tree.number_of_points > 1 {
+            // Find the candidate closest to the hyper-rectangle
+            let mut min_d = f64::INFINITY;
+            let mut c1 = candidates[0];
+            for &c in candidates {
+                let d = geometry::min_distance(&tree.h, &self.0[c], metric);
+                if d < min_d {
+                    min_d = d;
+                    c1 = c;
+                }
+            }
+
+            // Remove the candidates it dominates
+            for &c2 in candidates {
+                if c2 == c1 || !geometry::dominates(&self.0[c1], &self.0[c2], &tree.h, metric) {
+                    remaining[n] = c2;
+                    n += 1;
+                }
+            }
+
+            // If a single candidate remains, it owns every point in the node
+            if n == 1 {
+                Self::assign_all(tree, c1, labels);
+                return;
+            }
+            &remaining[..n]
+        } else {
+            candidates
+        };
+
+        match tree.children() {
+            // Else, descend in the child nodes with the remaining candidates
+            Children::NonLeaf(l, r) => {
+                self.assign_node(l, metric, candidates, labels);
+                self.assign_node(r, metric, candidates, labels);
+            },
+            // Or, in a leaf node, assign the closest candidate as normal, comparing the distances
+            // in the same way as closest_by
+            Children::Leaf(leaf) => {
+                for (point, _, i) in leaf.iter() {
+                    let mut min_d = f64::INFINITY;
+                    let mut min_c = candidates[0];
+                    for &c in candidates {
+                        let d = if D::EUCLIDEAN_ORDER { point.squared_distance(&self.0[c]) } else { metric.distance(point, &self.0[c]) };
+                        if d < min_d {
+                            min_d = d;
+                            min_c = c;
+                        }
+                    }
+                    labels[i] = min_c;
+                }
             }
         }
+    }
 
-        min_c
+    /// Assign every point in the node to center `c`.
+    fn assign_all(tree: NodeRef<M>, c: usize, labels: &mut [usize]) {
+        match tree.children() {
+            Children::NonLeaf(l, r) => {
+                Self::assign_all(l, c, labels);
+                Self::assign_all(r, c, labels);
+            },
+            Children::Leaf(leaf) => {
+                for &i in leaf.indices {
+                    labels[i] = c;
+                }
+            }
+        }
     }
 
-    /// owner_C(h) as defined in Section 3, Definition 1 (p. 278)
+    /// Update(h, C) with blacklisting on a [`BallTree`]: the centers that are dominated by the center
+    /// closest to the center of a ball, see [`geometry::dominates_ball`], are removed from the list
+    /// of candidates passed down the tree.
     ///
-    /// Time complexity: O(k * M)
-    pub fn owner(&self, h: &HyperRectangle<M>) -> Option<usize> {
-        // Find the center closest to the hyper-rectangle. If there are multiple, return early
-        let c1 = self.min_d(h)?;
+    /// Time complexity: worst case O(r * k * M), but typically far fewer candidates remain per node
+    pub fn update_ball(&self, tree: &BallTree<M>) -> ([Point<M>; K], [f64; K]) {
+        let mut sums = CenterSums::default();
+        let candidates: [usize; K] = std::array::from_fn(|k| k);
+        self.blacklist_ball(tree.root(), &candidates, &mut sums);
+        sums.values()
+    }
+
+    fn blacklist_ball(&self, node: BallNodeRef<M>, candidates: &[usize], sums: &mut CenterSums<K, M>) {
+        let mut remaining = [0; K];
+        let mut n = 0;
+        let candidates = if node.number_of_points > 1 {
+            // Find the candidate closest to the center of the ball
+            let mut min_d = f64::INFINITY;
+            let mut c1 = candidates[0];
+            for &c in candidates {
+                let d = node.center.squared_distance(&self.0[c]);
+                if d < min_d {
+                    min_d = d;
+                    c1 = c;
+                }
+            }
 
-        // Else, check if c1 dominates every other center
-        for c2 in 0..K {
-            if c1 != c2 && !self.dominates(c1, c2, h) {
-                return Option::None;
+            // Remove the candidates it dominates
+            for &c2 in candidates {
+                if c2 == c1 || !geometry::dominates_ball(&self.0[c1], &self.0[c2], &node.center, node.radius) {
+                    remaining[n] = c2;
+                    n += 1;
+                }
+            }
+
+            // If a single candidate remains, it owns every point in the node
+            if n == 1 {
+                node.add_to(sums, c1);
+                return;
+            }
+            &remaining[..n]
+        } else {
+            candidates
+        };
+
+        match node.children() {
+            // Else, descend in the child nodes with the remaining candidates
+            BallChildren::NonLeaf(l, r) => {
+                self.blacklist_ball(l, candidates, sums);
+                self.blacklist_ball(r, candidates, sums);
+            },
+            // Or, in a leaf node, update the closest candidate as normal
+            BallChildren::Leaf(points, weights, _) => {
+                for (point, &w) in points.iter().zip(weights) {
+                    let mut min_d = f64::INFINITY;
+                    let mut min_c = candidates[0];
+                    for &c in candidates {
+                        let d = point.squared_distance(&self.0[c]);
+                        if d < min_d {
+                            min_d = d;
+                            min_c = c;
+                        }
+                    }
+                    sums.add(min_c, point, w);
+                }
             }
         }
+    }
 
-        Option::Some(c1)
+    /// Closest center to a point. When multiple centers are equally close, the one with the lowest
+    /// index is returned.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn closest(&self, point: &Point<M>) -> usize {
+        self.closest_by(point, &SquaredEuclidean)
     }
 
-    /// min(d(c, h)) as in Section 3, Theorem 2 (p. 279)
+    /// Closest center to a point under `metric`, or the one with the lowest index of equally close
+    /// centers. Metrics that order points like Euclidean distance compare squared distances
+    /// instead, which skips the square roots.
     ///
     /// Time complexity: O(k * M)
-    fn min_d(&self, h: &HyperRectangle<M>) -> Option<usize> {
+    pub fn closest_by<D: Metric>(&self, point: &Point<M>, metric: &D) -> usize {
+        let mut distances = [0.0; K];
+        if D::EUCLIDEAN_ORDER {
+            point.squared_distances(&self.0, &mut distances);
+        } else {
+            metric.distances(point, &self.0, &mut distances);
+        }
+
         let mut min_d = f64::INFINITY;
         let mut min_c = 0;
-        let mut single_closest = true;
-
-        for c in 0..K {
-            let d = h.distance(&self.0[c]);
-            if d == min_d {
-                single_closest = false;
-            } else if d < min_d {
-                single_closest = true;
+        for (k, &d) in distances.iter().enumerate() {
+            if d < min_d {
                 min_d = d;
-                min_c = c;
+                min_c = k;
             }
         }
 
-        if single_closest {
-            Option::Some(min_c)
-        } else {
-            Option::None
-        }
+        min_c
+    }
+
+    /// owner_C(h) as defined in Section 3, Definition 1 (p. 278), see [`geometry::owner`].
+    ///
+    /// Time complexity: O(k * M)
+    pub fn owner(&self, h: &HyperRectangle<M>) -> Option<usize> {
+        geometry::owner(&self.0, h, &SquaredEuclidean)
     }
 
     /// domination as defined in Section 3, Definition 3 (p. 279)
     ///
     /// Time complexity: O(M)
     fn dominates(&self, c1: usize, c2: usize, h: &HyperRectangle<M>) -> bool {
-        // Find the point p in h that is the furthest in the direction c2 - c1
-        let mut p = [0.0; M];
-        for d in 0..M {
-            p[d] = if self.0[c1].0[d] < self.0[c2].0[d] {
-                h.1.0[d]
-            } else {
-                h.0.0[d]
-            };
-        }
-
-        // If the distance to that point is shorter from c1 than from c2, c1 dominates c2
-        let point = Point(p);
-        point.distance(&self.0[c1]) < point.distance(&self.0[c2])
+        geometry::dominates(&self.0[c1], &self.0[c2], h, &SquaredEuclidean)
     }
 }
 
@@ -148,4 +437,33 @@ mod tests {
         let centers = Centers::<2, 2>::new([Point([-2.5, -2.5]), Point([3.0, 1.0])]);
         assert_eq!(centers.owner(&h), Option::Some(1));
     }
+
+    #[test]
+    fn update_blacklist() {
+        use rand::SeedableRng;
+        use rand::rngs::StdRng;
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let points = vec![
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5]),
+            Point([4.0, 4.0])
+        ];
+        let tree = Tree::initialize(&points, &mut rng);
+        let centers = Centers::<2, 2>::new([Point([0.0, 0.0]), Point([5.0, 5.0])]);
+        let update = centers.update(&tree);
+        let blacklist = centers.update_blacklist(&tree);
+        assert_eq!((blacklist.sums, blacklist.counts), (update.sums, update.counts));
+        let exact: f64 = points.iter().map(|point| point.squared_distance(&centers.0[centers.closest(point)])).sum();
+        assert!((update.distortion - exact).abs() < 1e-9 && (blacklist.distortion - exact).abs() < 1e-9);
+
+        // The approximate updates stay within their bounds
+        for pruning in [Pruning { distortion: 0.5, ..Pruning::default() }, Pruning { movement: 1.0, weights: [4.0, 1.0], ..Pruning::default() }] {
+            let approximate = centers.update_approximate(&tree, &pruning);
+            assert_eq!(approximate.counts.iter().sum::<f64>(), 5.0);
+            assert!(approximate.distortion >= exact - 1e-9 && approximate.distortion - approximate.error <= exact + 1e-9);
+        }
+    }
 }
@@ -1,11 +1,45 @@
-use crate::centers::Centers;
+use crate::ball_tree::BallTree;
+use crate::bounds::{Elkan, Hamerly};
+use crate::builder::KMeansBuilder;
+use crate::clustering::Clustering;
+use crate::error::{check_rows, ClusterError, PredictError};
+use crate::estimator::{Clusterer, Predict};
+use crate::flow;
+#[cfg(feature = "gpu")]
+use crate::gpu::GpuAssigner;
+use crate::centers::{Centers, Pruning};
+use crate::hyper_rectangle::HyperRectangle;
+use crate::initialization::{self, Initialization};
+use crate::metric::{Euclidean, Metric};
 use crate::mrkd::Tree;
 use crate::point::Point;
-use rand::{Rng, SeedableRng};
-use rand::rngs::StdRng;
+use crate::seeding::SeedSequence;
+use crate::summation::CenterSums;
+use crate::voronoi;
+use rand::Rng;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::ControlFlow;
+use std::time::Instant;
+
+/// Number of low mantissa bits ignored when comparing center sets for cycle detection.
+const CYCLE_ROUNDING_BITS: u32 = 20;
+
+/// Number of points that are summed together in the assignment step of [`Algorithm::Naive`].
+const ASSIGN_CHUNK_SIZE: usize = 1024;
+
+/// Number of points from which [`KMeans::predict_many`] builds a tree over the points.
+const PREDICT_TREE_SIZE: usize = 4096;
 
 /// Algorithm used for [k-means clustering](https://en.wikipedia.org/wiki/K-means_clustering).
 ///
+/// The exact algorithms assign the points to the same centers, and sum the points of every center
+/// with compensated summation, whether one by one, by the nodes of a tree, or in parallel chunks.
+/// For the same random state, they therefore find the same centers, up to the rare sums that lie
+/// within a rounding error of the midpoint between two floating-point numbers.
+///
 /// # References
 ///
 /// Lloyd, S. (1982). Least squares quantization in PCM. IEEE Transactions on Information Theory,
@@ -14,127 +48,1388 @@ use rand::rngs::StdRng;
 /// Pelleg, D., & Moore, A. (1999). Accelerating exact k-means algorithms with geometric reasoning.
 ///     Proceedings of the Fifth ACM SIGKDD International Conference
 ///     on Knowledge Discovery and Data Mining, 277–281. <https://doi.org/10.1145/312129.312248>
-#[derive(PartialEq)]
+///
+/// Elkan, C. (2003). Using the triangle inequality to accelerate k-means. Proceedings of the
+///     Twentieth International Conference on Machine Learning, 147–153.
+///
+/// Hamerly, G. (2010). Making k-means even faster. Proceedings of the 2010 SIAM International
+///     Conference on Data Mining, 130–140. <https://doi.org/10.1137/1.9781611972801.12>
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Algorithm {
     /// Use Lloyd's algorithm (Lloyd, 1982) as described in (Pelleg & Moore, 1999).
     Naive,
     /// Use the "simple" algorithm described in (Pelleg & Moore).
-    Simple
+    Simple,
+    /// Use the "blacklisting" algorithm described in (Pelleg & Moore), which carries the list of
+    /// centers that can still own points down the tree.
+    Blacklist,
+    /// Use Elkan's algorithm (Elkan, 2003), which skips distance computations using the triangle
+    /// inequality. Unlike the tree-based algorithms, it remains effective in many dimensions.
+    Elkan,
+    /// Use Hamerly's algorithm (Hamerly, 2010), which keeps a single lower bound per point instead
+    /// of one per center like [`Algorithm::Elkan`]. It needs far less memory on many points, and is
+    /// often faster for a moderate number of clusters.
+    Hamerly,
+    /// Use the "blacklisting" algorithm on a [`BallTree`] instead of an mrkd-tree, for data with
+    /// more than roughly 15 dimensions, where the hyper-rectangles of a kd-tree no longer prune.
+    BallTree,
+    /// Pick one of the other algorithms based on the number of points, dimensions and clusters,
+    /// see [`Algorithm::resolve`].
+    Auto
+}
+
+impl Algorithm {
+    /// The algorithm that [`Algorithm::Auto`] picks to fit `k` clusters on `r` points with `m`
+    /// dimensions; other algorithms are returned as is. The rules follow the `algorithms`
+    /// benchmark:
+    ///
+    /// - With few dimensions and many clusters, [`Algorithm::Blacklist`], as the hyper-rectangles
+    ///   of the mrkd-tree prune well, and building the tree pays off over enough points.
+    /// - With more dimensions and several clusters, [`Algorithm::Hamerly`], or
+    ///   [`Algorithm::Elkan`] from 16 dimensions on, where its tighter bounds pay off, as long as
+    ///   its bounds of r * k distances fit in about 1 GiB.
+    /// - Otherwise [`Algorithm::Naive`], which has no overhead.
+    ///
+    /// Time complexity: O(1)
+    pub fn resolve(self, r: usize, m: usize, k: usize) -> Algorithm {
+        if self != Algorithm::Auto {
+            return self
+        }
+        if m <= 4 && k >= 8 && r >= 1000 {
+            Algorithm::Blacklist
+        } else if m >= 16 && k >= 4 && r.saturating_mul(k) <= 1 << 27 {
+            Algorithm::Elkan
+        } else if m >= 8 && k >= 4 {
+            Algorithm::Hamerly
+        } else {
+            Algorithm::Naive
+        }
+    }
+}
+
+/// The spatial index shared by the runs of a fit.
+#[derive(Clone, Copy)]
+enum Index<'a, const M: usize> {
+    Kd(&'a Tree<M>),
+    Ball(&'a BallTree<M>)
+}
+
+impl<'a, const M: usize> Index<'a, M> {
+    /// The mrkd-tree, which the tree-based algorithms other than [`Algorithm::BallTree`] use.
+    fn kd(self) -> &'a Tree<M> {
+        match self {
+            Index::Kd(tree) => tree,
+            Index::Ball(_) => unreachable!("the ball tree is only built for Algorithm::BallTree")
+        }
+    }
+
+    /// The ball tree, which [`Algorithm::BallTree`] uses.
+    fn ball(self) -> &'a BallTree<M> {
+        match self {
+            Index::Ball(tree) => tree,
+            Index::Kd(_) => unreachable!("the ball tree is built for Algorithm::BallTree")
+        }
+    }
+}
+
+/// The spatial index of a fit, built for it or borrowed from the caller.
+enum IndexStore<'a, const M: usize> {
+    None,
+    Borrowed(Index<'a, M>),
+    Kd(Tree<M>),
+    Ball(BallTree<M>)
+}
+
+impl<'a, const M: usize> IndexStore<'a, M> {
+    /// Build the index that `algorithm` or the initialization of `config` needs, if any.
+    ///
+    /// Time complexity: O(r * log(r) * M)
+    fn build<const K: usize, D>(points: &[Point<M>], weights: &[f64], config: &KMeansBuilder<K, M, D>, algorithm: Algorithm, rng: &mut impl Rng) -> Self {
+        let needs_tree = matches!(algorithm, Algorithm::Simple | Algorithm::Blacklist | Algorithm::BallTree)
+            || (config.initial_centers.is_none() && matches!(config.initialization, Initialization::DensityFiltered { .. }));
+        if !needs_tree {
+            IndexStore::None
+        } else if algorithm == Algorithm::BallTree {
+            IndexStore::Ball(BallTree::with_leaf_size(points, weights, config.max_leaf_size, rng))
+        } else {
+            IndexStore::Kd(Tree::with_leaf_size(points, weights, config.max_leaf_size, rng))
+        }
+    }
+
+    fn get(&self) -> Option<Index<'_, M>> {
+        match self {
+            IndexStore::None => Option::None,
+            IndexStore::Borrowed(index) => Option::Some(*index),
+            IndexStore::Kd(tree) => Option::Some(Index::Kd(tree)),
+            IndexStore::Ball(tree) => Option::Some(Index::Ball(tree))
+        }
+    }
 }
 
+/// What to do with a center that no point is assigned to during fitting.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum EmptyClusterPolicy {
+    /// Keep the center where it was.
+    #[default]
+    Keep,
+    /// Move the center to the point farthest from its closest center, which typically splits the
+    /// cluster with the largest spread.
+    ReseedFarthest,
+    /// Drop the cluster: its center is set to NaN, so that no point is assigned to it anymore.
+    Drop
+}
+
+/// The trajectory of the centers while fitting, recorded when enabled with
+/// [`KMeansBuilder::record_history`].
+///
+/// With feature weights, the centers are recorded in the weighted space, see
+/// [`KMeans::feature_weights`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FitHistory<const K: usize, const M: usize> {
+    /// The centers before the first iteration and after every iteration.
+    pub centers: Vec<[Point<M>; K]>,
+    /// The largest movement of a center in every iteration.
+    pub shifts: Vec<f64>
+}
+
+/// How [`Algorithm::Simple`] and [`Algorithm::Blacklist`] handled the nodes of the mrkd-tree in
+/// an iteration. The more nodes are owned, and the fewer points are compared one by one, the more
+/// the tree helps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NodeCounts {
+    /// The nodes whose points were all assigned to one center at once, including nodes assigned
+    /// approximately.
+    pub owned: usize,
+    /// The non-leaf nodes that were split into their children.
+    pub descended: usize,
+    /// The leaf nodes whose points were compared to the centers one by one.
+    pub leaves: usize,
+    /// The points in those leaf nodes.
+    pub points: usize
+}
+
+/// Statistics on how the mrkd-tree accelerated the fit, recorded when enabled with
+/// [`KMeansBuilder::record_diagnostics`], for example to check whether [`Algorithm::Simple`] is
+/// faster than [`Algorithm::Naive`] on the data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FitDiagnostics {
+    /// The algorithm that was used, after resolving [`Algorithm::Auto`] and falling back for
+    /// metrics and constraints that the accelerated algorithms do not support.
+    pub algorithm: Algorithm,
+    /// The number of edges on the longest path from the root to a leaf of the mrkd-tree, or 0 if
+    /// the algorithm does not use it.
+    pub tree_depth: usize,
+    /// The number of nodes in the mrkd-tree, or 0 if the algorithm does not use it.
+    pub tree_nodes: usize,
+    /// The number of leaf nodes in the mrkd-tree, or 0 if the algorithm does not use it.
+    pub tree_leaves: usize,
+    /// How the nodes were handled in every iteration, if the algorithm uses the mrkd-tree.
+    pub iterations: Vec<NodeCounts>
+}
+
+/// The state of a fit after an iteration, passed to the callback of
+/// [`KMeansBuilder::fit_with_callback`] and yielded by [`KMeansIter`].
+///
+/// With feature weights, the centers and inertia are in the weighted space, see
+/// [`KMeans::feature_weights`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct IterationState<const K: usize, const M: usize> {
+    /// The index of the run, below the `n_init` of the builder.
+    pub run: usize,
+    /// The number of iterations performed in this run so far, starting at 1.
+    pub iteration: usize,
+    /// The centers after the iteration.
+    pub centers: [Point<M>; K],
+    /// The inertia when assigning every point to the closest of the centers.
+    pub inertia: f64,
+    /// The largest movement of a center in the iteration.
+    pub max_shift: f64,
+    /// The distortion of the points with respect to the centers before the iteration, computed
+    /// from the statistics cached in the mrkd-tree by [`Algorithm::Simple`] and
+    /// [`Algorithm::Blacklist`], or `None` for the other algorithms. With an approximation, see
+    /// [`KMeansBuilder::approximation`] and [`KMeansBuilder::max_node_movement`], this is an upper
+    /// bound.
+    pub distortion: Option<f64>,
+    /// An upper bound on how much `distortion` exceeds the exact distortion, which is 0 without
+    /// an approximation.
+    pub distortion_error: Option<f64>
+}
+
+/// A callback that observes every iteration of a fit, and can stop the run it is in.
+pub(crate) type Observer<'a, const K: usize, const M: usize> = Option<&'a mut (dyn FnMut(&IterationState<K, M>) -> ControlFlow<()> + Send)>;
+
 /// Implements [k-means clustering](https://en.wikipedia.org/wiki/K-means_clustering).
-pub struct KMeans<const K: usize, const M: usize, const R: usize> {
+///
+/// The number of clusters `K` and the number of dimensions `M` are fixed at compile time, but
+/// the number of points is only known at runtime. Points are assigned to centers using the
+/// [`Metric`] `D`, which defaults to [`Euclidean`] distance.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KMeans<const K: usize, const M: usize, D = Euclidean> {
     /// The coordinates of the k means.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::array"))]
     pub centers: [Point<M>; K],
     /// The index of the center that each point belongs to.
-    pub point_centers: [usize; R]
+    pub point_centers: Vec<usize>,
+    /// The weight of each dimension in the squared Euclidean distance used to fit the model.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::array"))]
+    pub feature_weights: [f64; M],
+    /// The names of the dimensions, if known.
+    pub feature_names: Option<Vec<String>>,
+    /// The metric used to assign points to centers.
+    pub metric: D,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the centers converged within the maximum number of iterations.
+    pub converged: bool,
+    /// Whether fitting stopped early because the time budget ran out or it was cancelled.
+    pub truncated: bool,
+    /// The trajectory of the centers of the kept run, if recorded. It is not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub history: Option<FitHistory<K, M>>,
+    /// Statistics on the mrkd-tree in the kept run, if recorded. They are not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub diagnostics: Option<FitDiagnostics>,
+    inertia: f64
 }
 
-impl<const K: usize, const M: usize, const R: usize> KMeans<K, M, R> {
-    /// Get k clusters based on `points`.
-    pub fn fit(points: &[Point<M>; R], algorithm: Algorithm) -> Self {
-        Self::new(points, algorithm, Option::None)
+impl<const K: usize, const M: usize, D: Metric> KMeans<K, M, D> {
+    /// Configure a model before fitting it.
+    pub fn builder() -> KMeansBuilder<K, M, D> {
+        KMeansBuilder::new()
+    }
+
+    /// Get k clusters based on `points`, or an error if they have NaN or infinite coordinates or
+    /// there are fewer than k distinct points.
+    pub fn fit(points: &[Point<M>], algorithm: Algorithm) -> Result<Self, ClusterError> {
+        Self::builder().algorithm(algorithm).fit(points)
     }
 
     /// Get k clusters based on `points` with a pre-determined random state.
-    pub fn fit_with_random_state(points: &[Point<M>; R], algorithm: Algorithm, random_state: u64) -> Self {
-        Self::new(points, algorithm, Option::Some(random_state))
+    pub fn fit_with_random_state(points: &[Point<M>], algorithm: Algorithm, random_state: u64) -> Result<Self, ClusterError> {
+        Self::builder().algorithm(algorithm).random_state(random_state).fit(points)
+    }
+
+    /// Get k clusters based on `points`, keeping the best of `n_init` runs with different initial
+    /// centers.
+    pub fn fit_n(points: &[Point<M>], algorithm: Algorithm, n_init: usize) -> Result<Self, ClusterError> {
+        Self::builder().algorithm(algorithm).n_init(n_init).fit(points)
+    }
+
+    /// Get k clusters based on `points`, where each point has a weight in the centers of mass, or
+    /// an error if the weights do not match the points or are negative or not finite.
+    pub fn fit_weighted(points: &[Point<M>], weights: &[f64], algorithm: Algorithm) -> Result<Self, ClusterError> {
+        Self::builder().algorithm(algorithm).fit_weighted(points, weights)
     }
 
-    fn new(points: &[Point<M>; R], algorithm: Algorithm, random_state: Option<u64>) -> Self {
+    /// Get k clusters based on `points`, starting from `centers` instead of picking initial
+    /// centers, see [`KMeansBuilder::initial_centers`].
+    pub fn fit_with_centers(points: &[Point<M>], centers: [Point<M>; K], algorithm: Algorithm) -> Result<Self, ClusterError> {
+        Self::builder().algorithm(algorithm).initial_centers(centers).fit(points)
+    }
+
+    /// Fit the model again on `points` with `config`, starting from the current centers instead of
+    /// picking initial centers, see [`KMeansBuilder::initial_centers`]. When the points changed only
+    /// slightly since the model was fitted, this typically converges in a few iterations.
+    pub fn refit(&self, points: &[Point<M>], config: &KMeansBuilder<K, M, D>) -> Result<Self, ClusterError> {
+        config.clone().initial_centers(self.centers).fit(points)
+    }
+
+    /// Fit a model, reusing `tree` if it was built over `points` and `weights` already.
+    pub(crate) fn new(points: &[Point<M>], weights: &[f64], config: &KMeansBuilder<K, M, D>, observer: Observer<K, M>, tree: Option<&Tree<M>>) -> Self {
         // Initialize randomness
-        let mut rng = match random_state {
-            Option::Some(seed) => StdRng::seed_from_u64(seed),
-            None => StdRng::from_entropy()
+        let seeds = SeedSequence::from_random_state(config.random_state);
+        let deadline = config.time_budget.map(|budget| Instant::now() + budget);
+
+        let feature_weights = config.feature_weights();
+        if feature_weights.iter().all(|&w| w == 1.0) {
+            return Self::best_of_n(points, weights, config, seeds, deadline, observer, tree)
+        }
+
+        // Weighting the squared Euclidean distance is equivalent to scaling the dimensions by the
+        // square root of the weights, which keeps the geometric reasoning of the tree valid, but
+        // needs a tree over the scaled points
+        let (scaled_points, config) = Self::scale(points, Cow::Borrowed(config), &feature_weights);
+        let model = Self::best_of_n(&scaled_points, weights, &config, seeds, deadline, observer, Option::None);
+        Self::unscale(model, points, weights, feature_weights)
+    }
+
+    /// Scale the points and the initial centers by the square root of the feature weights.
+    ///
+    /// Time complexity: O(r * M)
+    fn scale<'a>(points: &[Point<M>], mut config: Cow<'a, KMeansBuilder<K, M, D>>, feature_weights: &[f64; M]) -> (Vec<Point<M>>, Cow<'a, KMeansBuilder<K, M, D>>) {
+        let scale = feature_weights.map(f64::sqrt);
+        if let Option::Some(centers) = config.initial_centers {
+            config.to_mut().initial_centers = Option::Some(centers.map(|center| center.component_mul(&scale)));
+        }
+        (points.iter().map(|point| point.component_mul(&scale)).collect(), config)
+    }
+
+    /// Compute the centers of a model fitted on scaled points as the centers of mass of the
+    /// original `points`.
+    ///
+    /// Time complexity: O(r * M)
+    fn unscale(mut model: Self, points: &[Point<M>], weights: &[f64], feature_weights: [f64; M]) -> Self {
+        let scale = feature_weights.map(f64::sqrt);
+        let mut centers = [Point::default(); K];
+        let mut counts = [0.0; K];
+        for ((point, &w), &k) in points.iter().zip(weights).zip(&model.point_centers) {
+            centers[k] += *point * w;
+            counts[k] += w;
+        }
+        for k in 0..K {
+            centers[k] = if counts[k] == 0.0 {
+                // Scale empty centers back, leaving dimensions without weight at 0
+                let mut coords = [0.0; M];
+                for d in 0..M {
+                    if scale[d] > 0.0 {
+                        coords[d] = model.centers[k].0[d] / scale[d];
+                    }
+                }
+                Point(coords)
+            } else {
+                centers[k] / counts[k]
+            };
+        }
+
+        model.centers = centers;
+        model.feature_weights = feature_weights;
+        model
+    }
+
+    /// Run the algorithm `n_init` times and keep the model with the lowest inertia. The first run
+    /// uses the random stream of `seeds` itself, and every restart `i` the stream of child `i`. No
+    /// restarts are started after the deadline.
+    ///
+    /// The mrkd-tree, or the ball tree for [`Algorithm::BallTree`], is built once from child 0,
+    /// unless an mrkd-tree is given, and shared by all runs, which are executed concurrently when
+    /// parallelism is enabled. The seeds of the runs do not depend on the scheduling, so the
+    /// result is the same with and without parallelism. With an observer, the runs are executed
+    /// one after another.
+    fn best_of_n(points: &[Point<M>], weights: &[f64], config: &KMeansBuilder<K, M, D>, seeds: SeedSequence, deadline: Option<Instant>, observer: Observer<K, M>, tree: Option<&Tree<M>>) -> Self {
+        let algorithm = Self::effective_algorithm(config, points.len());
+        let n_init = if config.initial_centers.is_some() { 1 } else { config.n_init.max(1) };
+        let store = match tree {
+            Option::Some(tree) if algorithm != Algorithm::BallTree => IndexStore::Borrowed(Index::Kd(tree)),
+            _ => IndexStore::build(points, weights, config, algorithm, &mut seeds.child(0).rng())
+        };
+        let index = store.get();
+
+        let run = |i: usize, observer: Observer<K, M>| {
+            if i > 0 && (config.cancelled() || deadline.is_some_and(|deadline| Instant::now() >= deadline)) {
+                return Option::None
+            }
+            let mut rng = if i == 0 { seeds.rng() } else { seeds.child(i as u64).rng() };
+            Option::Some(Self::lloyd(points, weights, config, index, &mut rng, deadline, observer))
+        };
+        let models: Vec<Option<Self>> = match observer {
+            // Tell the observer which run every iteration belongs to
+            Option::Some(observer) => (0..n_init).map(|i| {
+                run(i, Option::Some(&mut |state: &IterationState<K, M>| observer(&IterationState { run: i, ..state.clone() })))
+            }).collect(),
+            #[cfg(feature = "rayon")]
+            None => {
+                use rayon::prelude::*;
+                (0..n_init).into_par_iter().map(|i| run(i, Option::None)).collect()
+            },
+            #[cfg(not(feature = "rayon"))]
+            None => (0..n_init).map(|i| run(i, Option::None)).collect()
         };
 
-        // Initialize centers
-        let mut centers = Centers::new(Self::random_points(points, &mut rng));
+        // Keep the first of the models with the lowest inertia
+        let truncated = models.iter().any(Option::is_none);
+        let mut best = models.into_iter()
+            .flatten()
+            .reduce(|best, model| if model.inertia < best.inertia { model } else { best })
+            .unwrap();
+        best.truncated |= truncated;
+        best
+    }
 
-        // Initialize tree when necessary
-        let tree = match algorithm {
-            Algorithm::Simple => Option::Some(Tree::initialize(points, &mut rng)),
-            Algorithm::Naive => Option::None
+    /// The algorithm to run on `r` points: the geometric reasoning of the accelerated algorithms
+    /// only holds for Euclidean distance and assignments to the closest center.
+    fn effective_algorithm(config: &KMeansBuilder<K, M, D>, r: usize) -> Algorithm {
+        if D::EUCLIDEAN_ORDER && config.cluster_sizes.is_none() {
+            config.algorithm.resolve(r, M, K)
+        } else {
+            Algorithm::Naive
+        }
+    }
+
+    fn lloyd(points: &[Point<M>], weights: &[f64], config: &KMeansBuilder<K, M, D>, index: Option<Index<M>>, rng: &mut impl Rng, deadline: Option<Instant>, mut observer: Observer<K, M>) -> Self {
+        let index = match index {
+            Option::Some(index) => IndexStore::Borrowed(index),
+            None => IndexStore::None
         };
+        let mut run = KMeansIter::start(Cow::Borrowed(points), Cow::Borrowed(weights), Cow::Borrowed(config), index, rng);
 
-        // Update centers
+        let mut truncated = false;
+        let mut history = if config.record_history {
+            Option::Some(FitHistory { centers: vec![run.centers.0], shifts: Vec::new() })
+        } else {
+            Option::None
+        };
+        let mut diagnostics = if config.record_diagnostics {
+            let tree = match run.algorithm {
+                Algorithm::Simple | Algorithm::Blacklist => Option::Some(run.index.get().unwrap().kd()),
+                _ => Option::None
+            };
+            Option::Some(FitDiagnostics {
+                algorithm: run.algorithm,
+                tree_depth: tree.map_or(0, Tree::depth),
+                tree_nodes: tree.map_or(0, Tree::number_of_nodes),
+                tree_leaves: tree.map_or(0, Tree::number_of_leaves),
+                iterations: Vec::new()
+            })
+        } else {
+            Option::None
+        };
+        let mut seen = HashMap::new();
+        let mut visited = Vec::new();
+        let metric = &config.metric;
         loop {
-            let mut point_centers = [0; R];
-            let mut new_centers = [Point::default(); K];
-            let mut new_counts = [0; K];
-
-            match algorithm {
-                Algorithm::Simple => {
-                    // Use Update(h, C)
-                    let updated = centers.update(&tree.as_ref().unwrap());
-                    new_centers = updated.0;
-                    new_counts = updated.1;
-                },
-                Algorithm::Naive => {
-                    // For each data point
-                    for i in 0..R {
-                        // Find the closest center
-                        let mut min_d = f64::INFINITY;
-                        let mut min_c = 0;
-                        for k in 0..K {
-                            let d = centers.0[k].distance(&points[i]);
-                            if d < min_d {
-                                min_d = d;
-                                min_c = k;
-                            }
-                        }
-
-                        // Update the center associated with the data point
-                        point_centers[i] = min_c;
-
-                        // Update the center of mass
-                        new_centers[min_c] = new_centers[min_c] + points[i];
-                        new_counts[min_c] = new_counts[min_c] + 1;
-                    }
-                }
+            let start = Instant::now();
+            let shift = match run.step() {
+                Option::Some(shift) => shift,
+                None => break
+            };
+            if let Option::Some(history) = history.as_mut() {
+                history.centers.push(run.centers.0);
+                history.shifts.push(shift);
+            }
+            if let (Option::Some(diagnostics), Option::Some(nodes)) = (diagnostics.as_mut(), run.nodes) {
+                diagnostics.iterations.push(nodes);
+            }
+
+            // Report the iteration, and let the observer stop early
+            let stopped = observer.as_mut().is_some_and(|observer| observer(&run.state()).is_break());
+
+            // If all centers are converged, stop
+            if run.converged() || stopped {
+                break;
+            }
+
+            // If the centers returned to an earlier configuration, they will keep oscillating, so
+            // stop with the configuration of the cycle with the lowest inertia
+            let hash = Self::rounded_hash(&run.centers.0);
+            if let Option::Some(&cycle_start) = seen.get(&hash) {
+                let cycle: &[[Point<M>; K]] = &visited[cycle_start..];
+                run.centers.0 = *cycle.iter()
+                    .min_by(|a, b| Self::inertia_of(points, weights, a, metric).total_cmp(&Self::inertia_of(points, weights, b, metric)))
+                    .unwrap();
+                break;
             }
+            seen.insert(hash, visited.len());
+            visited.push(run.centers.0);
 
-            // For each new center
-            let mut different = false;
-            for k in 0..K {
-                // Finalize updating the centers of mass
-                let center = new_centers[k];
-                let count = new_counts[k];
-                let new_center = if count == 0 {
-                    center
-                } else {
-                    center / count
-                };
-
-                // Check whether convergence is reached
-                if centers.0[k] != new_center {
-                    different = true;
+            // Stop when cancelled, or at the iteration boundary closest to the deadline
+            if config.cancelled() {
+                truncated = true;
+                break;
+            }
+            if let Option::Some(deadline) = deadline {
+                let now = Instant::now();
+                if now + (now - start) / 2 >= deadline {
+                    truncated = true;
+                    break;
                 }
-                centers.0[k] = new_center;
             }
+        }
 
+        let mut model = run.finish();
+        model.truncated = truncated;
+        model.history = history;
+        model.diagnostics = diagnostics;
+        model
+    }
 
-            // If all centers are converged, return
-            if !different {
-                if algorithm == Algorithm::Simple {
-                    // Get point centers
-                    for i in 0..R {
-                        point_centers[i] = centers.closest(&points[i]);
-                    }
+    /// Hash of the centers with the lowest bits of every coordinate ignored, so that center sets
+    /// that only differ by rounding errors hash the same.
+    ///
+    /// Time complexity: O(k * M)
+    fn rounded_hash(centers: &[Point<M>; K]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for center in centers {
+            for x in center.0 {
+                (x.to_bits() >> CYCLE_ROUNDING_BITS).hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// The inertia when assigning every point to the closest of `centers`.
+    ///
+    /// Time complexity: O(r * k * M)
+    fn inertia_of(points: &[Point<M>], weights: &[f64], centers: &[Point<M>; K], metric: &D) -> f64 {
+        let centers = Centers::new(*centers);
+        points.iter()
+            .zip(weights)
+            .map(|(point, &w)| w * metric.cost(point, &centers.0[centers.closest_by(point, metric)]))
+            .sum()
+    }
+
+    /// The point with the largest distance to its closest center.
+    ///
+    /// Time complexity: O(r * k * M)
+    fn farthest_point(points: &[Point<M>], centers: &Centers<K, M>, metric: &D) -> Point<M> {
+        let mut max_d = f64::NEG_INFINITY;
+        let mut farthest = points[0];
+        for point in points {
+            let d = metric.distance(point, &centers.0[centers.closest_by(point, metric)]);
+            if d > max_d {
+                max_d = d;
+                farthest = *point;
+            }
+        }
+        farthest
+    }
+
+    /// Assign the points to the centers such that every center gets between `min` and `max`
+    /// points, minimizing the total weighted cost, and return the weighted sums and the total
+    /// weights of the points per center. This is a transportation problem with a unit of supply
+    /// per point, solved like in [`crate::CapacitatedKMeans`]. To enforce the minimum, every center
+    /// is split into a sink for its first `min` points, which is cheaper than any assignment to
+    /// the other sinks, and a sink for up to `max - min` more points. As the supplies and
+    /// capacities are whole numbers, no point is split over multiple centers.
+    ///
+    /// Time complexity: O(r * k^2 * log(r * k))
+    fn assign_balanced(points: &[Point<M>], weights: &[f64], centers: &Centers<K, M>, metric: &D, (min, max): (usize, usize), point_centers: &mut [usize]) -> ([Point<M>; K], [f64; K]) {
+        let cost: Vec<Vec<f64>> = points.iter()
+            .zip(weights)
+            .map(|(point, &w)| centers.0.iter().map(|center| w * metric.cost(point, center)).collect())
+            .collect();
+        let bonus = 1.0 + cost.iter().flatten().fold(0.0, |a: f64, &b| a.max(b));
+        let cost: Vec<Vec<f64>> = cost.iter()
+            .map(|row| row.iter().map(|c| c - bonus).chain(row.iter().copied()).collect())
+            .collect();
+        let capacity: Vec<f64> = (0..2 * K).map(|j| if j < K { min } else { max - min } as f64).collect();
+        let flow = flow::transport(&vec![1.0; points.len()], &capacity, &cost);
+
+        let mut new_centers = [Point::default(); K];
+        let mut new_counts = [0.0; K];
+        for (((point, &w), point_center), shares) in points.iter().zip(weights).zip(point_centers.iter_mut()).zip(&flow) {
+            let k = (0..2 * K).max_by(|&a, &b| shares[a].total_cmp(&shares[b])).unwrap() % K;
+            *point_center = k;
+            new_centers[k] += *point * w;
+            new_counts[k] += w;
+        }
+        (new_centers, new_counts)
+    }
+
+    /// Assign each point to the closest center, and return the weighted sums and the total weights
+    /// of the points per center.
+    ///
+    /// The points are summed in chunks of a fixed size, which are processed in parallel when
+    /// parallelism is enabled, and the sums of the chunks are added in order. The result therefore
+    /// does not depend on the number of threads, nor on whether parallelism is enabled.
+    ///
+    /// Time complexity: O(r * k * M / p) with p threads
+    fn assign(points: &[Point<M>], weights: &[f64], centers: &Centers<K, M>, metric: &D, point_centers: &mut [usize]) -> ([Point<M>; K], [f64; K]) {
+        let chunk = |((points, weights), point_centers): ((&[Point<M>], &[f64]), &mut [usize])| {
+            let mut sums = CenterSums::default();
+
+            // For each data point
+            for ((point, &w), point_center) in points.iter().zip(weights).zip(point_centers.iter_mut()) {
+                // Find the closest center
+                let k = centers.closest_by(point, metric);
+
+                // Update the center associated with the data point
+                *point_center = k;
+
+                // Update the center of mass
+                sums.add(k, point, w);
+            }
+
+            sums
+        };
+
+        #[cfg(feature = "rayon")]
+        let sums: Vec<CenterSums<K, M>> = {
+            use rayon::prelude::*;
+            points.par_chunks(ASSIGN_CHUNK_SIZE)
+                .zip(weights.par_chunks(ASSIGN_CHUNK_SIZE))
+                .zip(point_centers.par_chunks_mut(ASSIGN_CHUNK_SIZE))
+                .map(chunk)
+                .collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let sums: Vec<CenterSums<K, M>> = points.chunks(ASSIGN_CHUNK_SIZE)
+            .zip(weights.chunks(ASSIGN_CHUNK_SIZE))
+            .zip(point_centers.chunks_mut(ASSIGN_CHUNK_SIZE))
+            .map(chunk)
+            .collect();
+
+        // Merge the compensated sums of the chunks, such that the result hardly depends on how the
+        // points are split up
+        let mut total = CenterSums::default();
+        for sums in &sums {
+            total.merge(sums);
+        }
+        total.values()
+    }
+
+    /// The coordinates of the k means. For a given random state, version of this crate and
+    /// configuration, the centers are always in the same order, but the order depends on the
+    /// initialization; use [`KMeans::sort_centers`] for an order that does not.
+    pub fn centers(&self) -> &[Point<M>; K] {
+        &self.centers
+    }
+
+    /// The index of the center that each point belongs to. A point that is equally close to
+    /// multiple centers belongs to the one with the lowest index, regardless of the algorithm.
+    pub fn labels(&self) -> &[usize] {
+        &self.point_centers
+    }
+
+    /// Sort the centers lexicographically by their coordinates, and relabel the points and the
+    /// recorded history to match, such that equivalent models have the same labels. Points that
+    /// become equally close to a center with a lower index are not reassigned.
+    ///
+    /// Time complexity: O(r + k * log(k) * M)
+    pub fn sort_centers(self) -> Self {
+        let mut order: [usize; K] = std::array::from_fn(|k| k);
+        order.sort_by(|&a, &b| {
+            self.centers[a].0.iter().zip(&self.centers[b].0)
+                .map(|(x, y)| x.total_cmp(y))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        self.permute(order)
+    }
+
+    /// Renumber the clusters by decreasing size, such that cluster 0 is the largest, and equally
+    /// large clusters keep their relative order. See [`KMeans::sort_centers`].
+    ///
+    /// Time complexity: O(r + k * log(k))
+    pub fn relabel_by_size(self) -> Self {
+        let sizes = self.cluster_sizes();
+        let mut order: [usize; K] = std::array::from_fn(|k| k);
+        order.sort_by_key(|&k| std::cmp::Reverse(sizes[k]));
+        self.permute(order)
+    }
+
+    /// Renumber the clusters to agree with `reference_labels` on as many points as possible, for
+    /// example the labels of a previous model, such that a cluster keeps its number after
+    /// refitting. Points with a reference label of K or higher are ignored, and clusters without
+    /// a match get the remaining numbers. See [`crate::metrics::match_labels`].
+    ///
+    /// Time complexity: O(r + k^3)
+    ///
+    /// # Panics
+    ///
+    /// If the number of reference labels differs from the number of points the model was fitted
+    /// on.
+    pub fn relabel_to_match(self, reference_labels: &[usize]) -> Self {
+        assert_eq!(reference_labels.len(), self.point_centers.len(), "the number of labels differs from the number of points the model was fitted on");
+        let mut cost = vec![vec![0.0; K]; K];
+        for (&reference, &k) in reference_labels.iter().zip(&self.point_centers) {
+            if reference < K {
+                cost[k][reference] -= 1.0;
+            }
+        }
+        let mapping = flow::assignment(&cost);
+        let mut order = [0; K];
+        for (k, &label) in mapping.iter().enumerate() {
+            order[label] = k;
+        }
+        self.permute(order)
+    }
+
+    /// Renumber the clusters such that cluster `order[k]` becomes cluster k.
+    ///
+    /// Time complexity: O(r + k * M)
+    fn permute(mut self, order: [usize; K]) -> Self {
+        let mut labels = [0; K];
+        for (k, &old) in order.iter().enumerate() {
+            labels[old] = k;
+        }
+
+        self.centers = order.map(|k| self.centers[k]);
+        for point_center in &mut self.point_centers {
+            *point_center = labels[*point_center];
+        }
+        if let Option::Some(history) = &mut self.history {
+            for centers in &mut history.centers {
+                *centers = order.map(|k| centers[k]);
+            }
+        }
+        self
+    }
+
+    /// Merge clusters `a` and `b` of `points`, the points the model was fitted on, into a model
+    /// with L = K - 1 clusters. The merged cluster takes the lower of the two numbers, and its
+    /// center is the mean of its points; the clusters after the higher number move down by one.
+    /// As the weights of the points are not known, the inertia is recomputed without them.
+    ///
+    /// Time complexity: O(r * M)
+    ///
+    /// # Panics
+    ///
+    /// If L is not K - 1, `a` and `b` are the same or not below K, or the number of points differs
+    /// from the number of points the model was fitted on.
+    pub fn merge_clusters<const L: usize>(&self, points: &[Point<M>], a: usize, b: usize) -> KMeans<L, M, D> {
+        assert_eq!(L + 1, K, "merging two clusters leaves K - 1 clusters");
+        assert!(a != b && a < K && b < K, "cannot merge clusters {} and {} of {}", a, b, K);
+        assert_eq!(points.len(), self.point_centers.len(), "the number of points differs from the number of points the model was fitted on");
+        let (low, high) = (a.min(b), a.max(b));
+        let label = |k: usize| if k == high { low } else if k > high { k - 1 } else { k };
+
+        let mut centers: [Point<M>; L] = std::array::from_fn(|k| self.centers[if k < high { k } else { k + 1 }]);
+        let point_centers: Vec<usize> = self.point_centers.iter().map(|&k| label(k)).collect();
+        let mut sum = Point::default();
+        let mut count = 0.0;
+        for (point, &k) in points.iter().zip(&point_centers) {
+            if k == low {
+                sum += *point;
+                count += 1.0;
+            }
+        }
+        if count > 0.0 {
+            centers[low] = sum / count;
+        }
+
+        let scale = self.feature_weights.map(f64::sqrt);
+        let inertia = points.iter()
+            .zip(&point_centers)
+            .map(|(point, &k)| self.metric.cost(&point.component_mul(&scale), &centers[k].component_mul(&scale)))
+            .sum();
+
+        KMeans {
+            centers,
+            point_centers,
+            feature_weights: self.feature_weights,
+            feature_names: self.feature_names.clone(),
+            metric: self.metric,
+            iterations: self.iterations,
+            converged: self.converged,
+            truncated: self.truncated,
+            history: Option::None,
+            diagnostics: Option::None,
+            inertia
+        }
+    }
+
+    /// The same clustering of `points`, the points the model was fitted on, with `metric` to
+    /// assign points to centers. As the weights of the points are not known, the inertia is
+    /// recomputed without them.
+    ///
+    /// Time complexity: O(r * M)
+    pub(crate) fn with_metric<E: Metric>(self, points: &[Point<M>], metric: E) -> KMeans<K, M, E> {
+        let scale = self.feature_weights.map(f64::sqrt);
+        let inertia = points.iter()
+            .zip(&self.point_centers)
+            .map(|(point, &k)| metric.cost(&point.component_mul(&scale), &self.centers[k].component_mul(&scale)))
+            .sum();
+
+        KMeans {
+            centers: self.centers,
+            point_centers: self.point_centers,
+            feature_weights: self.feature_weights,
+            feature_names: self.feature_names,
+            metric,
+            iterations: self.iterations,
+            converged: self.converged,
+            truncated: self.truncated,
+            history: self.history,
+            diagnostics: self.diagnostics,
+            inertia
+        }
+    }
+
+    /// The number of points that belong to each center.
+    ///
+    /// Time complexity: O(r)
+    pub fn cluster_sizes(&self) -> [usize; K] {
+        let mut sizes = [0; K];
+        for &k in &self.point_centers {
+            sizes[k] += 1;
+        }
+        sizes
+    }
+
+    /// The number of iterations performed while fitting.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// Whether the centers converged within the maximum number of iterations.
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// The indices of the points that belong to center `k`.
+    ///
+    /// Time complexity: O(r)
+    pub fn cluster_points(&self, k: usize) -> impl Iterator<Item = usize> + '_ {
+        self.point_centers.iter().enumerate().filter(move |&(_, &c)| c == k).map(|(i, _)| i)
+    }
+
+    /// Statistics of every cluster of `points`, the points the model was fitted on, in the
+    /// original coordinates and Euclidean distance.
+    ///
+    /// Time complexity: O(r * M)
+    ///
+    /// # Panics
+    ///
+    /// If the number of points differs from the number of points the model was fitted on.
+    pub fn clustering(&self, points: &[Point<M>]) -> Clustering<M> {
+        Clustering::new(points, &self.centers, &self.point_centers)
+            .with_feature_names((0..M).map(|d| self.feature_name(d)))
+    }
+
+    /// The inertia, or within-cluster sum of squares: the sum of the squared distances of the
+    /// points to their centers, computed while fitting. With feature weights, the weighted squared
+    /// Euclidean distance is used, and with point weights, the squared distances are weighted.
+    /// For other metrics, the [`Metric::cost`] of each point is used instead.
+    pub fn inertia(&self) -> f64 {
+        self.inertia
+    }
+
+    /// Time complexity: O(r * M)
+    fn sum_of_squares(points: &[Point<M>], weights: &[f64], centers: &[Point<M>; K], metric: &D, point_centers: &[usize]) -> f64 {
+        points.iter()
+            .zip(weights)
+            .zip(point_centers)
+            .map(|((point, &w), &k)| w * metric.cost(point, &centers[k]))
+            .sum()
+    }
+
+    /// Index of the center closest to `point`.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn predict(&self, point: &Point<M>) -> usize {
+        let (centers, scale) = self.scaled_centers();
+        centers.closest_by(&point.component_mul(&scale), &self.metric)
+    }
+
+    /// Index of the center closest to each of `points`.
+    ///
+    /// For separable metrics and at least 4096 finite points, an mrkd-tree is built over the points
+    /// first, and every hyper-rectangle in which one center dominates the others is assigned to it
+    /// as a whole, as in [`Algorithm::Blacklist`]. This gives the same labels, but typically
+    /// compares far fewer points to far fewer centers.
+    ///
+    /// Time complexity: worst case O(r * k * M)
+    pub fn predict_many(&self, points: &[Point<M>]) -> Vec<usize> {
+        let (centers, scale) = self.scaled_centers();
+        if D::SEPARABLE && points.len() >= PREDICT_TREE_SIZE && points.iter().all(|point| point.0.iter().all(|x| x.is_finite())) {
+            let points: Vec<Point<M>> = points.iter().map(|point| point.component_mul(&scale)).collect();
+            return centers.assign_blacklist(&Tree::new(&points), &self.metric)
+        }
+        points.iter().map(|point| centers.closest_by(&point.component_mul(&scale), &self.metric)).collect()
+    }
+
+    /// Distance of each of `points` to every center, in the metric and feature weights of the
+    /// model, for example to use as features in another model.
+    ///
+    /// Time complexity: O(r * k * M)
+    pub fn transform(&self, points: &[Point<M>]) -> Vec<[f64; K]> {
+        let (centers, scale) = self.scaled_centers();
+        points.iter().map(|point| {
+            let point = point.component_mul(&scale);
+            centers.0.map(|center| self.metric.distance(&point, &center))
+        }).collect()
+    }
+
+    /// Distance of each of `points` to the closest center, in the metric and feature weights of
+    /// the model, as an anomaly score: points far away from every center fit the model poorly.
+    ///
+    /// Time complexity: O(r * k * M)
+    pub fn score_samples(&self, points: &[Point<M>]) -> Vec<f64> {
+        let (centers, scale) = self.scaled_centers();
+        points.iter().map(|point| {
+            let point = point.component_mul(&scale);
+            centers.0.iter().map(|center| self.metric.distance(&point, center)).fold(f64::INFINITY, f64::min)
+        }).collect()
+    }
+
+    /// Distance of each of `points` to the closest center, relative to the root mean square
+    /// distance of the points of that cluster in `fit_points`, the points the model was fitted
+    /// on. This makes scores comparable between tight and wide clusters; for example, a score of
+    /// 3 is three times the typical distance within the cluster. A cluster whose fitted points
+    /// all lie on the center gives a score of 0 on the center and infinity elsewhere.
+    ///
+    /// Time complexity: O((r + r') * k * M) for r' fitted points
+    ///
+    /// # Panics
+    ///
+    /// If the number of fitted points differs from the number of points the model was fitted on.
+    pub fn normalized_scores(&self, fit_points: &[Point<M>], points: &[Point<M>]) -> Vec<f64> {
+        assert_eq!(fit_points.len(), self.point_centers.len(), "the number of points differs from the number of points the model was fitted on");
+        let (centers, scale) = self.scaled_centers();
+        let mut sums = [0.0; K];
+        let mut counts = [0.0; K];
+        for (point, &k) in fit_points.iter().zip(&self.point_centers) {
+            sums[k] += self.metric.distance(&point.component_mul(&scale), &centers.0[k]).powi(2);
+            counts[k] += 1.0;
+        }
+        let radii: [f64; K] = std::array::from_fn(|k| if counts[k] > 0.0 { (sums[k] / counts[k]).sqrt() } else { 0.0 });
+
+        points.iter().map(|point| {
+            let point = point.component_mul(&scale);
+            let k = centers.closest_by(&point, &self.metric);
+            let d = self.metric.distance(&point, &centers.0[k]);
+            if d == 0.0 { 0.0 } else { d / radii[k] }
+        }).collect()
+    }
+
+    /// The score of [`KMeans::score_samples`] that a fraction `contamination` of `points` exceeds,
+    /// for example the points the model was fitted on, to flag new points with higher scores as
+    /// anomalies.
+    ///
+    /// Time complexity: O(r * k * M)
+    ///
+    /// # Panics
+    ///
+    /// If there are no points or `contamination` is not in [0, 1].
+    pub fn anomaly_threshold(&self, points: &[Point<M>], contamination: f64) -> f64 {
+        assert!(!points.is_empty(), "cannot compute a threshold without points");
+        assert!((0.0..=1.0).contains(&contamination), "the contamination must be between 0 and 1");
+        let mut scores = self.score_samples(points);
+        let n = scores.len();
+        let i = (((1.0 - contamination) * n as f64).ceil() as usize).clamp(1, n) - 1;
+        *scores.select_nth_unstable_by(i, f64::total_cmp).1
+    }
+
+    /// Index of the center closest to `point`, or an error if it has NaN or infinite coordinates.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn try_predict(&self, point: &Point<M>) -> Result<usize, PredictError> {
+        check_rows([&point.0[..]], M)?;
+        Ok(self.predict(point))
+    }
+
+    /// Index of the center closest to each of `rows`, for data whose shape is only known at
+    /// runtime, for example when scoring records from a file. Returns an error if a row does not
+    /// have M values or has NaN or infinite values, instead of silently predicting garbage.
+    ///
+    /// Time complexity: O(r * k * M)
+    pub fn try_predict_rows<R: AsRef<[f64]>>(&self, rows: &[R]) -> Result<Vec<usize>, PredictError> {
+        check_rows(rows.iter().map(AsRef::as_ref), M)?;
+        let points: Vec<Point<M>> = rows.iter()
+            .map(|row| Point(std::array::from_fn(|d| row.as_ref()[d])))
+            .collect();
+        Ok(self.predict_many(&points))
+    }
+
+    /// Attach names to the dimensions, for example the column names of the data.
+    ///
+    /// # Panics
+    ///
+    /// If the number of names differs from M.
+    pub fn with_feature_names<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        assert_eq!(names.len(), M, "every dimension needs a name");
+        self.feature_names = Option::Some(names);
+        self
+    }
+
+    /// The name of dimension `d`, or `x{d}` if the model has no feature names.
+    pub fn feature_name(&self, d: usize) -> String {
+        match &self.feature_names {
+            Option::Some(names) => names[d].clone(),
+            None => format!("x{}", d)
+        }
+    }
+
+    /// Write the centers as CSV, with a header of feature names.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn write_centers(&self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+        let header: Vec<String> = (0..M).map(|d| self.feature_name(d)).collect();
+        writeln!(writer, "{}", header.join(","))?;
+        for center in &self.centers {
+            let values: Vec<String> = center.0.iter().map(f64::to_string).collect();
+            writeln!(writer, "{}", values.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// Like [`KMeans::try_predict_rows`], but with the values of every row in the order of
+    /// `columns`. The columns are matched to the features by name, so they may be in any order and
+    /// include columns the model does not use.
+    ///
+    /// Time complexity: O(r * k * M + M * c) for c columns
+    pub fn try_predict_named<S: AsRef<str>, R: AsRef<[f64]>>(&self, columns: &[S], rows: &[R]) -> Result<Vec<usize>, PredictError> {
+        let mut order = [0; M];
+        for (d, index) in order.iter_mut().enumerate() {
+            let name = self.feature_name(d);
+            *index = columns.iter()
+                .position(|column| column.as_ref() == name)
+                .ok_or(PredictError::MissingColumn { name })?;
+        }
+        check_rows(rows.iter().map(AsRef::as_ref), columns.len())?;
+        let points: Vec<Point<M>> = rows.iter()
+            .map(|row| Point(order.map(|i| row.as_ref()[i])))
+            .collect();
+        Ok(self.predict_many(&points))
+    }
+
+    /// The centers in the space where the feature weights are applied, and the scale factors of
+    /// that space.
+    fn scaled_centers(&self) -> (Centers<K, M>, [f64; M]) {
+        let scale = self.feature_weights.map(f64::sqrt);
+        (Centers::new(self.centers.map(|center| center.component_mul(&scale))), scale)
+    }
+}
+
+impl<const K: usize, D: Metric> KMeans<K, 2, D> {
+    /// The Voronoi cell of every center within `bounds`: the polygon of the points that are
+    /// closer to that center than to any other, given the feature weights of the model, which
+    /// shows the decision boundaries of a 2-dimensional model. Every cell is a closed ring of
+    /// vertices in counter-clockwise order, where the last vertex repeats the first, as in the
+    /// polygons of GeoJSON. A center whose cell lies outside the bounds gets an empty list.
+    ///
+    /// The cells are exact for the Euclidean metric, and use Euclidean distance for other metrics.
+    ///
+    /// Time complexity: O(k^3)
+    pub fn voronoi_cells(&self, bounds: &HyperRectangle<2>) -> Vec<Vec<[f64; 2]>> {
+        voronoi::cells(&self.centers, &self.feature_weights, bounds)
+    }
+}
+
+/// Fits a single run of k-means one iteration at a time: every call to `next` performs one
+/// iteration and yields the resulting centers and inertia, created with [`KMeansBuilder::iter`].
+///
+/// This allows animating convergence, stopping on a custom rule, or interleaving fitting with other
+/// work. The iterator ends after the iteration in which no center moves more than the tolerance,
+/// or after the maximum number of iterations; [`KMeansIter::into_model`] turns the current centers
+/// into a model at any point.
+///
+/// With feature weights, the centers and inertia are in the weighted space, see
+/// [`KMeans::feature_weights`].
+pub struct KMeansIter<'a, const K: usize, const M: usize, D: Metric = Euclidean> {
+    points: Cow<'a, [Point<M>]>,
+    weights: Cow<'a, [f64]>,
+    config: Cow<'a, KMeansBuilder<K, M, D>>,
+    algorithm: Algorithm,
+    index: IndexStore<'a, M>,
+    centers: Centers<K, M>,
+    point_centers: Vec<usize>,
+    elkan: Option<Elkan<K, M>>,
+    hamerly: Option<Hamerly<K, M>>,
+    iterations: usize,
+    shift: f64,
+    distortion: Option<f64>,
+    distortion_error: Option<f64>,
+    /// How the nodes of the mrkd-tree were handled in the last iteration
+    nodes: Option<NodeCounts>,
+    /// The total weight of the points of every center in the last iteration
+    counts: [f64; K],
+    /// Whether the distortion stopped improving enough in approximate mode
+    settled: bool,
+    /// The points before scaling by the feature weights, if they are not all 1
+    unscaled: Option<Cow<'a, [Point<M>]>>,
+    /// The points on the GPU, if enabled and available
+    #[cfg(feature = "gpu")]
+    gpu: Option<GpuAssigner<M>>
+}
+
+impl<'a, const K: usize, const M: usize, D: Metric> KMeansIter<'a, K, M, D> {
+    /// Start a run on valid `points`, initialized like the first run of a fit.
+    pub(crate) fn new(points: Cow<'a, [Point<M>]>, weights: Cow<'a, [f64]>, config: Cow<'a, KMeansBuilder<K, M, D>>) -> Self {
+        let seeds = SeedSequence::from_random_state(config.random_state);
+        let feature_weights = config.feature_weights();
+        let (points, config, unscaled) = if feature_weights.iter().all(|&w| w == 1.0) {
+            (points, config, Option::None)
+        } else {
+            let (scaled, config) = KMeans::scale(&points, config, &feature_weights);
+            (Cow::Owned(scaled), config, Option::Some(points))
+        };
+
+        let algorithm = KMeans::effective_algorithm(&config, points.len());
+        let index = IndexStore::build(&points, &weights, &config, algorithm, &mut seeds.child(0).rng());
+        let mut run = Self::start(points, weights, config, index, &mut seeds.rng());
+        run.unscaled = unscaled;
+        run
+    }
+
+    /// Initialize the centers of a run.
+    fn start(points: Cow<'a, [Point<M>]>, weights: Cow<'a, [f64]>, config: Cow<'a, KMeansBuilder<K, M, D>>, index: IndexStore<'a, M>, rng: &mut impl Rng) -> Self {
+        let r = points.len();
+        let algorithm = KMeans::effective_algorithm(&config, r);
+        let centers = Centers::new(match (config.initial_centers, config.initialization) {
+            (Option::Some(centers), _) => centers,
+            (None, Initialization::Random) => initialization::random_points(&points, rng),
+            (None, Initialization::DensityFiltered { neighbors, outlier_fraction }) => match index.get().unwrap() {
+                Index::Kd(tree) => initialization::density_filtered_points(&points, tree, neighbors, outlier_fraction, rng),
+                Index::Ball(tree) => initialization::density_filtered_points(&points, tree, neighbors, outlier_fraction, rng)
+            }
+        });
+        let elkan = match algorithm {
+            Algorithm::Elkan => Option::Some(Elkan::new(r)),
+            _ => Option::None
+        };
+        let hamerly = match algorithm {
+            Algorithm::Hamerly => Option::Some(Hamerly::new(r)),
+            _ => Option::None
+        };
+        #[cfg(feature = "gpu")]
+        let gpu = match algorithm {
+            Algorithm::Naive | Algorithm::Elkan if config.gpu && D::EUCLIDEAN_ORDER && config.cluster_sizes.is_none() => GpuAssigner::new(&points, K),
+            _ => Option::None
+        };
+
+        Self {
+            points,
+            weights,
+            config,
+            algorithm,
+            index,
+            centers,
+            point_centers: vec![0; r],
+            elkan,
+            hamerly,
+            iterations: 0,
+            shift: f64::INFINITY,
+            distortion: Option::None,
+            distortion_error: Option::None,
+            nodes: Option::None,
+            counts: [0.0; K],
+            settled: false,
+            unscaled: Option::None,
+            #[cfg(feature = "gpu")]
+            gpu
+        }
+    }
+
+    /// Perform one iteration, and return the largest movement of a center, unless the run has
+    /// converged or reached the maximum number of iterations.
+    ///
+    /// Time complexity: worst case O(r * k * M)
+    fn step(&mut self) -> Option<f64> {
+        if self.converged() || self.iterations >= self.config.max_iter {
+            return Option::None
+        }
+        self.iterations += 1;
+
+        let assigned = self.assign_gpu();
+        let (points, weights, metric) = (&self.points[..], &self.weights[..], &self.config.metric);
+        let epsilon = self.config.approximation;
+        let mut distortion = Option::None;
+        self.nodes = Option::None;
+        let (new_centers, new_counts) = if let Option::Some(updated) = assigned {
+            updated
+        } else {
+            match self.algorithm {
+                // Use Update(h, C), with blacklisting, or assigning nodes approximately if allowed
+                Algorithm::Simple | Algorithm::Blacklist => {
+                    let tree = self.index.get().unwrap().kd();
+                    let pruning = Pruning { distortion: epsilon, movement: self.config.max_node_movement, weights: self.counts };
+                    let update = if pruning.distortion > 0.0 || pruning.movement > 0.0 {
+                        self.centers.update_approximate(tree, &pruning)
+                    } else if self.algorithm == Algorithm::Simple {
+                        self.centers.update(tree)
+                    } else {
+                        self.centers.update_blacklist(tree)
+                    };
+                    distortion = Option::Some((update.distortion, update.error));
+                    self.nodes = Option::Some(update.nodes);
+                    (update.sums, update.counts)
+                },
+                // Use Update(h, C) with blacklisting on balls
+                Algorithm::BallTree => self.centers.update_ball(self.index.get().unwrap().ball()),
+                Algorithm::Elkan => {
+                    // Use the distance bounds to skip distance computations
+                    let state = self.elkan.as_mut().unwrap();
+                    let updated = state.step(points, weights, &self.centers.0);
+                    self.point_centers.copy_from_slice(&state.labels);
+                    updated
+                },
+                Algorithm::Hamerly => {
+                    let state = self.hamerly.as_mut().unwrap();
+                    let updated = state.step(points, weights, &self.centers.0);
+                    self.point_centers.copy_from_slice(&state.labels);
+                    updated
+                },
+                Algorithm::Naive => match self.config.cluster_sizes {
+                    Option::Some(sizes) => KMeans::<K, M, D>::assign_balanced(points, weights, &self.centers, metric, sizes, &mut self.point_centers),
+                    None => KMeans::<K, M, D>::assign(points, weights, &self.centers, metric, &mut self.point_centers)
+                },
+                Algorithm::Auto => unreachable!("Algorithm::Auto is resolved before fitting")
+            }
+        };
+
+        // For each new center
+        let mut shift: f64 = 0.0;
+        for k in 0..K {
+            // Finalize updating the centers of mass
+            let center = new_centers[k];
+            let count = new_counts[k];
+            let new_center = if count > 0.0 {
+                center / count
+            } else if self.config.empty_cluster_policy == EmptyClusterPolicy::Drop {
+                Point([f64::NAN; M])
+            } else {
+                self.centers.0[k]
+            };
+
+            // Keep track of the largest movement of a center, ignoring dropped centers
+            shift = shift.max(self.centers.0[k].distance(&new_center));
+            self.centers.0[k] = new_center;
+        }
+
+        // Move empty centers to the points that are worst represented
+        if self.config.empty_cluster_policy == EmptyClusterPolicy::ReseedFarthest {
+            for (k, &count) in new_counts.iter().enumerate() {
+                if count == 0.0 {
+                    let new_center = KMeans::<K, M, D>::farthest_point(points, &self.centers, metric);
+                    shift = shift.max(self.centers.0[k].distance(&new_center));
+                    self.centers.0[k] = new_center;
                 }
-                return KMeans { centers: centers.0, point_centers }
             }
         }
+
+        // When approximating, stop once an iteration hardly improves the distortion
+        if let (Option::Some(previous), Option::Some((current, _))) = (self.distortion, distortion) {
+            self.settled = epsilon > 0.0 && previous - current <= epsilon * previous;
+        }
+        self.distortion = distortion.map(|(distortion, _)| distortion);
+        self.distortion_error = distortion.map(|(_, error)| error);
+        self.counts = new_counts;
+
+        self.shift = shift;
+        Option::Some(shift)
+    }
+
+    /// Assign the points on the GPU for [`Algorithm::Naive`] and [`Algorithm::Elkan`] when enabled
+    /// with [`KMeansBuilder::gpu`], and return the weighted sums and the total weights of the
+    /// points per center, or `None` to assign them on the CPU. After a failure, the run continues
+    /// on the CPU.
+    ///
+    /// Time complexity: O(r * k * M / p) with p GPU threads
+    fn assign_gpu(&mut self) -> Option<([Point<M>; K], [f64; K])> {
+        #[cfg(feature = "gpu")]
+        if let Option::Some(gpu) = &self.gpu {
+            if gpu.assign(&self.centers.0, &mut self.point_centers) {
+                return Option::Some(crate::bounds::weighted_sums(&self.points, &self.weights, &self.point_centers))
+            }
+            self.gpu = Option::None;
+        }
+        Option::None
+    }
+
+    /// Whether no center moved more than the tolerance in the last iteration, or the distortion
+    /// stopped improving in approximate mode.
+    fn converged(&self) -> bool {
+        self.shift <= self.config.tol || self.settled
+    }
+
+    /// The state after the last iteration.
+    ///
+    /// Time complexity: O(r * k * M)
+    fn state(&self) -> IterationState<K, M> {
+        IterationState {
+            run: 0,
+            iteration: self.iterations,
+            centers: self.centers.0,
+            inertia: KMeans::inertia_of(&self.points, &self.weights, &self.centers.0, &self.config.metric),
+            max_shift: self.shift,
+            distortion: self.distortion,
+            distortion_error: self.distortion_error
+        }
+    }
+
+    /// Assign the points to the current centers.
+    ///
+    /// Time complexity: O(r * k * M)
+    fn finish(mut self) -> KMeans<K, M, D> {
+        // Get point centers, unless the points were already assigned to the final centers. The
+        // bounds of Elkan and Hamerly keep a point at its center when another becomes equally
+        // close, so those points are reassigned to break ties by index. Points assigned on the GPU
+        // are reassigned with exact distances.
+        let (points, weights, metric) = (&self.points[..], &self.weights[..], &self.config.metric);
+        #[cfg(feature = "gpu")]
+        let assigned = self.algorithm == Algorithm::Naive && self.gpu.is_none();
+        #[cfg(not(feature = "gpu"))]
+        let assigned = self.algorithm == Algorithm::Naive;
+        if let (Option::Some(sizes), true) = (self.config.cluster_sizes, self.shift > 0.0) {
+            KMeans::<K, M, D>::assign_balanced(points, weights, &self.centers, metric, sizes, &mut self.point_centers);
+        } else if !assigned || self.shift > 0.0 {
+            for (point_center, point) in self.point_centers.iter_mut().zip(points) {
+                *point_center = self.centers.closest_by(point, metric);
+            }
+        }
+
+        let inertia = KMeans::sum_of_squares(points, weights, &self.centers.0, metric, &self.point_centers);
+        let converged = self.converged();
+
+        KMeans {
+            centers: self.centers.0,
+            point_centers: self.point_centers,
+            feature_weights: [1.0; M],
+            feature_names: Option::None,
+            metric: *metric,
+            iterations: self.iterations,
+            converged,
+            truncated: false,
+            history: Option::None,
+            diagnostics: Option::None,
+            inertia
+        }
+    }
+
+    /// The current centers.
+    pub fn centers(&self) -> &[Point<M>; K] {
+        &self.centers.0
+    }
+
+    /// The number of iterations performed so far.
+    pub fn iterations(&self) -> usize {
+        self.iterations
     }
 
-    fn random_points(points: &[Point<M>; R], rng: &mut impl Rng) -> [Point<M>; K] {
-        // Ensure initialization so the compiler does not complain
-        let mut indices = [0; K];
-        // Sample random points to initialize centers
-        for (k, i) in rand::seq::index::sample(rng, R, K).iter().enumerate() {
-            indices[k] = i;
+    /// Assign every point to the closest of the current centers, and return the model. With
+    /// feature weights, the centers are the centers of mass of the points in the original space.
+    ///
+    /// Time complexity: O(r * k * M)
+    pub fn into_model(mut self) -> KMeans<K, M, D> {
+        let unscaled = self.unscaled.take();
+        let feature_weights = self.config.feature_weights();
+        let weights = self.weights.clone();
+        let sort_centers = self.config.sort_centers;
+        let mut model = self.finish();
+        if let Option::Some(points) = unscaled {
+            model = KMeans::unscale(model, &points, &weights, feature_weights);
         }
-        indices.map(|i| points[i].clone())
+        if sort_centers {
+            model = model.sort_centers();
+        }
+        model
+    }
+}
+
+impl<'a, const K: usize, const M: usize, D: Metric> Iterator for KMeansIter<'a, K, M, D> {
+    type Item = IterationState<K, M>;
+
+    /// Perform one iteration, and return the centers and the inertia after it.
+    ///
+    /// Time complexity: worst case O(r * k * M)
+    fn next(&mut self) -> Option<IterationState<K, M>> {
+        self.step().map(|_| self.state())
+    }
+}
+
+impl<const K: usize, const M: usize, D: Metric> Clusterer<M> for KMeans<K, M, D> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.iter().map(|&k| Option::Some(k)).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.to_vec())
+    }
+}
+
+impl<const K: usize, const M: usize, D: Metric> Predict<M> for KMeans<K, M, D> {
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        Option::Some(KMeans::predict(self, point))
+    }
+
+    fn predict_many(&self, points: &[Point<M>]) -> Vec<Option<usize>> {
+        KMeans::predict_many(self, points).into_iter().map(Option::Some).collect()
     }
 }
 
@@ -151,7 +1446,7 @@ mod tests {
             Point([0.5, 1.5]),
             Point([1.5, 1.5])
         ];
-        let KMeans { centers, point_centers } = KMeans::<4, 2, 4>::fit_with_random_state(&points, Algorithm::Naive, 0);
+        let KMeans { centers, point_centers, .. } = KMeans::<4, 2>::fit_with_random_state(&points, Algorithm::Naive, 0).unwrap();
         assert_eq!(centers, [
             Point([0.5, 0.5]),
             Point([1.5, 1.5]),
@@ -169,7 +1464,99 @@ mod tests {
             Point([0.5, 1.5]),
             Point([1.5, 1.5])
         ];
-        let KMeans { centers, point_centers } = KMeans::<4, 2, 4>::fit_with_random_state(&points, Algorithm::Simple, 0);
+        let KMeans { centers, point_centers, .. } = KMeans::<4, 2>::fit_with_random_state(&points, Algorithm::Simple, 0).unwrap();
+        assert_eq!(centers, [
+            Point([0.5, 0.5]),
+            Point([1.5, 1.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5])
+        ]);
+        assert_eq!(point_centers, [0, 2, 3, 1]);
+    }
+
+    #[test]
+    fn predict() {
+        let points = [
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5])
+        ];
+        let model = KMeans::<4, 2>::fit_with_random_state(&points, Algorithm::Simple, 0).unwrap();
+        assert_eq!(model.predict(&Point([1.4, 0.6])), 2);
+        assert_eq!(model.predict_many(&points), model.point_centers);
+    }
+
+    #[test]
+    fn transform() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 2.0]),
+            Point([3.0, 0.0]),
+            Point([3.0, 2.0])
+        ];
+        let (model, distances) = KMeans::<2, 2>::builder().random_state(0).fit_transform(&points).unwrap();
+        for (point, distances) in points.iter().zip(&distances) {
+            let k = model.predict(point);
+            assert_eq!(distances[k], point.distance(&model.centers[k]));
+            assert_eq!(distances[k], distances[0].min(distances[1]));
+        }
+        assert_eq!(model.transform(&points), distances);
+    }
+
+    #[test]
+    fn max_iter() {
+        let points = [
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5]),
+            Point([4.0, 4.0])
+        ];
+        let model = KMeans::<2, 2>::builder().random_state(0).max_iter(1).fit(&points).unwrap();
+        assert_eq!(model.iterations, 1);
+
+        let model = KMeans::<2, 2>::builder().random_state(0).max_iter(100).fit(&points).unwrap();
+        assert!(model.converged);
+        assert!(model.iterations < 100);
+    }
+
+    #[test]
+    fn inertia() {
+        let points = [
+            Point([0.0, 1.0]),
+            Point([4.0, 1.0]),
+            Point([2.0, 0.0]),
+            Point([2.0, 2.0])
+        ];
+        let model = KMeans::<1, 2>::fit_with_random_state(&points, Algorithm::Naive, 0).unwrap();
+        assert_eq!(model.inertia(), 10.0);
+    }
+
+    #[test]
+    fn fit_n() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([4.0, 0.0]),
+            Point([4.0, 1.0])
+        ];
+        let single = KMeans::<2, 2>::builder().random_state(0).fit(&points).unwrap();
+        let best = KMeans::<2, 2>::builder().random_state(0).n_init(10).fit(&points).unwrap();
+        assert!(best.inertia() <= single.inertia());
+        assert_eq!(best.point_centers[0], best.point_centers[1]);
+        assert_eq!(best.point_centers[2], best.point_centers[3]);
+    }
+
+    #[test]
+    fn fit_blacklist_with_random_state() {
+        let points = [
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5])
+        ];
+        let KMeans { centers, point_centers, .. } = KMeans::<4, 2>::fit_with_random_state(&points, Algorithm::Blacklist, 0).unwrap();
         assert_eq!(centers, [
             Point([0.5, 0.5]),
             Point([1.5, 1.5]),
@@ -178,4 +1565,346 @@ mod tests {
         ]);
         assert_eq!(point_centers, [0, 2, 3, 1]);
     }
+
+    #[test]
+    fn fit_elkan_with_random_state() {
+        let points = [
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5]),
+            Point([4.0, 4.0])
+        ];
+        let naive = KMeans::<2, 2>::fit_with_random_state(&points, Algorithm::Naive, 0).unwrap();
+        for algorithm in [Algorithm::Elkan, Algorithm::Hamerly] {
+            let model = KMeans::<2, 2>::fit_with_random_state(&points, algorithm, 0).unwrap();
+            assert_eq!(model.centers, naive.centers);
+            assert_eq!(model.point_centers, naive.point_centers);
+        }
+    }
+
+    #[test]
+    fn fit_ball_tree_with_random_state() {
+        let points: Vec<Point<20>> = (0..300)
+            .map(|i| Point(std::array::from_fn(|d| (i % 3 * 10) as f64 + ((i * (d + 1)) % 7) as f64 * 0.25)))
+            .collect();
+        let naive = KMeans::<3, 20>::fit_with_random_state(&points, Algorithm::Naive, 0).unwrap();
+        let ball = KMeans::<3, 20>::fit_with_random_state(&points, Algorithm::BallTree, 0).unwrap();
+        assert_eq!(ball.point_centers, naive.point_centers);
+        assert_eq!(ball.centers, naive.centers);
+    }
+
+    #[test]
+    fn fit_weighted() {
+        let points = [
+            Point([0.0, 2.0]),
+            Point([3.0, 1.0]),
+            Point([10.0, 0.0])
+        ];
+        let model = KMeans::<1, 2>::fit_weighted(&points, &[1.0, 2.0, 1.0], Algorithm::Simple).unwrap();
+        assert_eq!(model.centers, [Point([4.0, 1.0])]);
+    }
+
+    #[test]
+    fn fit_with_metric() {
+        use crate::metric::Manhattan;
+
+        let points = [
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5]),
+            Point([4.0, 4.0])
+        ];
+        let naive = KMeans::<2, 2>::builder().metric(Manhattan).algorithm(Algorithm::Naive).random_state(0).fit(&points).unwrap();
+        let simple = KMeans::<2, 2, Manhattan>::fit_with_random_state(&points, Algorithm::Simple, 0).unwrap();
+        assert_eq!(simple.centers, naive.centers);
+        assert_eq!(simple.predict_many(&points), naive.point_centers);
+    }
+
+    #[test]
+    fn accessors() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([0.5, 0.5]),
+            Point([4.0, 0.0])
+        ];
+        let model = KMeans::<2, 2>::fit_with_random_state(&points, Algorithm::Naive, 0).unwrap();
+        let k = model.labels()[3];
+        assert_eq!(model.centers()[k], Point([4.0, 0.0]));
+        assert_eq!(model.cluster_sizes()[k], 1);
+        assert_eq!(model.cluster_sizes()[1 - k], 3);
+        assert_eq!(model.cluster_points(1 - k).collect::<Vec<_>>(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn empty_cluster_policy() {
+        // With this seed, both copies of the origin are picked as initial centers
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 0.0]),
+            Point([10.0, 0.0]),
+            Point([11.0, 0.0]),
+            Point([12.0, 0.0])
+        ];
+        let builder = KMeans::<3, 2>::builder().algorithm(Algorithm::Naive).random_state(2);
+
+        let keep = builder.clone().fit(&points).unwrap();
+        assert_eq!(keep.cluster_sizes(), [2, 0, 3]);
+        assert_eq!(keep.centers[1], Point([0.0, 0.0]));
+
+        let reseed = builder.clone().empty_cluster_policy(EmptyClusterPolicy::ReseedFarthest).fit(&points).unwrap();
+        assert!(reseed.cluster_sizes().iter().all(|&size| size > 0));
+        assert!(reseed.inertia() < keep.inertia());
+
+        let drop = builder.empty_cluster_policy(EmptyClusterPolicy::Drop).fit(&points).unwrap();
+        assert!(drop.centers[1].0.iter().all(|x| x.is_nan()));
+        assert_eq!(drop.point_centers, keep.point_centers);
+    }
+
+    #[test]
+    fn time_budget() {
+        let points = [
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5]),
+            Point([4.0, 4.0])
+        ];
+        let model = KMeans::<2, 2>::builder().random_state(0).n_init(10).time_budget(std::time::Duration::ZERO).fit(&points).unwrap();
+        assert!(model.truncated);
+        assert!(!model.converged);
+        assert_eq!(model.iterations, 1);
+    }
+
+    #[test]
+    fn cancellation_token() {
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let points: Vec<Point<2>> = (0..20).map(|i| Point([i as f64, (i * i % 7) as f64])).collect();
+        let token = Arc::new(AtomicBool::new(false));
+        let builder = KMeans::<3, 2>::builder().random_state(0).n_init(3).cancellation_token(token.clone());
+        assert!(!builder.fit(&points).unwrap().truncated);
+
+        // Cancel from within the first iteration
+        let model = builder.fit_with_callback(&points, |_| {
+            token.store(true, Ordering::Relaxed);
+            std::ops::ControlFlow::Continue(())
+        }).unwrap();
+        assert!(model.truncated);
+        assert_eq!(model.iterations, 1);
+    }
+
+    #[test]
+    fn record_history() {
+        let points = [
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5]),
+            Point([4.0, 4.0])
+        ];
+        let model = KMeans::<2, 2>::builder().random_state(0).record_history(true).fit(&points).unwrap();
+        let history = model.history.unwrap();
+        assert_eq!(history.centers.len(), model.iterations + 1);
+        assert_eq!(history.shifts.len(), model.iterations);
+        assert_eq!(history.centers.last(), Option::Some(&model.centers));
+        assert_eq!(history.shifts.last(), Option::Some(&0.0));
+    }
+
+    #[test]
+    fn rounded_hash() {
+        let centers = [Point([0.1 + 0.2, 1.0]), Point([2.0, 3.0])];
+        let rounded = [Point([0.3, 1.0]), Point([2.0, 3.0])];
+        let moved = [Point([0.3001, 1.0]), Point([2.0, 3.0])];
+        assert_eq!(KMeans::<2, 2>::rounded_hash(&centers), KMeans::<2, 2>::rounded_hash(&rounded));
+        assert_ne!(KMeans::<2, 2>::rounded_hash(&centers), KMeans::<2, 2>::rounded_hash(&moved));
+    }
+
+    #[test]
+    fn try_predict_rows() {
+        let points = [
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5])
+        ];
+        let model = KMeans::<4, 2>::fit_with_random_state(&points, Algorithm::Simple, 0).unwrap();
+        assert_eq!(model.try_predict_rows(&[vec![1.4, 0.6]]), Ok(vec![2]));
+        assert_eq!(model.try_predict_rows(&[vec![1.4, 0.6], vec![1.4]]), Err(PredictError::DimensionMismatch { row: 1, expected: 2, found: 1 }));
+        assert_eq!(model.try_predict(&Point([f64::NAN, 0.0])), Err(PredictError::NonFinite { row: 0, dimension: 0 }));
+    }
+
+    #[test]
+    fn feature_names() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([4.0, 0.0]),
+            Point([4.0, 1.0])
+        ];
+        let model = KMeans::<1, 2>::builder().feature_names(["x", "y"]).fit(&points).unwrap();
+        let mut csv = Vec::new();
+        model.write_centers(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "x,y\n2,0.5\n");
+        assert_eq!(model.try_predict_named(&["id", "y", "x"], &[[7.0, 0.0, 1.0]]), Ok(vec![0]));
+        assert_eq!(model.try_predict_named(&["x"], &[[1.0]]), Err(PredictError::MissingColumn { name: String::from("y") }));
+    }
+
+    #[test]
+    fn invalid_points() {
+        use crate::preprocess::MissingPolicy;
+
+        let points = [
+            Point([0.0, 0.0]),
+            Point([-0.0, 0.0]),
+            Point([1.0, 0.0])
+        ];
+        assert_eq!(KMeans::<3, 2>::fit(&points, Algorithm::Simple).err(), Option::Some(ClusterError::TooFewPoints { clusters: 3, distinct: 2 }));
+        assert_eq!(KMeans::<2, 2>::fit(&[Point([0.0, f64::NAN])], Algorithm::Simple).err(), Option::Some(ClusterError::NonFinite { point: 0, dimension: 1 }));
+        for algorithm in [Algorithm::Naive, Algorithm::Simple, Algorithm::Auto] {
+            assert_eq!(KMeans::<0, 2>::fit(&points, algorithm).err(), Option::Some(ClusterError::NoClusters));
+        }
+        assert_eq!(KMeans::<2, 2>::fit_weighted(&points, &[1.0], Algorithm::Simple).err(), Option::Some(ClusterError::WeightMismatch { points: 3, weights: 1 }));
+        assert_eq!(KMeans::<2, 2>::fit_with_centers(&points, [Point([0.0, 0.0]), Point([f64::INFINITY, 0.0])], Algorithm::Simple).err(), Option::Some(ClusterError::NonFiniteCenter { center: 1, dimension: 0 }));
+        assert!(KMeans::<2, 2>::fit(&points, Algorithm::Simple).is_ok());
+
+        let missing = [Point([0.0, 0.0]), Point([f64::NAN, 1.0]), Point([4.0, 1.0])];
+        let model = KMeans::<2, 2>::builder().missing_policy(MissingPolicy::DropRow).fit(&missing).unwrap();
+        assert_eq!(model.point_centers.len(), 2);
+        let model = KMeans::<1, 2>::builder().missing_policy(MissingPolicy::ImputeMean).fit(&missing).unwrap();
+        assert_eq!(model.centers, [Point([2.0, 2.0 / 3.0])]);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn reproducible_across_thread_counts() {
+        use rand::{Rng, SeedableRng};
+        use rand::rngs::StdRng;
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let points: Vec<Point<3>> = (0..10_000).map(|_| Point([(); 3].map(|_| rng.gen::<f64>()))).collect();
+        let fit = |threads: usize, algorithm: Algorithm| rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap()
+            .install(|| KMeans::<5, 3>::builder().algorithm(algorithm).random_state(7).n_init(3).max_iter(20).fit(&points));
+        for algorithm in [Algorithm::Naive, Algorithm::Simple, Algorithm::Blacklist, Algorithm::Elkan, Algorithm::Hamerly] {
+            let reference = fit(1, algorithm).unwrap();
+            for threads in [2, 3, 8] {
+                let model = fit(threads, algorithm).unwrap();
+                assert_eq!(model.centers, reference.centers);
+                assert_eq!(model.point_centers, reference.point_centers);
+            }
+        }
+    }
+
+    #[test]
+    fn predict_many_with_tree() {
+        use rand::{Rng, SeedableRng};
+        use rand::rngs::StdRng;
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let points: Vec<Point<2>> = (0..PREDICT_TREE_SIZE).map(|_| Point([rng.gen::<f64>(), rng.gen::<f64>()])).collect();
+        let model = KMeans::<6, 2>::builder().random_state(0).max_iter(5).fit(&points[..100]).unwrap();
+        let labels: Vec<usize> = points.iter().map(|point| model.predict(point)).collect();
+        assert_eq!(model.predict_many(&points), labels);
+    }
+
+    #[test]
+    fn auto() {
+        assert_eq!(Algorithm::Auto.resolve(10_000, 2, 16), Algorithm::Blacklist);
+        assert_eq!(Algorithm::Auto.resolve(10_000, 16, 16), Algorithm::Elkan);
+        assert_eq!(Algorithm::Auto.resolve(100_000_000, 16, 16), Algorithm::Hamerly);
+        assert_eq!(Algorithm::Auto.resolve(10_000, 8, 16), Algorithm::Hamerly);
+        assert_eq!(Algorithm::Auto.resolve(10_000, 2, 4), Algorithm::Naive);
+        assert_eq!(Algorithm::Simple.resolve(10_000, 2, 4), Algorithm::Simple);
+
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([5.0, 0.0]),
+            Point([5.0, 1.0])
+        ];
+        let model = KMeans::<2, 2>::fit_with_random_state(&points, Algorithm::Auto, 0).unwrap();
+        let naive = KMeans::<2, 2>::fit_with_random_state(&points, Algorithm::Naive, 0).unwrap();
+        assert_eq!(model.point_centers, naive.point_centers);
+    }
+
+    #[test]
+    fn refit() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([5.0, 0.0]),
+            Point([5.0, 1.0])
+        ];
+        let config = KMeans::<2, 2>::builder().random_state(0);
+        let model = config.fit(&points).unwrap();
+        let moved = points.map(|point| point + Point([1.0, 0.0]));
+        let refitted = model.refit(&moved, &config).unwrap();
+        assert_eq!(refitted.point_centers, model.point_centers);
+        assert_eq!(refitted.centers, model.centers.map(|center| center + Point([1.0, 0.0])));
+        assert!(refitted.iterations <= 2);
+    }
+
+    #[test]
+    fn score_samples() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([5.0, 0.0]),
+            Point([5.0, 1.0])
+        ];
+        let model = KMeans::<2, 2>::fit_with_centers(&points, [points[0], points[2]], Algorithm::Naive).unwrap();
+        let new = [Point([0.0, 0.5]), Point([0.0, 3.0])];
+        assert_eq!(model.score_samples(&new), vec![0.0, 2.5]);
+        assert_eq!(model.normalized_scores(&points, &new), vec![0.0, 5.0]);
+        assert_eq!(model.anomaly_threshold(&points, 0.0), 0.5);
+        assert_eq!(model.anomaly_threshold(&points, 1.0), 0.5);
+    }
+
+    #[test]
+    fn relabel() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([5.0, 0.0]),
+            Point([5.0, 1.0]),
+            Point([9.0, 0.0]),
+            Point([9.0, 1.0]),
+            Point([9.0, 2.0])
+        ];
+        let model = KMeans::<3, 2>::fit_with_centers(&points, [points[0], points[1], points[3]], Algorithm::Naive).unwrap();
+        assert_eq!(model.point_centers, vec![0, 1, 1, 2, 2, 2]);
+
+        let model = model.relabel_by_size();
+        assert_eq!(model.point_centers, vec![2, 1, 1, 0, 0, 0]);
+        assert_eq!(model.centers[0], Point([9.0, 1.0]));
+
+        let model = model.relabel_to_match(&[1, 0, 0, 2, 2, 5]);
+        assert_eq!(model.point_centers, vec![1, 0, 0, 2, 2, 2]);
+        assert_eq!(model.centers[1], Point([0.0, 0.0]));
+
+        let merged = model.merge_clusters::<2>(&points, 2, 1);
+        assert_eq!(merged.point_centers, vec![1, 0, 0, 1, 1, 1]);
+        assert_eq!(merged.centers, [Point([5.0, 0.5]), Point([6.75, 0.75])]);
+        assert!((merged.inertia() - 64.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn identical_centers() {
+        // With compensated sums, adding the points one by one or by the nodes of the tree gives
+        // the same centers, not just the same labels
+        let centers = [Point([0.1, 0.2, 0.3]), Point([10.7, 0.0, -3.3]), Point([-5.5, 8.1, 2.2])];
+        let (points, _) = crate::datasets::gaussian_blobs(5000, &centers, 1.3, 0);
+        let builder = KMeansBuilder::<3, 3>::new().random_state(0);
+        let naive = builder.clone().algorithm(Algorithm::Naive).fit(&points).unwrap();
+        for algorithm in [Algorithm::Simple, Algorithm::Blacklist, Algorithm::Elkan, Algorithm::Hamerly, Algorithm::BallTree] {
+            let model = builder.clone().algorithm(algorithm).fit(&points).unwrap();
+            assert_eq!(model.point_centers, naive.point_centers);
+            assert_eq!(model.centers, naive.centers);
+        }
+    }
 }
@@ -0,0 +1,153 @@
+use crate::hyper_rectangle::HyperRectangle;
+use crate::point::Point;
+use std::fmt::Write;
+
+/// Statistics of a single cluster of a [`Clustering`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClusterStats<const M: usize> {
+    /// The number of points in the cluster.
+    pub size: usize,
+    /// The sum of the squared Euclidean distances of the points to the center.
+    pub sse: f64,
+    /// The largest Euclidean distance of a point to the center, or 0 for an empty cluster.
+    pub radius: f64,
+    /// The smallest hyper-rectangle that contains the points, or `None` for an empty cluster.
+    pub bounds: Option<HyperRectangle<M>>,
+    /// The mean of the points, which can differ from the center if the model was fitted with
+    /// weights, another metric, or did not converge. For an empty cluster, this is the center.
+    pub centroid: Point<M>
+}
+
+/// A clustering of points with statistics per cluster, for example to report on a fitted model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clustering<const M: usize> {
+    /// The coordinates of the centers.
+    pub centers: Vec<Point<M>>,
+    /// The index of the center that each point belongs to.
+    pub labels: Vec<usize>,
+    /// The statistics of every cluster, in the order of the centers.
+    pub clusters: Vec<ClusterStats<M>>,
+    /// The names of the dimensions, used as the column headers of [`Clustering::summary`].
+    pub feature_names: Vec<String>
+}
+
+impl<const M: usize> Clustering<M> {
+    /// Compute the statistics of the clusters of `points` given the index of the center of every
+    /// point in `labels`.
+    ///
+    /// Time complexity: O(r * M)
+    ///
+    /// # Panics
+    ///
+    /// If the number of labels differs from the number of points, or if a label is not the index
+    /// of a center.
+    pub fn new(points: &[Point<M>], centers: &[Point<M>], labels: &[usize]) -> Self {
+        assert_eq!(points.len(), labels.len(), "every point needs a label");
+        assert!(labels.iter().all(|&k| k < centers.len()), "labels must be below {}", centers.len());
+
+        let mut clusters: Vec<ClusterStats<M>> = centers.iter().map(|&center| ClusterStats {
+            size: 0,
+            sse: 0.0,
+            radius: 0.0,
+            bounds: Option::None,
+            centroid: center
+        }).collect();
+        let mut sums = vec![Point::default(); centers.len()];
+        for (point, &k) in points.iter().zip(labels) {
+            let cluster = &mut clusters[k];
+            let d = point.squared_distance(&centers[k]);
+            cluster.size += 1;
+            cluster.sse += d;
+            cluster.radius = cluster.radius.max(d);
+            cluster.bounds = Option::Some(match cluster.bounds {
+                Option::Some(HyperRectangle(min, max)) => HyperRectangle(
+                    Point(std::array::from_fn(|d| min.0[d].min(point.0[d]))),
+                    Point(std::array::from_fn(|d| max.0[d].max(point.0[d])))
+                ),
+                None => HyperRectangle(*point, *point)
+            });
+            sums[k] += *point;
+        }
+        for (cluster, sum) in clusters.iter_mut().zip(sums) {
+            cluster.radius = cluster.radius.sqrt();
+            if cluster.size > 0 {
+                cluster.centroid = sum / cluster.size;
+            }
+        }
+
+        Clustering {
+            centers: centers.to_vec(),
+            labels: labels.to_vec(),
+            clusters,
+            feature_names: (0..M).map(|d| format!("x{}", d)).collect()
+        }
+    }
+
+    /// Use `names` as the names of the dimensions.
+    ///
+    /// # Panics
+    ///
+    /// If the number of names differs from M.
+    pub fn with_feature_names<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        assert_eq!(names.len(), M, "every dimension needs a name");
+        self.feature_names = names;
+        self
+    }
+
+    /// The sum of the squared Euclidean distances of all points to their centers.
+    pub fn sse(&self) -> f64 {
+        self.clusters.iter().map(|cluster| cluster.sse).sum()
+    }
+
+    /// A table with a row per cluster, with its size, SSE, radius and centroid, followed by a row
+    /// with the totals.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn summary(&self) -> String {
+        let mut table = String::new();
+        let _ = write!(table, "{:>8} {:>8} {:>12} {:>12}", "cluster", "size", "sse", "radius");
+        for name in &self.feature_names {
+            let _ = write!(table, " {:>12}", name);
+        }
+        table.push('\n');
+
+        for (k, cluster) in self.clusters.iter().enumerate() {
+            let _ = write!(table, "{:>8} {:>8} {:>12.4} {:>12.4}", k, cluster.size, cluster.sse, cluster.radius);
+            for x in &cluster.centroid.0 {
+                let _ = write!(table, " {:>12.4}", x);
+            }
+            table.push('\n');
+        }
+
+        let radius = self.clusters.iter().map(|cluster| cluster.radius).fold(0.0, f64::max);
+        let _ = writeln!(table, "{:>8} {:>8} {:>12.4} {:>12.4}", "total", self.labels.len(), self.sse(), radius);
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 2.0]),
+            Point([10.0, 0.0])
+        ];
+        let centers = [Point([0.0, 1.0]), Point([10.0, 0.0]), Point([5.0, 5.0])];
+        let clustering = Clustering::new(&points, &centers, &[0, 0, 1]);
+        assert_eq!(clustering.clusters[0], ClusterStats {
+            size: 2,
+            sse: 2.0,
+            radius: 1.0,
+            bounds: Option::Some(HyperRectangle(Point([0.0, 0.0]), Point([0.0, 2.0]))),
+            centroid: Point([0.0, 1.0])
+        });
+        assert_eq!(clustering.clusters[2].size, 0);
+        assert_eq!(clustering.clusters[2].bounds, Option::None);
+        assert_eq!(clustering.summary().lines().count(), 5);
+    }
+}
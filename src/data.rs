@@ -0,0 +1,189 @@
+use crate::error::{check_finite_points, ClusterError};
+use crate::point::Point;
+use std::collections::HashMap;
+
+/// How a categorical value is mapped to a number by a [`FrequencyEncoder`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Encoding {
+    /// The number of times the category occurred in the fitted data.
+    Count,
+    /// The fraction of the fitted data that had the category.
+    Frequency
+}
+
+/// Target-free encoding of a categorical column as a numeric one, so that mixed data can be
+/// clustered with plain k-means. Categories that were not seen while fitting are encoded as 0.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrequencyEncoder {
+    /// How categories are mapped to numbers.
+    pub encoding: Encoding,
+    /// Number of occurrences of each category in the fitted data.
+    pub counts: HashMap<String, usize>,
+    /// Number of values in the fitted data.
+    pub total: usize
+}
+
+impl FrequencyEncoder {
+    /// Count the categories in `values`.
+    ///
+    /// Time complexity: O(r)
+    pub fn fit<S: AsRef<str>>(values: &[S], encoding: Encoding) -> Self {
+        let mut counts = HashMap::new();
+        for value in values {
+            *counts.entry(value.as_ref().to_owned()).or_insert(0) += 1;
+        }
+
+        Self { encoding, counts, total: values.len() }
+    }
+
+    /// Encode a single category.
+    ///
+    /// Time complexity: O(1)
+    pub fn transform(&self, value: &str) -> f64 {
+        let count = self.counts.get(value).copied().unwrap_or(0);
+        match self.encoding {
+            Encoding::Count => count as f64,
+            Encoding::Frequency => if self.total == 0 { 0.0 } else { count as f64 / self.total as f64 }
+        }
+    }
+
+    /// Encode a column of categories.
+    ///
+    /// Time complexity: O(r)
+    pub fn transform_many<S: AsRef<str>>(&self, values: &[S]) -> Vec<f64> {
+        values.iter().map(|value| self.transform(value.as_ref())).collect()
+    }
+}
+
+/// Target-free embedding of a categorical column as one dimension per category, which is `weight`
+/// for the category of a row and 0 otherwise. Every two distinct categories are equally far apart:
+/// a mismatch adds `2 * weight^2` to the squared Euclidean distance, so a weight of
+/// `(gamma / 2).sqrt()` matches the `gamma` of [`crate::mixed::KPrototypes`]. Categories that were
+/// not seen while fitting are encoded as all zeros.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OneHotEncoder {
+    /// The distinct categories in the fitted data, in ascending order, one per dimension.
+    pub categories: Vec<String>,
+    /// The value of the dimension of the category of a row.
+    pub weight: f64
+}
+
+impl OneHotEncoder {
+    /// Find the distinct categories in `values`.
+    ///
+    /// Time complexity: O(r * log(r))
+    pub fn fit<S: AsRef<str>>(values: &[S], weight: f64) -> Self {
+        let mut categories: Vec<String> = values.iter().map(|value| value.as_ref().to_owned()).collect();
+        categories.sort_unstable();
+        categories.dedup();
+
+        Self { categories, weight }
+    }
+
+    /// Embed a single category.
+    ///
+    /// Time complexity: O(c) for c categories
+    pub fn transform(&self, value: &str) -> Vec<f64> {
+        let mut embedding = vec![0.0; self.categories.len()];
+        if let Ok(i) = self.categories.binary_search_by(|category| category.as_str().cmp(value)) {
+            embedding[i] = self.weight;
+        }
+        embedding
+    }
+
+    /// Embed a column of categories.
+    ///
+    /// Time complexity: O(r * c) for c categories
+    pub fn transform_many<S: AsRef<str>>(&self, values: &[S]) -> Vec<Vec<f64>> {
+        values.iter().map(|value| self.transform(value.as_ref())).collect()
+    }
+}
+
+/// Target-free embedding of a categorical column by the numeric columns of the same rows: every
+/// category is embedded as the mean of the points that have it, so that categories whose rows are
+/// alike end up close together. Categories that were not seen while fitting are embedded as the
+/// mean of all points.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeanEmbedding<const M: usize> {
+    /// The mean of the points of each category in the fitted data.
+    pub means: HashMap<String, Point<M>>,
+    /// The mean of all points in the fitted data.
+    pub mean: Point<M>
+}
+
+impl<const M: usize> MeanEmbedding<M> {
+    /// Embed the categories in `values` by the mean of the `points` in the same rows, or an error
+    /// if the number of values differs from the number of points or the points have NaN or
+    /// infinite coordinates.
+    ///
+    /// Time complexity: O(r * M)
+    pub fn fit<S: AsRef<str>>(values: &[S], points: &[Point<M>]) -> Result<Self, ClusterError> {
+        if values.len() != points.len() {
+            return Err(ClusterError::LabelMismatch { points: points.len(), labels: values.len() })
+        }
+        check_finite_points(points, &vec![1.0; points.len()])?;
+
+        let mut sums: HashMap<String, (Point<M>, usize)> = HashMap::new();
+        for (value, point) in values.iter().zip(points) {
+            let (sum, count) = sums.entry(value.as_ref().to_owned()).or_insert((Point::default(), 0));
+            *sum += *point;
+            *count += 1;
+        }
+        let means = sums.into_iter().map(|(category, (sum, count))| (category, sum / count as f64)).collect();
+        let mean = if points.is_empty() {
+            Point::default()
+        } else {
+            points.iter().fold(Point::default(), |sum, point| sum + *point) / points.len() as f64
+        };
+
+        Ok(Self { means, mean })
+    }
+
+    /// Embed a single category.
+    ///
+    /// Time complexity: O(M)
+    pub fn transform(&self, value: &str) -> Point<M> {
+        self.means.get(value).copied().unwrap_or(self.mean)
+    }
+
+    /// Embed a column of categories.
+    ///
+    /// Time complexity: O(r * M)
+    pub fn transform_many<S: AsRef<str>>(&self, values: &[S]) -> Vec<Point<M>> {
+        values.iter().map(|value| self.transform(value.as_ref())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequency_encoding() {
+        let column = ["a", "b", "a", "c"];
+        let count = FrequencyEncoder::fit(&column, Encoding::Count);
+        assert_eq!(count.transform_many(&["a", "b", "d"]), vec![2.0, 1.0, 0.0]);
+        let frequency = FrequencyEncoder::fit(&column, Encoding::Frequency);
+        assert_eq!(frequency.transform_many(&["a", "c"]), vec![0.5, 0.25]);
+    }
+
+    #[test]
+    fn one_hot_encoding() {
+        let encoder = OneHotEncoder::fit(&["b", "a", "b", "c"], 2.0);
+        assert_eq!(encoder.categories, vec!["a", "b", "c"]);
+        assert_eq!(encoder.transform_many(&["b", "d"]), vec![vec![0.0, 2.0, 0.0], vec![0.0, 0.0, 0.0]]);
+    }
+
+    #[test]
+    fn mean_embedding() {
+        let column = ["a", "b", "a", "b"];
+        let points = [Point([0.0, 1.0]), Point([4.0, 0.0]), Point([2.0, 3.0]), Point([6.0, 0.0])];
+        let embedding = MeanEmbedding::fit(&column, &points).unwrap();
+        assert_eq!(embedding.transform_many(&["a", "b", "c"]), vec![Point([1.0, 2.0]), Point([5.0, 0.0]), Point([3.0, 1.0])]);
+        assert_eq!(MeanEmbedding::fit(&column[..1], &points).err(), Option::Some(ClusterError::LabelMismatch { points: 4, labels: 1 }));
+    }
+}
@@ -0,0 +1,129 @@
+use crate::error::{check_finite_points, ClusterError};
+use crate::io::binary::MappedFile;
+use crate::mrkd::{Tree, DEFAULT_LEAF_SIZE};
+use crate::point::Point;
+use crate::seeding::SeedSequence;
+use std::io;
+use std::ops::Deref;
+use std::path::Path;
+
+/// Points with the mrkd-tree (Pelleg & Moore, 1999) built over them, to fit many models on the
+/// same data, for example with different numbers of clusters or random states, without building
+/// the tree again for every fit. See [`crate::KMeansBuilder::fit_dataset`].
+///
+/// The tree only depends on the points and weights. It is built with a fixed random state: its
+/// shape does not change the result of the exact algorithms, apart from rounding.
+///
+/// # References
+///
+/// Pelleg, D., & Moore, A. (1999). Accelerating exact k-means algorithms with geometric reasoning.
+///     Proceedings of the Fifth ACM SIGKDD International Conference
+///     on Knowledge Discovery and Data Mining, 277–281. <https://doi.org/10.1145/312129.312248>
+pub struct Dataset<const M: usize> {
+    pub(crate) points: Points<M>,
+    pub(crate) weights: Vec<f64>,
+    pub(crate) tree: Tree<M>
+}
+
+impl<const M: usize> Dataset<M> {
+    /// Build the tree over `points`, or return an error if they have NaN or infinite coordinates.
+    ///
+    /// Time complexity: O(r * log(r) * M)
+    pub fn new(points: Vec<Point<M>>) -> Result<Self, ClusterError> {
+        let weights = vec![1.0; points.len()];
+        Self::weighted(points, weights)
+    }
+
+    /// Build the tree over `points`, where each point has a weight in the centers of mass, or
+    /// return an error for the reasons of [`crate::KMeansBuilder::fit_weighted`].
+    ///
+    /// Time complexity: O(r * log(r) * M)
+    pub fn weighted(points: Vec<Point<M>>, weights: Vec<f64>) -> Result<Self, ClusterError> {
+        Self::with_leaf_size(points, weights, DEFAULT_LEAF_SIZE)
+    }
+
+    /// Build the tree over weighted `points` with leaves of at most `max_leaf_size` points, see
+    /// [`crate::KMeansBuilder::max_leaf_size`], which is not used when fitting a dataset.
+    ///
+    /// Time complexity: O(r * log(r) * M)
+    pub fn with_leaf_size(points: Vec<Point<M>>, weights: Vec<f64>, max_leaf_size: usize) -> Result<Self, ClusterError> {
+        Self::build(Points::Owned(points), weights, max_leaf_size)
+    }
+
+    /// Map the file at `path` in the format of [`crate::io::binary`] into memory and build the tree
+    /// over its points, which are not copied or parsed. Returns an error if the file cannot be
+    /// read, does not have M columns, or has NaN or infinite values.
+    ///
+    /// Time complexity: O(r * log(r) * M)
+    pub fn mmap(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = MappedFile::open(path)?;
+        let r = file.points::<M>()?.len();
+        Self::build(Points::Mapped(file), vec![1.0; r], DEFAULT_LEAF_SIZE)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    fn build(points: Points<M>, weights: Vec<f64>, max_leaf_size: usize) -> Result<Self, ClusterError> {
+        check_finite_points(&points, &weights)?;
+        let tree = Tree::with_leaf_size(&points, &weights, max_leaf_size, &mut SeedSequence::new(0).rng());
+        Ok(Self { points, weights, tree })
+    }
+
+    /// The points.
+    pub fn points(&self) -> &[Point<M>] {
+        &self.points
+    }
+
+    /// The weight of each point.
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+}
+
+/// The points of a dataset, in memory or in a mapped file.
+pub(crate) enum Points<const M: usize> {
+    Owned(Vec<Point<M>>),
+    /// A file with M columns.
+    Mapped(MappedFile)
+}
+
+impl<const M: usize> Deref for Points<M> {
+    type Target = [Point<M>];
+
+    fn deref(&self) -> &[Point<M>] {
+        match self {
+            Points::Owned(points) => points,
+            Points::Mapped(file) => file.points().unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clusterer::{Algorithm, KMeans};
+    use super::*;
+
+    #[test]
+    fn fit_dataset() {
+        let points: Vec<Point<2>> = (0..30).map(|i| Point([(i % 10) as f64, (i * i % 7) as f64])).collect();
+        let dataset = Dataset::with_leaf_size(points.clone(), vec![1.0; 30], 1).unwrap();
+        for algorithm in [Algorithm::Simple, Algorithm::Blacklist, Algorithm::Naive] {
+            for random_state in 0..3 {
+                let builder = KMeans::<3, 2>::builder().algorithm(algorithm).random_state(random_state);
+                assert_eq!(builder.fit_dataset(&dataset).unwrap().centers, builder.fit(&points).unwrap().centers);
+            }
+        }
+        assert_eq!(KMeans::<2, 2>::builder().random_state(0).fit_dataset(&dataset).unwrap().point_centers.len(), 30);
+        assert!(Dataset::new(vec![Point([f64::NAN, 0.0])]).is_err());
+
+        let path = std::env::temp_dir().join("kmeans_fit_dataset.bin");
+        let mut writer = crate::io::binary::BinaryWriter::new(std::fs::File::create(&path).unwrap()).unwrap();
+        writer.write_points(&points).unwrap();
+        writer.finish(&["x".to_owned(), "y".to_owned()]).unwrap();
+        let mapped = Dataset::<2>::mmap(&path).unwrap();
+        assert!(Dataset::<3>::mmap(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(mapped.points(), points);
+        let builder = KMeans::<3, 2>::builder().random_state(0);
+        assert_eq!(builder.fit_dataset(&mapped).unwrap().centers, builder.fit(&points).unwrap().centers);
+    }
+}
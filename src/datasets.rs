@@ -0,0 +1,73 @@
+//! Seeded generators of synthetic datasets with known clusters, for example to test or benchmark
+//! clustering algorithms. Every generator returns the points and the index of the cluster that
+//! each point was drawn from, and gives the same dataset for the same random state.
+
+use crate::point::Point;
+use crate::seeding::{standard_normal, SeedSequence};
+use rand::Rng;
+
+/// `n` points drawn from isotropic Gaussian blobs with standard deviation `std_dev` around
+/// `centers`. The points are spread over the blobs in turn, so their sizes differ by at most 1.
+///
+/// Time complexity: O(n * M)
+///
+/// # Panics
+///
+/// If there are no centers.
+pub fn gaussian_blobs<const M: usize>(n: usize, centers: &[Point<M>], std_dev: f64, random_state: u64) -> (Vec<Point<M>>, Vec<usize>) {
+    assert!(!centers.is_empty(), "blobs need at least one center");
+
+    let mut rng = SeedSequence::new(random_state).rng();
+    let labels: Vec<usize> = (0..n).map(|i| i % centers.len()).collect();
+    let points = labels.iter()
+        .map(|&k| Point(std::array::from_fn(|d| centers[k].0[d] + std_dev * standard_normal(&mut rng))))
+        .collect();
+    (points, labels)
+}
+
+/// `n` points on concentric circles around the origin with `radii`, where the distance of every
+/// point to the origin has Gaussian noise with standard deviation `noise`. The points are spread
+/// over the rings in turn, so their sizes differ by at most 1.
+///
+/// Rings are not linearly separable, so centroid-based algorithms such as k-means are expected to
+/// cut them into wedges instead, which makes them a counterexample rather than a benchmark of
+/// accuracy.
+///
+/// Time complexity: O(n)
+///
+/// # Panics
+///
+/// If there are no radii.
+pub fn rings(n: usize, radii: &[f64], noise: f64, random_state: u64) -> (Vec<Point<2>>, Vec<usize>) {
+    assert!(!radii.is_empty(), "rings need at least one radius");
+
+    let mut rng = SeedSequence::new(random_state).rng();
+    let labels: Vec<usize> = (0..n).map(|i| i % radii.len()).collect();
+    let points = labels.iter().map(|&k| {
+        let angle = rng.gen::<f64>() * 2.0 * std::f64::consts::PI;
+        let radius = radii[k] + noise * standard_normal(&mut rng);
+        Point([radius * angle.cos(), radius * angle.sin()])
+    }).collect();
+    (points, labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gaussian_blobs() {
+        let centers = [Point([0.0, 0.0]), Point([100.0, 0.0])];
+        let (points, labels) = super::gaussian_blobs(1000, &centers, 1.0, 0);
+        assert_eq!(points.len(), 1000);
+        assert_eq!(labels.iter().filter(|&&k| k == 1).count(), 500);
+        assert_eq!(super::gaussian_blobs(1000, &centers, 1.0, 0).0, points);
+
+        // The sample mean and standard deviation of a blob are close to the parameters
+        let blob: Vec<f64> = points.iter().zip(&labels).filter(|(_, &k)| k == 1).map(|(point, _)| point.0[0]).collect();
+        let mean = blob.iter().sum::<f64>() / 500.0;
+        let std_dev = (blob.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / 500.0).sqrt();
+        assert!((mean - 100.0).abs() < 0.2);
+        assert!((std_dev - 1.0).abs() < 0.1);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::error::{check_finite_points, ClusterError};
+use crate::estimator::{Clusterer, Fit};
+use crate::mrkd::Tree;
+use crate::point::Point;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Implements the DBSCAN density-based clustering algorithm (Ester et al., 1996), which finds
+/// clusters of arbitrary shape and labels points in low-density regions as noise. The number of
+/// clusters follows from the data instead of being fixed in advance.
+///
+/// The neighborhoods are found with radius queries on an mrkd-tree.
+///
+/// # References
+///
+/// Ester, M., Kriegel, H.-P., Sander, J., & Xu, X. (1996). A density-based algorithm for
+///     discovering clusters in large spatial databases with noise. Proceedings of the Second
+///     International Conference on Knowledge Discovery and Data Mining, 226–231.
+pub struct Dbscan {
+    /// The number of clusters found.
+    pub clusters: usize,
+    /// The index of the cluster that each point belongs to, or `None` for noise.
+    pub point_clusters: Vec<Option<usize>>,
+    /// Whether each point is a core point, with at least `min_pts` points within distance `eps`.
+    pub core: Vec<bool>
+}
+
+impl Dbscan {
+    /// Cluster `points` as in [`Dbscan::fit`], or an error if they have NaN or infinite
+    /// coordinates.
+    pub fn fit<const M: usize>(points: &[Point<M>], eps: f64, min_pts: usize) -> Result<Self, ClusterError> {
+        let r = points.len();
+        check_finite_points(points, &vec![1.0; r])?;
+        let mut point_clusters = vec![Option::None; r];
+        let mut core = vec![false; r];
+        let mut clusters = 0;
+        if r == 0 {
+            return Ok(Dbscan { clusters, point_clusters, core })
+        }
+
+        // The tree does not depend on the pivots chosen while building it
+        let tree = Tree::initialize(points, &mut StdRng::seed_from_u64(0));
+        let mut visited = vec![false; r];
+
+        for i in 0..r {
+            if visited[i] {
+                continue;
+            }
+            visited[i] = true;
+
+            let neighbors = tree.within_radius(&points[i], eps);
+            if neighbors.len() < min_pts {
+                // Noise, unless it is later found to be in the neighborhood of a core point
+                continue;
+            }
+
+            // Expand a new cluster from the core point
+            let cluster = clusters;
+            clusters += 1;
+            core[i] = true;
+            point_clusters[i] = Option::Some(cluster);
+            let mut queue = neighbors;
+            while let Option::Some(j) = queue.pop() {
+                if point_clusters[j].is_none() {
+                    point_clusters[j] = Option::Some(cluster);
+                }
+                if visited[j] {
+                    continue;
+                }
+                visited[j] = true;
+
+                let neighbors = tree.within_radius(&points[j], eps);
+                if neighbors.len() >= min_pts {
+                    core[j] = true;
+                    queue.extend(neighbors);
+                }
+            }
+        }
+
+        Ok(Dbscan { clusters, point_clusters, core })
+    }
+}
+
+/// The parameters of [`Dbscan::fit`], to fit it through the [`Fit`] trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DbscanParams {
+    eps: f64,
+    min_pts: usize
+}
+
+impl DbscanParams {
+    /// Find clusters of core points with at least `min_pts` points within distance `eps`.
+    pub fn new(eps: f64, min_pts: usize) -> Self {
+        Self { eps, min_pts }
+    }
+}
+
+impl<const M: usize> Fit<M> for DbscanParams {
+    type Model = Dbscan;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<Dbscan, ClusterError> {
+        Dbscan::fit(points, self.eps, self.min_pts)
+    }
+}
+
+impl<const M: usize> Clusterer<M> for Dbscan {
+    fn n_clusters(&self) -> usize {
+        self.clusters
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_clusters.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit() {
+        // Two elongated clusters, one with a duplicate point, and an outlier
+        let points = [
+            Point([0.0, 0.0]),
+            Point([1.0, 0.0]),
+            Point([2.0, 0.0]),
+            Point([2.0, 0.0]),
+            Point([0.0, 5.0]),
+            Point([1.0, 5.0]),
+            Point([2.0, 5.0]),
+            Point([10.0, 10.0])
+        ];
+        let model = Dbscan::fit(&points, 1.0, 2).unwrap();
+        assert_eq!(model.clusters, 2);
+        assert_eq!(model.point_clusters, [
+            Option::Some(0),
+            Option::Some(0),
+            Option::Some(0),
+            Option::Some(0),
+            Option::Some(1),
+            Option::Some(1),
+            Option::Some(1),
+            Option::None
+        ]);
+        assert!(!model.core[7]);
+
+        assert_eq!(Dbscan::fit::<2>(&[], 1.0, 2).map(|model| model.clusters), Ok(0));
+        assert_eq!(Dbscan::fit(&[Point([0.0, f64::INFINITY])], 1.0, 2).err(), Option::Some(ClusterError::NonFinite { point: 0, dimension: 1 }));
+    }
+}
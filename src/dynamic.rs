@@ -0,0 +1,208 @@
+use crate::error::{check_point_rows, check_rows, ClusterError, PredictError};
+use crate::initialization::random_indices;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Maximum number of iterations.
+const MAX_ITER: usize = 300;
+
+/// A point with a number of dimensions that is only known at runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynPoint (pub Vec<f64>);
+
+impl DynPoint {
+    pub fn new(coords: Vec<f64>) -> Self {
+        Self(coords)
+    }
+
+    /// The origin in `m` dimensions.
+    pub fn zeros(m: usize) -> Self {
+        Self(vec![0.0; m])
+    }
+
+    /// The number of dimensions of the point.
+    pub fn dimensions(&self) -> usize {
+        self.0.len()
+    }
+
+    /// d(x, y) as defined in Section 2 (p. 278) of (Pelleg & Moore, 1999)
+    ///
+    /// Time complexity: O(M)
+    pub fn distance(&self, point: &Self) -> f64 {
+        self.squared_distance(point).sqrt()
+    }
+
+    /// The squared distance, which orders points the same way as [`DynPoint::distance`] without
+    /// the square root.
+    ///
+    /// Time complexity: O(M)
+    pub fn squared_distance(&self, point: &Self) -> f64 {
+        self.0.iter().zip(&point.0).map(|(a, b)| (a - b).powi(2)).sum()
+    }
+}
+
+impl From<Vec<f64>> for DynPoint {
+    fn from(coords: Vec<f64>) -> Self {
+        Self(coords)
+    }
+}
+
+/// Implements [k-means clustering](https://en.wikipedia.org/wiki/K-means_clustering) for data
+/// where both the number of clusters and the number of dimensions are only known at runtime.
+///
+/// Uses Lloyd's algorithm; when the dimensions are known at compile time, [`crate::KMeans`]
+/// offers the accelerated algorithms.
+pub struct DynKMeans {
+    /// The coordinates of the k means.
+    pub centers: Vec<DynPoint>,
+    /// The index of the center that each point belongs to.
+    pub point_centers: Vec<usize>,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the centers converged within the maximum number of iterations.
+    pub converged: bool
+}
+
+impl DynKMeans {
+    /// Get `k` clusters based on `points`, or an error if `k` is 0, the points differ in
+    /// dimensions or have NaN or infinite coordinates, or there are fewer than `k` distinct points.
+    pub fn fit(points: &[DynPoint], k: usize) -> Result<Self, ClusterError> {
+        Self::new(points, k, Option::None)
+    }
+
+    /// Get `k` clusters based on `points` with a pre-determined random state, or an error, see
+    /// [`DynKMeans::fit`].
+    pub fn fit_with_random_state(points: &[DynPoint], k: usize, random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, k, Option::Some(random_state))
+    }
+
+    fn new(points: &[DynPoint], k: usize, random_state: Option<u64>) -> Result<Self, ClusterError> {
+        let r = points.len();
+        let m = points.first().map_or(0, DynPoint::dimensions);
+        check_point_rows(points.iter().map(|point| &point.0[..]), m, k)?;
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        // Initialize centers
+        let mut centers: Vec<DynPoint> = random_indices(r, k, &mut rng).into_iter().map(|i| points[i].clone()).collect();
+        let mut point_centers = vec![0; r];
+        let mut iterations = 0;
+
+        // Update centers
+        let mut converged = false;
+        while iterations < MAX_ITER {
+            iterations += 1;
+            let mut new_centers = vec![DynPoint::zeros(m); k];
+            let mut new_counts = vec![0; k];
+
+            // For each data point
+            for i in 0..r {
+                // Find the closest center
+                let c = closest(&centers, &points[i]);
+
+                // Update the center associated with the data point
+                point_centers[i] = c;
+
+                // Update the center of mass
+                for d in 0..m {
+                    new_centers[c].0[d] += points[i].0[d];
+                }
+                new_counts[c] += 1;
+            }
+
+            // For each new center
+            let mut different = false;
+            for c in 0..k {
+                // Finalize updating the centers of mass, keeping empty centers in place
+                if new_counts[c] == 0 {
+                    continue;
+                }
+                for x in new_centers[c].0.iter_mut() {
+                    *x /= new_counts[c] as f64;
+                }
+
+                // Check whether convergence is reached
+                if centers[c] != new_centers[c] {
+                    different = true;
+                    centers[c] = new_centers[c].clone();
+                }
+            }
+
+            // If all centers are converged, stop
+            if !different {
+                converged = true;
+                break;
+            }
+        }
+
+        Ok(DynKMeans { centers, point_centers, iterations, converged })
+    }
+
+    /// Get the index of the center closest to `point`.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn predict(&self, point: &DynPoint) -> usize {
+        closest(&self.centers, point)
+    }
+
+    /// Get the index of the center closest to each of `points`, or an error if a point has a
+    /// different number of dimensions than the centers or has NaN or infinite coordinates.
+    ///
+    /// Time complexity: O(r * k * M)
+    pub fn try_predict_many(&self, points: &[DynPoint]) -> Result<Vec<usize>, PredictError> {
+        let m = self.centers.first().map_or(0, DynPoint::dimensions);
+        check_rows(points.iter().map(|point| &point.0[..]), m)?;
+        Ok(points.iter().map(|point| self.predict(point)).collect())
+    }
+}
+
+/// Closest center to a point.
+///
+/// Time complexity: O(k * M)
+fn closest(centers: &[DynPoint], point: &DynPoint) -> usize {
+    let mut min_d = f64::INFINITY;
+    let mut min_c = 0;
+
+    for (c, center) in centers.iter().enumerate() {
+        let d = point.squared_distance(center);
+        if d < min_d {
+            min_d = d;
+            min_c = c;
+        }
+    }
+
+    min_c
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        let points: Vec<DynPoint> = vec![
+            DynPoint(vec![0.0, 0.0, 0.0]),
+            DynPoint(vec![1.0, 0.0, 1.0]),
+            DynPoint(vec![10.0, 0.0, 10.0]),
+            DynPoint(vec![11.0, 0.0, 11.0])
+        ];
+        let DynKMeans { centers, point_centers, .. } = DynKMeans::fit_with_random_state(&points, 2, 1).unwrap();
+        assert_eq!(point_centers[0], point_centers[1]);
+        assert_eq!(point_centers[2], point_centers[3]);
+        assert_ne!(point_centers[0], point_centers[2]);
+        assert_eq!(centers[point_centers[0]], DynPoint(vec![0.5, 0.0, 0.5]));
+    }
+
+    #[test]
+    fn invalid_points() {
+        let points = [DynPoint(vec![0.0, 0.0]), DynPoint(vec![1.0, f64::NAN]), DynPoint(vec![2.0])];
+        assert_eq!(DynKMeans::fit(&points[..1], 0).err(), Option::Some(ClusterError::NoClusters));
+        assert_eq!(DynKMeans::fit(&points[..1], 2).err(), Option::Some(ClusterError::TooFewPoints { clusters: 2, distinct: 1 }));
+        assert_eq!(DynKMeans::fit(&points[..2], 1).err(), Option::Some(ClusterError::NonFinite { point: 1, dimension: 1 }));
+        assert_eq!(DynKMeans::fit(&[points[0].clone(), points[2].clone()], 1).err(), Option::Some(ClusterError::DimensionMismatch { point: 1, expected: 2, found: 1 }));
+    }
+}
@@ -0,0 +1,324 @@
+use crate::memory::MemoryBudgetExceeded;
+use crate::point::Point;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The error returned when a model cannot be fitted on the given data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClusterError {
+    /// There are fewer distinct points than clusters.
+    TooFewPoints {
+        /// The number of clusters.
+        clusters: usize,
+        /// The number of distinct points.
+        distinct: usize
+    },
+    /// The number of clusters is 0.
+    NoClusters,
+    /// A point has a different number of coordinates than the first point.
+    DimensionMismatch {
+        /// The index of the point.
+        point: usize,
+        /// The number of coordinates of the first point.
+        expected: usize,
+        /// The number of coordinates of the point.
+        found: usize
+    },
+    /// A coordinate is NaN or infinite.
+    NonFinite {
+        /// The index of the point.
+        point: usize,
+        /// The dimension of the coordinate.
+        dimension: usize
+    },
+    /// The number of weights differs from the number of points.
+    WeightMismatch {
+        /// The number of points.
+        points: usize,
+        /// The number of weights.
+        weights: usize
+    },
+    /// The number of labels differs from the number of points.
+    LabelMismatch {
+        /// The number of points.
+        points: usize,
+        /// The number of labels.
+        labels: usize
+    },
+    /// A label is not the index of a cluster.
+    InvalidLabel {
+        /// The index of the point.
+        point: usize
+    },
+    /// A weight is negative, NaN or infinite.
+    InvalidWeight {
+        /// The index of the point.
+        point: usize
+    },
+    /// The total capacity of the clusters is smaller than the total demand of the points, or NaN.
+    InsufficientCapacity {
+        /// The total demand of the points.
+        demand: f64,
+        /// The total capacity of the clusters.
+        capacity: f64
+    },
+    /// The number of initial centers differs from the number of clusters.
+    CenterCountMismatch {
+        /// The number of clusters.
+        expected: usize,
+        /// The number of initial centers.
+        found: usize
+    },
+    /// An initial center has a different number of coordinates than the points have dimensions.
+    CenterDimensionMismatch {
+        /// The index of the center.
+        center: usize,
+        /// The number of dimensions of the points.
+        expected: usize,
+        /// The number of coordinates of the center.
+        found: usize
+    },
+    /// A coordinate of an initial center is NaN or infinite.
+    NonFiniteCenter {
+        /// The index of the center.
+        center: usize,
+        /// The dimension of the coordinate.
+        dimension: usize
+    },
+    /// The points cannot be divided over the clusters within the cluster sizes.
+    InfeasibleClusterSizes {
+        /// The number of points.
+        points: usize,
+        /// The number of clusters.
+        clusters: usize,
+        /// The smallest number of points per cluster.
+        min: usize,
+        /// The largest number of points per cluster.
+        max: usize
+    },
+    /// A latitude is outside of [-90, 90] degrees.
+    InvalidLatitude {
+        /// The index of the point.
+        point: usize
+    },
+    /// Dimension weights were given for geographic coordinates, which are compared by their
+    /// great-circle distance instead.
+    GeographicWeights,
+    /// The fit would exceed the memory budget.
+    MemoryBudget(MemoryBudgetExceeded)
+}
+
+impl fmt::Display for ClusterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClusterError::TooFewPoints { clusters, distinct } =>
+                write!(f, "cannot make {} clusters out of {} distinct points", clusters, distinct),
+            ClusterError::NoClusters =>
+                write!(f, "the number of clusters must be positive"),
+            ClusterError::DimensionMismatch { point, expected, found } =>
+                write!(f, "point {} has {} coordinates, but the first point has {}", point, found, expected),
+            ClusterError::NonFinite { point, dimension } =>
+                write!(f, "point {} has a NaN or infinite value in dimension {}", point, dimension),
+            ClusterError::WeightMismatch { points, weights } =>
+                write!(f, "{} weights were given for {} points", weights, points),
+            ClusterError::LabelMismatch { points, labels } =>
+                write!(f, "{} labels were given for {} points", labels, points),
+            ClusterError::InvalidLabel { point } =>
+                write!(f, "point {} has a label that is not the index of a cluster", point),
+            ClusterError::InvalidWeight { point } =>
+                write!(f, "point {} has a negative, NaN or infinite weight", point),
+            ClusterError::InsufficientCapacity { demand, capacity } =>
+                write!(f, "the total capacity {} is smaller than the total demand {}", capacity, demand),
+            ClusterError::CenterCountMismatch { expected, found } =>
+                write!(f, "{} initial centers were given for {} clusters", found, expected),
+            ClusterError::CenterDimensionMismatch { center, expected, found } =>
+                write!(f, "initial center {} has {} coordinates, but the points have {} dimensions", center, found, expected),
+            ClusterError::NonFiniteCenter { center, dimension } =>
+                write!(f, "initial center {} has a NaN or infinite value in dimension {}", center, dimension),
+            ClusterError::InfeasibleClusterSizes { points, clusters, min, max } =>
+                write!(f, "cannot divide {} points over {} clusters of {} to {} points", points, clusters, min, max),
+            ClusterError::InvalidLatitude { point } =>
+                write!(f, "point {} has a latitude outside of [-90, 90]", point),
+            ClusterError::GeographicWeights =>
+                write!(f, "dimension weights do not apply to geographic coordinates"),
+            ClusterError::MemoryBudget(error) => error.fmt(f)
+        }
+    }
+}
+
+impl std::error::Error for ClusterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClusterError::MemoryBudget(error) => Option::Some(error),
+            _ => Option::None
+        }
+    }
+}
+
+impl From<MemoryBudgetExceeded> for ClusterError {
+    fn from(error: MemoryBudgetExceeded) -> Self {
+        ClusterError::MemoryBudget(error)
+    }
+}
+
+/// Check that there is at least one cluster, that there is a finite, non-negative weight for every
+/// point, that every point has finite coordinates, and that there are at least `k` distinct points.
+///
+/// Time complexity: O(r * M)
+pub(crate) fn check_points<const M: usize>(points: &[Point<M>], weights: &[f64], k: usize) -> Result<(), ClusterError> {
+    if k == 0 {
+        return Err(ClusterError::NoClusters)
+    }
+    check_distinct_points(points, weights, k)
+}
+
+/// Check that there is a finite, non-negative weight for every point and that every point has
+/// finite coordinates, for points that are not clustered into a fixed number of clusters.
+///
+/// Time complexity: O(r * M)
+pub(crate) fn check_finite_points<const M: usize>(points: &[Point<M>], weights: &[f64]) -> Result<(), ClusterError> {
+    check_distinct_points(points, weights, 0)
+}
+
+/// Check the weights and coordinates of the points, and that there are at least `k` distinct
+/// points.
+///
+/// Time complexity: O(r * M)
+fn check_distinct_points<const M: usize>(points: &[Point<M>], weights: &[f64], k: usize) -> Result<(), ClusterError> {
+    if points.len() != weights.len() {
+        return Err(ClusterError::WeightMismatch { points: points.len(), weights: weights.len() })
+    }
+    if let Option::Some(point) = weights.iter().position(|w| !(w.is_finite() && *w >= 0.0)) {
+        return Err(ClusterError::InvalidWeight { point })
+    }
+
+    // Adding 0 turns -0 into 0, so that both have the same bits
+    let mut distinct = HashSet::new();
+    for (i, point) in points.iter().enumerate() {
+        if let Option::Some(dimension) = point.0.iter().position(|x| !x.is_finite()) {
+            return Err(ClusterError::NonFinite { point: i, dimension })
+        }
+        if distinct.len() < k {
+            distinct.insert(point.0.map(|x| (x + 0.0).to_bits()));
+        }
+    }
+    if distinct.len() < k {
+        return Err(ClusterError::TooFewPoints { clusters: k, distinct: distinct.len() })
+    }
+    Ok(())
+}
+
+/// Check that there is at least one cluster, that every row has `m` finite values, and that there
+/// are at least `k` distinct rows, for points whose number of dimensions is only known at runtime
+/// or that are converted on the fly.
+///
+/// Time complexity: O(r * M)
+pub(crate) fn check_point_rows<R: AsRef<[f64]>>(rows: impl IntoIterator<Item = R>, m: usize, k: usize) -> Result<(), ClusterError> {
+    if k == 0 {
+        return Err(ClusterError::NoClusters)
+    }
+
+    let mut distinct = HashSet::new();
+    for (i, row) in rows.into_iter().enumerate() {
+        let values = row.as_ref();
+        if values.len() != m {
+            return Err(ClusterError::DimensionMismatch { point: i, expected: m, found: values.len() })
+        }
+        if let Option::Some(dimension) = values.iter().position(|x| !x.is_finite()) {
+            return Err(ClusterError::NonFinite { point: i, dimension })
+        }
+        if distinct.len() < k {
+            distinct.insert(values.iter().map(|x| (x + 0.0).to_bits()).collect::<Vec<_>>());
+        }
+    }
+    if distinct.len() < k {
+        return Err(ClusterError::TooFewPoints { clusters: k, distinct: distinct.len() })
+    }
+    Ok(())
+}
+
+/// Check that `r` points can be divided over `k` clusters of `sizes` points, if given.
+///
+/// Time complexity: O(1)
+pub(crate) fn check_cluster_sizes(r: usize, k: usize, sizes: Option<(usize, usize)>) -> Result<(), ClusterError> {
+    match sizes {
+        Option::Some((min, max)) if min > max || min.saturating_mul(k) > r || max.saturating_mul(k) < r =>
+            Err(ClusterError::InfeasibleClusterSizes { points: r, clusters: k, min, max }),
+        _ => Ok(())
+    }
+}
+
+/// Check that there are `k` centers with `m` finite coordinates each.
+///
+/// Time complexity: O(k * M)
+pub(crate) fn check_centers<'a>(centers: impl ExactSizeIterator<Item = &'a [f64]>, k: usize, m: usize) -> Result<(), ClusterError> {
+    if centers.len() != k {
+        return Err(ClusterError::CenterCountMismatch { expected: k, found: centers.len() })
+    }
+    for (center, values) in centers.enumerate() {
+        if values.len() != m {
+            return Err(ClusterError::CenterDimensionMismatch { center, expected: m, found: values.len() })
+        }
+        if let Option::Some(dimension) = values.iter().position(|x| !x.is_finite()) {
+            return Err(ClusterError::NonFiniteCenter { center, dimension })
+        }
+    }
+    Ok(())
+}
+
+/// The error returned when data passed to a fitted model does not match what the model was fitted
+/// on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PredictError {
+    /// A row has a different number of values than the model has dimensions.
+    DimensionMismatch {
+        /// The index of the row.
+        row: usize,
+        /// The number of dimensions of the model.
+        expected: usize,
+        /// The number of values in the row.
+        found: usize
+    },
+    /// A value is NaN or infinite.
+    NonFinite {
+        /// The index of the row.
+        row: usize,
+        /// The dimension of the value.
+        dimension: usize
+    },
+    /// A feature of the model is not among the columns of the data.
+    MissingColumn {
+        /// The name of the feature.
+        name: String
+    }
+}
+
+impl fmt::Display for PredictError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PredictError::DimensionMismatch { row, expected, found } =>
+                write!(f, "row {} has {} values, but the model has {} dimensions", row, found, expected),
+            PredictError::NonFinite { row, dimension } =>
+                write!(f, "row {} has a NaN or infinite value in dimension {}", row, dimension),
+            PredictError::MissingColumn { name } =>
+                write!(f, "the data has no column for feature {}", name)
+        }
+    }
+}
+
+impl std::error::Error for PredictError {}
+
+/// Check that every row has `m` finite values.
+///
+/// Time complexity: O(r * M)
+pub(crate) fn check_rows<'a>(rows: impl IntoIterator<Item = &'a [f64]>, m: usize) -> Result<(), PredictError> {
+    for (row, values) in rows.into_iter().enumerate() {
+        if values.len() != m {
+            return Err(PredictError::DimensionMismatch { row, expected: m, found: values.len() })
+        }
+        if let Option::Some(dimension) = values.iter().position(|x| !x.is_finite()) {
+            return Err(PredictError::NonFinite { row, dimension })
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use crate::error::ClusterError;
+use crate::point::Point;
+
+/// A clustering of the points an estimator was fitted on. Implemented by the fitted models, so
+/// that pipelines can report on them without knowing the algorithm, for example behind a
+/// `Box<dyn Clusterer<M>>`.
+pub trait Clusterer<const M: usize> {
+    /// The number of clusters.
+    fn n_clusters(&self) -> usize;
+
+    /// The index of the cluster that each point belongs to, or `None` for points that belong to
+    /// no cluster, such as noise or trimmed points.
+    fn labels(&self) -> Vec<Option<usize>>;
+
+    /// The center of every cluster, if the clusters have centers.
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::None
+    }
+}
+
+/// A clustering that can also assign new points to its clusters. The points are of type `P`,
+/// which is a [`Point`] except for estimators of other data, such as the rows of
+/// [`crate::mixed::KModes`].
+pub trait Predict<const M: usize, P = Point<M>>: Clusterer<M> {
+    /// The index of the cluster that `point` belongs to, or `None` if it belongs to no cluster.
+    fn predict(&self, point: &P) -> Option<usize>;
+
+    /// The index of the cluster that each of `points` belongs to.
+    fn predict_many(&self, points: &[P]) -> Vec<Option<usize>> {
+        points.iter().map(|point| self.predict(point)).collect()
+    }
+}
+
+/// A configured estimator that fits a model on points of type `P`, such as the
+/// [`crate::KMeansBuilder`] or the parameters of the other estimators, for example
+/// [`crate::TrimmedKMeansParams`].
+pub trait Fit<const M: usize, P = Point<M>> {
+    /// The fitted model.
+    type Model: Clusterer<M>;
+
+    /// Fit a model on `points`.
+    fn fit(&self, points: &[P]) -> Result<Self::Model, ClusterError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::KMeansBuilder;
+    use crate::dbscan::Dbscan;
+    use crate::mixed::KModesParams;
+    use crate::trimmed::TrimmedKMeansParams;
+    use super::*;
+
+    fn sizes<const M: usize>(clusterer: &dyn Clusterer<M>) -> Vec<usize> {
+        let mut sizes = vec![0; clusterer.n_clusters()];
+        for label in clusterer.labels().into_iter().flatten() {
+            sizes[label] += 1;
+        }
+        sizes
+    }
+
+    /// The labels of the points that `estimator` is fitted on, and the clusters they are
+    /// predicted to belong to.
+    fn fit_predict<const M: usize, P, E: Fit<M, P>>(estimator: &E, points: &[P]) -> (Vec<Option<usize>>, Vec<Option<usize>>)
+    where E::Model: Predict<M, P> {
+        let model = estimator.fit(points).unwrap();
+        (model.labels(), model.predict_many(points))
+    }
+
+    #[test]
+    fn clusterers() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([0.0, 2.0]),
+            Point([9.0, 0.0]),
+            Point([9.0, 1.0]),
+            Point([20.0, 20.0])
+        ];
+        let builder = KMeansBuilder::<3, 2>::new().initial_centers([points[0], points[3], points[5]]);
+        let kmeans = Fit::fit(&builder, &points).unwrap();
+        let dbscan = Dbscan::fit(&points, 1.5, 2).unwrap();
+        let clusterers: [Box<dyn Clusterer<2>>; 2] = [Box::new(kmeans), Box::new(dbscan)];
+        assert_eq!(sizes(clusterers[0].as_ref()), vec![3, 2, 1]);
+        assert_eq!(sizes(clusterers[1].as_ref()), vec![3, 2]);
+        assert_eq!(clusterers[0].centers().unwrap()[1], Point([9.0, 0.5]));
+        assert_eq!(clusterers[1].centers(), Option::None);
+
+        let kmeans = Fit::fit(&builder, &points).unwrap();
+        assert_eq!(Predict::predict_many(&kmeans, &[Point([8.0, 0.0])]), vec![Option::Some(1)]);
+    }
+
+    #[test]
+    fn estimators() {
+        let points = [Point([0.0, 0.0]), Point([0.0, 1.0]), Point([9.0, 0.0]), Point([9.0, 1.0])];
+        let builder = KMeansBuilder::<2, 2>::new().initial_centers([points[0], points[2]]);
+        let expected = vec![Option::Some(0), Option::Some(0), Option::Some(1), Option::Some(1)];
+        assert_eq!(fit_predict(&builder, &points), (expected.clone(), expected));
+
+        let (labels, predictions) = fit_predict(&TrimmedKMeansParams::<2>::new(0.0).random_state(0), &points);
+        assert_eq!(labels, predictions);
+        assert_eq!(labels[0], labels[1]);
+        assert_eq!(labels[2], labels[3]);
+        assert_ne!(labels[0], labels[2]);
+
+        let rows = [[0, 0], [0, 1], [2, 2], [3, 2]];
+        let (labels, predictions) = fit_predict(&KModesParams::<2>::new().random_state(0), &rows);
+        assert_eq!(labels, predictions);
+        assert!(labels.iter().all(Option::is_some));
+    }
+}
@@ -0,0 +1,220 @@
+use crate::error::{check_point_rows, ClusterError};
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::initialization::random_points;
+use crate::point::Point;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Maximum number of iterations.
+const MAX_ITER: usize = 300;
+
+/// Implements entropy weighting k-means (EWKM) as described in (Jing et al., 2007), which learns a
+/// weight for each dimension in each cluster while fitting, so that clusters can live in a soft
+/// subspace of the data.
+///
+/// # References
+///
+/// Jing, L., Ng, M. K., & Huang, J. Z. (2007). An entropy weighting k-means algorithm for subspace
+///     clustering of high-dimensional sparse data. IEEE Transactions on Knowledge and Data
+///     Engineering, 19(8), 1026–1041. <https://doi.org/10.1109/TKDE.2007.1048>
+pub struct EntropyWeightedKMeans<const K: usize, const M: usize> {
+    /// The coordinates of the k means.
+    pub centers: [Point<M>; K],
+    /// The index of the center that each point belongs to.
+    pub point_centers: Vec<usize>,
+    /// The weight of each dimension in each cluster. The weights of a cluster sum to 1.
+    pub feature_weights: [[f64; M]; K],
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the assignments converged within the maximum number of iterations.
+    pub converged: bool
+}
+
+impl<const K: usize, const M: usize> EntropyWeightedKMeans<K, M> {
+    /// Get k clusters based on `points`, or an error if they have NaN or infinite coordinates or
+    /// there are fewer than k distinct points.
+    pub fn fit(points: &[Point<M>], gamma: f64) -> Result<Self, ClusterError> {
+        Self::new(points, gamma, Option::None)
+    }
+
+    /// Get k clusters based on `points` with a pre-determined random state, or an error, see
+    /// [`EntropyWeightedKMeans::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], gamma: f64, random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, gamma, Option::Some(random_state))
+    }
+
+    fn new(points: &[Point<M>], gamma: f64, random_state: Option<u64>) -> Result<Self, ClusterError> {
+        let r = points.len();
+        check_point_rows(points.iter().map(|point| point.0), M, K)?;
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        // Initialize centers and weights
+        let mut centers = random_points(points, &mut rng);
+        let mut feature_weights = [[1.0 / M as f64; M]; K];
+        let mut point_centers = vec![usize::MAX; r];
+
+        // The weights change the objective function every iteration, so stopping when no
+        // assignment changes is not guaranteed
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < MAX_ITER {
+            iterations += 1;
+
+            // Assign each point to the center with the lowest weighted distance
+            let mut different = false;
+            for i in 0..r {
+                let c = Self::closest(&centers, &feature_weights, &points[i]);
+                if point_centers[i] != c {
+                    point_centers[i] = c;
+                    different = true;
+                }
+            }
+
+            // If no assignment changed, the objective function cannot decrease any further
+            if !different {
+                converged = true;
+                break;
+            }
+
+            // Update the centers of mass, keeping empty centers in place
+            let mut sums = [Point::default(); K];
+            let mut counts = [0; K];
+            for (point, &k) in points.iter().zip(&point_centers) {
+                sums[k] += *point;
+                counts[k] += 1;
+            }
+            for k in 0..K {
+                if counts[k] > 0 {
+                    centers[k] = sums[k] / counts[k];
+                }
+            }
+
+            // Update the weights based on the dispersion of each dimension within each cluster
+            let mut dispersion = [[0.0; M]; K];
+            for (point, &k) in points.iter().zip(&point_centers) {
+                for ((dispersion, x), c) in dispersion[k].iter_mut().zip(point.0).zip(centers[k].0) {
+                    *dispersion += (x - c).powi(2);
+                }
+            }
+            for k in 0..K {
+                feature_weights[k] = Self::entropy_weights(&dispersion[k], gamma);
+            }
+        }
+
+        Ok(EntropyWeightedKMeans { centers, point_centers, feature_weights, iterations, converged })
+    }
+
+    /// Weights as defined in Theorem 3 of (Jing et al., 2007)
+    ///
+    /// Time complexity: O(M)
+    fn entropy_weights(dispersion: &[f64; M], gamma: f64) -> [f64; M] {
+        // Subtract the smallest dispersion to avoid all exponentials underflowing
+        let min = dispersion.iter().cloned().fold(f64::INFINITY, f64::min);
+        let mut weights = dispersion.map(|x| (-(x - min) / gamma).exp());
+        let total: f64 = weights.iter().sum();
+        for w in weights.iter_mut() {
+            *w /= total;
+        }
+        weights
+    }
+
+    /// Time complexity: O(k * M)
+    fn closest(centers: &[Point<M>; K], weights: &[[f64; M]; K], point: &Point<M>) -> usize {
+        let mut min_d = f64::INFINITY;
+        let mut min_c = 0;
+
+        for k in 0..K {
+            let d: f64 = (0..M).map(|d| weights[k][d] * (point.0[d] - centers[k].0[d]).powi(2)).sum();
+            if d < min_d {
+                min_d = d;
+                min_c = k;
+            }
+        }
+
+        min_c
+    }
+}
+
+/// The parameters of [`EntropyWeightedKMeans::fit`], to fit it through the [`Fit`] trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntropyWeightedKMeansParams<const K: usize> {
+    gamma: f64,
+    random_state: Option<u64>
+}
+
+impl<const K: usize> EntropyWeightedKMeansParams<K> {
+    /// Fit with the entropy weight `gamma`.
+    pub fn new(gamma: f64) -> Self {
+        Self { gamma, random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for EntropyWeightedKMeansParams<K> {
+    type Model = EntropyWeightedKMeans<K, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<EntropyWeightedKMeans<K, M>, ClusterError> {
+        EntropyWeightedKMeans::new(points, self.gamma, self.random_state)
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for EntropyWeightedKMeans<K, M> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.iter().map(|&k| Option::Some(k)).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.to_vec())
+    }
+}
+
+impl<const K: usize, const M: usize> Predict<M> for EntropyWeightedKMeans<K, M> {
+    /// The closest center under the weighted distance of its cluster.
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        Option::Some(Self::closest(&self.centers, &self.feature_weights, point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        // The first dimension separates the clusters, the second is noise
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.1, 5.0]),
+            Point([0.2, 9.0]),
+            Point([10.0, 1.0]),
+            Point([10.1, 4.0]),
+            Point([10.2, 8.0])
+        ];
+        let model = EntropyWeightedKMeans::<2, 2>::fit_with_random_state(&points, 1.0, 2).unwrap();
+        let labels = &model.point_centers;
+        assert!(labels[0] == labels[1] && labels[1] == labels[2]);
+        assert!(labels[3] == labels[4] && labels[4] == labels[5]);
+        assert_ne!(labels[0], labels[3]);
+        for weights in model.feature_weights {
+            assert!(weights[0] > weights[1]);
+        }
+
+        let points = [Point([0.0, 0.0]), Point([f64::NAN, 1.0])];
+        assert_eq!(EntropyWeightedKMeans::<2, 2>::fit(&points, 1.0).err(), Option::Some(ClusterError::NonFinite { point: 1, dimension: 0 }));
+    }
+}
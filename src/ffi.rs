@@ -0,0 +1,134 @@
+//! A C API over row-major `double` buffers, declared in `include/kmeans.h`:
+//!
+//! ```c
+//! KMeansModel *model = kmeans_fit(data, n, m, k, seed);
+//! if (model != NULL && kmeans_predict(model, queries, q, m, labels) == 0) { ... }
+//! kmeans_free(model);
+//! ```
+//!
+//! Invalid arguments and panics are reported through the return value instead of unwinding into
+//! the caller. The header is generated with `cbindgen --config cbindgen.toml --output include/kmeans.h`.
+
+use crate::dynamic::{DynKMeans, DynPoint};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// A fitted model, owned by the caller until it is passed to [`kmeans_free`].
+pub struct KMeansModel(DynKMeans);
+
+/// Fit k clusters on the `n` rows of `m` values in `data`, with a pre-determined random state.
+/// Returns NULL if `data` is NULL, `m` is 0, there are fewer than `k` distinct rows or `k` is 0, or a value
+/// is NaN or infinite.
+///
+/// # Safety
+///
+/// `data` must point to `n * m` readable values.
+#[no_mangle]
+pub unsafe extern "C" fn kmeans_fit(data: *const f64, n: usize, m: usize, k: usize, seed: u64) -> *mut KMeansModel {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let points = read_points(data, n, m)?;
+        DynKMeans::fit_with_random_state(&points, k, seed).ok()
+    }));
+
+    match result {
+        Ok(Option::Some(kmeans)) => Box::into_raw(Box::new(KMeansModel(kmeans))),
+        _ => std::ptr::null_mut()
+    }
+}
+
+/// Write the index of the closest center of each of the `n` rows of `m` values in `data` to
+/// `labels`. Returns 0 on success, or -1 if an argument is NULL, `m` differs from the number of
+/// dimensions of the model, or a value is NaN or infinite, in which case `labels` is not written.
+///
+/// # Safety
+///
+/// `model` must have been returned by [`kmeans_fit`] and not freed, `data` must point to `n * m`
+/// readable values and `labels` to `n` writable values.
+#[no_mangle]
+pub unsafe extern "C" fn kmeans_predict(model: *const KMeansModel, data: *const f64, n: usize, m: usize, labels: *mut usize) -> i32 {
+    if model.is_null() || labels.is_null() {
+        return -1
+    }
+    let model = &(*model).0;
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let points = read_points(data, n, m)?;
+        model.try_predict_many(&points).ok()
+    }));
+
+    match result {
+        Ok(Option::Some(predicted)) => {
+            std::slice::from_raw_parts_mut(labels, n).copy_from_slice(&predicted);
+            0
+        },
+        _ => -1
+    }
+}
+
+/// Free a model returned by [`kmeans_fit`]. Does nothing if `model` is NULL.
+///
+/// # Safety
+///
+/// `model` must have been returned by [`kmeans_fit`] and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn kmeans_free(model: *mut KMeansModel) {
+    if !model.is_null() {
+        drop(Box::from_raw(model));
+    }
+}
+
+/// Split a row-major buffer into `n` points of `m` dimensions, or `None` if it is NULL, `m` is 0,
+/// or a value is NaN or infinite.
+///
+/// # Safety
+///
+/// `data` must point to `n * m` readable values.
+unsafe fn read_points(data: *const f64, n: usize, m: usize) -> Option<Vec<DynPoint>> {
+    if data.is_null() || m == 0 {
+        return Option::None
+    }
+    let values = std::slice::from_raw_parts(data, n.checked_mul(m)?);
+    if values.iter().any(|x| !x.is_finite()) {
+        return Option::None
+    }
+    Option::Some(values.chunks(m).map(|row| DynPoint(row.to_vec())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    // Resolve the functions by their exported symbols, with the model as an opaque type, as a C
+    // caller would
+    #[repr(C)]
+    struct KMeansModel {
+        _private: [u8; 0]
+    }
+
+    extern "C" {
+        fn kmeans_fit(data: *const f64, n: usize, m: usize, k: usize, seed: u64) -> *mut KMeansModel;
+        fn kmeans_predict(model: *const KMeansModel, data: *const f64, n: usize, m: usize, labels: *mut usize) -> i32;
+        fn kmeans_free(model: *mut KMeansModel);
+    }
+
+    #[test]
+    fn fit_predict_free() {
+        let data = [0.0, 0.0, 0.0, 1.0, 10.0, 10.0, 10.0, 11.0];
+        let queries = [0.5, 0.5, 9.0, 9.0, 1.0, 0.0];
+        let mut labels = [usize::MAX; 3];
+        unsafe {
+            let model = kmeans_fit(data.as_ptr(), 4, 2, 2, 0);
+            assert!(!model.is_null());
+            assert_eq!(kmeans_predict(model, queries.as_ptr(), 3, 2, labels.as_mut_ptr()), 0);
+            assert_eq!(labels[2], labels[0]);
+            assert_ne!(labels[1], labels[0]);
+
+            // The model has 2 dimensions, and the labels are left alone on errors
+            let before = labels;
+            assert_eq!(kmeans_predict(model, queries.as_ptr(), 2, 3, labels.as_mut_ptr()), -1);
+            assert_eq!(kmeans_predict(model, [f64::NAN, 0.0].as_ptr(), 1, 2, labels.as_mut_ptr()), -1);
+            assert_eq!(labels, before);
+            kmeans_free(model);
+
+            assert!(kmeans_fit(data.as_ptr(), 4, 2, 5, 0).is_null());
+            assert!(kmeans_fit(std::ptr::null(), 4, 2, 2, 0).is_null());
+            kmeans_free(std::ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,281 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Amounts below this are treated as zero.
+const EPS: f64 = 1e-9;
+
+/// Solve the transportation problem: ship `supply[i]` units from every source `i` to the sinks, such
+/// that no sink `k` receives more than `capacity[k]` units, at the minimum total cost, where sending
+/// one unit from `i` to `k` costs `cost[i][k]`. Returns the amount sent from every source to every
+/// sink. The total capacity should be at least the total supply; otherwise the capacity is filled,
+/// but not necessarily at the minimum cost.
+///
+/// Uses the successive shortest path algorithm, adding one source at a time and sending its supply
+/// along shortest paths in the residual network. As there are far fewer sinks than sources, the
+/// paths are searched over the sinks only: an edge from sink a to sink b moves units that some
+/// source i sends to a over to b, at a cost of cost[i][b] - cost[i][a], and a heap per pair of
+/// sinks keeps the cheapest such move. Potentials on the sinks make the reduced costs of these
+/// moves non-negative (Edmonds & Karp, 1972), so that the shortest paths can be found with
+/// Dijkstra's algorithm.
+///
+/// # References
+///
+/// Edmonds, J., & Karp, R. M. (1972). Theoretical improvements in algorithmic efficiency for
+///     network flow problems. Journal of the ACM, 19(2), 248–264. <https://doi.org/10.1145/321694.321699>
+///
+/// Time complexity: O(p * k^2 * log(r * k)), with p augmenting paths, usually about r
+pub(crate) fn transport(supply: &[f64], capacity: &[f64], cost: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let r = supply.len();
+    let k = capacity.len();
+    let mut flow = vec![vec![0.0; k]; r];
+    let mut capacity_left = capacity.to_vec();
+
+    // The moves from sink a to sink b, at index a * k + b, and the potentials of the sinks
+    let mut moves: Vec<BinaryHeap<Move>> = (0..k * k).map(|_| BinaryHeap::new()).collect();
+    let mut potentials = vec![0.0; k];
+
+    for source in 0..r {
+        let mut supply_left = supply[source];
+        while supply_left > EPS {
+            // Shortest paths from the source, which reaches every sink directly, in reduced costs
+            let mut d: Vec<f64> = (0..k).map(|c| cost[source][c] - potentials[c]).collect();
+            let mut pred: Vec<Option<(usize, usize)>> = vec![Option::None; k];
+            let mut done = vec![false; k];
+            for _ in 0..k {
+                let a = (0..k).filter(|&c| !done[c]).min_by(|&x, &y| d[x].total_cmp(&d[y])).unwrap();
+                done[a] = true;
+                for b in (0..k).filter(|&c| !done[c]) {
+                    if let Option::Some(Move { cost: move_cost, source: i }) = cheapest(&mut moves[a * k + b], &flow, a) {
+                        let reduced = d[a] + move_cost + potentials[a] - potentials[b];
+                        if reduced < d[b] {
+                            d[b] = reduced;
+                            pred[b] = Option::Some((a, i));
+                        }
+                    }
+                }
+            }
+
+            // The distances become the potentials, which keeps the reduced costs of all moves,
+            // including the ones added below, non-negative
+            for c in 0..k {
+                potentials[c] += d[c];
+            }
+
+            // Find the closest sink with capacity left
+            let target = (0..k)
+                .filter(|&c| capacity_left[c] > EPS)
+                .min_by(|&a, &b| potentials[a].total_cmp(&potentials[b]));
+            let target = match target {
+                Option::Some(c) => c,
+                None => return flow
+            };
+
+            // Determine how much can be sent along the path
+            let mut amount = supply_left.min(capacity_left[target]);
+            let mut c = target;
+            while let Option::Some((previous, i)) = pred[c] {
+                amount = amount.min(flow[i][previous]);
+                c = previous;
+            }
+
+            // Send it, moving the units of the sources along the path
+            let mut c = target;
+            while let Option::Some((previous, i)) = pred[c] {
+                send(&mut flow, &mut moves, cost, i, c, amount);
+                flow[i][previous] -= amount;
+                if flow[i][previous] <= EPS {
+                    flow[i][previous] = 0.0;
+                }
+                c = previous;
+            }
+            send(&mut flow, &mut moves, cost, source, c, amount);
+            supply_left -= amount;
+            capacity_left[target] -= amount;
+        }
+    }
+
+    flow
+}
+
+/// Moving the units that `source` sends to one sink over to another sink, at `cost` per unit.
+/// Ordered such that a [`BinaryHeap`] returns the cheapest move first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Move {
+    cost: f64,
+    source: usize
+}
+
+impl Eq for Move {}
+
+impl PartialOrd for Move {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Option::Some(self.cmp(other))
+    }
+}
+
+impl Ord for Move {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost).then(other.source.cmp(&self.source))
+    }
+}
+
+/// The cheapest move out of sink `a`, dropping the moves of sources that no longer send to it.
+///
+/// Time complexity: O(log(r)) amortized
+fn cheapest(moves: &mut BinaryHeap<Move>, flow: &[Vec<f64>], a: usize) -> Option<Move> {
+    while let Option::Some(next) = moves.peek() {
+        if flow[next.source][a] > EPS {
+            return Option::Some(*next)
+        }
+        moves.pop();
+    }
+    Option::None
+}
+
+/// Send `amount` more units from `source` to `sink`, adding the moves out of the sink if the source
+/// did not send to it yet.
+///
+/// Time complexity: O(k * log(r))
+fn send(flow: &mut [Vec<f64>], moves: &mut [BinaryHeap<Move>], cost: &[Vec<f64>], source: usize, sink: usize, amount: f64) {
+    let k = cost[source].len();
+    if flow[source][sink] <= EPS {
+        for b in (0..k).filter(|&b| b != sink) {
+            moves[sink * k + b].push(Move { cost: cost[source][b] - cost[source][sink], source });
+        }
+    }
+    flow[source][sink] += amount;
+}
+
+/// Solve the assignment problem: assign every row of the square matrix `cost` to a different
+/// column, at the minimum total cost. Returns the column of every row.
+///
+/// Uses the Hungarian algorithm (Kuhn, 1955) with shortest augmenting paths and potentials, as in
+/// (Jonker & Volgenant, 1987), adding one row at a time.
+///
+/// # References
+///
+/// Kuhn, H. W. (1955). The Hungarian method for the assignment problem. Naval Research Logistics
+///     Quarterly, 2(1–2), 83–97. <https://doi.org/10.1002/nav.3800020109>
+///
+/// Jonker, R., & Volgenant, A. (1987). A shortest augmenting path algorithm for dense and sparse
+///     linear assignment problems. Computing, 38(4), 325–340. <https://doi.org/10.1007/BF02278710>
+///
+/// Time complexity: O(n^3) for n rows
+pub(crate) fn assignment(cost: &[Vec<f64>]) -> Vec<usize> {
+    let n = cost.len();
+    // The potentials of the rows and columns, and the row assigned to every column, counting from
+    // 1 so that column 0 can hold the row that is being added
+    let mut u = vec![0.0; n + 1];
+    let mut v = vec![0.0; n + 1];
+    let mut row = vec![0; n + 1];
+    let mut previous = vec![0; n + 1];
+
+    for i in 1..=n {
+        row[0] = i;
+        let mut j0 = 0;
+        let mut min_d = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+
+        // Grow the tree of shortest paths from the new row until it reaches a free column
+        loop {
+            used[j0] = true;
+            let i0 = row[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+            for j in 1..=n {
+                if !used[j] {
+                    let d = cost[i0 - 1][j - 1] - u[i0] - v[j];
+                    if d < min_d[j] {
+                        min_d[j] = d;
+                        previous[j] = j0;
+                    }
+                    if min_d[j] < delta {
+                        delta = min_d[j];
+                        j1 = j;
+                    }
+                }
+            }
+            for j in 0..=n {
+                if used[j] {
+                    u[row[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_d[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if row[j0] == 0 {
+                break;
+            }
+        }
+
+        // Shift the assignments along the path
+        while j0 != 0 {
+            let j1 = previous[j0];
+            row[j0] = row[j1];
+            j0 = j1;
+        }
+    }
+
+    let mut columns = vec![0; n];
+    for j in 1..=n {
+        columns[row[j] - 1] = j - 1;
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use std::time::{Duration, Instant};
+    use super::{assignment, transport};
+
+    #[test]
+    fn transport_reroutes() {
+        // The first source should move to the second sink to make room for the second source
+        let supply = [1.0, 1.0];
+        let capacity = [1.0, 1.0];
+        let cost = vec![vec![0.0, 1.0], vec![0.0, 10.0]];
+        assert_eq!(transport(&supply, &capacity, &cost), vec![vec![0.0, 1.0], vec![1.0, 0.0]]);
+    }
+
+    #[test]
+    fn transport_is_optimal() {
+        // Compare with every way to send 6 units to 3 sinks that can receive 2 each
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let cost: Vec<Vec<f64>> = (0..6).map(|_| (0..3).map(|_| rng.gen_range(0.0..10.0)).collect()).collect();
+            let flow = transport(&[1.0; 6], &[2.0; 3], &cost);
+            let total: f64 = (0..6).map(|i| (0..3).map(|c| flow[i][c] * cost[i][c]).sum::<f64>()).sum();
+
+            let mut best = f64::INFINITY;
+            for code in 0..3usize.pow(6) {
+                let sinks: Vec<usize> = (0..6).map(|i| code / 3usize.pow(i) % 3).collect();
+                if (0..3).all(|c| sinks.iter().filter(|&&sink| sink == c).count() <= 2) {
+                    best = best.min((0..6).map(|i| cost[i][sinks[i]]).sum());
+                }
+            }
+            assert!((total - best).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn transport_scales() {
+        // Searching the paths over all sources instead of over the sinks took minutes at this size
+        let mut rng = StdRng::seed_from_u64(0);
+        let cost: Vec<Vec<f64>> = (0..5000).map(|_| (0..8).map(|_| rng.gen_range(0.0..1.0)).collect()).collect();
+        let start = Instant::now();
+        let flow = transport(&[1.0; 5000], &[625.0; 8], &cost);
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(flow.iter().all(|shares| shares.iter().sum::<f64>() == 1.0));
+    }
+
+    #[test]
+    fn assignment_minimizes_cost() {
+        // Greedily taking the cheapest entry first costs 1 + 10 instead of 2 + 3
+        let cost = vec![vec![1.0, 2.0, 9.0], vec![3.0, 10.0, 9.0], vec![9.0, 9.0, 0.0]];
+        assert_eq!(assignment(&cost), vec![1, 0, 2]);
+        assert!(assignment(&[]).is_empty());
+    }
+}
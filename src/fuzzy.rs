@@ -0,0 +1,217 @@
+use crate::centers::Centers;
+use crate::error::{check_point_rows, ClusterError};
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::initialization::random_points;
+use crate::point::Point;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Maximum number of iterations.
+const MAX_ITER: usize = 300;
+
+/// Fitting stops once no center moves more than this distance in an iteration.
+const TOL: f64 = 1e-9;
+
+/// Implements fuzzy c-means (Bezdek, 1981), which gives every point a degree of membership in
+/// every cluster instead of assigning it to one. With fuzzifier m > 1, the membership of point i
+/// in cluster k is 1 / sum_j (d_ik / d_ij)^(2 / (m - 1)), and every center is the mean of all
+/// points weighted by their memberships to the power m. As m approaches 1, the memberships become
+/// hard assignments; larger m gives softer boundaries.
+///
+/// # References
+///
+/// Bezdek, J. C. (1981). Pattern recognition with fuzzy objective function algorithms. Plenum
+///     Press. <https://doi.org/10.1007/978-1-4757-0450-1>
+pub struct FuzzyCMeans<const K: usize, const M: usize> {
+    /// The coordinates of the c means.
+    pub centers: [Point<M>; K],
+    /// The membership of each point in every cluster, which sums to 1 per point.
+    pub memberships: Vec<[f64; K]>,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the centers converged within the maximum number of iterations.
+    pub converged: bool
+}
+
+impl<const K: usize, const M: usize> FuzzyCMeans<K, M> {
+    /// Get k fuzzy clusters based on `points` with the given `fuzzifier`, or an error if the points
+    /// have NaN or infinite coordinates or there are fewer than k distinct points.
+    ///
+    /// # Panics
+    ///
+    /// If the fuzzifier is not larger than 1.
+    pub fn fit(points: &[Point<M>], fuzzifier: f64) -> Result<Self, ClusterError> {
+        Self::new(points, fuzzifier, Option::None)
+    }
+
+    /// Get k fuzzy clusters based on `points` with a pre-determined random state, or an error, see
+    /// [`FuzzyCMeans::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], fuzzifier: f64, random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, fuzzifier, Option::Some(random_state))
+    }
+
+    fn new(points: &[Point<M>], fuzzifier: f64, random_state: Option<u64>) -> Result<Self, ClusterError> {
+        assert!(fuzzifier > 1.0, "the fuzzifier must be larger than 1");
+        check_point_rows(points.iter().map(|point| point.0), M, K)?;
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        let mut centers = random_points::<K, M>(points, &mut rng);
+        let mut memberships = vec![[0.0; K]; points.len()];
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < MAX_ITER {
+            iterations += 1;
+
+            // Update the memberships given the centers
+            for (point, membership) in points.iter().zip(memberships.iter_mut()) {
+                *membership = Self::membership(&centers, point, fuzzifier);
+            }
+
+            // Move the centers to the means weighted by the memberships to the power m
+            let mut shift: f64 = 0.0;
+            for k in 0..K {
+                let mut sum = Point::default();
+                let mut total = 0.0;
+                for (point, membership) in points.iter().zip(&memberships) {
+                    let w = membership[k].powf(fuzzifier);
+                    sum += *point * w;
+                    total += w;
+                }
+                if total > 0.0 {
+                    let center = sum / total;
+                    shift = shift.max(center.distance(&centers[k]));
+                    centers[k] = center;
+                }
+            }
+
+            if shift <= TOL {
+                converged = true;
+                break;
+            }
+        }
+
+        Ok(FuzzyCMeans { centers, memberships, iterations, converged })
+    }
+
+    /// The membership of `point` in every cluster. A point that coincides with one or more
+    /// centers belongs to them in equal parts.
+    ///
+    /// Time complexity: O(k * M)
+    fn membership(centers: &[Point<M>; K], point: &Point<M>, fuzzifier: f64) -> [f64; K] {
+        let distances = centers.map(|center| point.squared_distance(&center));
+        let coinciding = distances.iter().filter(|&&d| d == 0.0).count();
+        if coinciding > 0 {
+            return distances.map(|d| if d == 0.0 { 1.0 / coinciding as f64 } else { 0.0 })
+        }
+
+        // (d_ik / d_ij)^(2 / (m - 1)) with squared distances is (d_ik^2 / d_ij^2)^(1 / (m - 1))
+        let exponent = 1.0 / (fuzzifier - 1.0);
+        distances.map(|d_k| 1.0 / distances.iter().map(|d_j| (d_k / d_j).powf(exponent)).sum::<f64>())
+    }
+
+    /// The membership of `point` in every cluster.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn predict(&self, point: &Point<M>, fuzzifier: f64) -> [f64; K] {
+        Self::membership(&self.centers, point, fuzzifier)
+    }
+
+    /// The index of the cluster that each point has the largest membership in.
+    ///
+    /// Time complexity: O(r * k)
+    pub fn point_centers(&self) -> Vec<usize> {
+        self.memberships.iter()
+            .map(|membership| (0..K).max_by(|&a, &b| membership[a].total_cmp(&membership[b])).unwrap_or(0))
+            .collect()
+    }
+}
+
+/// The parameters of [`FuzzyCMeans::fit`], to fit it through the [`Fit`] trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FuzzyCMeansParams<const K: usize> {
+    fuzzifier: f64,
+    random_state: Option<u64>
+}
+
+impl<const K: usize> FuzzyCMeansParams<K> {
+    /// Fit with the given `fuzzifier`.
+    ///
+    /// # Panics
+    ///
+    /// If the fuzzifier is not larger than 1, when fitting.
+    pub fn new(fuzzifier: f64) -> Self {
+        Self { fuzzifier, random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for FuzzyCMeansParams<K> {
+    type Model = FuzzyCMeans<K, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<FuzzyCMeans<K, M>, ClusterError> {
+        FuzzyCMeans::new(points, self.fuzzifier, self.random_state)
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for FuzzyCMeans<K, M> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    /// The cluster that each point has the largest membership in.
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers().into_iter().map(Option::Some).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.to_vec())
+    }
+}
+
+impl<const K: usize, const M: usize> Predict<M> for FuzzyCMeans<K, M> {
+    /// The cluster that `point` has the largest membership in, which is the closest center for
+    /// any fuzzifier.
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        Option::Some(Centers::new(self.centers).closest(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([1.0, 0.0]),
+            Point([5.0, 0.0]),
+            Point([9.0, 0.0]),
+            Point([10.0, 0.0])
+        ];
+        let model = FuzzyCMeans::<2, 2>::fit_with_random_state(&points, 2.0, 0).unwrap();
+        let labels = model.point_centers();
+        assert_eq!(labels[0], labels[1]);
+        assert_eq!(labels[3], labels[4]);
+        assert_ne!(labels[0], labels[3]);
+        for membership in &model.memberships {
+            assert!((membership.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        }
+        // The point in the middle belongs to both clusters equally
+        assert!((model.memberships[2][0] - 0.5).abs() < 1e-6);
+        assert!(model.converged);
+
+        assert_eq!(FuzzyCMeans::<2, 2>::fit(&points[..1], 2.0).err(), Option::Some(ClusterError::TooFewPoints { clusters: 2, distinct: 1 }));
+    }
+}
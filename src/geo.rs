@@ -0,0 +1,368 @@
+//! Helpers for clustering geographic coordinates. Points are `Point([latitude, longitude])` in
+//! degrees, and projected coordinates are in kilometers.
+
+use crate::builder::KMeansBuilder;
+use crate::clusterer::KMeans;
+use crate::error::{check_points, ClusterError};
+use crate::metric::Metric;
+use crate::point::Point;
+
+/// Mean radius of the Earth in kilometers.
+pub const EARTH_RADIUS: f64 = 6371.0088;
+
+/// Unit vector pointing to a latitude and longitude.
+fn to_cartesian(point: &Point<2>) -> [f64; 3] {
+    let (lat, lon) = (point.0[0].to_radians(), point.0[1].to_radians());
+    [lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+}
+
+/// Latitude and longitude that a vector points to.
+fn from_cartesian(v: [f64; 3]) -> Point<2> {
+    let lat = v[2].atan2((v[0] * v[0] + v[1] * v[1]).sqrt());
+    let lon = v[1].atan2(v[0]);
+    Point([lat.to_degrees(), lon.to_degrees()])
+}
+
+/// The centroid of points on the sphere: the mean of their unit vectors, projected back onto the
+/// sphere. Unlike the mean of the latitudes and longitudes, this is correct near the poles and the
+/// antimeridian. Returns `None` if there are no points or their unit vectors cancel out.
+///
+/// Time complexity: O(r)
+pub fn spherical_centroid(points: &[Point<2>]) -> Option<Point<2>> {
+    let mut sum = [0.0; 3];
+    for point in points {
+        let v = to_cartesian(point);
+        for d in 0..3 {
+            sum[d] += v[d];
+        }
+    }
+
+    if sum.iter().all(|&x| x.abs() < 1e-12) {
+        Option::None
+    } else {
+        Option::Some(from_cartesian(sum))
+    }
+}
+
+/// A [Lambert azimuthal equal-area projection](https://en.wikipedia.org/wiki/Lambert_azimuthal_equal-area_projection)
+/// centered on a point, mapping latitude and longitude to kilometers. Areas are preserved
+/// everywhere and distances are nearly preserved close to the center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LocalProjection {
+    /// The latitude and longitude of the center of the projection.
+    pub center: Point<2>
+}
+
+impl LocalProjection {
+    pub fn new(center: Point<2>) -> Self {
+        Self { center }
+    }
+
+    /// A projection centered on the spherical centroid of `points`.
+    pub fn around(points: &[Point<2>]) -> Self {
+        Self::new(spherical_centroid(points).unwrap_or_default())
+    }
+
+    /// Time complexity: O(1)
+    pub fn project(&self, point: &Point<2>) -> Point<2> {
+        let (lat0, lon0) = (self.center.0[0].to_radians(), self.center.0[1].to_radians());
+        let (lat, lon) = (point.0[0].to_radians(), point.0[1].to_radians());
+        let k = (2.0 / (1.0 + lat0.sin() * lat.sin() + lat0.cos() * lat.cos() * (lon - lon0).cos())).sqrt();
+        Point([
+            EARTH_RADIUS * k * lat.cos() * (lon - lon0).sin(),
+            EARTH_RADIUS * k * (lat0.cos() * lat.sin() - lat0.sin() * lat.cos() * (lon - lon0).cos())
+        ])
+    }
+
+    /// Time complexity: O(1)
+    pub fn unproject(&self, point: &Point<2>) -> Point<2> {
+        let (lat0, lon0) = (self.center.0[0].to_radians(), self.center.0[1].to_radians());
+        let (x, y) = (point.0[0], point.0[1]);
+        let rho = (x * x + y * y).sqrt();
+        if rho == 0.0 {
+            return self.center;
+        }
+
+        let c = 2.0 * (rho / (2.0 * EARTH_RADIUS)).clamp(-1.0, 1.0).asin();
+        let lat = (c.cos() * lat0.sin() + y * c.sin() * lat0.cos() / rho).clamp(-1.0, 1.0).asin();
+        let lon = lon0 + (x * c.sin()).atan2(rho * lat0.cos() * c.cos() - y * lat0.sin() * c.sin());
+        Point([lat.to_degrees(), normalize_longitude(lon.to_degrees())])
+    }
+}
+
+/// Wrap a longitude in degrees to [-180, 180).
+fn normalize_longitude(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Check that every point has a finite longitude and a latitude in [-90, 90].
+///
+/// Time complexity: O(r)
+pub fn check_coordinates(points: &[Point<2>]) -> Result<(), ClusterError> {
+    for (i, point) in points.iter().enumerate() {
+        if let Option::Some(dimension) = point.0.iter().position(|x| !x.is_finite()) {
+            return Err(ClusterError::NonFinite { point: i, dimension })
+        }
+        if point.0[0].abs() > 90.0 {
+            return Err(ClusterError::InvalidLatitude { point: i })
+        }
+    }
+    Ok(())
+}
+
+/// Replace the `centers` of a fit on `points` projected with `projection` by the spherical
+/// centroid of the points with each label in `labels`. A cluster without points, or whose points
+/// cancel out, gets its projected center unprojected instead.
+///
+/// Time complexity: O(r + k)
+pub fn spherical_centers(points: &[Point<2>], labels: &[usize], projection: &LocalProjection, centers: &mut [Point<2>]) {
+    let mut members = vec![Vec::new(); centers.len()];
+    for (point, &k) in points.iter().zip(labels) {
+        members[k].push(*point);
+    }
+    for (center, members) in centers.iter_mut().zip(&members) {
+        *center = spherical_centroid(members).unwrap_or_else(|| projection.unproject(center));
+    }
+}
+
+/// Get k clusters of geographic `points` by fitting `config` to the points projected with a
+/// [`LocalProjection`] around their centroid, with the spherical centroid of every cluster as its
+/// center. Initial centers of `config` are given in latitude and longitude as well, and are
+/// projected along with the points.
+///
+/// Returns an error if the points have NaN or infinite coordinates or latitudes outside of
+/// [-90, 90], there are fewer than k distinct points, `config` has dimension weights, which do not
+/// apply to the great-circle distance, or the fit would exceed the memory budget.
+pub fn fit<const K: usize>(points: &[Point<2>], config: &KMeansBuilder<K, 2>) -> Result<KMeans<K, 2, Haversine>, ClusterError> {
+    check_coordinates(points)?;
+    check_points(points, &vec![1.0; points.len()], K)?;
+    if config.feature_weights() != [1.0; 2] {
+        return Err(ClusterError::GeographicWeights)
+    }
+    let projection = LocalProjection::around(points);
+    let projected: Vec<Point<2>> = points.iter().map(|point| projection.project(point)).collect();
+    let mut config = config.clone();
+    config.initial_centers = config.initial_centers.map(|centers| centers.map(|center| projection.project(&center)));
+    let mut model = config.fit(&projected)?;
+
+    spherical_centers(points, &model.point_centers, &projection, &mut model.centers);
+    Ok(model.with_metric(points, Haversine))
+}
+
+/// The great-circle distance in kilometers between points `Point([latitude, longitude])` in
+/// degrees, see [`haversine`]. Unlike the Euclidean distance on latitudes and longitudes, this is
+/// correct near the poles and across the antimeridian. It only applies to 2-dimensional points:
+/// computing it between points of any other dimension fails to compile.
+///
+/// ```compile_fail
+/// use kmeans::geo::Haversine;
+/// use kmeans::{Metric, Point};
+///
+/// Haversine.distance(&Point([52.0, 4.9, 0.0]), &Point([48.9, 2.4, 0.0]));
+/// ```
+///
+/// The tree-based algorithms and the bounds of Elkan's algorithm do not apply to this metric, so
+/// fitting with it directly falls back to [`crate::Algorithm::Naive`], and the means it moves the
+/// centers to are not spherical centroids. Use [`fit`] instead, and this metric to assign new
+/// points to the centers of the model.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Haversine;
+
+impl Metric for Haversine {
+    /// Time complexity: O(1)
+    fn distance<const M: usize>(&self, a: &Point<M>, b: &Point<M>) -> f64 {
+        const { assert!(M == 2, "the haversine distance is only defined between latitude-longitude points") };
+        haversine(&Point([a.0[0], a.0[1]]), &Point([b.0[0], b.0[1]]))
+    }
+}
+
+/// Great-circle distance in kilometers between two points, using the
+/// [haversine formula](https://en.wikipedia.org/wiki/Haversine_formula).
+///
+/// Time complexity: O(1)
+pub fn haversine(a: &Point<2>, b: &Point<2>) -> f64 {
+    let (lat1, lat2) = (a.0[0].to_radians(), b.0[0].to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (b.0[1] - a.0[1]).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * h.sqrt().min(1.0).asin()
+}
+
+/// How well a cluster center covers the points assigned to it, in kilometers.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Coverage {
+    /// Number of points in the cluster.
+    pub size: usize,
+    /// Great-circle distance from the center to the farthest point.
+    pub radius: f64,
+    /// 95th percentile (nearest rank) of the great-circle distances from the center to the points.
+    pub p95: f64,
+    /// Area of the convex hull of the points in square kilometers, measured in a
+    /// [`LocalProjection`] around the center.
+    pub hull_area: f64
+}
+
+/// Coverage statistics of each cluster of a model fitted on geographic `points`.
+///
+/// Time complexity: O(r * log(r))
+pub fn coverage<const K: usize, D: Metric>(points: &[Point<2>], model: &KMeans<K, 2, D>) -> [Coverage; K] {
+    let mut members = [(); K].map(|_| Vec::new());
+    for (point, &k) in points.iter().zip(&model.point_centers) {
+        members[k].push(*point);
+    }
+
+    let mut coverage = [Coverage::default(); K];
+    for k in 0..K {
+        let center = model.centers[k];
+        let mut distances: Vec<f64> = members[k].iter().map(|point| haversine(&center, point)).collect();
+        distances.sort_by(f64::total_cmp);
+
+        let projection = LocalProjection::new(center);
+        let projected: Vec<Point<2>> = members[k].iter().map(|point| projection.project(point)).collect();
+
+        coverage[k] = Coverage {
+            size: members[k].len(),
+            radius: distances.last().copied().unwrap_or(0.0),
+            p95: if distances.is_empty() { 0.0 } else { distances[(0.95 * distances.len() as f64).ceil() as usize - 1] },
+            hull_area: polygon_area(&convex_hull(projected))
+        };
+    }
+    coverage
+}
+
+/// Convex hull of planar points in counter-clockwise order, using
+/// [Andrew's monotone chain algorithm](https://en.wikibooks.org/wiki/Algorithm_Implementation/Geometry/Convex_hull/Monotone_chain).
+///
+/// Time complexity: O(r * log(r))
+pub(crate) fn convex_hull(mut points: Vec<Point<2>>) -> Vec<Point<2>> {
+    points.sort_by(|a, b| a.0[0].total_cmp(&b.0[0]).then(a.0[1].total_cmp(&b.0[1])));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    let cross = |o: &Point<2>, a: &Point<2>, b: &Point<2>| {
+        (a.0[0] - o.0[0]) * (b.0[1] - o.0[1]) - (a.0[1] - o.0[1]) * (b.0[0] - o.0[0])
+    };
+    // Build the lower chain from left to right
+    let mut hull: Vec<Point<2>> = Vec::with_capacity(2 * points.len());
+    for point in &points {
+        while hull.len() >= 2 && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], point) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(*point);
+    }
+
+    // Build the upper chain from right to left, starting at the end of the lower chain
+    let lower = hull.len() + 1;
+    for point in points.iter().rev().skip(1) {
+        while hull.len() >= lower && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], point) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(*point);
+    }
+
+    // The last point is the first point
+    hull.pop();
+    hull
+}
+
+/// Area of a simple polygon, using the [shoelace formula](https://en.wikipedia.org/wiki/Shoelace_formula).
+///
+/// Time complexity: O(n)
+pub(crate) fn polygon_area(polygon: &[Point<2>]) -> f64 {
+    let n = polygon.len();
+    let mut area = 0.0;
+    for i in 0..n {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        area += a.0[0] * b.0[1] - b.0[0] * a.0[1];
+    }
+    area.abs() / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn centroid_across_antimeridian() {
+        let centroid = spherical_centroid(&[Point([10.0, 179.0]), Point([10.0, -179.0])]).unwrap();
+        assert!((centroid.0[0] - 10.0).abs() < 0.01);
+        assert!((centroid.0[1].abs() - 180.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn projection_round_trip() {
+        let projection = LocalProjection::new(Point([89.0, 170.0]));
+        let point = Point([88.0, -175.0]);
+        let round_trip = projection.unproject(&projection.project(&point));
+        assert!(round_trip.distance(&point) < 1e-9);
+    }
+
+    #[test]
+    fn fit_invalid() {
+        let points = [Point([10.0, 20.0]), Point([f64::NAN, 20.0])];
+        assert_eq!(fit(&points, &KMeansBuilder::<1, 2>::new()).err(), Option::Some(ClusterError::NonFinite { point: 1, dimension: 0 }));
+        let points = [Point([10.0, 20.0]), Point([-90.5, 20.0])];
+        assert_eq!(fit(&points, &KMeansBuilder::<1, 2>::new()).err(), Option::Some(ClusterError::InvalidLatitude { point: 1 }));
+        let points = [Point([10.0, 20.0]), Point([11.0, 20.0])];
+        let config = KMeansBuilder::<1, 2>::new().dimension_weights([1.0, 2.0]);
+        assert_eq!(fit(&points, &config).err(), Option::Some(ClusterError::GeographicWeights));
+    }
+
+    #[test]
+    fn fit_initial_centers() {
+        // Initial centers in latitude and longitude, which would both be next to the origin of the
+        // projection if they were used as kilometers
+        let points = [Point([10.0, 20.0]), Point([10.1, 20.1]), Point([40.0, -60.0]), Point([40.1, -60.1])];
+        let config = KMeansBuilder::<2, 2>::new().initial_centers([Point([40.0, -60.0]), Point([10.0, 20.0])]);
+        let model = fit(&points, &config).unwrap();
+        assert_eq!(model.point_centers, vec![1, 1, 0, 0]);
+        assert!(haversine(&model.centers[1], &Point([10.05, 20.05])) < 1.0);
+    }
+
+    #[test]
+    fn haversine_distance() {
+        // One degree of latitude is about 111.2 km
+        assert!((haversine(&Point([0.0, 0.0]), &Point([1.0, 0.0])) - 111.195).abs() < 0.001);
+    }
+
+    #[test]
+    fn fit_across_antimeridian() {
+        // Two groups straddling the antimeridian and one near the pole, which are far apart in
+        // raw latitude and longitude
+        let points = [
+            Point([10.0, 179.5]),
+            Point([10.5, -179.5]),
+            Point([9.5, 179.9]),
+            Point([89.0, 0.0]),
+            Point([89.0, 120.0]),
+            Point([89.0, -120.0])
+        ];
+        let config = KMeansBuilder::<2, 2>::new().n_init(10).random_state(0);
+        let model = fit(&points, &config).unwrap();
+        let a = model.point_centers[0];
+        assert_eq!(model.point_centers, vec![a, a, a, 1 - a, 1 - a, 1 - a]);
+        assert!((model.centers[1 - a].0[0] - 90.0).abs() < 0.1);
+        assert_eq!(model.predict(&Point([10.0, -179.9])), a);
+
+        let inertia: f64 = points.iter().zip(&model.point_centers).map(|(point, &k)| haversine(point, &model.centers[k]).powi(2)).sum();
+        assert!((model.inertia() - inertia).abs() < 1e-6 * inertia);
+    }
+
+    #[test]
+    fn hull_area() {
+        let square = vec![
+            Point([0.0, 0.0]),
+            Point([2.0, 0.0]),
+            Point([1.0, 1.0]),
+            Point([2.0, 2.0]),
+            Point([0.0, 2.0])
+        ];
+        let hull = convex_hull(square);
+        assert_eq!(hull.len(), 4);
+        assert_eq!(polygon_area(&hull), 4.0);
+    }
+}
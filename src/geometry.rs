@@ -0,0 +1,133 @@
+//! The geometric reasoning of (Pelleg & Moore, 1999) on hyper-rectangles, as building blocks for
+//! tree-pruned clustering algorithms.
+//!
+//! The bounds are exact for [separable](Metric::SEPARABLE) metrics, such as [`crate::Euclidean`]
+//! and [`crate::Manhattan`] distance, where the closest and farthest points of a hyper-rectangle
+//! can be found one dimension at a time.
+//!
+//! # References
+//!
+//! Pelleg, D., & Moore, A. (1999). Accelerating exact k-means algorithms with geometric reasoning.
+//!     Proceedings of the Fifth ACM SIGKDD International Conference
+//!     on Knowledge Discovery and Data Mining, 277–281. <https://doi.org/10.1145/312129.312248>
+
+pub use crate::hyper_rectangle::HyperRectangle;
+use crate::metric::Metric;
+use crate::point::Point;
+
+/// The smallest distance under `metric` of `point` to any point in `h`, which is 0 inside `h`.
+///
+/// Time complexity: O(M)
+///
+/// # Panics
+///
+/// If `metric` is not separable.
+pub fn min_distance<const M: usize, D: Metric>(h: &HyperRectangle<M>, point: &Point<M>, metric: &D) -> f64 {
+    assert!(D::SEPARABLE, "distance bounds need a separable metric");
+    metric.distance(&h.closest(point), point)
+}
+
+/// The largest distance under `metric` of `point` to any point in `h`.
+///
+/// Time complexity: O(M)
+///
+/// # Panics
+///
+/// If `metric` is not separable.
+pub fn max_distance<const M: usize, D: Metric>(h: &HyperRectangle<M>, point: &Point<M>, metric: &D) -> f64 {
+    assert!(D::SEPARABLE, "distance bounds need a separable metric");
+    metric.distance(&h.farthest(point), point)
+}
+
+/// Whether `c1` dominates `c2` with respect to `h`: every point in `h` is closer to `c1` than to
+/// `c2` under `metric`, as defined in Section 3, Definition 3 (p. 279). Centers with NaN
+/// coordinates, such as dropped centers, are dominated by any other center.
+///
+/// Time complexity: O(M)
+///
+/// # Panics
+///
+/// If `metric` is not separable.
+pub fn dominates<const M: usize, D: Metric>(c1: &Point<M>, c2: &Point<M>, h: &HyperRectangle<M>, metric: &D) -> bool {
+    assert!(D::SEPARABLE, "domination needs a separable metric");
+
+    // Find the point p in h that is the furthest in the direction c2 - c1. For separable metrics
+    // the difference of the distances to c1 and c2 is largest there in every dimension
+    let point = Point(std::array::from_fn(|d| if c1.0[d] < c2.0[d] {
+        h.1.0[d]
+    } else {
+        h.0.0[d]
+    }));
+
+    // If the distance to that point is shorter from c1 than from c2, c1 dominates c2
+    let d2 = metric.distance(&point, c2);
+    d2.is_nan() || metric.distance(&point, c1) < d2
+}
+
+/// Whether `c1` dominates `c2` with respect to the ball around `center` with `radius`: every
+/// point in the ball is closer to `c1` than to `c2` under Euclidean distance, because the center
+/// lies more than the radius away from their bisecting hyperplane, on the side of `c1`. Centers
+/// with NaN coordinates are dominated by any other center.
+///
+/// Time complexity: O(M)
+pub fn dominates_ball<const M: usize>(c1: &Point<M>, c2: &Point<M>, center: &Point<M>, radius: f64) -> bool {
+    // Project the center onto the direction c2 - c1, relative to the midpoint of c1 and c2
+    let mut projection = 0.0;
+    let mut norm = 0.0;
+    for d in 0..M {
+        let w = c2.0[d] - c1.0[d];
+        projection += w * (center.0[d] - (c1.0[d] + c2.0[d]) / 2.0);
+        norm += w * w;
+    }
+    let reach = projection + radius * norm.sqrt();
+    reach.is_nan() || reach < 0.0
+}
+
+/// owner_C(h) as defined in Section 3, Definition 1 (p. 278): the index of the center in `centers`
+/// that every point in `h` is closest to, if there is one that can be proven to be.
+///
+/// Time complexity: O(k * M)
+///
+/// # Panics
+///
+/// If `metric` is not separable.
+pub fn owner<const M: usize, D: Metric>(centers: &[Point<M>], h: &HyperRectangle<M>, metric: &D) -> Option<usize> {
+    // Find the center closest to the hyper-rectangle, min(d(c, h)) as in Section 3, Theorem 2
+    // (p. 279). If there are multiple, no center owns h
+    let mut min_d = f64::INFINITY;
+    let mut c1 = Option::None;
+    for (c, center) in centers.iter().enumerate() {
+        let d = min_distance(h, center, metric);
+        if d == min_d {
+            c1 = Option::None;
+        } else if d < min_d {
+            min_d = d;
+            c1 = Option::Some(c);
+        }
+    }
+    let c1 = c1?;
+
+    // Else, check if c1 dominates every other center
+    centers.iter()
+        .enumerate()
+        .all(|(c2, center)| c1 == c2 || dominates(&centers[c1], center, h, metric))
+        .then_some(c1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metric::{Euclidean, Manhattan};
+    use super::*;
+
+    #[test]
+    fn owner_by_metric() {
+        let h = HyperRectangle(Point([0.0, 0.0]), Point([2.0, 2.0]));
+        let centers = [Point([-1.0, 2.0]), Point([4.0, 4.0])];
+        assert_eq!(min_distance(&h, &centers[1], &Manhattan), 4.0);
+        assert_eq!(max_distance(&h, &centers[1], &Manhattan), 8.0);
+
+        // The corner (2, 2) is closer to the first center in Manhattan distance only
+        assert_eq!(owner(&centers, &h, &Manhattan), Option::Some(0));
+        assert_eq!(owner(&centers, &h, &Euclidean), Option::None);
+    }
+}
@@ -0,0 +1,233 @@
+//! The assignment step of k-means on the GPU, with the `gpu` feature.
+//!
+//! The points are uploaded once as `f32`, and every assignment uploads the centers, computes the
+//! squared distances from every point to every center in a compute shader, and reads back the
+//! index of the closest center of every point. The sums of the points per center are still
+//! computed on the CPU, in `f64`.
+
+use crate::point::Point;
+use wgpu::util::DeviceExt;
+
+/// Number of points handled by a workgroup of the shader.
+const WORKGROUP_SIZE: u32 = 256;
+
+/// Assigns every point to the closest center, breaking ties by the lowest index like
+/// [`crate::centers::Centers::closest_by`]. Centers with NaN coordinates are never closest.
+const SHADER: &str = r#"
+struct Params {
+    points: u32,
+    centers: u32,
+    dimensions: u32,
+    padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> points: array<f32>;
+@group(0) @binding(2) var<storage, read> centers: array<f32>;
+@group(0) @binding(3) var<storage, read_write> labels: array<u32>;
+
+@compute @workgroup_size(256)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.points) {
+        return;
+    }
+    var closest = 0u;
+    var min_distance = 3.4028235e38;
+    for (var k = 0u; k < params.centers; k = k + 1u) {
+        var distance = 0.0;
+        for (var d = 0u; d < params.dimensions; d = d + 1u) {
+            let difference = points[i * params.dimensions + d] - centers[k * params.dimensions + d];
+            distance = distance + difference * difference;
+        }
+        if (distance < min_distance) {
+            min_distance = distance;
+            closest = k;
+        }
+    }
+    labels[i] = closest;
+}
+"#;
+
+/// The buffers of a consecutive range of the points, small enough for a single binding and
+/// dispatch.
+struct Chunk {
+    len: usize,
+    labels: wgpu::Buffer,
+    staging: wgpu::Buffer,
+    bind_group: wgpu::BindGroup
+}
+
+/// The points of a fit on the GPU.
+pub(crate) struct GpuAssigner<const M: usize> {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    centers: wgpu::Buffer,
+    chunks: Vec<Chunk>,
+    /// The mean of the points, subtracted from the points and centers before converting them to
+    /// `f32`, which keeps more significant bits of data far from the origin
+    offset: Point<M>
+}
+
+impl<const M: usize> GpuAssigner<M> {
+    /// Upload `points` for assigning them to `k` centers, or return `None` if no GPU is available
+    /// or the points do not fit on it.
+    ///
+    /// Time complexity: O(r * M)
+    pub(crate) fn new(points: &[Point<M>], k: usize) -> Option<Self> {
+        if points.is_empty() || M == 0 || k == 0 {
+            return Option::None
+        }
+        pollster::block_on(Self::connect(points, k))
+    }
+
+    async fn connect(points: &[Point<M>], k: usize) -> Option<Self> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+        let adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }).await.ok()?;
+        // Software adapters are slower than the CPU path
+        if adapter.get_info().device_type == wgpu::DeviceType::Cpu {
+            return Option::None
+        }
+        let limits = adapter.limits();
+        let (device, queue) = adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Option::Some("kmeans"),
+            required_limits: limits.clone(),
+            ..Default::default()
+        }).await.ok()?;
+
+        let point_bytes = (4 * M) as u64;
+        let max_chunk = (limits.max_storage_buffer_binding_size as u64 / point_bytes)
+            .min(limits.max_buffer_size / point_bytes)
+            .min(limits.max_compute_workgroups_per_dimension as u64 * WORKGROUP_SIZE as u64) as usize;
+        if max_chunk == 0 || (4 * k * M) as u64 > limits.max_storage_buffer_binding_size as u64 {
+            return Option::None
+        }
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Option::Some("kmeans assignment"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into())
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Option::Some("kmeans assignment"),
+            layout: Option::None,
+            module: &module,
+            entry_point: Option::Some("main"),
+            compilation_options: Default::default(),
+            cache: Option::None
+        });
+        let centers = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Option::Some("centers"),
+            size: (4 * k * M) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        });
+
+        let offset = points.iter().fold(Point::default(), |sum, point| sum + *point) / points.len() as f64;
+        let layout = pipeline.get_bind_group_layout(0);
+        let chunks = points.chunks(max_chunk).map(|chunk| {
+            let params = [chunk.len() as u32, k as u32, M as u32, 0];
+            let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Option::Some("params"),
+                contents: &params.iter().flat_map(|x| x.to_ne_bytes()).collect::<Vec<u8>>(),
+                usage: wgpu::BufferUsages::UNIFORM
+            });
+            let coordinates = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Option::Some("points"),
+                contents: &to_bytes(chunk, &offset),
+                usage: wgpu::BufferUsages::STORAGE
+            });
+            let labels = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Option::Some("labels"),
+                size: 4 * chunk.len() as u64,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false
+            });
+            let staging = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Option::Some("staging"),
+                size: 4 * chunk.len() as u64,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Option::Some("kmeans assignment"),
+                layout: &layout,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 1, resource: coordinates.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 2, resource: centers.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 3, resource: labels.as_entire_binding() }
+                ]
+            });
+            Chunk { len: chunk.len(), labels, staging, bind_group }
+        }).collect();
+
+        Option::Some(Self { device, queue, pipeline, centers, chunks, offset })
+    }
+
+    /// Write the index of the closest of `centers` to every point to `point_centers`, or return
+    /// `false` if the GPU failed, in which case `point_centers` may be partially written.
+    ///
+    /// The distances are computed in `f32`, so points that are almost equally close to two
+    /// centers may be assigned to a different one than on the CPU.
+    ///
+    /// Time complexity: O(r * k * M / p) with p GPU threads
+    pub(crate) fn assign(&self, centers: &[Point<M>], point_centers: &mut [usize]) -> bool {
+        self.queue.write_buffer(&self.centers, 0, &to_bytes(centers, &self.offset));
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Option::None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: Option::None, timestamp_writes: Option::None });
+            pass.set_pipeline(&self.pipeline);
+            for chunk in &self.chunks {
+                pass.set_bind_group(0, &chunk.bind_group, &[]);
+                pass.dispatch_workgroups((chunk.len as u32).div_ceil(WORKGROUP_SIZE), 1, 1);
+            }
+        }
+        for chunk in &self.chunks {
+            encoder.copy_buffer_to_buffer(&chunk.labels, 0, &chunk.staging, 0, Option::None);
+        }
+        self.queue.submit([encoder.finish()]);
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        for chunk in &self.chunks {
+            let sender = sender.clone();
+            chunk.staging.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result.is_ok());
+            });
+        }
+        drop(sender);
+        if self.device.poll(wgpu::PollType::wait_indefinitely()).is_err() || !receiver.iter().all(|mapped| mapped) {
+            return false
+        }
+
+        let mut start = 0;
+        for chunk in &self.chunks {
+            {
+                let labels = match chunk.staging.get_mapped_range(..) {
+                    Ok(labels) => labels,
+                    Err(_) => return false
+                };
+                for (point_center, label) in point_centers[start..start + chunk.len].iter_mut().zip(labels.chunks_exact(4)) {
+                    *point_center = u32::from_ne_bytes([label[0], label[1], label[2], label[3]]) as usize;
+                }
+            }
+            chunk.staging.unmap();
+            start += chunk.len;
+        }
+        true
+    }
+}
+
+/// The coordinates of `points` minus `offset`, as the bytes of consecutive `f32`s.
+///
+/// Time complexity: O(n * M) for n points
+fn to_bytes<const M: usize>(points: &[Point<M>], offset: &Point<M>) -> Vec<u8> {
+    points.iter()
+        .flat_map(|point| point.0.iter().zip(&offset.0).map(|(x, o)| x - o))
+        .flat_map(|x| (x as f32).to_ne_bytes())
+        .collect()
+}
@@ -1,17 +1,36 @@
+//! Axis-aligned hyper-rectangles, the bounding boxes of the nodes of an [`crate::mrkd::Tree`].
+
 use crate::point::Point;
 
-#[derive(PartialEq, Debug)]
+/// An axis-aligned hyper-rectangle, given by its lower and upper corner: the closed set of points
+/// x with `self.0[d] <= x[d] <= self.1[d]` in every dimension d. The methods assume that the lower
+/// corner is not above the upper corner in any dimension, which [`HyperRectangle::new`] ensures.
+/// A hyper-rectangle may be flat in some dimensions, for example around a single point.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HyperRectangle<const M: usize> (pub Point<M>, pub Point<M>);
 
 impl<const M: usize> HyperRectangle<M> {
+    /// The hyper-rectangle with opposite corners `a` and `b`, in any order.
+    ///
+    /// Time complexity: O(M)
     pub fn new(a: Point<M>, b: Point<M>) -> Self {
-        HyperRectangle(a, b)
+        let mut min = a;
+        let mut max = b;
+        for d in 0..M {
+            if min.0[d] > max.0[d] {
+                std::mem::swap(&mut min.0[d], &mut max.0[d]);
+            }
+        }
+        HyperRectangle(min, max)
     }
 
+    /// Split the hyper-rectangle in two at value `v` of dimension `d`, into the part below and the
+    /// part above it. Both parts contain the points at `v`.
     pub fn split(&self, d: usize, v: f64) -> (Self, Self) {
-        let mut a = self.1.clone();
+        let mut a = self.1;
         a.0[d] = v;
-        let mut b = self.0.clone();
+        let mut b = self.0;
         b.0[d] = v;
 
         (
@@ -24,11 +43,18 @@ impl<const M: usize> HyperRectangle<M> {
     ///
     /// Time complexity: O(M)
     pub fn closest(&self, point: &Point<M>) -> Point<M> {
-        let mut coords = [0.0; M];
-        for d in 0..M {
-            coords[d] = point.0[d].clamp(self.0.0[d], self.1.0[d]);
-        }
-        Point(coords)
+        Point(std::array::from_fn(|d| point.0[d].clamp(self.0.0[d], self.1.0[d])))
+    }
+
+    /// The corner of the hyper-rectangle farthest from `point`.
+    ///
+    /// Time complexity: O(M)
+    pub fn farthest(&self, point: &Point<M>) -> Point<M> {
+        Point(std::array::from_fn(|d| if point.0[d] - self.0.0[d] > self.1.0[d] - point.0[d] {
+            self.0.0[d]
+        } else {
+            self.1.0[d]
+        }))
     }
 
     /// d(x, h) as defined in Section 2 (p. 278)
@@ -38,15 +64,48 @@ impl<const M: usize> HyperRectangle<M> {
         self.closest(point).distance(point)
     }
 
+    /// The squared distance d(x, h)^2, which orders points the same way without the square root.
+    ///
+    /// Time complexity: O(M)
+    pub fn squared_distance(&self, point: &Point<M>) -> f64 {
+        self.closest(point).squared_distance(point)
+    }
+
+    /// Whether `point` lies in the hyper-rectangle, including its boundary.
+    ///
+    /// Time complexity: O(M)
+    pub fn contains(&self, point: &Point<M>) -> bool {
+        (0..M).all(|d| self.0.0[d] <= point.0[d] && point.0[d] <= self.1.0[d])
+    }
+
+    /// Whether the hyper-rectangle shares at least one point with `other`, including when they
+    /// only touch at their boundaries.
+    ///
+    /// Time complexity: O(M)
+    pub fn intersects(&self, other: &Self) -> bool {
+        (0..M).all(|d| self.0.0[d] <= other.1.0[d] && other.0.0[d] <= self.1.0[d])
+    }
+
+    /// The product of the widths in every dimension, which is 0 if the hyper-rectangle is flat in
+    /// any dimension.
+    ///
+    /// Time complexity: O(M)
+    pub fn volume(&self) -> f64 {
+        self.width().0.iter().product()
+    }
+
+    /// The point halfway between the corners.
+    ///
+    /// Time complexity: O(M)
+    pub fn center(&self) -> Point<M> {
+        (self.0 + self.1) / 2.0
+    }
+
     /// width(h) as defined in Section 2 (p. 278)
     ///
     /// Time complexity: O(M)
     pub fn width(&self) -> Point<M> {
-        let mut coords = [0.0; M];
-        for d in 0..M {
-            coords[d] = self.1.0[d] - self.0.0[d];
-        }
-        Point(coords)
+        self.1 - self.0
     }
 }
 
@@ -77,6 +136,18 @@ mod tests {
         assert_eq!(h.distance(&point), 2.23606797749979);
     }
 
+    #[test]
+    fn intersects() {
+        let h = HyperRectangle::new(Point([2.0, 0.0]), Point([0.0, 2.0]));
+        assert_eq!(h, HyperRectangle(Point([0.0, 0.0]), Point([2.0, 2.0])));
+        assert!(h.contains(&Point([2.0, 1.0])));
+        assert!(!h.contains(&Point([2.5, 1.0])));
+        assert!(h.intersects(&HyperRectangle(Point([2.0, 2.0]), Point([3.0, 3.0]))));
+        assert!(!h.intersects(&HyperRectangle(Point([1.0, 2.5]), Point([3.0, 3.0]))));
+        assert_eq!(h.volume(), 4.0);
+        assert_eq!(h.center(), Point([1.0, 1.0]));
+    }
+
     #[test]
     fn width() {
         let h = HyperRectangle(Point([1.0, 0.0]), Point([2.0, 2.0]));
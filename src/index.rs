@@ -0,0 +1,36 @@
+use crate::point::Point;
+
+/// A spatial index over a set of points, answering nearest-neighbor and range queries with
+/// Euclidean distance. Implemented by the mrkd-tree [`crate::Tree`], which prunes with
+/// hyper-rectangles, and the [`crate::BallTree`], which prunes with balls and degrades less in many
+/// dimensions.
+pub trait SpatialIndex<const M: usize> {
+    /// The number of points in the index.
+    fn len(&self) -> usize;
+
+    /// Whether the index has no points.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The point in the index closest to `point`, and its distance.
+    ///
+    /// # Panics
+    ///
+    /// If the index has no points.
+    fn nearest(&self, point: &Point<M>) -> (Point<M>, f64);
+
+    /// The indices of the `n` points closest to `point`, in the points the index was built from,
+    /// with their distances, in ascending order of distance. If `point` is in the index, it is
+    /// included with distance 0.
+    fn k_nearest(&self, point: &Point<M>, n: usize) -> Vec<(usize, f64)>;
+
+    /// Distances from `point` to its `n` nearest points in the index, in ascending order.
+    fn k_nearest_distances(&self, point: &Point<M>, n: usize) -> Vec<f64> {
+        self.k_nearest(point, n).into_iter().map(|(_, d)| d).collect()
+    }
+
+    /// Indices of the points in the index within distance `radius` of `point`, in no particular
+    /// order.
+    fn within_radius(&self, point: &Point<M>, radius: f64) -> Vec<usize>;
+}
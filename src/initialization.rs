@@ -0,0 +1,87 @@
+use crate::index::SpatialIndex;
+use crate::point::Point;
+use rand::Rng;
+
+/// Method used to pick the initial centers.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Initialization {
+    /// Sample k distinct points uniformly at random.
+    #[default]
+    Random,
+    /// Sample k distinct points uniformly at random, excluding the points in low-density regions.
+    /// The density of a point is estimated by the distance to its `neighbors`-th nearest neighbor,
+    /// and the `outlier_fraction` of points with the largest such distance are excluded. This
+    /// prevents the initial centers from landing on outliers, which would produce singleton
+    /// clusters.
+    DensityFiltered {
+        neighbors: usize,
+        outlier_fraction: f64
+    }
+}
+
+/// Sample k distinct indices below r uniformly at random, for points that are not stored as a
+/// slice of [`Point`].
+///
+/// Time complexity: O(k)
+pub(crate) fn random_indices(r: usize, k: usize, rng: &mut impl Rng) -> Vec<usize> {
+    rand::seq::index::sample(rng, r, k).into_vec()
+}
+
+/// Sample k distinct points uniformly at random from `points`.
+///
+/// Time complexity: O(k)
+pub(crate) fn random_points<const K: usize, const M: usize>(points: &[Point<M>], rng: &mut impl Rng) -> [Point<M>; K] {
+    let indices = random_indices(points.len(), K, rng);
+    std::array::from_fn(|k| points[indices[k]])
+}
+
+/// Sample k distinct points uniformly at random from the points that are not in low-density
+/// regions, see [`Initialization::DensityFiltered`].
+///
+/// Time complexity: O(r * log(r) * n * M) on average
+pub(crate) fn density_filtered_points<const K: usize, const M: usize>(
+    points: &[Point<M>],
+    tree: &impl SpatialIndex<M>,
+    neighbors: usize,
+    outlier_fraction: f64,
+    rng: &mut impl Rng
+) -> [Point<M>; K] {
+    // Estimate the density of each point, skipping the point itself
+    let distances: Vec<f64> = points.iter()
+        .map(|point| tree.k_nearest_distances(point, neighbors + 1).last().copied().unwrap_or(0.0))
+        .collect();
+
+    // Keep the densest points, but always at least k
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|&a, &b| distances[a].total_cmp(&distances[b]));
+    let keep = ((points.len() as f64 * (1.0 - outlier_fraction)).ceil() as usize).clamp(K, points.len());
+    let candidates: Vec<Point<M>> = order[..keep].iter().map(|&i| points[i]).collect();
+
+    random_points(&candidates, rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mrkd::Tree;
+    use crate::point::Point;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use super::*;
+
+    #[test]
+    fn density_filtered() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.1, 0.0]),
+            Point([0.0, 0.1]),
+            Point([100.0, 100.0])
+        ];
+        let tree = Tree::initialize(&points, &mut rng);
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let centers = density_filtered_points::<3, 2>(&points, &tree, 1, 0.25, &mut rng);
+            assert!(!centers.contains(&Point([100.0, 100.0])));
+        }
+    }
+}
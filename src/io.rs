@@ -0,0 +1,7 @@
+//! Reading points from files, at once or in chunks.
+
+pub mod binary;
+pub mod csv;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod source;
@@ -0,0 +1,250 @@
+//! A binary format for points that can be memory-mapped, so large datasets are loaded without
+//! parsing.
+//!
+//! A file consists of:
+//!
+//! 1. A header of 32 bytes: the magic bytes `CLUSTRS1`, followed by the number of rows, the
+//!    number of columns and the byte offset of the names, as little-endian `u64`s.
+//! 2. The values of every row after each other, as little-endian `f64`s. As the values start at
+//!    byte 32, they are aligned in memory when the file is mapped.
+//! 3. The name of every column, as a little-endian `u64` byte length followed by UTF-8. The names
+//!    come last, so that a file can be written while reading another one, before all names are
+//!    known.
+
+use crate::point::Point;
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// The bytes that every file starts with.
+pub const MAGIC: [u8; 8] = *b"CLUSTRS1";
+
+/// The number of bytes of the header, at which the values start.
+const HEADER_SIZE: u64 = 32;
+
+/// Writes rows of values to a file in the binary format, and the header once they are complete.
+pub struct BinaryWriter<W: Write + Seek> {
+    writer: W,
+    rows: u64,
+    columns: Option<usize>
+}
+
+impl<W: Write + Seek> BinaryWriter<W> {
+    /// Start a file at the current position of `writer`, which should be the start.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        // The header is written when the number of rows is known
+        writer.write_all(&[0; HEADER_SIZE as usize])?;
+        Ok(Self { writer, rows: 0, columns: Option::None })
+    }
+
+    /// Write a row, which needs as many values as the first row.
+    ///
+    /// Time complexity: O(M)
+    pub fn write_row(&mut self, row: &[f64]) -> io::Result<()> {
+        let columns = *self.columns.get_or_insert(row.len());
+        if row.len() != columns {
+            return Err(invalid_input(format!("a row has {} values, but the first row has {}", row.len(), columns)))
+        }
+        for x in row {
+            self.writer.write_all(&x.to_le_bytes())?;
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Write every point as a row.
+    ///
+    /// Time complexity: O(r * M)
+    pub fn write_points<const M: usize>(&mut self, points: &[Point<M>]) -> io::Result<()> {
+        points.iter().try_for_each(|point| self.write_row(&point.0))
+    }
+
+    /// Write the `names` of the columns, one per value of a row, and the header.
+    ///
+    /// Time complexity: O(n) for n columns
+    pub fn finish(mut self, names: &[String]) -> io::Result<W> {
+        let columns = self.columns.unwrap_or(names.len());
+        if names.len() != columns {
+            return Err(invalid_input(format!("{} names were given for {} columns", names.len(), columns)))
+        }
+        let names_offset = HEADER_SIZE + self.rows * columns as u64 * 8;
+        for name in names {
+            self.writer.write_all(&(name.len() as u64).to_le_bytes())?;
+            self.writer.write_all(name.as_bytes())?;
+        }
+
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.writer.write_all(&MAGIC)?;
+        for value in [self.rows, columns as u64, names_offset] {
+            self.writer.write_all(&value.to_le_bytes())?;
+        }
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// A file in the binary format, mapped into memory where possible. On other platforms, and on
+/// big-endian targets, the values are read into memory instead.
+pub struct MappedFile {
+    rows: usize,
+    columns: usize,
+    names: Vec<String>,
+    values: Values
+}
+
+enum Values {
+    #[cfg(all(unix, target_endian = "little"))]
+    Mapped { address: *mut libc::c_void, length: usize },
+    #[cfg_attr(all(unix, target_endian = "little"), allow(dead_code))]
+    Owned(Vec<f64>)
+}
+
+// The mapping is private and read-only, so it can be shared like a `Vec<f64>`
+unsafe impl Send for MappedFile {}
+unsafe impl Sync for MappedFile {}
+
+impl MappedFile {
+    /// Map the file at `path`, or return an error if it cannot be read or is not in the binary
+    /// format.
+    ///
+    /// Time complexity: O(n) for n columns when mapped, O(r * n) otherwise
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let length = file.metadata()?.len();
+
+        let mut header = [0; HEADER_SIZE as usize];
+        file.read_exact(&mut header).map_err(|_| invalid_data("the file is too short for a header"))?;
+        if header[..8] != MAGIC {
+            return Err(invalid_data("the file does not start with the magic bytes"))
+        }
+        let field = |i: usize| u64::from_le_bytes(header[8 * i..8 * i + 8].try_into().unwrap());
+        let (rows, columns, names_offset) = (field(1), field(2), field(3));
+        let values_end = rows.checked_mul(columns)
+            .and_then(|n| n.checked_mul(8))
+            .and_then(|n| n.checked_add(HEADER_SIZE))
+            .filter(|&end| end == names_offset && end <= length)
+            .ok_or_else(|| invalid_data("the number of values does not match the size of the file"))?;
+
+        // Read the names at the end
+        file.seek(SeekFrom::Start(values_end))?;
+        let mut names = Vec::with_capacity(columns.min(length) as usize);
+        for _ in 0..columns {
+            let mut size = [0; 8];
+            file.read_exact(&mut size)?;
+            let size = u64::from_le_bytes(size);
+            if size > length {
+                return Err(invalid_data("a name is longer than the file"))
+            }
+            let mut name = vec![0; size as usize];
+            file.read_exact(&mut name)?;
+            names.push(String::from_utf8(name).map_err(invalid_data)?);
+        }
+
+        let (rows, columns) = (rows as usize, columns as usize);
+        let values = Self::map(&mut file, length as usize, rows * columns)?;
+        Ok(Self { rows, columns, names, values })
+    }
+
+    #[cfg(all(unix, target_endian = "little"))]
+    fn map(file: &mut File, length: usize, _: usize) -> io::Result<Values> {
+        use std::os::unix::io::AsRawFd;
+
+        // The length is at least the size of the header, as mapping nothing fails
+        let address = unsafe { libc::mmap(std::ptr::null_mut(), length, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0) };
+        if address == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error())
+        }
+        Ok(Values::Mapped { address, length })
+    }
+
+    #[cfg(not(all(unix, target_endian = "little")))]
+    fn map(file: &mut File, _: usize, n: usize) -> io::Result<Values> {
+        file.seek(SeekFrom::Start(HEADER_SIZE))?;
+        let mut bytes = vec![0; n * 8];
+        file.read_exact(&mut bytes)?;
+        Ok(Values::Owned(bytes.chunks_exact(8).map(|x| f64::from_le_bytes(x.try_into().unwrap())).collect()))
+    }
+
+    /// The number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The number of values per row.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// The name of every column.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The values of every row after each other.
+    pub fn values(&self) -> &[f64] {
+        match &self.values {
+            #[cfg(all(unix, target_endian = "little"))]
+            Values::Mapped { address, .. } => unsafe {
+                // The values start at an offset of 32 bytes from a page boundary, so they are
+                // aligned, and the file was checked to be long enough
+                std::slice::from_raw_parts((*address as *const u8).add(HEADER_SIZE as usize) as *const f64, self.rows * self.columns)
+            },
+            Values::Owned(values) => values
+        }
+    }
+
+    /// The rows as points with M dimensions, or an error if the rows have a different number of
+    /// values.
+    pub fn points<const M: usize>(&self) -> io::Result<&[Point<M>]> {
+        if self.columns != M {
+            return Err(invalid_data(format!("the file has {} columns, not {}", self.columns, M)))
+        }
+        let values = self.values();
+        // A point has the same layout as M values
+        Ok(unsafe { std::slice::from_raw_parts(values.as_ptr() as *const Point<M>, self.rows) })
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        #[cfg(all(unix, target_endian = "little"))]
+        if let Values::Mapped { address, length } = self.values {
+            unsafe { libc::munmap(address, length) };
+        }
+    }
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+fn invalid_input(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_map() {
+        let path = std::env::temp_dir().join("kmeans_write_and_map.bin");
+        let points = [Point([1.0, 2.0]), Point([3.0, -4.5])];
+        let mut writer = BinaryWriter::new(File::create(&path).unwrap()).unwrap();
+        writer.write_points(&points).unwrap();
+        assert!(writer.write_row(&[1.0]).is_err());
+        writer.finish(&["x".to_owned(), "y".to_owned()]).unwrap();
+
+        let file = MappedFile::open(&path).unwrap();
+        assert_eq!((file.rows(), file.columns()), (2, 2));
+        assert_eq!(file.names(), ["x", "y"]);
+        assert_eq!(file.points::<2>().unwrap(), points);
+        assert!(file.points::<3>().is_err());
+
+        std::fs::write(&path, b"CLUSTRS1").unwrap();
+        assert!(MappedFile::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
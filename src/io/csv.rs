@@ -0,0 +1,331 @@
+//! Reading points from CSV files.
+
+use crate::dynamic::DynPoint;
+use crate::point::Point;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+
+/// A column of a CSV file, selected by the name in its header or by its index.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Column {
+    Name(String),
+    Index(usize)
+}
+
+impl From<&str> for Column {
+    fn from(name: &str) -> Self {
+        Column::Name(name.to_owned())
+    }
+}
+
+impl From<String> for Column {
+    fn from(name: String) -> Self {
+        Column::Name(name)
+    }
+}
+
+impl From<usize> for Column {
+    fn from(index: usize) -> Self {
+        Column::Index(index)
+    }
+}
+
+/// The error returned when a CSV file cannot be read as points. Lines are numbered from 1,
+/// including the header.
+#[derive(Debug)]
+pub enum CsvError {
+    /// Reading failed.
+    Io(io::Error),
+    /// A selected column is not in the header.
+    MissingColumn {
+        /// The name of the column.
+        name: String
+    },
+    /// A selected column index is out of range, or names were used without a header.
+    InvalidColumn {
+        /// The selected column.
+        column: Column
+    },
+    /// A different number of columns was selected than the points have dimensions.
+    DimensionMismatch {
+        /// The number of dimensions of the points.
+        expected: usize,
+        /// The number of selected columns.
+        found: usize
+    },
+    /// A line has a different number of values than the first line.
+    ValueCount {
+        /// The number of the line.
+        line: usize,
+        /// The number of values in the first line.
+        expected: usize,
+        /// The number of values in the line.
+        found: usize
+    },
+    /// A value is not a number.
+    InvalidValue {
+        /// The number of the line.
+        line: usize,
+        /// The index of the column.
+        column: usize,
+        /// The value.
+        value: String
+    }
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CsvError::Io(error) => error.fmt(f),
+            CsvError::MissingColumn { name } => write!(f, "the header has no column {}", name),
+            CsvError::InvalidColumn { column: Column::Index(index) } => write!(f, "there is no column {}", index),
+            CsvError::InvalidColumn { column: Column::Name(name) } =>
+                write!(f, "column {} can only be selected by name with a header", name),
+            CsvError::DimensionMismatch { expected, found } =>
+                write!(f, "{} columns were selected for points with {} dimensions", found, expected),
+            CsvError::ValueCount { line, expected, found } =>
+                write!(f, "line {} has {} values, but the first line has {}", line, found, expected),
+            CsvError::InvalidValue { line, column, value } =>
+                write!(f, "line {} has a value {:?} in column {} that is not a number", line, value, column)
+        }
+    }
+}
+
+impl std::error::Error for CsvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CsvError::Io(error) => Option::Some(error),
+            _ => Option::None
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(error: io::Error) -> Self {
+        CsvError::Io(error)
+    }
+}
+
+/// Points read from a CSV file, with the names of their dimensions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Table<P> {
+    /// The names of the selected columns, or `x0`, `x1`, ... without a header.
+    pub names: Vec<String>,
+    /// One point per line.
+    pub points: Vec<P>
+}
+
+/// Reads numeric columns of a CSV file as points.
+///
+/// Values may be enclosed in double quotes, for example to contain the delimiter, with `""` for a
+/// quote inside a quoted value; values cannot span multiple lines. Empty values are read as NaN,
+/// so that they can be handled with a [`MissingPolicy`](crate::preprocess::MissingPolicy). Empty
+/// lines are skipped.
+#[derive(Clone, Debug)]
+pub struct CsvReader {
+    delimiter: char,
+    header: bool,
+    columns: Option<Vec<Column>>
+}
+
+impl Default for CsvReader {
+    fn default() -> Self {
+        Self { delimiter: ',', header: true, columns: Option::None }
+    }
+}
+
+impl CsvReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the character between values. Defaults to a comma.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set whether the first line is a header with the names of the columns. Defaults to true.
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Read only `columns`, in the given order. Defaults to all columns.
+    pub fn columns<C: Into<Column>>(mut self, columns: impl IntoIterator<Item = C>) -> Self {
+        self.columns = Option::Some(columns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Read points with M dimensions, which needs M selected columns.
+    ///
+    /// Time complexity: O(r * n) for n columns
+    pub fn read<const M: usize>(&self, reader: impl Read) -> Result<Table<Point<M>>, CsvError> {
+        let table = self.read_rows(reader, Option::Some(M))?;
+        Ok(Table {
+            names: table.names,
+            points: table.points.into_iter().map(|row| Point(std::array::from_fn(|d| row[d]))).collect()
+        })
+    }
+
+    /// Read points with as many dimensions as there are selected columns.
+    ///
+    /// Time complexity: O(r * n) for n columns
+    pub fn read_dyn(&self, reader: impl Read) -> Result<Table<DynPoint>, CsvError> {
+        let table = self.read_rows(reader, Option::None)?;
+        Ok(Table { names: table.names, points: table.points.into_iter().map(DynPoint).collect() })
+    }
+
+    /// Read points with M dimensions in chunks of at most `chunk_size` points, calling `f` with
+    /// every chunk, without holding more than one chunk in memory. Returns the names of the
+    /// selected columns.
+    ///
+    /// Time complexity: O(r * n) for n columns
+    pub fn read_chunks<const M: usize>(&self, reader: impl Read, chunk_size: usize, mut f: impl FnMut(&[Point<M>])) -> Result<Vec<String>, CsvError> {
+        let chunk_size = chunk_size.max(1);
+        let mut chunk = Vec::with_capacity(chunk_size);
+        let names = self.visit_rows(reader, Option::Some(M), |row| {
+            chunk.push(Point(std::array::from_fn(|d| row[d])));
+            if chunk.len() == chunk_size {
+                f(&chunk);
+                chunk.clear();
+            }
+        })?;
+        if !chunk.is_empty() {
+            f(&chunk);
+        }
+        Ok(names)
+    }
+
+    /// Read points with as many dimensions as there are selected columns in chunks of at most
+    /// `chunk_size` points, see [`CsvReader::read_chunks`].
+    ///
+    /// Time complexity: O(r * n) for n columns
+    pub fn read_dyn_chunks(&self, reader: impl Read, chunk_size: usize, mut f: impl FnMut(&[DynPoint])) -> Result<Vec<String>, CsvError> {
+        let chunk_size = chunk_size.max(1);
+        let mut chunk = Vec::with_capacity(chunk_size);
+        let names = self.visit_rows(reader, Option::None, |row| {
+            chunk.push(DynPoint(row));
+            if chunk.len() == chunk_size {
+                f(&chunk);
+                chunk.clear();
+            }
+        })?;
+        if !chunk.is_empty() {
+            f(&chunk);
+        }
+        Ok(names)
+    }
+
+    fn read_rows(&self, reader: impl Read, m: Option<usize>) -> Result<Table<Vec<f64>>, CsvError> {
+        let mut rows = Vec::new();
+        let names = self.visit_rows(reader, m, |row| rows.push(row))?;
+        Ok(Table { names, points: rows })
+    }
+
+    /// Parse the selected values of every line after the header, and call `f` with them in order.
+    /// Returns the names of the selected columns.
+    fn visit_rows(&self, reader: impl Read, m: Option<usize>, mut f: impl FnMut(Vec<f64>)) -> Result<Vec<String>, CsvError> {
+        let mut lines = BufReader::new(reader).lines()
+            .enumerate()
+            .map(|(i, line)| line.map(|line| (i + 1, line)))
+            .filter(|line| !matches!(line, Ok((_, line)) if line.trim().is_empty()));
+
+        // The header, or the first line to count the columns
+        let first = match lines.next() {
+            Option::Some(line) => Option::Some(line?),
+            None => Option::None
+        };
+        let first_values = first.as_ref().map_or_else(Vec::new, |(_, line)| self.split(line));
+        let n = first_values.len();
+        let header = if self.header { Option::Some(&first_values) } else { Option::None };
+
+        // Resolve the selected columns to indices
+        let indices: Vec<usize> = match &self.columns {
+            None => (0..n).collect(),
+            Option::Some(columns) => columns.iter().map(|column| match (column, header) {
+                (Column::Index(index), _) if *index < n => Ok(*index),
+                (Column::Name(name), Option::Some(header)) => header.iter()
+                    .position(|value| value == name)
+                    .ok_or_else(|| CsvError::MissingColumn { name: name.clone() }),
+                _ => Err(CsvError::InvalidColumn { column: column.clone() })
+            }).collect::<Result<_, _>>()?
+        };
+        if let Option::Some(m) = m {
+            if indices.len() != m {
+                return Err(CsvError::DimensionMismatch { expected: m, found: indices.len() })
+            }
+        }
+        let names = indices.iter()
+            .map(|&i| header.map_or_else(|| format!("x{}", i), |header| header[i].clone()))
+            .collect();
+
+        let mut parse = |line: usize, values: Vec<String>| {
+            if values.len() != n {
+                return Err(CsvError::ValueCount { line, expected: n, found: values.len() })
+            }
+            let row = indices.iter().map(|&column| {
+                let value = values[column].trim();
+                if value.is_empty() {
+                    return Ok(f64::NAN)
+                }
+                value.parse().map_err(|_| CsvError::InvalidValue { line, column, value: value.to_owned() })
+            }).collect::<Result<_, _>>()?;
+            f(row);
+            Ok(())
+        };
+        if let (Option::Some((line, _)), false) = (&first, self.header) {
+            parse(*line, first_values.clone())?;
+        }
+        for line in lines {
+            let (line, text) = line?;
+            parse(line, self.split(&text))?;
+        }
+
+        Ok(names)
+    }
+
+    /// Split a line at the delimiters outside of quotes.
+    fn split(&self, line: &str) -> Vec<String> {
+        let mut values = Vec::new();
+        let mut value = String::new();
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+        while let Option::Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Option::Some(&'"') => {
+                    value.push('"');
+                    chars.next();
+                },
+                '"' => quoted = !quoted,
+                c if c == self.delimiter && !quoted => values.push(std::mem::take(&mut value)),
+                c => value.push(c)
+            }
+        }
+        values.push(value);
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read() {
+        let csv = "id;\"x;1\";y\n\na;1.5;2\nb;;4\n";
+        let reader = CsvReader::new().delimiter(';').columns(["y", "x;1"]);
+        let table = reader.read::<2>(csv.as_bytes()).unwrap();
+        assert_eq!(table.names, ["y", "x;1"]);
+        assert_eq!(table.points[0], Point([2.0, 1.5]));
+        assert!(table.points[1].0[1].is_nan());
+
+        let table = CsvReader::new().header(false).columns([2]).read_dyn("1,2,3\n4,5,6".as_bytes()).unwrap();
+        assert_eq!(table.points, [DynPoint(vec![3.0]), DynPoint(vec![6.0])]);
+
+        match CsvReader::new().read::<2>("x,y\n1,a\n".as_bytes()) {
+            Err(CsvError::InvalidValue { line, column, .. }) => assert_eq!((line, column), (2, 1)),
+            _ => panic!("expected an invalid value")
+        }
+    }
+}
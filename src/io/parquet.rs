@@ -0,0 +1,186 @@
+//! Reading numeric columns of Parquet and Arrow IPC files into points.
+//!
+//! Only the selected columns are read, one record batch at a time, so other columns such as
+//! strings are never materialized. Integer and floating-point columns are converted to `f64`, and
+//! null values become NaN, which can then be handled with a
+//! [`MissingPolicy`](crate::preprocess::MissingPolicy).
+
+use crate::dynamic::DynPoint;
+use crate::point::Point;
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type, UInt32Type, UInt64Type, UInt8Type};
+use arrow_array::{Array, ArrowPrimitiveType, RecordBatch};
+use arrow_ipc::reader::FileReader;
+use arrow_schema::{ArrowError, DataType, Schema};
+use ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use ::parquet::arrow::ProjectionMask;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// Read the `columns` of a Parquet file as points, in the order of the columns.
+///
+/// Time complexity: O(r * M)
+pub fn read_parquet<const M: usize>(path: impl AsRef<Path>, columns: [&str; M]) -> io::Result<Vec<Point<M>>> {
+    let values = parquet_values(path.as_ref(), &columns)?;
+    Ok(values.chunks_exact(M.max(1)).map(|row| Point(std::array::from_fn(|d| row[d]))).collect())
+}
+
+/// Read the `columns` of a Parquet file as points with as many dimensions as there are columns.
+///
+/// Time complexity: O(r * M)
+pub fn read_parquet_dyn(path: impl AsRef<Path>, columns: &[&str]) -> io::Result<Vec<DynPoint>> {
+    let values = parquet_values(path.as_ref(), columns)?;
+    Ok(values.chunks_exact(columns.len().max(1)).map(|row| DynPoint(row.to_vec())).collect())
+}
+
+/// Read the `columns` of a Parquet file as points in chunks of at most `chunk_size` points,
+/// calling `f` with every chunk, without holding more than one chunk in memory.
+///
+/// Time complexity: O(r * M)
+pub fn read_parquet_chunks<const M: usize>(path: impl AsRef<Path>, columns: [&str; M], chunk_size: usize, mut f: impl FnMut(&[Point<M>])) -> io::Result<()> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(path.as_ref())?).map_err(invalid_data)?;
+    let indices = column_indices(builder.schema(), &columns)?;
+    let mask = ProjectionMask::roots(builder.parquet_schema(), indices);
+    let reader = builder.with_projection(mask).with_batch_size(chunk_size.max(1)).build().map_err(invalid_data)?;
+    for batch in reader {
+        let values = batch_values(&batch.map_err(invalid_data)?, &columns)?;
+        let chunk: Vec<Point<M>> = values.chunks_exact(M.max(1)).map(|row| Point(std::array::from_fn(|d| row[d]))).collect();
+        f(&chunk);
+    }
+    Ok(())
+}
+
+/// Read the `columns` of an Arrow IPC file, also known as Feather version 2, as points, in the
+/// order of the columns.
+///
+/// Time complexity: O(r * M)
+pub fn read_arrow_ipc<const M: usize>(path: impl AsRef<Path>, columns: [&str; M]) -> io::Result<Vec<Point<M>>> {
+    let values = arrow_ipc_values(path.as_ref(), &columns)?;
+    Ok(values.chunks_exact(M.max(1)).map(|row| Point(std::array::from_fn(|d| row[d]))).collect())
+}
+
+/// Read the `columns` of an Arrow IPC file as points with as many dimensions as there are
+/// columns.
+///
+/// Time complexity: O(r * M)
+pub fn read_arrow_ipc_dyn(path: impl AsRef<Path>, columns: &[&str]) -> io::Result<Vec<DynPoint>> {
+    let values = arrow_ipc_values(path.as_ref(), columns)?;
+    Ok(values.chunks_exact(columns.len().max(1)).map(|row| DynPoint(row.to_vec())).collect())
+}
+
+fn parquet_values(path: &Path, columns: &[&str]) -> io::Result<Vec<f64>> {
+    let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?).map_err(invalid_data)?;
+    let indices = column_indices(builder.schema(), columns)?;
+    let mask = ProjectionMask::roots(builder.parquet_schema(), indices);
+    let reader = builder.with_projection(mask).build().map_err(invalid_data)?;
+    read_batches(reader, columns)
+}
+
+fn arrow_ipc_values(path: &Path, columns: &[&str]) -> io::Result<Vec<f64>> {
+    // The schema is in the footer, so it is read before the record batches
+    let schema = FileReader::try_new_buffered(File::open(path)?, Option::None).map_err(invalid_data)?.schema();
+    let indices = column_indices(&schema, columns)?;
+    let reader = FileReader::try_new(BufReader::new(File::open(path)?), Option::Some(indices)).map_err(invalid_data)?;
+    read_batches(reader, columns)
+}
+
+/// The indices of `columns` in `schema`, checking that they are numeric.
+fn column_indices(schema: &Schema, columns: &[&str]) -> io::Result<Vec<usize>> {
+    columns.iter().map(|&name| {
+        let index = schema.index_of(name).map_err(invalid_data)?;
+        let data_type = schema.field(index).data_type();
+        if !data_type.is_numeric() {
+            return Err(invalid_data(format!("column {} has non-numeric type {}", name, data_type)))
+        }
+        Ok(index)
+    }).collect()
+}
+
+/// The values of `columns` in all batches, one row after another.
+fn read_batches(batches: impl Iterator<Item = Result<RecordBatch, ArrowError>>, columns: &[&str]) -> io::Result<Vec<f64>> {
+    let mut values = Vec::new();
+    for batch in batches {
+        values.extend(batch_values(&batch.map_err(invalid_data)?, columns)?);
+    }
+    Ok(values)
+}
+
+/// The values of `columns` in a batch, one row after another.
+fn batch_values(batch: &RecordBatch, columns: &[&str]) -> io::Result<Vec<f64>> {
+    let m = columns.len();
+    let mut values = vec![0.0; batch.num_rows() * m];
+    for (d, name) in columns.iter().enumerate() {
+        let array = batch.column_by_name(name).ok_or_else(|| invalid_data(format!("column {} is missing", name)))?;
+        for (i, x) in column_values(array.as_ref())?.into_iter().enumerate() {
+            values[i * m + d] = x;
+        }
+    }
+    Ok(values)
+}
+
+/// The values of a numeric array as `f64`, with NaN for nulls.
+fn column_values(array: &dyn Array) -> io::Result<Vec<f64>> {
+    fn convert<T: ArrowPrimitiveType>(array: &dyn Array, f: impl Fn(T::Native) -> f64) -> Vec<f64> {
+        array.as_primitive::<T>().iter().map(|x| x.map_or(f64::NAN, &f)).collect()
+    }
+
+    Ok(match array.data_type() {
+        DataType::Float64 => convert::<Float64Type>(array, |x| x),
+        DataType::Float32 => convert::<Float32Type>(array, f64::from),
+        DataType::Int8 => convert::<Int8Type>(array, f64::from),
+        DataType::Int16 => convert::<Int16Type>(array, f64::from),
+        DataType::Int32 => convert::<Int32Type>(array, f64::from),
+        DataType::Int64 => convert::<Int64Type>(array, |x| x as f64),
+        DataType::UInt8 => convert::<UInt8Type>(array, f64::from),
+        DataType::UInt16 => convert::<UInt16Type>(array, f64::from),
+        DataType::UInt32 => convert::<UInt32Type>(array, f64::from),
+        DataType::UInt64 => convert::<UInt64Type>(array, |x| x as f64),
+        data_type => return Err(invalid_data(format!("unsupported column type {}", data_type)))
+    })
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Float64Array, Int32Array, StringArray};
+    use arrow_schema::Field;
+    use std::sync::Arc;
+
+    #[test]
+    fn read_columns() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("x", DataType::Float64, true),
+            Field::new("y", DataType::Int32, false)
+        ]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![
+            Arc::new(StringArray::from(vec!["a", "b"])),
+            Arc::new(Float64Array::from(vec![Option::Some(0.5), Option::None])),
+            Arc::new(Int32Array::from(vec![1, 2]))
+        ]).unwrap();
+
+        let path = std::env::temp_dir().join("kmeans_read_columns.parquet");
+        let mut writer = ::parquet::arrow::ArrowWriter::try_new(File::create(&path).unwrap(), schema.clone(), Option::None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let points = read_parquet(&path, ["y", "x"]).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(points[0], Point([1.0, 0.5]));
+        assert!(points[1].0[1].is_nan());
+
+        let path = std::env::temp_dir().join("kmeans_read_columns.arrow");
+        let mut writer = arrow_ipc::writer::FileWriter::try_new(File::create(&path).unwrap(), &schema).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        let points = read_arrow_ipc(&path, ["y"]).unwrap();
+        assert_eq!(read_arrow_ipc_dyn(&path, &["y", "y"]).unwrap()[1], DynPoint(vec![2.0, 2.0]));
+        assert!(read_arrow_ipc(&path, ["name"]).is_err());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(points, [Point([1.0]), Point([2.0])]);
+    }
+}
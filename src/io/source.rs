@@ -0,0 +1,111 @@
+//! Sources of points that are read in chunks, so that datasets larger than memory can be
+//! clustered with [`OutOfCoreKMeans`](crate::OutOfCoreKMeans).
+
+use crate::io::csv::{CsvError, CsvReader};
+use crate::point::Point;
+use std::convert::Infallible;
+use std::fs::File;
+use std::path::PathBuf;
+
+/// The number of points per chunk of the file sources, unless set otherwise: 8 MiB of points
+/// with 16 dimensions.
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 16;
+
+/// A dataset that can be read any number of times as a sequence of chunks of points.
+pub trait PointSource<const M: usize> {
+    /// The error returned when reading fails.
+    type Error;
+
+    /// Call `f` with every chunk of points. Every pass over the source has to give the same
+    /// points in the same order.
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[Point<M>])) -> Result<(), Self::Error>;
+}
+
+/// Points in memory are a source of a single chunk.
+impl<const M: usize> PointSource<M> for [Point<M>] {
+    type Error = Infallible;
+
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[Point<M>])) -> Result<(), Self::Error> {
+        f(self);
+        Ok(())
+    }
+}
+
+/// A CSV file that is read with a [`CsvReader`] in chunks on every pass.
+#[derive(Clone, Debug)]
+pub struct CsvSource<const M: usize> {
+    path: PathBuf,
+    reader: CsvReader,
+    chunk_size: usize
+}
+
+impl<const M: usize> CsvSource<M> {
+    /// The file at `path`, read with `reader`, which has to select M columns.
+    pub fn new(path: impl Into<PathBuf>, reader: CsvReader) -> Self {
+        Self { path: path.into(), reader, chunk_size: DEFAULT_CHUNK_SIZE }
+    }
+
+    /// Set the maximum number of points per chunk. Defaults to [`DEFAULT_CHUNK_SIZE`].
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+}
+
+impl<const M: usize> PointSource<M> for CsvSource<M> {
+    type Error = CsvError;
+
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[Point<M>])) -> Result<(), Self::Error> {
+        self.reader.read_chunks(File::open(&self.path)?, self.chunk_size, f)?;
+        Ok(())
+    }
+}
+
+/// Numeric columns of a Parquet file that are read in chunks on every pass.
+#[cfg(feature = "parquet")]
+#[derive(Clone, Debug)]
+pub struct ParquetSource<const M: usize> {
+    path: PathBuf,
+    columns: [String; M],
+    chunk_size: usize
+}
+
+#[cfg(feature = "parquet")]
+impl<const M: usize> ParquetSource<M> {
+    /// The `columns` of the file at `path`, in the order of the columns.
+    pub fn new(path: impl Into<PathBuf>, columns: [&str; M]) -> Self {
+        Self { path: path.into(), columns: columns.map(str::to_owned), chunk_size: DEFAULT_CHUNK_SIZE }
+    }
+
+    /// Set the maximum number of points per chunk. Defaults to [`DEFAULT_CHUNK_SIZE`].
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl<const M: usize> PointSource<M> for ParquetSource<M> {
+    type Error = std::io::Error;
+
+    fn for_each_chunk(&self, f: &mut dyn FnMut(&[Point<M>])) -> Result<(), Self::Error> {
+        let columns = std::array::from_fn(|d| self.columns[d].as_str());
+        crate::io::parquet::read_parquet_chunks(&self.path, columns, self.chunk_size, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_source() {
+        let path = std::env::temp_dir().join("kmeans_csv_source.csv");
+        std::fs::write(&path, "x,y\n1,2\n3,4\n5,6\n").unwrap();
+        let source = CsvSource::<2>::new(&path, CsvReader::new()).chunk_size(2);
+        let mut chunks = Vec::new();
+        source.for_each_chunk(&mut |chunk| chunks.push(chunk.to_vec())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(chunks, [vec![Point([1.0, 2.0]), Point([3.0, 4.0])], vec![Point([5.0, 6.0])]]);
+    }
+}
@@ -0,0 +1,114 @@
+//! JNI bindings for the static native methods of a Java class `kmeans.NativeKMeans`:
+//!
+//! ```java
+//! package kmeans;
+//!
+//! public final class NativeKMeans {
+//!     static { System.loadLibrary("kmeans"); }
+//!
+//!     /** Row-major `data` with `m` columns; returns the row-major `k` x `m` centers. */
+//!     public static native double[] fit(double[] data, int m, int k, long seed);
+//!
+//!     /** Row-major `data` with `m` columns; returns the index of the closest center per row. */
+//!     public static native int[] predict(double[] centers, int m, double[] data);
+//! }
+//! ```
+//!
+//! Invalid arguments throw an `IllegalArgumentException` instead of aborting the JVM.
+
+use crate::dynamic::{DynKMeans, DynPoint};
+use jni::JNIEnv;
+use jni::objects::{JClass, JDoubleArray, JIntArray};
+use jni::sys::{jdoubleArray, jint, jintArray, jlong};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+const ILLEGAL_ARGUMENT: &str = "java/lang/IllegalArgumentException";
+
+#[no_mangle]
+pub extern "system" fn Java_kmeans_NativeKMeans_fit<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    data: JDoubleArray<'local>,
+    m: jint,
+    k: jint,
+    seed: jlong
+) -> jdoubleArray {
+    let result = catch_unwind(AssertUnwindSafe(|| -> Result<jdoubleArray, String> {
+        let points = read_points(&env, &data, m)?;
+        if k < 1 || k as usize > points.len() {
+            return Err(format!("cannot make {} clusters out of {} points", k, points.len()))
+        }
+
+        let kmeans = DynKMeans::fit_with_random_state(&points, k as usize, seed as u64).map_err(|e| e.to_string())?;
+        let centers: Vec<f64> = kmeans.centers.into_iter().flat_map(|center| center.0).collect();
+
+        let array = env.new_double_array(centers.len() as i32).map_err(|e| e.to_string())?;
+        env.set_double_array_region(&array, 0, &centers).map_err(|e| e.to_string())?;
+        Ok(array.into_raw())
+    }));
+
+    unwrap_or_throw(&mut env, result)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_kmeans_NativeKMeans_predict<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    centers: JDoubleArray<'local>,
+    m: jint,
+    data: JDoubleArray<'local>
+) -> jintArray {
+    let result = catch_unwind(AssertUnwindSafe(|| -> Result<jintArray, String> {
+        let centers = read_points(&env, &centers, m)?;
+        if centers.is_empty() {
+            return Err(String::from("no centers given"))
+        }
+        let points = read_points(&env, &data, m)?;
+
+        let kmeans = DynKMeans { centers, point_centers: Vec::new(), iterations: 0, converged: true };
+        let labels: Vec<jint> = points.iter().map(|point| kmeans.predict(point) as jint).collect();
+
+        let array: JIntArray = env.new_int_array(labels.len() as i32).map_err(|e| e.to_string())?;
+        env.set_int_array_region(&array, 0, &labels).map_err(|e| e.to_string())?;
+        Ok(array.into_raw())
+    }));
+
+    unwrap_or_throw(&mut env, result)
+}
+
+/// Split a row-major array into points of `m` dimensions.
+fn read_points(env: &JNIEnv, array: &JDoubleArray, m: jint) -> Result<Vec<DynPoint>, String> {
+    if m < 1 {
+        return Err(format!("number of dimensions must be positive, got {}", m))
+    }
+    let len = env.get_array_length(array).map_err(|e| e.to_string())? as usize;
+    let m = m as usize;
+    if !len.is_multiple_of(m) {
+        return Err(format!("array of length {} cannot be split into rows of {} values", len, m))
+    }
+
+    let mut values = vec![0.0; len];
+    env.get_double_array_region(array, 0, &mut values).map_err(|e| e.to_string())?;
+    if values.iter().any(|x| !x.is_finite()) {
+        return Err(String::from("array contains NaN or infinite values"))
+    }
+
+    Ok(values.chunks(m).map(|row| DynPoint(row.to_vec())).collect())
+}
+
+/// Return the array, or throw an `IllegalArgumentException` and return null.
+fn unwrap_or_throw<T>(env: &mut JNIEnv, result: std::thread::Result<Result<*mut T, String>>) -> *mut T {
+    let message = match result {
+        Ok(Ok(array)) => return array,
+        Ok(Err(message)) => message,
+        Err(panic) => panic.downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| String::from("k-means panicked"))
+    };
+
+    // If an exception is already pending there is nothing more to report
+    if !env.exception_check().unwrap_or(true) {
+        let _ = env.throw_new(ILLEGAL_ARGUMENT, message);
+    }
+    std::ptr::null_mut()
+}
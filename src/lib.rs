@@ -1,9 +1,105 @@
+pub use ball_tree::BallTree;
+pub use birch::{Birch, BirchParams, ClusteringFeature};
+pub use builder::{FeatureGroup, KMeansBuilder};
+pub use capacitated::{CapacitatedKMeans, CapacitatedKMeansParams};
 pub use clusterer::*;
+pub use clustering::{ClusterStats, Clustering};
+pub use dataset::Dataset;
+pub use dbscan::{Dbscan, DbscanParams};
+pub use dynamic::{DynKMeans, DynPoint};
+pub use error::{ClusterError, PredictError};
+pub use estimator::{Clusterer, Fit, Predict};
+pub use ewkm::{EntropyWeightedKMeans, EntropyWeightedKMeansParams};
+pub use fuzzy::{FuzzyCMeans, FuzzyCMeansParams};
+pub use hyper_rectangle::HyperRectangle;
+pub use io::source::PointSource;
+pub use index::SpatialIndex;
+pub use initialization::Initialization;
+#[cfg(feature = "linfa")]
+pub use self::linfa::LinfaError;
+#[cfg(feature = "half")]
+pub use low_precision::{LowPrecision, LowPrecisionKMeans, LowPrecisionKMeansParams};
+pub use memory::{estimate_memory, MemoryBudgetExceeded, MemoryEstimate, MemoryPolicy};
+pub use metric::{Chebyshev, Cosine, Euclidean, Manhattan, Metric, SquaredEuclidean};
+pub use minibatch::{MiniBatchKMeans, MiniBatchKMeansParams};
+pub use mrkd::Tree;
+pub use noise::{NoiseKMeans, NoiseKMeansParams, NoiseThreshold};
+pub use out_of_core::{OutOfCoreError, OutOfCoreKMeans};
+pub use parallelism::Parallelism;
 pub use point::Point;
+pub use proclus::{Proclus, ProclusParams};
+pub use radius::{RadiusConstrainedKMeans, RadiusConstrainedKMeansParams};
+pub use rolling::{Drift, RollingKMeans};
+pub use seeded::{SeededKMeans, SeededKMeansParams};
+pub use som::{Som, SomParams};
+pub use spherical::{SphericalKMeans, SphericalKMeansParams};
+pub use sweep::{Sweep, SweepModel};
+pub use trimmed::{TrimmedKMeans, TrimmedKMeansParams};
 
+pub mod apps;
+pub mod data;
+#[cfg(any(test, feature = "datasets"))]
+pub mod datasets;
+pub mod geo;
+pub mod geometry;
+pub mod hyper_rectangle;
+pub mod io;
+pub mod metrics;
+pub mod mixed;
+pub mod model_selection;
+pub mod mrkd;
+pub mod preprocess;
+pub mod reduce;
+pub mod seeding;
+
+mod ball_tree;
+mod birch;
+mod bounds;
+mod builder;
+mod capacitated;
 mod centers;
 mod clusterer;
-mod hyper_rectangle;
-mod mrkd;
+mod clustering;
+mod dataset;
+mod dbscan;
+mod dynamic;
+mod error;
+mod estimator;
+mod ewkm;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod flow;
+mod fuzzy;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod index;
+mod initialization;
+#[cfg(feature = "jni")]
+mod jni;
+#[cfg(feature = "linfa")]
+mod linfa;
+#[cfg(feature = "half")]
+mod low_precision;
+mod memory;
+mod metric;
+mod minibatch;
+mod noise;
+mod out_of_core;
+mod parallelism;
 mod point;
+#[cfg(test)]
+mod properties;
+mod proclus;
 mod quickselect;
+mod radius;
+mod rolling;
+mod seeded;
+mod som;
+#[cfg(feature = "serde")]
+mod serialization;
+mod simd;
+mod spherical;
+mod summation;
+mod sweep;
+mod trimmed;
+mod voronoi;
@@ -0,0 +1,120 @@
+//! An adapter to the traits of [`linfa`](https://github.com/rust-ml/linfa), with the `linfa`
+//! feature, so that k-means can be used in linfa pipelines, for example with its dataset splitting,
+//! cross-validation and metrics, while still fitting with the algorithms of this crate.
+//!
+//! [`KMeansBuilder`] implements `linfa::traits::Fit` for datasets whose records have M columns,
+//! and [`KMeans`] implements `linfa::traits::PredictInplace`, through which linfa implements
+//! `linfa::traits::Predict` for records and datasets.
+
+use crate::builder::KMeansBuilder;
+use crate::clusterer::KMeans;
+use crate::error::ClusterError;
+use crate::metric::Metric;
+use crate::point::Point;
+use linfa::DatasetBase;
+use linfa::traits::{Fit, PredictInplace};
+use ndarray::{Array1, ArrayBase, Data, Ix2};
+use std::fmt;
+
+/// The error returned when a model cannot be fitted on a linfa dataset.
+#[derive(Debug)]
+pub enum LinfaError {
+    /// The dataset is invalid for linfa.
+    Linfa(linfa::Error),
+    /// The records cannot be clustered.
+    Cluster(ClusterError)
+}
+
+impl fmt::Display for LinfaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinfaError::Linfa(error) => error.fmt(f),
+            LinfaError::Cluster(error) => error.fmt(f)
+        }
+    }
+}
+
+impl std::error::Error for LinfaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LinfaError::Linfa(error) => Option::Some(error),
+            LinfaError::Cluster(error) => Option::Some(error)
+        }
+    }
+}
+
+impl From<linfa::Error> for LinfaError {
+    fn from(error: linfa::Error) -> Self {
+        LinfaError::Linfa(error)
+    }
+}
+
+impl From<ClusterError> for LinfaError {
+    fn from(error: ClusterError) -> Self {
+        LinfaError::Cluster(error)
+    }
+}
+
+/// The rows of `records` as points, or an error if they do not have M columns.
+///
+/// Time complexity: O(r * M)
+fn to_points<S: Data<Elem = f64>, const M: usize>(records: &ArrayBase<S, Ix2>) -> Result<Vec<Point<M>>, ClusterError> {
+    if records.ncols() != M {
+        return Err(ClusterError::DimensionMismatch { point: 0, expected: M, found: records.ncols() })
+    }
+    Ok(records.rows().into_iter().map(|row| Point(std::array::from_fn(|d| row[d]))).collect())
+}
+
+impl<const K: usize, const M: usize, D: Metric, S: Data<Elem = f64>, T> Fit<ArrayBase<S, Ix2>, T, LinfaError> for KMeansBuilder<K, M, D> {
+    type Object = KMeans<K, M, D>;
+
+    /// Fit on the records of `dataset`, as in [`KMeansBuilder::fit`], ignoring its targets.
+    fn fit(&self, dataset: &DatasetBase<ArrayBase<S, Ix2>, T>) -> Result<KMeans<K, M, D>, LinfaError> {
+        let points = to_points(dataset.records())?;
+        Ok(KMeansBuilder::fit(self, &points)?)
+    }
+}
+
+impl<const K: usize, const M: usize, D: Metric, S: Data<Elem = f64>> PredictInplace<ArrayBase<S, Ix2>, Array1<usize>> for KMeans<K, M, D> {
+    /// Write the index of the closest center of every row of `records` to `labels`, as in
+    /// [`KMeans::predict_many`].
+    ///
+    /// # Panics
+    ///
+    /// If the records do not have M columns, or there are not as many labels as records.
+    fn predict_inplace(&self, records: &ArrayBase<S, Ix2>, labels: &mut Array1<usize>) {
+        assert_eq!(records.nrows(), labels.len(), "the number of labels must match the number of records");
+        let points = to_points(records).expect("the records must have a column per dimension");
+        for (label, k) in labels.iter_mut().zip(self.predict_many(&points)) {
+            *label = k;
+        }
+    }
+
+    fn default_target(&self, records: &ArrayBase<S, Ix2>) -> Array1<usize> {
+        Array1::zeros(records.nrows())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use linfa::traits::Predict;
+    use ndarray::array;
+    use super::*;
+
+    #[test]
+    fn fit_dataset() {
+        let records = array![[0.0, 0.0], [0.0, 1.0], [9.0, 0.0], [9.0, 1.0]];
+        let dataset = DatasetBase::new(records, array![0_usize, 0, 1, 1]);
+        let builder = KMeansBuilder::<2, 2>::new().initial_centers([Point([0.0, 0.0]), Point([9.0, 0.0])]);
+        let model = Fit::fit(&builder, &dataset).unwrap();
+        assert_eq!(model.centers, [Point([0.0, 0.5]), Point([9.0, 0.5])]);
+
+        let labels: Array1<usize> = Predict::predict(&model, dataset.records());
+        assert_eq!(&labels, dataset.targets());
+
+        let records = array![[0.0, 0.0, 0.0]];
+        let error = Fit::fit(&builder, &DatasetBase::new(records, ())).err();
+        assert!(matches!(error, Option::Some(LinfaError::Cluster(ClusterError::DimensionMismatch { point: 0, expected: 2, found: 3 }))));
+    }
+}
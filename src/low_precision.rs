@@ -0,0 +1,238 @@
+use crate::error::{check_point_rows, ClusterError};
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::initialization::random_indices;
+use crate::point::Point;
+use half::{bf16, f16};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Maximum number of iterations.
+const MAX_ITER: usize = 300;
+
+/// A floating-point format with fewer bits than `f64`, used to store points compactly.
+pub trait LowPrecision: Copy + Send + Sync {
+    /// The largest relative rounding error of the format.
+    const UNIT_ROUNDOFF: f64;
+
+    fn to_f64(self) -> f64;
+}
+
+impl LowPrecision for f16 {
+    const UNIT_ROUNDOFF: f64 = 1.0 / 2048.0;
+
+    fn to_f64(self) -> f64 {
+        f16::to_f64(self)
+    }
+}
+
+impl LowPrecision for bf16 {
+    const UNIT_ROUNDOFF: f64 = 1.0 / 256.0;
+
+    fn to_f64(self) -> f64 {
+        bf16::to_f64(self)
+    }
+}
+
+impl LowPrecision for f32 {
+    const UNIT_ROUNDOFF: f64 = f32::EPSILON as f64 / 2.0;
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+/// Implements [k-means clustering](https://en.wikipedia.org/wiki/K-means_clustering) using
+/// Lloyd's algorithm on points stored in a low-precision format such as `f16` or `bf16`, which
+/// reduces the memory and memory bandwidth needed for the points, for example for embeddings that
+/// are already stored in low precision. Points are converted on the fly, and distances and centers
+/// are computed in `f64`.
+///
+/// The rounding of the inputs limits the accuracy of the result: see [`LowPrecisionKMeans::max_input_error`]
+/// and [`LowPrecisionKMeans::ambiguous`].
+pub struct LowPrecisionKMeans<const K: usize, const M: usize> {
+    /// The coordinates of the k means.
+    pub centers: [Point<M>; K],
+    /// The index of the center that each point belongs to.
+    pub point_centers: Vec<usize>,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the centers converged within the maximum number of iterations.
+    pub converged: bool,
+    /// The largest error in the distance of a point to a center caused by the rounding of the
+    /// point to the low-precision format, assuming it was rounded to the nearest value. The
+    /// centers are means of the points, so they are off by at most this much as well.
+    pub max_input_error: f64,
+    /// The number of points whose two closest centers are so close that the rounding of the
+    /// point may have decided its assignment.
+    pub ambiguous: usize
+}
+
+impl<const K: usize, const M: usize> LowPrecisionKMeans<K, M> {
+    /// Get k clusters based on `points`, or an error if they have NaN or infinite coordinates or
+    /// there are fewer than k distinct points.
+    pub fn fit<T: LowPrecision>(points: &[[T; M]]) -> Result<Self, ClusterError> {
+        Self::new(points, Option::None)
+    }
+
+    /// Get k clusters based on `points` with a pre-determined random state, or an error, see
+    /// [`LowPrecisionKMeans::fit`].
+    pub fn fit_with_random_state<T: LowPrecision>(points: &[[T; M]], random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, Option::Some(random_state))
+    }
+
+    fn new<T: LowPrecision>(points: &[[T; M]], random_state: Option<u64>) -> Result<Self, ClusterError> {
+        let r = points.len();
+        check_point_rows(points.iter().map(|point| point.map(T::to_f64)), M, K)?;
+        let convert = |point: &[T; M]| Point(point.map(T::to_f64));
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        // Initialize centers
+        let mut centers = [Point::default(); K];
+        for (k, i) in random_indices(r, K, &mut rng).into_iter().enumerate() {
+            centers[k] = convert(&points[i]);
+        }
+        let mut point_centers = vec![0; r];
+        let mut iterations = 0;
+
+        // Update centers, accumulating in f64
+        let mut converged = false;
+        while iterations < MAX_ITER {
+            iterations += 1;
+            let mut sums = [Point::default(); K];
+            let mut counts = [0; K];
+            for (point, point_center) in points.iter().zip(point_centers.iter_mut()) {
+                let point = convert(point);
+                let k = closest_two(&centers, &point).0;
+                *point_center = k;
+                sums[k] += point;
+                counts[k] += 1;
+            }
+
+            let mut different = false;
+            for k in 0..K {
+                // Keep empty centers in place
+                if counts[k] > 0 {
+                    let center = sums[k] / counts[k];
+                    different |= center != centers[k];
+                    centers[k] = center;
+                }
+            }
+            if !different {
+                converged = true;
+                break;
+            }
+        }
+
+        // A point rounded to the nearest value is off by at most the unit roundoff relative to
+        // each coordinate, which moves its distances by at most the norm of those errors
+        let max_input_error = points.iter()
+            .map(|point| point.iter().map(|x| (x.to_f64() * T::UNIT_ROUNDOFF).powi(2)).sum::<f64>().sqrt())
+            .fold(0.0, f64::max);
+        let ambiguous = points.iter()
+            .filter(|point| {
+                let (_, d1, d2) = closest_two(&centers, &convert(point));
+                d2 - d1 <= 2.0 * max_input_error
+            })
+            .count();
+
+        Ok(LowPrecisionKMeans { centers, point_centers, iterations, converged, max_input_error, ambiguous })
+    }
+}
+
+/// The parameters of [`LowPrecisionKMeans::fit`], to fit it through the [`Fit`] trait on points
+/// stored in any [`LowPrecision`] format.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LowPrecisionKMeansParams<const K: usize> {
+    random_state: Option<u64>
+}
+
+impl<const K: usize> LowPrecisionKMeansParams<K> {
+    /// Fit k-means with k = K.
+    pub fn new() -> Self {
+        Self { random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize, T: LowPrecision> Fit<M, [T; M]> for LowPrecisionKMeansParams<K> {
+    type Model = LowPrecisionKMeans<K, M>;
+
+    fn fit(&self, points: &[[T; M]]) -> Result<LowPrecisionKMeans<K, M>, ClusterError> {
+        LowPrecisionKMeans::new(points, self.random_state)
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for LowPrecisionKMeans<K, M> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.iter().map(|&k| Option::Some(k)).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.to_vec())
+    }
+}
+
+impl<const K: usize, const M: usize, T: LowPrecision> Predict<M, [T; M]> for LowPrecisionKMeans<K, M> {
+    /// The index of the closest center, computed in `f64`.
+    fn predict(&self, point: &[T; M]) -> Option<usize> {
+        Option::Some(closest_two(&self.centers, &Point(point.map(T::to_f64))).0)
+    }
+}
+
+/// The index of the closest center, and the distances to the closest and second closest centers.
+///
+/// Time complexity: O(k * M)
+fn closest_two<const K: usize, const M: usize>(centers: &[Point<M>; K], point: &Point<M>) -> (usize, f64, f64) {
+    let mut min_k = 0;
+    let mut d1 = f64::INFINITY;
+    let mut d2 = f64::INFINITY;
+    for (k, center) in centers.iter().enumerate() {
+        let d = point.squared_distance(center);
+        if d < d1 {
+            d2 = d1;
+            d1 = d;
+            min_k = k;
+        } else if d < d2 {
+            d2 = d;
+        }
+    }
+    (min_k, d1.sqrt(), d2.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_f16() {
+        let points: Vec<[f16; 2]> = [[0.1, 0.0], [0.3, 0.0], [10.1, 0.0], [10.3, 0.0]]
+            .iter()
+            .map(|point| point.map(f16::from_f64))
+            .collect();
+        let model = LowPrecisionKMeans::<2, 2>::fit_with_random_state(&points, 0).unwrap();
+        assert_eq!(model.point_centers[0], model.point_centers[1]);
+        assert_ne!(model.point_centers[0], model.point_centers[2]);
+        let center = model.centers[model.point_centers[0]];
+        assert!((center.0[0] - 0.2).abs() <= model.max_input_error);
+        assert_eq!(model.ambiguous, 0);
+        assert!(model.converged);
+
+        let points = [[f16::from_f64(0.0)], [f16::NAN]];
+        assert_eq!(LowPrecisionKMeans::<1, 1>::fit(&points).err(), Option::Some(ClusterError::NonFinite { point: 1, dimension: 0 }));
+        assert_eq!(LowPrecisionKMeans::<3, 1>::fit(&points[..1]).err(), Option::Some(ClusterError::TooFewPoints { clusters: 3, distinct: 1 }));
+    }
+}
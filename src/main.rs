@@ -1,44 +1,529 @@
-use kmeans::{KMeans, Algorithm, Point};
-use std::convert::TryInto;
-use std::io::BufRead;
-use std::time::Instant;
-
-const K: usize = 4;
-const M: usize = 2;
-const R: usize = 50000;
-
-const ALGO: Algorithm = Algorithm::Simple;
-
-fn read_data() -> [Point<M>; R] {
-    let mut data = Vec::with_capacity(R);
-    let stdin = std::io::stdin();
-    for line in stdin.lock().lines().skip(1) {
-        match line {
-            Err(_) => break,
-            Ok(text) => {
-                let mut point = [0.0; M];
-                for (i, value) in text.split(',').enumerate() {
-                    point[i] = value.parse::<f64>().unwrap();
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use kmeans::geo::{check_coordinates, haversine, spherical_centers, LocalProjection};
+use kmeans::io::binary::{BinaryWriter, MappedFile};
+use kmeans::io::csv::{Column, CsvReader, Table};
+use kmeans::metrics::{adjusted_rand_index, normalized_mutual_info};
+use kmeans::preprocess::MissingPolicy;
+use kmeans::{ClusterError, DynKMeans, DynPoint, KMeansBuilder, Point};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// k-means clustering of CSV, Parquet, Arrow IPC and binary files.
+#[derive(Parser)]
+#[command(name = "cluste-rs")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Fit a model and write its centers as JSON.
+    Fit {
+        /// The number of clusters.
+        #[arg(long)]
+        k: usize,
+        #[arg(long, value_enum, default_value_t = Algorithm::Simple)]
+        algorithm: Algorithm,
+        /// The random state, for reproducible results.
+        #[arg(long)]
+        seed: Option<u64>,
+        /// What to do with rows with missing (empty) values. Infinite values are always an error.
+        #[arg(long, value_enum, default_value_t = Missing::Error)]
+        missing: Missing,
+        #[command(flatten)]
+        input: Input,
+        /// The column with the latitudes in degrees, to cluster geographic coordinates by
+        /// great-circle distance instead of the selected columns. Requires --longitude.
+        #[arg(long, requires = "longitude", conflicts_with = "columns")]
+        latitude: Option<String>,
+        /// The column with the longitudes in degrees. Requires --latitude.
+        #[arg(long, requires = "latitude")]
+        longitude: Option<String>,
+        /// The JSON file to write the model to. Defaults to standard output.
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// A CSV file to write the points to, with the index of their cluster in an extra
+        /// `cluster` column.
+        #[arg(long)]
+        assignments: Option<PathBuf>
+    },
+    /// Write the index of the closest center of every point, one per line.
+    Predict {
+        /// The JSON file of a fitted model.
+        #[arg(long)]
+        model: PathBuf,
+        #[command(flatten)]
+        input: Input,
+        /// The file to write the labels to. Defaults to standard output.
+        #[arg(long)]
+        output: Option<PathBuf>
+    },
+    /// Print the inertia of a model on the points, and how well its labels agree with known labels.
+    Score {
+        /// The JSON file of a fitted model.
+        #[arg(long)]
+        model: PathBuf,
+        #[command(flatten)]
+        input: Input,
+        /// A file with the known label of every point, one per line.
+        #[arg(long)]
+        labels: Option<PathBuf>
+    },
+    /// Convert the selected columns to the binary format, which is memory-mapped instead of
+    /// parsed when it is read. A CSV is converted in chunks, so it does not need to fit in memory.
+    Convert {
+        #[command(flatten)]
+        input: Input,
+        /// The binary file to write, conventionally with the extension .bin.
+        #[arg(long)]
+        output: PathBuf
+    }
+}
+
+/// The largest number of clusters that the algorithms are compiled for, see [`fit`].
+const MAX_K: usize = 16;
+/// The largest number of dimensions that the algorithms are compiled for, see [`fit`].
+const MAX_M: usize = 8;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Algorithm {
+    /// Lloyd's algorithm, for any number of clusters and dimensions.
+    Naive,
+    /// The "simple" algorithm of Pelleg & Moore on an mrkd-tree.
+    Simple,
+    /// The blacklisting algorithm of Pelleg & Moore on an mrkd-tree.
+    Blacklist,
+    /// Elkan's algorithm, which skips distance computations using the triangle inequality.
+    Elkan,
+    /// Hamerly's algorithm, which keeps a single lower bound per point instead of one per center.
+    Hamerly,
+    /// The blacklisting algorithm on a ball tree, for data with more dimensions.
+    BallTree,
+    /// Pick one of the other algorithms based on the number of rows, columns and clusters.
+    Auto
+}
+
+impl From<Algorithm> for kmeans::Algorithm {
+    fn from(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Naive => kmeans::Algorithm::Naive,
+            Algorithm::Simple => kmeans::Algorithm::Simple,
+            Algorithm::Blacklist => kmeans::Algorithm::Blacklist,
+            Algorithm::Elkan => kmeans::Algorithm::Elkan,
+            Algorithm::Hamerly => kmeans::Algorithm::Hamerly,
+            Algorithm::BallTree => kmeans::Algorithm::BallTree,
+            Algorithm::Auto => kmeans::Algorithm::Auto
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Missing {
+    /// Refuse to fit.
+    Error,
+    /// Leave out rows with missing values, also from the assignments.
+    Drop,
+    /// Replace missing values by the mean of their column.
+    ImputeMean
+}
+
+impl From<Missing> for MissingPolicy {
+    fn from(missing: Missing) -> Self {
+        match missing {
+            Missing::Error => MissingPolicy::Error,
+            Missing::Drop => MissingPolicy::DropRow,
+            Missing::ImputeMean => MissingPolicy::ImputeMean
+        }
+    }
+}
+
+#[derive(Args)]
+struct Input {
+    /// The CSV, Parquet (.parquet), Arrow IPC (.arrow, .feather) or binary (.bin) file with the
+    /// points. Defaults to CSV on standard input.
+    #[arg(long)]
+    input: Option<PathBuf>,
+    /// The columns to read, by name, or by index if the CSV has no header. Defaults to the
+    /// columns of the model, or to all columns of a CSV.
+    #[arg(long, value_delimiter = ',')]
+    columns: Vec<String>,
+    /// The delimiter of a CSV.
+    #[arg(long, default_value_t = ',')]
+    delimiter: char,
+    /// Read the first row of a CSV as values instead of column names.
+    #[arg(long)]
+    no_header: bool
+}
+
+impl Input {
+    /// Read the selected columns, or `default_columns` if none were selected.
+    fn read(&self, default_columns: &[String]) -> Result<Table<DynPoint>, Box<dyn Error>> {
+        let columns = if self.columns.is_empty() { default_columns } else { &self.columns };
+        let path = self.input.as_deref();
+        match (path, self.extension()) {
+            (Option::Some(path), Option::Some("parquet" | "arrow" | "feather")) => read_columnar(path, columns),
+            (Option::Some(path), Option::Some("bin")) => read_binary(path, columns),
+            _ => Ok(self.csv_reader(columns).read_dyn(open(path)?)?)
+        }
+    }
+
+    /// The extension of the input file.
+    fn extension(&self) -> Option<&str> {
+        self.input.as_deref().and_then(Path::extension).and_then(|extension| extension.to_str())
+    }
+
+    /// A reader of the `columns` of a CSV, or of all columns if there are none.
+    fn csv_reader(&self, columns: &[String]) -> CsvReader {
+        let reader = CsvReader::new().delimiter(self.delimiter).header(!self.no_header);
+        if columns.is_empty() {
+            return reader
+        }
+        reader.columns(columns.iter().map(|column| match column.parse::<usize>() {
+            Ok(index) if self.no_header => Column::Index(index),
+            _ => Column::Name(column.clone())
+        }))
+    }
+}
+
+/// Read the `columns` of a binary file, or all columns if there are none.
+fn read_binary(path: &Path, columns: &[String]) -> Result<Table<DynPoint>, Box<dyn Error>> {
+    let file = MappedFile::open(path)?;
+    let indices: Vec<usize> = if columns.is_empty() {
+        (0..file.columns()).collect()
+    } else {
+        columns.iter()
+            .map(|column| file.names().iter().position(|name| name == column).ok_or_else(|| format!("the file has no column {}", column)))
+            .collect::<Result<_, _>>()?
+    };
+    let points = file.values()
+        .chunks_exact(file.columns().max(1))
+        .take(file.rows())
+        .map(|row| DynPoint(indices.iter().map(|&i| row[i]).collect()))
+        .collect();
+    Ok(Table { names: indices.iter().map(|&i| file.names()[i].clone()).collect(), points })
+}
+
+/// Write the selected columns of the input to a binary file, streaming a CSV in chunks.
+fn convert(input: &Input, output: &Path) -> Result<(), Box<dyn Error>> {
+    let mut writer = BinaryWriter::new(io::BufWriter::new(File::create(output)?))?;
+    let names = match input.extension() {
+        Option::Some("parquet" | "arrow" | "feather" | "bin") => {
+            let table = input.read(&[])?;
+            table.points.iter().try_for_each(|point| writer.write_row(&point.0))?;
+            table.names
+        },
+        _ => {
+            let mut result = Ok(());
+            let names = input.csv_reader(&input.columns).read_dyn_chunks(open(input.input.as_deref())?, 1 << 16, |chunk| {
+                if result.is_ok() {
+                    result = chunk.iter().try_for_each(|point| writer.write_row(&point.0));
+                }
+            })?;
+            result?;
+            names
+        }
+    };
+    writer.finish(&names)?;
+    Ok(())
+}
+
+/// Apply `policy` to the rows with missing (NaN) values, or return an error that names the row and
+/// column of the first missing value with [`MissingPolicy::Error`], or of the first infinite value.
+fn handle_missing(mut table: Table<DynPoint>, policy: MissingPolicy) -> Result<Table<DynPoint>, Box<dyn Error>> {
+    for (row, point) in table.points.iter().enumerate() {
+        for (x, name) in point.0.iter().zip(&table.names) {
+            if x.is_infinite() {
+                return Err(format!("row {} has an infinite value in column {}", row, name).into())
+            }
+            if x.is_nan() && policy == MissingPolicy::Error {
+                return Err(format!("row {} has a missing value in column {}; use --missing to drop or impute it", row, name).into())
+            }
+        }
+    }
+
+    match policy {
+        MissingPolicy::Error => {},
+        MissingPolicy::DropRow => table.points.retain(|point| point.0.iter().all(|x| !x.is_nan())),
+        MissingPolicy::ImputeMean => {
+            let m = table.names.len();
+            let mut sums = vec![0.0; m];
+            let mut counts = vec![0usize; m];
+            for point in &table.points {
+                for (d, &x) in point.0.iter().enumerate().filter(|(_, x)| !x.is_nan()) {
+                    sums[d] += x;
+                    counts[d] += 1;
+                }
+            }
+            for point in &mut table.points {
+                for (d, x) in point.0.iter_mut().enumerate().filter(|(_, x)| x.is_nan()) {
+                    *x = if counts[d] > 0 { sums[d] / counts[d] as f64 } else { 0.0 };
                 }
-                data.push(Point(point));
             }
         }
     }
+    Ok(table)
+}
+
+#[cfg(feature = "parquet")]
+fn read_columnar(path: &Path, columns: &[String]) -> Result<Table<DynPoint>, Box<dyn Error>> {
+    use kmeans::io::parquet::{read_arrow_ipc_dyn, read_parquet_dyn};
 
-    data.try_into().unwrap()
+    if columns.is_empty() {
+        return Err("select the columns to read with --columns".into())
+    }
+    let names: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let points = if path.extension() == Option::Some("parquet".as_ref()) {
+        read_parquet_dyn(path, &names)?
+    } else {
+        read_arrow_ipc_dyn(path, &names)?
+    };
+    Ok(Table { names: columns.to_vec(), points })
 }
 
-fn main() {
-    let data = read_data();
+#[cfg(not(feature = "parquet"))]
+fn read_columnar(_: &Path, _: &[String]) -> Result<Table<DynPoint>, Box<dyn Error>> {
+    Err("reading Parquet and Arrow IPC files needs the parquet feature".into())
+}
 
-    let now = Instant::now();
-    let model = KMeans::<K, M, R>::fit_with_random_state(&data, ALGO, 0);
-    eprintln!("total: {:?}", now.elapsed());
+fn open(path: Option<&Path>) -> io::Result<Box<dyn Read>> {
+    Ok(match path {
+        Option::Some(path) => Box::new(File::open(path)?),
+        None => Box::new(io::stdin())
+    })
+}
 
-    for point in model.centers {
-        for x in point.0 {
-            print!("{},", x);
+fn create(path: Option<&Path>) -> io::Result<Box<dyn Write>> {
+    Ok(match path {
+        Option::Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout())
+    })
+}
+
+/// Fit `algorithm` to points with K clusters and M dimensions with a [`KMeansBuilder`].
+fn fit_builder<const K: usize, const M: usize>(points: &[DynPoint], algorithm: Algorithm, seed: Option<u64>) -> Result<DynKMeans, ClusterError> {
+    let points: Vec<Point<M>> = points.iter().map(|point| Point(std::array::from_fn(|d| point.0[d]))).collect();
+    let mut builder = KMeansBuilder::<K, M>::new().algorithm(algorithm.into());
+    if let Option::Some(seed) = seed {
+        builder = builder.random_state(seed);
+    }
+    let model = builder.fit(&points)?;
+    Ok(DynKMeans {
+        centers: model.centers.iter().map(|center| DynPoint(center.0.to_vec())).collect(),
+        point_centers: model.point_centers,
+        iterations: model.iterations,
+        converged: model.converged
+    })
+}
+
+/// Fit `algorithm` to points with M dimensions, if `k` is at most [`MAX_K`].
+fn fit_dimensions<const M: usize>(points: &[DynPoint], k: usize, algorithm: Algorithm, seed: Option<u64>) -> Option<Result<DynKMeans, ClusterError>> {
+    macro_rules! fit_clusters {
+        ($($k:literal)*) => {
+            match k {
+                $($k => Option::Some(fit_builder::<$k, M>(points, algorithm, seed)),)*
+                _ => Option::None
+            }
+        };
+    }
+    fit_clusters!(1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16)
+}
+
+/// Fit `algorithm` to `points` with `m` dimensions. The library needs the number of clusters and
+/// dimensions at compile time, so the algorithms are compiled for up to [`MAX_K`] clusters and
+/// [`MAX_M`] dimensions; beyond that, only the naive algorithm is available, on [`DynKMeans`],
+/// which is also what `auto` falls back to.
+fn fit(points: &[DynPoint], m: usize, k: usize, algorithm: Algorithm, seed: Option<u64>) -> Result<DynKMeans, Box<dyn Error>> {
+    if k == 0 {
+        return Err(ClusterError::NoClusters.into())
+    }
+    macro_rules! fit_sized {
+        ($($m:literal)*) => {
+            match m {
+                $($m => fit_dimensions::<$m>(points, k, algorithm, seed),)*
+                _ => Option::None
+            }
+        };
+    }
+    if let Option::Some(result) = fit_sized!(1 2 3 4 5 6 7 8) {
+        return Ok(result?)
+    }
+
+    match algorithm {
+        Algorithm::Naive | Algorithm::Auto => Ok(match seed {
+            Option::Some(seed) => DynKMeans::fit_with_random_state(points, k, seed)?,
+            None => DynKMeans::fit(points, k)?
+        }),
+        _ => {
+            let name = algorithm.to_possible_value().map_or(String::new(), |value| value.get_name().to_owned());
+            Err(format!(
+                "--algorithm {} supports up to {} clusters and {} dimensions; use --algorithm naive",
+                name, MAX_K, MAX_M
+            ).into())
         }
-        println!("");
     }
 }
+
+/// Fit points of latitude and longitude in degrees by projecting them with a [`LocalProjection`]
+/// around their centroid, and reporting the spherical centroid of every cluster as its center
+/// with [`spherical_centers`], like `kmeans::geo::fit`.
+fn fit_geographic(points: &[DynPoint], k: usize, algorithm: Algorithm, seed: Option<u64>) -> Result<DynKMeans, Box<dyn Error>> {
+    let points: Vec<Point<2>> = points.iter().map(lat_lon).collect();
+    check_coordinates(&points)?;
+    let projection = LocalProjection::around(&points);
+    let projected: Vec<DynPoint> = points.iter().map(|point| DynPoint(projection.project(point).0.to_vec())).collect();
+    let mut model = fit(&projected, 2, k, algorithm, seed)?;
+
+    let mut centers: Vec<Point<2>> = model.centers.iter().map(lat_lon).collect();
+    spherical_centers(&points, &model.point_centers, &projection, &mut centers);
+    model.centers = centers.into_iter().map(|center| DynPoint(center.0.to_vec())).collect();
+    Ok(model)
+}
+
+/// The latitude and longitude of a point with two dimensions.
+fn lat_lon(point: &DynPoint) -> Point<2> {
+    Point([point.0[0], point.0[1]])
+}
+
+/// Read a model written by `fit`: the names of its columns, its centers, and whether it is
+/// geographic.
+fn read_model(path: &Path) -> Result<(Vec<String>, DynKMeans, bool), Box<dyn Error>> {
+    let model: Value = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    let invalid = || format!("{} is not a model written by fit", path.display());
+    let columns = model["columns"].as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|name| name.as_str().map(str::to_owned))
+        .collect::<Option<_>>()
+        .ok_or_else(invalid)?;
+    let centers = model["centers"].as_array()
+        .ok_or_else(invalid)?
+        .iter()
+        .map(|center| center.as_array()?.iter().map(Value::as_f64).collect::<Option<_>>().map(DynPoint))
+        .collect::<Option<_>>()
+        .ok_or_else(invalid)?;
+    let geographic = match model["metric"].as_str() {
+        Option::None | Option::Some("euclidean") => false,
+        Option::Some("haversine") => true,
+        Option::Some(metric) => return Err(format!("unknown metric {}", metric).into())
+    };
+    Ok((columns, DynKMeans { centers, point_centers: Vec::new(), iterations: 0, converged: true }, geographic))
+}
+
+/// The distance between two points, or the great-circle distance in kilometers if they are
+/// geographic.
+fn distance(a: &DynPoint, b: &DynPoint, geographic: bool) -> f64 {
+    if geographic {
+        haversine(&lat_lon(a), &lat_lon(b))
+    } else {
+        a.distance(b)
+    }
+}
+
+/// The index of the closest center of every point.
+fn predict(model: &DynKMeans, points: &[DynPoint], geographic: bool) -> Result<Vec<usize>, Box<dyn Error>> {
+    let labels = model.try_predict_many(points)?;
+    if !geographic {
+        return Ok(labels)
+    }
+    Ok(points.iter()
+        .map(|point| (0..model.centers.len())
+            .min_by(|&a, &b| distance(point, &model.centers[a], true).total_cmp(&distance(point, &model.centers[b], true)))
+            .unwrap_or(0))
+        .collect())
+}
+
+/// The sum of the squared distances of the points to their centers.
+fn inertia(points: &[DynPoint], centers: &[DynPoint], labels: &[usize], geographic: bool) -> f64 {
+    points.iter().zip(labels).map(|(point, &c)| distance(point, &centers[c], geographic).powi(2)).sum()
+}
+
+/// Write the points as a CSV with a header, with the index of their cluster in the last column.
+fn write_assignments(mut writer: impl Write, table: &Table<DynPoint>, labels: &[usize]) -> io::Result<()> {
+    for name in &table.names {
+        // Quote names that would otherwise be split or unquoted when read back
+        if name.contains([',', '"', '\n', '\r']) {
+            write!(writer, "\"{}\",", name.replace('"', "\"\""))?;
+        } else {
+            write!(writer, "{},", name)?;
+        }
+    }
+    writeln!(writer, "cluster")?;
+    for (point, label) in table.points.iter().zip(labels) {
+        for x in &point.0 {
+            // Missing values are written as empty fields
+            if x.is_nan() {
+                write!(writer, ",")?;
+            } else {
+                write!(writer, "{},", x)?;
+            }
+        }
+        writeln!(writer, "{}", label)?;
+    }
+    writer.flush()
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command {
+        Command::Fit { k, algorithm, seed, missing, input, latitude, longitude, output, assignments } => {
+            let geographic = latitude.is_some();
+            let table = handle_missing(input.read(&latitude.into_iter().chain(longitude).collect::<Vec<_>>())?, missing.into())?;
+            let model = if geographic {
+                fit_geographic(&table.points, k, algorithm, seed)?
+            } else {
+                fit(&table.points, table.names.len(), k, algorithm, seed)?
+            };
+
+            let centers: Vec<&[f64]> = model.centers.iter().map(|center| &center.0[..]).collect();
+            let mut writer = create(output.as_deref())?;
+            serde_json::to_writer_pretty(&mut writer, &json!({
+                "k": k,
+                "m": table.names.len(),
+                "columns": table.names,
+                "centers": centers,
+                "metric": if geographic { "haversine" } else { "euclidean" },
+                "inertia": inertia(&table.points, &model.centers, &model.point_centers, geographic),
+                "iterations": model.iterations
+            }))?;
+            writeln!(writer)?;
+
+            if let Option::Some(path) = assignments {
+                write_assignments(io::BufWriter::new(File::create(path)?), &table, &model.point_centers)?;
+            }
+        },
+        Command::Predict { model, input, output } => {
+            let (columns, model, geographic) = read_model(&model)?;
+            let labels = predict(&model, &input.read(&columns)?.points, geographic)?;
+            let mut writer = create(output.as_deref())?;
+            for label in labels {
+                writeln!(writer, "{}", label)?;
+            }
+        },
+        Command::Score { model, input, labels } => {
+            let (columns, model, geographic) = read_model(&model)?;
+            let points = input.read(&columns)?.points;
+            let predicted = predict(&model, &points, geographic)?;
+            println!("inertia\t{}", inertia(&points, &model.centers, &predicted, geographic));
+
+            if let Option::Some(labels) = labels {
+                let mut known = Vec::new();
+                for line in BufReader::new(File::open(labels)?).lines() {
+                    let line = line?;
+                    if !line.trim().is_empty() {
+                        known.push(line.trim().parse::<usize>()?);
+                    }
+                }
+                if known.len() != predicted.len() {
+                    return Err(format!("{} labels were given for {} points", known.len(), predicted.len()).into())
+                }
+                println!("adjusted_rand_index\t{}", adjusted_rand_index(&known, &predicted));
+                println!("normalized_mutual_info\t{}", normalized_mutual_info(&known, &predicted));
+            }
+        },
+        Command::Convert { input, output } => convert(&input, &output)?
+    }
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use crate::clusterer::Algorithm;
+use crate::mrkd::DEFAULT_LEAF_SIZE;
+use std::fmt;
+use std::mem::size_of;
+
+/// Predicted peak memory use of a fit, in bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryEstimate {
+    /// The mrkd-tree or ball tree, including its copy of the points.
+    pub tree: usize,
+    /// The distance bounds of Elkan's and Hamerly's algorithms.
+    pub bounds: usize,
+    /// The fitted model: centers, labels, and the unit point weights.
+    pub model: usize
+}
+
+impl MemoryEstimate {
+    pub fn total(&self) -> usize {
+        self.tree + self.bounds + self.model
+    }
+}
+
+/// Predict the peak memory use of fitting `k` clusters on `r` points with `m` dimensions using
+/// `algorithm`. The input points themselves are not included, and the tree is assumed to
+/// have leaves of the default size.
+///
+/// Time complexity: O(1)
+pub fn estimate_memory(r: usize, k: usize, m: usize, algorithm: Algorithm) -> MemoryEstimate {
+    let algorithm = algorithm.resolve(r, m, k);
+    let float = size_of::<f64>();
+    let index = size_of::<usize>();
+
+    let tree = match algorithm {
+        Algorithm::Simple | Algorithm::Blacklist => {
+            // Every node caches its hyper-rectangle, center of mass, point count, weight and norm
+            // sum, and the split and indices of its children or the range of its points. Leaves
+            // are split until they have at most the default leaf size, so they hold at least half
+            // of it.
+            let node_size = 3 * m * float + 3 * float + 5 * index;
+            let leaves = r.div_ceil(DEFAULT_LEAF_SIZE.div_ceil(2)).max(1);
+            let nodes = 2 * leaves - 1;
+            // The tree holds a copy of the points and weights in the order of the leaves, and the
+            // buffer of their indices that is partitioned in place while splitting
+            let copies = r * (m * float + float + index);
+            nodes * node_size + copies
+        },
+        Algorithm::BallTree => {
+            // Every node caches the center and radius of its ball, its center of mass, point count
+            // and weight, and the indices of its children or the range of its points
+            let node_size = 2 * m * float + 2 * float + 3 * index;
+            let leaves = r.div_ceil(DEFAULT_LEAF_SIZE.div_ceil(2)).max(1);
+            let copies = r * (m * float + float + index);
+            (2 * leaves - 1) * node_size + copies
+        },
+        Algorithm::Naive | Algorithm::Elkan | Algorithm::Hamerly | Algorithm::Auto => 0
+    };
+
+    let bounds = match algorithm {
+        Algorithm::Elkan => r * (index + float + k * float + size_of::<bool>()) + k * m * float,
+        Algorithm::Hamerly => r * (index + 2 * float) + k * m * float,
+        _ => 0
+    };
+
+    // The centers and labels are kept for the best model and the current run
+    let model = 2 * (k * m * float + r * index + m * float) + r * float;
+
+    MemoryEstimate { tree, bounds, model }
+}
+
+/// What to do when a fit is predicted to exceed its memory budget.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MemoryPolicy {
+    /// Refuse to fit.
+    #[default]
+    Reject,
+    /// Fall back to [`Algorithm::Naive`], which needs neither a tree nor bounds, and only refuse
+    /// to fit if that still exceeds the budget.
+    Downgrade
+}
+
+/// The error returned when a fit would exceed its memory budget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryBudgetExceeded {
+    /// The predicted peak memory use in bytes.
+    pub estimate: usize,
+    /// The budget in bytes.
+    pub budget: usize
+}
+
+impl fmt::Display for MemoryBudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "fit needs an estimated {} bytes, exceeding the budget of {} bytes", self.estimate, self.budget)
+    }
+}
+
+impl std::error::Error for MemoryBudgetExceeded {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_memory() {
+        let naive = super::estimate_memory(1000, 8, 4, Algorithm::Naive);
+        let simple = super::estimate_memory(1000, 8, 4, Algorithm::Simple);
+        let elkan = super::estimate_memory(1000, 8, 4, Algorithm::Elkan);
+        let hamerly = super::estimate_memory(1000, 8, 4, Algorithm::Hamerly);
+        assert_eq!((naive.tree, naive.bounds), (0, 0));
+        assert!(simple.tree > 0 && simple.bounds == 0);
+        assert!(elkan.tree == 0 && elkan.bounds > 0);
+        assert!(hamerly.tree == 0 && 0 < hamerly.bounds && hamerly.bounds < elkan.bounds);
+        assert_eq!(naive.model, simple.model);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::point::Point;
+use std::fmt::Debug;
+
+/// A distance between points, used to assign points to their closest center.
+///
+/// The centers themselves are still updated to the (weighted) means of their points, so for
+/// metrics other than (squared) Euclidean distance the result is a heuristic rather than an exact
+/// minimizer of the inertia.
+pub trait Metric: Clone + Copy + Debug + Default + Send + Sync {
+    /// Whether the closest center under this metric is always the closest center under Euclidean
+    /// distance. Only then the geometric pruning of the tree-based algorithms and the bounds of
+    /// Elkan's algorithm are valid; otherwise fitting falls back to [`crate::Algorithm::Naive`].
+    const EUCLIDEAN_ORDER: bool = false;
+
+    /// Whether the distance is an increasing function of a sum over the dimensions of a convex
+    /// function of the coordinate differences, like the Euclidean and Manhattan distance. Only then
+    /// the bounds in [`crate::geometry`] are exact.
+    const SEPARABLE: bool = false;
+
+    /// The distance between `a` and `b`.
+    fn distance<const M: usize>(&self, a: &Point<M>, b: &Point<M>) -> f64;
+
+    /// The contribution of point `a` to the inertia when `b` is its center. Defaults to the squared
+    /// distance.
+    fn cost<const M: usize>(&self, a: &Point<M>, b: &Point<M>) -> f64 {
+        self.distance(a, b).powi(2)
+    }
+
+    /// The distances from `point` to each of `centers`, written to `distances`. Defaults to
+    /// [`Metric::distance`] per center; the (squared) Euclidean distance computes them in batches.
+    ///
+    /// Time complexity: O(k * M) for k centers
+    fn distances<const M: usize>(&self, point: &Point<M>, centers: &[Point<M>], distances: &mut [f64]) {
+        for (distance, center) in distances.iter_mut().zip(centers) {
+            *distance = self.distance(point, center);
+        }
+    }
+}
+
+/// The Euclidean distance, sqrt(sum((a_i - b_i)^2)).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Euclidean;
+
+impl Metric for Euclidean {
+    const EUCLIDEAN_ORDER: bool = true;
+    const SEPARABLE: bool = true;
+
+    /// Time complexity: O(M)
+    fn distance<const M: usize>(&self, a: &Point<M>, b: &Point<M>) -> f64 {
+        a.distance(b)
+    }
+
+    fn distances<const M: usize>(&self, point: &Point<M>, centers: &[Point<M>], distances: &mut [f64]) {
+        point.squared_distances(centers, distances);
+        for distance in distances {
+            *distance = distance.sqrt();
+        }
+    }
+}
+
+/// The squared Euclidean distance, sum((a_i - b_i)^2), which orders points the same way as
+/// [`Euclidean`] but skips the square root. The inertia is the same as with [`Euclidean`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SquaredEuclidean;
+
+impl Metric for SquaredEuclidean {
+    const EUCLIDEAN_ORDER: bool = true;
+    const SEPARABLE: bool = true;
+
+    /// Time complexity: O(M)
+    fn distance<const M: usize>(&self, a: &Point<M>, b: &Point<M>) -> f64 {
+        a.squared_distance(b)
+    }
+
+    fn cost<const M: usize>(&self, a: &Point<M>, b: &Point<M>) -> f64 {
+        self.distance(a, b)
+    }
+
+    fn distances<const M: usize>(&self, point: &Point<M>, centers: &[Point<M>], distances: &mut [f64]) {
+        point.squared_distances(centers, distances)
+    }
+}
+
+/// The Manhattan or taxicab distance, sum(|a_i - b_i|).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Manhattan;
+
+impl Metric for Manhattan {
+    const SEPARABLE: bool = true;
+
+    /// Time complexity: O(M)
+    fn distance<const M: usize>(&self, a: &Point<M>, b: &Point<M>) -> f64 {
+        (0..M).map(|d| (a.0[d] - b.0[d]).abs()).sum()
+    }
+}
+
+/// The Chebyshev distance, max(|a_i - b_i|).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Chebyshev;
+
+impl Metric for Chebyshev {
+    /// Time complexity: O(M)
+    fn distance<const M: usize>(&self, a: &Point<M>, b: &Point<M>) -> f64 {
+        (0..M).map(|d| (a.0[d] - b.0[d]).abs()).fold(0.0, f64::max)
+    }
+}
+
+/// The cosine distance, 1 - a · b / (|a| |b|), which only depends on the directions of the points.
+/// The distance to the origin is taken to be 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cosine;
+
+impl Metric for Cosine {
+    /// Time complexity: O(M)
+    fn distance<const M: usize>(&self, a: &Point<M>, b: &Point<M>) -> f64 {
+        let origin = Point::default();
+        let norms = a.distance(&origin) * b.distance(&origin);
+        if norms == 0.0 {
+            return 1.0
+        }
+        let dot: f64 = (0..M).map(|d| a.0[d] * b.0[d]).sum();
+        1.0 - dot / norms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances() {
+        let a = Point([1.0, 0.0]);
+        let b = Point([4.0, 4.0]);
+        assert_eq!(Euclidean.distance(&a, &b), 5.0);
+        assert_eq!(SquaredEuclidean.distance(&a, &b), 25.0);
+        assert_eq!(Manhattan.distance(&a, &b), 7.0);
+        assert_eq!(Chebyshev.distance(&a, &b), 4.0);
+        assert_eq!(Cosine.distance(&a, &Point([0.0, 2.0])), 1.0);
+        assert_eq!(Cosine.distance(&a, &Point([3.0, 0.0])), 0.0);
+    }
+}
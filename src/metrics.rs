@@ -0,0 +1,339 @@
+//! Validation metrics to compare clusterings, for example across different numbers of clusters.
+//!
+//! The internal metrics score a clustering of `points` given the index of the cluster of each
+//! point in `labels`. Labels do not need to be consecutive: clusters without points are ignored.
+//! The external metrics compare two clusterings of the same points, for example to a ground truth.
+
+use crate::flow;
+use crate::point::Point;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::collections::HashMap;
+
+/// The mean silhouette coefficient (Rousseeuw, 1987) of all points, between -1 and 1, where higher
+/// is better. Points in singleton clusters have a silhouette of 0.
+///
+/// # References
+///
+/// Rousseeuw, P. J. (1987). Silhouettes: A graphical aid to the interpretation and validation of
+///     cluster analysis. Journal of Computational and Applied Mathematics, 20, 53–65.
+///     <https://doi.org/10.1016/0377-0427(87)90125-7>
+///
+/// Time complexity: O(r^2 * M)
+///
+/// # Panics
+///
+/// If the number of labels differs from the number of points, or if there are fewer than 2
+/// clusters.
+pub fn silhouette_score<const M: usize>(points: &[Point<M>], labels: &[usize]) -> f64 {
+    let sample: Vec<usize> = (0..points.len()).collect();
+    silhouette(points, labels, &sample)
+}
+
+/// The mean silhouette coefficient of `n` points sampled uniformly at random, which estimates
+/// [`silhouette_score`] for large numbers of points.
+///
+/// Time complexity: O(n * r * M)
+///
+/// # Panics
+///
+/// If the number of labels differs from the number of points, or if there are fewer than 2
+/// clusters.
+pub fn sampled_silhouette_score<const M: usize>(points: &[Point<M>], labels: &[usize], n: usize, random_state: u64) -> f64 {
+    let mut rng = StdRng::seed_from_u64(random_state);
+    let sample = rand::seq::index::sample(&mut rng, points.len(), n.min(points.len())).into_vec();
+    silhouette(points, labels, &sample)
+}
+
+fn silhouette<const M: usize>(points: &[Point<M>], labels: &[usize], sample: &[usize]) -> f64 {
+    let sizes = cluster_sizes(points, labels);
+    assert!(sizes.iter().filter(|&&size| size > 0).count() >= 2, "silhouette needs at least 2 clusters");
+
+    let mut total = 0.0;
+    for &i in sample {
+        // Mean distance to the points of every cluster
+        let mut sums = vec![0.0; sizes.len()];
+        for (point, &label) in points.iter().zip(labels) {
+            sums[label] += points[i].distance(point);
+        }
+
+        let own = labels[i];
+        if sizes[own] == 1 {
+            continue;
+        }
+        let a = sums[own] / (sizes[own] - 1) as f64;
+        let b = (0..sizes.len())
+            .filter(|&c| c != own && sizes[c] > 0)
+            .map(|c| sums[c] / sizes[c] as f64)
+            .fold(f64::INFINITY, f64::min);
+        if a.max(b) > 0.0 {
+            total += (b - a) / a.max(b);
+        }
+    }
+    total / sample.len() as f64
+}
+
+/// The Davies-Bouldin index (Davies & Bouldin, 1979): the mean over all clusters of the largest
+/// ratio of within-cluster scatter to between-cluster separation, where lower is better.
+///
+/// # References
+///
+/// Davies, D. L., & Bouldin, D. W. (1979). A cluster separation measure. IEEE Transactions on
+///     Pattern Analysis and Machine Intelligence, PAMI-1(2), 224–227.
+///     <https://doi.org/10.1109/TPAMI.1979.4766909>
+///
+/// Time complexity: O(r * M + k^2 * M)
+///
+/// # Panics
+///
+/// If the number of labels differs from the number of points, or if there are fewer than 2
+/// clusters.
+pub fn davies_bouldin<const M: usize>(points: &[Point<M>], labels: &[usize]) -> f64 {
+    let (centroids, sizes) = centroids(points, labels);
+    let clusters: Vec<usize> = (0..sizes.len()).filter(|&c| sizes[c] > 0).collect();
+    assert!(clusters.len() >= 2, "Davies-Bouldin index needs at least 2 clusters");
+
+    // Mean distance of the points of every cluster to its centroid
+    let mut scatter = vec![0.0; sizes.len()];
+    for (point, &label) in points.iter().zip(labels) {
+        scatter[label] += point.distance(&centroids[label]) / sizes[label] as f64;
+    }
+
+    let total: f64 = clusters.iter().map(|&a| {
+        clusters.iter()
+            .filter(|&&b| b != a)
+            .map(|&b| (scatter[a] + scatter[b]) / centroids[a].distance(&centroids[b]))
+            .fold(0.0, f64::max)
+    }).sum();
+    total / clusters.len() as f64
+}
+
+/// The Calinski-Harabasz index or variance ratio criterion (Caliński & Harabasz, 1974): the ratio
+/// of between-cluster to within-cluster dispersion, corrected for the number of clusters, where
+/// higher is better.
+///
+/// # References
+///
+/// Caliński, T., & Harabasz, J. (1974). A dendrite method for cluster analysis. Communications in
+///     Statistics, 3(1), 1–27. <https://doi.org/10.1080/03610927408827101>
+///
+/// Time complexity: O(r * M)
+///
+/// # Panics
+///
+/// If the number of labels differs from the number of points, or if there are fewer than 2
+/// clusters.
+pub fn calinski_harabasz<const M: usize>(points: &[Point<M>], labels: &[usize]) -> f64 {
+    let (centroids, sizes) = centroids(points, labels);
+    let k = sizes.iter().filter(|&&size| size > 0).count();
+    assert!(k >= 2, "Calinski-Harabasz index needs at least 2 clusters");
+
+    let r = points.len();
+    let mean = points.iter().sum::<Point<M>>() / r;
+
+    let between: f64 = (0..sizes.len())
+        .filter(|&c| sizes[c] > 0)
+        .map(|c| sizes[c] as f64 * centroids[c].squared_distance(&mean))
+        .sum();
+    let within: f64 = points.iter()
+        .zip(labels)
+        .map(|(point, &label)| point.squared_distance(&centroids[label]))
+        .sum();
+
+    if within == 0.0 {
+        return f64::INFINITY
+    }
+    (between / (k - 1) as f64) / (within / (r - k) as f64)
+}
+
+/// The adjusted Rand index (Hubert & Arabie, 1985) of two clusterings: the fraction of pairs of
+/// points on which they agree, corrected for chance. It is 1 for identical clusterings up to a
+/// permutation of the labels, and around 0 for independent clusterings.
+///
+/// # References
+///
+/// Hubert, L., & Arabie, P. (1985). Comparing partitions. Journal of Classification, 2(1),
+///     193–218. <https://doi.org/10.1007/BF01908075>
+///
+/// Time complexity: O(r)
+///
+/// # Panics
+///
+/// If the clusterings have different numbers of labels.
+pub fn adjusted_rand_index(labels_a: &[usize], labels_b: &[usize]) -> f64 {
+    let (table, sums_a, sums_b) = contingency_table(labels_a, labels_b);
+    let pairs = |n: usize| (n as f64) * (n as f64 - 1.0) / 2.0;
+
+    let index: f64 = table.values().map(|&n| pairs(n)).sum();
+    let pairs_a: f64 = sums_a.values().map(|&n| pairs(n)).sum();
+    let pairs_b: f64 = sums_b.values().map(|&n| pairs(n)).sum();
+    let expected = pairs_a * pairs_b / pairs(labels_a.len());
+    let max = (pairs_a + pairs_b) / 2.0;
+
+    // Both clusterings put all points in one cluster, or every point in its own cluster
+    if max == expected {
+        return 1.0
+    }
+    (index - expected) / (max - expected)
+}
+
+/// The normalized mutual information of two clusterings: their mutual information divided by the
+/// arithmetic mean of their entropies (Vinh et al., 2010). It is 1 for identical clusterings up to
+/// a permutation of the labels, and 0 for independent clusterings.
+///
+/// # References
+///
+/// Vinh, N. X., Epps, J., & Bailey, J. (2010). Information theoretic measures for clusterings
+///     comparison: Variants, properties, normalization and correction for chance. Journal of
+///     Machine Learning Research, 11, 2837–2854.
+///
+/// Time complexity: O(r)
+///
+/// # Panics
+///
+/// If the clusterings have different numbers of labels.
+pub fn normalized_mutual_info(labels_a: &[usize], labels_b: &[usize]) -> f64 {
+    let (table, sums_a, sums_b) = contingency_table(labels_a, labels_b);
+    let r = labels_a.len() as f64;
+    let entropy = |sums: &Counts| -> f64 {
+        sums.values().map(|&n| n as f64 / r).map(|p| -p * p.ln()).sum()
+    };
+
+    let mutual_info: f64 = table.iter().map(|((a, b), &n)| {
+        let n = n as f64;
+        n / r * (r * n / (sums_a[a] as f64 * sums_b[b] as f64)).ln()
+    }).sum();
+    let mean_entropy = (entropy(&sums_a) + entropy(&sums_b)) / 2.0;
+
+    // Both clusterings put all points in one cluster
+    if mean_entropy == 0.0 {
+        return 1.0
+    }
+    (mutual_info / mean_entropy).clamp(0.0, 1.0)
+}
+
+/// The relabeling of the second clustering that agrees with the first on the most points: label
+/// `l` in `labels_b` corresponds to label `mapping[l]` in `labels_a`, for example to compare the
+/// clusters of runs with different random states, which number the same clusters differently.
+/// The labels are matched one to one; if `labels_b` has more labels than `labels_a`, the
+/// remaining ones are mapped to new labels after those of `labels_a`. The matching is found with
+/// the Hungarian algorithm (Kuhn, 1955; Jonker & Volgenant, 1987).
+///
+/// # References
+///
+/// Kuhn, H. W. (1955). The Hungarian method for the assignment problem. Naval Research Logistics
+///     Quarterly, 2(1–2), 83–97. <https://doi.org/10.1002/nav.3800020109>
+///
+/// Jonker, R., & Volgenant, A. (1987). A shortest augmenting path algorithm for dense and sparse
+///     linear assignment problems. Computing, 38(4), 325–340. <https://doi.org/10.1007/BF02278710>
+///
+/// Time complexity: O(r + k^3) for k labels
+///
+/// # Panics
+///
+/// If the clusterings have different numbers of labels.
+pub fn match_labels(labels_a: &[usize], labels_b: &[usize]) -> Vec<usize> {
+    let (table, _, _) = contingency_table(labels_a, labels_b);
+    let k = |labels: &[usize]| labels.iter().max().map_or(0, |&max| max + 1);
+    let (k_a, k_b) = (k(labels_a), k(labels_b));
+
+    // Maximize the number of points on which the clusterings agree
+    let n = k_a.max(k_b);
+    let mut cost = vec![vec![0.0; n]; n];
+    for (&(a, b), &count) in &table {
+        cost[b][a] = -(count as f64);
+    }
+    let mut mapping = flow::assignment(&cost);
+    mapping.truncate(k_b);
+    mapping
+}
+
+/// The number of points per label.
+type Counts = HashMap<usize, usize>;
+
+/// The number of points with every combination of labels, and the number of points per label in
+/// either clustering. Only combinations that occur are stored, so that large numbers of clusters
+/// take O(r) memory instead of O(k_a * k_b).
+///
+/// Time complexity: O(r)
+fn contingency_table(labels_a: &[usize], labels_b: &[usize]) -> (HashMap<(usize, usize), usize>, Counts, Counts) {
+    assert_eq!(labels_a.len(), labels_b.len(), "clusterings must label the same points");
+    let mut table = HashMap::new();
+    let mut sums_a = HashMap::new();
+    let mut sums_b = HashMap::new();
+    for (&a, &b) in labels_a.iter().zip(labels_b) {
+        *table.entry((a, b)).or_insert(0) += 1;
+        *sums_a.entry(a).or_insert(0) += 1;
+        *sums_b.entry(b).or_insert(0) += 1;
+    }
+    (table, sums_a, sums_b)
+}
+
+/// The number of points per label.
+///
+/// Time complexity: O(r)
+fn cluster_sizes<const M: usize>(points: &[Point<M>], labels: &[usize]) -> Vec<usize> {
+    assert_eq!(points.len(), labels.len(), "every point needs a label");
+    let mut sizes = vec![0; labels.iter().max().map_or(0, |&max| max + 1)];
+    for &label in labels {
+        sizes[label] += 1;
+    }
+    sizes
+}
+
+/// The centroid and the number of points per label.
+///
+/// Time complexity: O(r * M)
+fn centroids<const M: usize>(points: &[Point<M>], labels: &[usize]) -> (Vec<Point<M>>, Vec<usize>) {
+    let sizes = cluster_sizes(points, labels);
+    let mut centroids = vec![Point::default(); sizes.len()];
+    for (point, &label) in points.iter().zip(labels) {
+        centroids[label] += *point;
+    }
+    for c in 0..sizes.len() {
+        if sizes[c] > 0 {
+            centroids[c] = centroids[c] / sizes[c];
+        }
+    }
+    (centroids, sizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_metrics() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([1.0, 0.0]),
+            Point([10.0, 0.0]),
+            Point([11.0, 0.0])
+        ];
+        let labels = [0, 0, 1, 1];
+        let silhouette = (2.0 * (1.0 - 1.0 / 10.5) + 2.0 * (1.0 - 1.0 / 9.5)) / 4.0;
+        assert!((silhouette_score(&points, &labels) - silhouette).abs() < 1e-12);
+        assert!((sampled_silhouette_score(&points, &labels, 4, 0) - silhouette).abs() < 1e-12);
+        assert_eq!(davies_bouldin(&points, &labels), 0.1);
+        assert_eq!(calinski_harabasz(&points, &labels), 200.0);
+    }
+
+    #[test]
+    fn external_metrics() {
+        let a = [0, 0, 1, 1, 2, 2];
+        let b = [5, 5, 3, 3, 9, 9];
+        assert_eq!(adjusted_rand_index(&a, &b), 1.0);
+        assert!((normalized_mutual_info(&a, &b) - 1.0).abs() < 1e-12);
+
+        // 2 pairs are together in both, against an expected 1.2 out of a maximum of 4.5
+        let c = [0, 0, 0, 1, 1, 1];
+        let d = [0, 0, 1, 1, 2, 2];
+        assert!((adjusted_rand_index(&c, &d) - 0.8 / 3.3).abs() < 1e-12);
+        assert!(normalized_mutual_info(&c, &d) < 1.0);
+
+        // Match the labels of runs that number the same clusters differently
+        assert_eq!(match_labels(&a, &[2, 2, 0, 0, 1, 1]), [1, 2, 0]);
+        assert_eq!(match_labels(&a, &[2, 2, 0, 1, 1, 1]), [1, 2, 0]);
+        assert_eq!(match_labels(&a, &[0, 0, 1, 1, 2, 3]), [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::error::{check_point_rows, ClusterError};
+use crate::centers::Centers;
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::initialization::random_points;
+use crate::point::Point;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Implements mini-batch k-means (Sculley, 2010), which updates the centers using small random
+/// batches of points with a per-center learning rate. This gives approximate results on datasets
+/// that are too large to cluster exactly, and allows the data to be fed in chunks with
+/// [`MiniBatchKMeans::partial_fit`].
+///
+/// # References
+///
+/// Sculley, D. (2010). Web-scale k-means clustering. Proceedings of the 19th International
+///     Conference on World Wide Web, 1177–1178. <https://doi.org/10.1145/1772690.1772862>
+pub struct MiniBatchKMeans<const K: usize, const M: usize> {
+    /// The coordinates of the k means.
+    pub centers: [Point<M>; K],
+    /// The number of points that updated each center so far.
+    pub counts: [usize; K],
+    /// The index of the center closest to each point the model was last fitted on: all points
+    /// for [`MiniBatchKMeans::fit`], or the last batch for [`MiniBatchKMeans::partial_fit`].
+    pub point_centers: Vec<usize>,
+    initialized: bool,
+    rng: StdRng
+}
+
+impl<const K: usize, const M: usize> Default for MiniBatchKMeans<K, M> {
+    fn default() -> Self {
+        Self::from_rng(StdRng::from_entropy())
+    }
+}
+
+impl<const K: usize, const M: usize> MiniBatchKMeans<K, M> {
+    /// An empty model, to be fitted with [`MiniBatchKMeans::partial_fit`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty model with a pre-determined random state.
+    pub fn with_random_state(random_state: u64) -> Self {
+        Self::from_rng(StdRng::seed_from_u64(random_state))
+    }
+
+    fn from_rng(rng: StdRng) -> Self {
+        Self {
+            centers: [Point::default(); K],
+            counts: [0; K],
+            point_centers: Vec::new(),
+            initialized: false,
+            rng
+        }
+    }
+
+    /// Get k clusters based on `iterations` random batches of `batch_size` points, or an error if
+    /// they have NaN or infinite coordinates or there are fewer than k distinct points.
+    pub fn fit(points: &[Point<M>], batch_size: usize, iterations: usize) -> Result<Self, ClusterError> {
+        Self::new().fit_batches(points, batch_size, iterations)
+    }
+
+    /// Get k clusters based on `points` with a pre-determined random state, or an error, see
+    /// [`MiniBatchKMeans::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], batch_size: usize, iterations: usize, random_state: u64) -> Result<Self, ClusterError> {
+        Self::with_random_state(random_state).fit_batches(points, batch_size, iterations)
+    }
+
+    fn fit_batches(mut self, points: &[Point<M>], batch_size: usize, iterations: usize) -> Result<Self, ClusterError> {
+        check_point_rows(points.iter().map(|point| point.0), M, K)?;
+        let batch_size = batch_size.clamp(K, points.len());
+        for _ in 0..iterations {
+            let batch: Vec<Point<M>> = rand::seq::index::sample(&mut self.rng, points.len(), batch_size)
+                .iter()
+                .map(|i| points[i])
+                .collect();
+            self.partial_fit(&batch);
+        }
+        let centers = Centers::new(self.centers);
+        self.point_centers = points.iter().map(|point| centers.closest(point)).collect();
+        Ok(self)
+    }
+
+    /// Update the centers with a batch of points. The first batch also initializes the centers,
+    /// so it should contain at least k points.
+    ///
+    /// Time complexity: O(b * k * M)
+    pub fn partial_fit(&mut self, points: &[Point<M>]) {
+        if !self.initialized {
+            if points.len() < K {
+                return;
+            }
+            self.centers = random_points(points, &mut self.rng);
+            self.initialized = true;
+        }
+
+        // Assign the whole batch to the centers before updating them
+        let centers = Centers::new(self.centers);
+        let point_centers: Vec<usize> = points.iter().map(|point| centers.closest(point)).collect();
+
+        // Move each center towards its points with a decreasing learning rate
+        for (point, &k) in points.iter().zip(&point_centers) {
+            self.counts[k] += 1;
+            let eta = 1.0 / self.counts[k] as f64;
+            self.centers[k] = (1.0 - eta) * self.centers[k] + eta * *point;
+        }
+        let centers = Centers::new(self.centers);
+        self.point_centers = points.iter().map(|point| centers.closest(point)).collect();
+    }
+
+    /// Index of the center closest to `point`.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn predict(&self, point: &Point<M>) -> usize {
+        Centers::new(self.centers).closest(point)
+    }
+}
+
+/// The parameters of [`MiniBatchKMeans::fit`], to fit it through the [`Fit`] trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MiniBatchKMeansParams<const K: usize> {
+    batch_size: usize,
+    iterations: usize,
+    random_state: Option<u64>
+}
+
+impl<const K: usize> MiniBatchKMeansParams<K> {
+    /// Fit on `iterations` random batches of `batch_size` points.
+    pub fn new(batch_size: usize, iterations: usize) -> Self {
+        Self { batch_size, iterations, random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for MiniBatchKMeansParams<K> {
+    type Model = MiniBatchKMeans<K, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<MiniBatchKMeans<K, M>, ClusterError> {
+        let model = match self.random_state {
+            Option::Some(random_state) => MiniBatchKMeans::with_random_state(random_state),
+            None => MiniBatchKMeans::new()
+        };
+        model.fit_batches(points, self.batch_size, self.iterations)
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for MiniBatchKMeans<K, M> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    /// The center of each point the model was last fitted on, see
+    /// [`MiniBatchKMeans::point_centers`].
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.iter().map(|&k| Option::Some(k)).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.to_vec())
+    }
+}
+
+impl<const K: usize, const M: usize> Predict<M> for MiniBatchKMeans<K, M> {
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        Option::Some(MiniBatchKMeans::predict(self, point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn partial_fit() {
+        let mut model = MiniBatchKMeans::<2, 1>::with_random_state(0);
+        model.partial_fit(&[Point([0.0]), Point([10.0])]);
+        model.partial_fit(&[Point([1.0]), Point([11.0]), Point([0.5]), Point([10.5])]);
+        assert_ne!(model.predict(&Point([0.0])), model.predict(&Point([10.0])));
+        assert_eq!(model.counts, [3, 3]);
+        assert_eq!(model.centers[model.predict(&Point([0.0]))], Point([0.5]));
+        assert_eq!(model.point_centers.len(), 4);
+    }
+
+    #[test]
+    fn fit() {
+        let points = [Point([0.0]), Point([10.0])];
+        assert_eq!(MiniBatchKMeans::<2, 1>::fit_with_random_state(&points, 8, 2, 0).map(|model| model.counts), Ok([2, 2]));
+        assert_eq!(MiniBatchKMeans::<3, 1>::fit(&points, 8, 2).err(), Option::Some(ClusterError::TooFewPoints { clusters: 3, distinct: 2 }));
+    }
+}
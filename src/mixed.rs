@@ -0,0 +1,451 @@
+//! Clustering of categorical and mixed numeric and categorical data, where categories are encoded
+//! as integer codes, for example with one code per distinct value of a column.
+
+use crate::error::ClusterError;
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::initialization::random_indices;
+use crate::point::Point;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Maximum number of iterations.
+const MAX_ITER: usize = 300;
+
+/// A row with M numeric and C categorical values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MixedRow<const M: usize, const C: usize> {
+    /// The numeric values.
+    pub numeric: Point<M>,
+    /// The category codes.
+    pub categorical: [usize; C]
+}
+
+impl<const M: usize, const C: usize> MixedRow<M, C> {
+    /// Create a row from its numeric values and category codes.
+    pub fn new(numeric: [f64; M], categorical: [usize; C]) -> Self {
+        Self { numeric: Point(numeric), categorical }
+    }
+
+    /// The squared Euclidean distance of the numeric values plus `gamma` times the number of
+    /// categories that differ.
+    ///
+    /// Time complexity: O(M + C)
+    pub fn dissimilarity(&self, other: &Self, gamma: f64) -> f64 {
+        self.numeric.squared_distance(&other.numeric) + gamma * mismatches(&self.categorical, &other.categorical) as f64
+    }
+}
+
+/// Implements k-modes (Huang, 1998), the counterpart of k-means for categorical data. Rows are
+/// compared by the number of categories in which they differ, and the center of a cluster is its
+/// mode: the most frequent category in every column, where ties go to the smallest code.
+/// Empty clusters keep their mode.
+///
+/// # References
+///
+/// Huang, Z. (1998). Extensions to the k-means algorithm for clustering large data sets with
+///     categorical values. Data Mining and Knowledge Discovery, 2(3), 283–304.
+///     <https://doi.org/10.1023/A:1009769707641>
+pub struct KModes<const K: usize, const C: usize> {
+    /// The mode of every cluster.
+    pub modes: [[usize; C]; K],
+    /// The index of the mode that each row belongs to.
+    pub point_modes: Vec<usize>,
+    /// The total number of mismatches of the rows with their modes.
+    pub cost: usize,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the modes converged within the maximum number of iterations.
+    pub converged: bool
+}
+
+impl<const K: usize, const C: usize> KModes<K, C> {
+    /// Get k clusters based on `rows`, or an error if there are fewer than k distinct rows.
+    pub fn fit(rows: &[[usize; C]]) -> Result<Self, ClusterError> {
+        Self::new(rows, Option::None)
+    }
+
+    /// Get k clusters based on `rows` with a pre-determined random state, or an error, see
+    /// [`KModes::fit`].
+    pub fn fit_with_random_state(rows: &[[usize; C]], random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(rows, Option::Some(random_state))
+    }
+
+    fn new(rows: &[[usize; C]], random_state: Option<u64>) -> Result<Self, ClusterError> {
+        check_distinct(rows.iter(), K)?;
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        let mut modes = random_rows::<K, _>(rows, &mut rng);
+        let mut point_modes = vec![usize::MAX; rows.len()];
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < MAX_ITER {
+            iterations += 1;
+
+            // Assign each row to the mode with the fewest mismatches
+            let mut different = false;
+            for (row, point_mode) in rows.iter().zip(point_modes.iter_mut()) {
+                let k = closest(&modes, |mode| mismatches(row, mode) as f64);
+                if *point_mode != k {
+                    *point_mode = k;
+                    different = true;
+                }
+            }
+
+            if !different {
+                converged = true;
+                break;
+            }
+            modes = cluster_modes(rows, &point_modes, &modes);
+        }
+
+        let cost = rows.iter().zip(&point_modes).map(|(row, &k)| mismatches(row, &modes[k])).sum();
+        Ok(KModes { modes, point_modes, cost, iterations, converged })
+    }
+
+    /// Index of the mode with the fewest mismatches with `row`.
+    ///
+    /// Time complexity: O(k * C)
+    pub fn predict(&self, row: &[usize; C]) -> usize {
+        closest(&self.modes, |mode| mismatches(row, mode) as f64)
+    }
+}
+
+/// The parameters of [`KModes::fit`], to fit it through the [`Fit`] trait on rows of C category
+/// codes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct KModesParams<const K: usize> {
+    random_state: Option<u64>
+}
+
+impl<const K: usize> KModesParams<K> {
+    /// Fit k-modes with k = K.
+    pub fn new() -> Self {
+        Self { random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const C: usize> Fit<C, [usize; C]> for KModesParams<K> {
+    type Model = KModes<K, C>;
+
+    fn fit(&self, rows: &[[usize; C]]) -> Result<KModes<K, C>, ClusterError> {
+        KModes::new(rows, self.random_state)
+    }
+}
+
+impl<const K: usize, const C: usize> Clusterer<C> for KModes<K, C> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_modes.iter().map(|&k| Option::Some(k)).collect()
+    }
+}
+
+impl<const K: usize, const C: usize> Predict<C, [usize; C]> for KModes<K, C> {
+    fn predict(&self, row: &[usize; C]) -> Option<usize> {
+        Option::Some(KModes::predict(self, row))
+    }
+}
+
+/// Implements k-prototypes (Huang, 1998), which clusters rows with both numeric and categorical
+/// values. Rows are compared by [`MixedRow::dissimilarity`]: the squared Euclidean distance of the
+/// numeric values plus `gamma` times the number of mismatched categories. The center of a cluster
+/// has the mean of its numeric values, as in k-means, and the mode of its categorical values, as
+/// in [`KModes`].
+///
+/// `gamma` balances the two parts; Huang suggests a value between a third and two thirds of the
+/// mean standard deviation of the numeric columns, after scaling them, for example with
+/// [`crate::preprocess`].
+///
+/// # References
+///
+/// Huang, Z. (1998). Extensions to the k-means algorithm for clustering large data sets with
+///     categorical values. Data Mining and Knowledge Discovery, 2(3), 283–304.
+///     <https://doi.org/10.1023/A:1009769707641>
+pub struct KPrototypes<const K: usize, const M: usize, const C: usize> {
+    /// The prototype of every cluster.
+    pub centers: [MixedRow<M, C>; K],
+    /// The index of the prototype that each row belongs to.
+    pub point_centers: Vec<usize>,
+    /// The weight of the categorical values.
+    pub gamma: f64,
+    /// The total dissimilarity of the rows to their prototypes.
+    pub cost: f64,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the prototypes converged within the maximum number of iterations.
+    pub converged: bool
+}
+
+impl<const K: usize, const M: usize, const C: usize> KPrototypes<K, M, C> {
+    /// Get k clusters based on `rows`, or an error if they have NaN or infinite numeric values or
+    /// there are fewer than k distinct rows.
+    ///
+    /// # Panics
+    ///
+    /// If `gamma` is negative or NaN.
+    pub fn fit(rows: &[MixedRow<M, C>], gamma: f64) -> Result<Self, ClusterError> {
+        Self::new(rows, gamma, Option::None)
+    }
+
+    /// Get k clusters based on `rows` with a pre-determined random state, or an error, see
+    /// [`KPrototypes::fit`].
+    ///
+    /// # Panics
+    ///
+    /// If `gamma` is negative or NaN.
+    pub fn fit_with_random_state(rows: &[MixedRow<M, C>], gamma: f64, random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(rows, gamma, Option::Some(random_state))
+    }
+
+    fn new(rows: &[MixedRow<M, C>], gamma: f64, random_state: Option<u64>) -> Result<Self, ClusterError> {
+        assert!(gamma >= 0.0, "gamma must not be negative");
+        for (i, row) in rows.iter().enumerate() {
+            if let Option::Some(dimension) = row.numeric.0.iter().position(|x| !x.is_finite()) {
+                return Err(ClusterError::NonFinite { point: i, dimension })
+            }
+        }
+        // Adding 0 turns -0 into 0, so that both have the same bits
+        check_distinct(rows.iter().map(|row| (row.numeric.0.map(|x| (x + 0.0).to_bits()), row.categorical)), K)?;
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        let mut centers = random_rows::<K, _>(rows, &mut rng);
+        let mut point_centers = vec![usize::MAX; rows.len()];
+        let categorical: Vec<[usize; C]> = rows.iter().map(|row| row.categorical).collect();
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < MAX_ITER {
+            iterations += 1;
+
+            // Assign each row to the least dissimilar prototype
+            let mut different = false;
+            for (row, point_center) in rows.iter().zip(point_centers.iter_mut()) {
+                let k = closest(&centers, |center| row.dissimilarity(center, gamma));
+                if *point_center != k {
+                    *point_center = k;
+                    different = true;
+                }
+            }
+
+            if !different {
+                converged = true;
+                break;
+            }
+
+            // Move the numeric values to the means and the categories to the modes, keeping
+            // empty clusters in place
+            let mut sums = [Point::default(); K];
+            let mut counts = [0usize; K];
+            for (row, &k) in rows.iter().zip(&point_centers) {
+                sums[k] += row.numeric;
+                counts[k] += 1;
+            }
+            let modes = cluster_modes(&categorical, &point_centers, &centers.map(|center| center.categorical));
+            for k in 0..K {
+                if counts[k] > 0 {
+                    centers[k].numeric = sums[k] / counts[k] as f64;
+                }
+                centers[k].categorical = modes[k];
+            }
+        }
+
+        let cost = rows.iter().zip(&point_centers).map(|(row, &k)| row.dissimilarity(&centers[k], gamma)).sum();
+        Ok(KPrototypes { centers, point_centers, gamma, cost, iterations, converged })
+    }
+
+    /// Index of the prototype least dissimilar to `row`.
+    ///
+    /// Time complexity: O(k * (M + C))
+    pub fn predict(&self, row: &MixedRow<M, C>) -> usize {
+        closest(&self.centers, |center| row.dissimilarity(center, self.gamma))
+    }
+}
+
+/// The parameters of [`KPrototypes::fit`], to fit it through the [`Fit`] trait on rows with C
+/// categorical values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KPrototypesParams<const K: usize, const C: usize> {
+    gamma: f64,
+    random_state: Option<u64>
+}
+
+impl<const K: usize, const C: usize> KPrototypesParams<K, C> {
+    /// Fit k-prototypes with k = K and weight `gamma` of the categorical values.
+    pub fn new(gamma: f64) -> Self {
+        Self { gamma, random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize, const C: usize> Fit<M, MixedRow<M, C>> for KPrototypesParams<K, C> {
+    type Model = KPrototypes<K, M, C>;
+
+    fn fit(&self, rows: &[MixedRow<M, C>]) -> Result<KPrototypes<K, M, C>, ClusterError> {
+        KPrototypes::new(rows, self.gamma, self.random_state)
+    }
+}
+
+impl<const K: usize, const M: usize, const C: usize> Clusterer<M> for KPrototypes<K, M, C> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.iter().map(|&k| Option::Some(k)).collect()
+    }
+}
+
+impl<const K: usize, const M: usize, const C: usize> Predict<M, MixedRow<M, C>> for KPrototypes<K, M, C> {
+    fn predict(&self, row: &MixedRow<M, C>) -> Option<usize> {
+        Option::Some(KPrototypes::predict(self, row))
+    }
+}
+
+/// The number of categories that differ.
+///
+/// Time complexity: O(C)
+fn mismatches<const C: usize>(a: &[usize; C], b: &[usize; C]) -> usize {
+    a.iter().zip(b).filter(|(a, b)| a != b).count()
+}
+
+/// Index of the center with the smallest dissimilarity.
+///
+/// Time complexity: O(k) dissimilarities
+fn closest<T, const K: usize>(centers: &[T; K], dissimilarity: impl Fn(&T) -> f64) -> usize {
+    let mut min_d = f64::INFINITY;
+    let mut min_c = 0;
+    for (k, center) in centers.iter().enumerate() {
+        let d = dissimilarity(center);
+        if d < min_d {
+            min_d = d;
+            min_c = k;
+        }
+    }
+    min_c
+}
+
+/// Check that there is at least one cluster and that there are at least `k` distinct rows.
+///
+/// Time complexity: O(r) hashes
+fn check_distinct<T: Hash + Eq>(rows: impl IntoIterator<Item = T>, k: usize) -> Result<(), ClusterError> {
+    if k == 0 {
+        return Err(ClusterError::NoClusters)
+    }
+
+    let mut distinct = HashSet::new();
+    for row in rows {
+        if distinct.len() == k {
+            break;
+        }
+        distinct.insert(row);
+    }
+    if distinct.len() < k {
+        return Err(ClusterError::TooFewPoints { clusters: k, distinct: distinct.len() })
+    }
+    Ok(())
+}
+
+/// Sample k distinct rows uniformly at random.
+///
+/// Time complexity: O(k)
+fn random_rows<const K: usize, T: Copy>(rows: &[T], rng: &mut impl Rng) -> [T; K] {
+    let indices = random_indices(rows.len(), K, rng);
+    std::array::from_fn(|k| rows[indices[k]])
+}
+
+/// The mode of every cluster, where ties go to the smallest code, or the previous mode for empty
+/// clusters.
+///
+/// Time complexity: O(r * C)
+fn cluster_modes<const K: usize, const C: usize>(rows: &[[usize; C]], labels: &[usize], previous: &[[usize; C]; K]) -> [[usize; C]; K] {
+    let mut counts: Vec<Vec<HashMap<usize, usize>>> = vec![vec![HashMap::new(); C]; K];
+    for (row, &k) in rows.iter().zip(labels) {
+        for c in 0..C {
+            *counts[k][c].entry(row[c]).or_insert(0) += 1;
+        }
+    }
+    let mut modes = *previous;
+    for k in 0..K {
+        for c in 0..C {
+            if let Option::Some((&code, _)) = counts[k][c].iter().max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0))) {
+                modes[k][c] = code;
+            }
+        }
+    }
+    modes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn k_modes() {
+        let rows = [[0, 0, 1], [0, 0, 0], [0, 1, 1], [2, 2, 2], [2, 3, 2], [3, 2, 2]];
+        let model = KModes::<2, 3>::fit_with_random_state(&rows, 0).unwrap();
+        let a = model.point_modes[0];
+        assert_eq!(model.point_modes, vec![a, a, a, 1 - a, 1 - a, 1 - a]);
+        assert_eq!(model.modes[a], [0, 0, 1]);
+        assert_eq!(model.modes[1 - a], [2, 2, 2]);
+        assert_eq!(model.cost, 4);
+        assert!(model.converged);
+        assert_eq!(model.predict(&[3, 3, 2]), 1 - a);
+    }
+
+    #[test]
+    fn k_prototypes() {
+        // The categories separate rows that the numeric values cannot
+        let rows = [
+            MixedRow::new([0.0], [0]),
+            MixedRow::new([1.0], [0]),
+            MixedRow::new([0.5], [0]),
+            MixedRow::new([0.0], [1]),
+            MixedRow::new([1.0], [1]),
+            MixedRow::new([0.5], [1])
+        ];
+        let model = KPrototypes::<2, 1, 1>::fit_with_random_state(&rows, 10.0, 5).unwrap();
+        let a = model.point_centers[0];
+        assert_eq!(model.point_centers, vec![a, a, a, 1 - a, 1 - a, 1 - a]);
+        assert_eq!(model.centers[a], MixedRow::new([0.5], [0]));
+        assert_eq!(model.centers[1 - a], MixedRow::new([0.5], [1]));
+        assert!((model.cost - 1.0).abs() < 1e-12);
+        assert!(model.converged);
+        assert_eq!(model.predict(&MixedRow::new([0.0], [1])), 1 - a);
+    }
+
+    #[test]
+    fn fit() {
+        let rows = [[0, 1], [0, 1], [1, 1]];
+        assert_eq!(KModes::<3, 2>::fit(&rows).err(), Option::Some(ClusterError::TooFewPoints { clusters: 3, distinct: 2 }));
+        assert_eq!(KModes::<0, 2>::fit(&rows).err(), Option::Some(ClusterError::NoClusters));
+
+        let rows = [MixedRow::new([0.0], [0]), MixedRow::new([-0.0], [0]), MixedRow::new([0.0], [1])];
+        assert_eq!(KPrototypes::<3, 1, 1>::fit(&rows, 1.0).err(), Option::Some(ClusterError::TooFewPoints { clusters: 3, distinct: 2 }));
+        let rows = [MixedRow::new([0.0], [0]), MixedRow::new([f64::NAN], [1])];
+        assert_eq!(KPrototypes::<2, 1, 1>::fit(&rows, 1.0).err(), Option::Some(ClusterError::NonFinite { point: 1, dimension: 0 }));
+    }
+}
@@ -0,0 +1,219 @@
+//! Helpers to choose the number of clusters k, by fitting k-means over a range of k with a
+//! [`Sweep`] and comparing the fits.
+
+use crate::error::ClusterError;
+use crate::point::Point;
+use crate::seeding::SeedSequence;
+use crate::sweep::Sweep;
+use rand::Rng;
+
+/// Settings shared by the model selection helpers.
+#[derive(Clone, Debug)]
+pub struct SelectionConfig {
+    random_state: Option<u64>,
+    max_iter: usize,
+    references: usize,
+    warm_start: bool
+}
+
+impl Default for SelectionConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SelectionConfig {
+    pub fn new() -> Self {
+        Self {
+            random_state: Option::None,
+            max_iter: 300,
+            references: 10,
+            warm_start: true
+        }
+    }
+
+    /// Set a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+
+    /// Set the maximum number of iterations per fit. Defaults to 300.
+    pub fn max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Set the number of reference datasets of the gap statistic. Defaults to 10.
+    pub fn references(mut self, references: usize) -> Self {
+        self.references = references;
+        self
+    }
+
+    /// Initialize the fit for every k from the centers of the next smaller k, see
+    /// [`Sweep::warm_start`], which makes the inertia decrease with k. Otherwise, the fits are
+    /// initialized independently and run concurrently when parallelism is enabled. Defaults to
+    /// true.
+    pub fn warm_start(mut self, warm_start: bool) -> Self {
+        self.warm_start = warm_start;
+        self
+    }
+
+    fn sweep(&self, ks: &[usize], seeds: SeedSequence) -> Sweep {
+        Sweep::new(ks.iter().copied()).random_state(seeds.0).max_iter(self.max_iter).warm_start(self.warm_start)
+    }
+}
+
+/// The inertia, the sum of the squared distances of the points to their centers, for every k in
+/// `ks`, in the order in which they were given. Plotted against k, the point where the inertia
+/// stops decreasing sharply (the elbow) suggests a number of clusters.
+///
+/// The fits share a single tree, see [`SelectionConfig::warm_start`] for how they are
+/// initialized.
+///
+/// # Errors
+///
+/// If a k is 0 or larger than the number of distinct points, or if a point is not finite.
+pub fn inertia_curve<const M: usize>(points: &[Point<M>], ks: impl IntoIterator<Item = usize>, config: &SelectionConfig) -> Result<Vec<(usize, f64)>, ClusterError> {
+    let ks: Vec<usize> = ks.into_iter().collect();
+    let seeds = SeedSequence::from_random_state(config.random_state);
+    Ok(config.sweep(&ks, seeds)
+        .fit(points)?
+        .into_iter()
+        .map(|model| (model.k, model.inertia))
+        .collect())
+}
+
+/// The gap statistic of one number of clusters `k`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GapScore {
+    /// The number of clusters.
+    pub k: usize,
+    /// The inertia of the fit on the points.
+    pub inertia: f64,
+    /// The mean log inertia of the fits on the reference datasets minus the log inertia of the
+    /// fit on the points.
+    pub gap: f64,
+    /// The standard error of the mean log inertia of the reference datasets.
+    pub standard_error: f64
+}
+
+/// The result of [`gap_statistic`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct GapStatistic {
+    /// The recommended number of clusters.
+    pub k: usize,
+    /// The scores of every k, in increasing order of k.
+    pub scores: Vec<GapScore>
+}
+
+/// The gap statistic (Tibshirani, Walther & Hastie, 2001) for every k in `ks`, which compares the
+/// log inertia of the points to its expected value under a reference distribution without
+/// clusters: points drawn uniformly from the bounding box of the data. The recommended k is the
+/// smallest k for which gap(k) >= gap(k') - s(k'), where k' is the next larger k in `ks` and s is
+/// the standard error, or the k with the largest gap if there is none.
+///
+/// Every reference dataset has as many points as the data, and is fitted over all k with its own
+/// random stream, concurrently when parallelism is enabled.
+///
+/// # References
+///
+/// Tibshirani, R., Walther, G., & Hastie, T. (2001). Estimating the number of clusters in a data
+///     set via the gap statistic. Journal of the Royal Statistical Society: Series B (Statistical
+///     Methodology), 63(2), 411–423. <https://doi.org/10.1111/1467-9868.00293>
+///
+/// Time complexity: worst case O(B * r * k * M) per iteration, for B reference datasets
+///
+/// # Errors
+///
+/// If a k is 0 or larger than the number of distinct points, or if a point is not finite.
+///
+/// # Panics
+///
+/// If `ks` is empty or if there are no reference datasets.
+pub fn gap_statistic<const M: usize>(points: &[Point<M>], ks: impl IntoIterator<Item = usize>, config: &SelectionConfig) -> Result<GapStatistic, ClusterError> {
+    let mut ks: Vec<usize> = ks.into_iter().collect();
+    ks.sort_unstable();
+    ks.dedup();
+    assert!(!ks.is_empty(), "the gap statistic needs at least one k");
+    assert!(config.references > 0, "the gap statistic needs at least one reference dataset");
+
+    let seeds = SeedSequence::from_random_state(config.random_state);
+    let inertias: Vec<f64> = config.sweep(&ks, seeds.child(0)).fit(points)?.iter().map(|model| model.inertia).collect();
+
+    // Bounding box of the data
+    let mut min = [f64::INFINITY; M];
+    let mut max = [f64::NEG_INFINITY; M];
+    for point in points {
+        for d in 0..M {
+            min[d] = min[d].min(point.0[d]);
+            max[d] = max[d].max(point.0[d]);
+        }
+    }
+
+    let reference = |b: usize| -> Result<Vec<f64>, ClusterError> {
+        let seeds = seeds.child(b as u64 + 1);
+        let mut rng = seeds.child(0).rng();
+        let sample: Vec<Point<M>> = (0..points.len())
+            .map(|_| Point(std::array::from_fn(|d| min[d] + rng.gen::<f64>() * (max[d] - min[d]))))
+            .collect();
+        Ok(config.sweep(&ks, seeds.child(1)).fit(&sample)?.iter().map(|model| log(model.inertia)).collect())
+    };
+    #[cfg(feature = "rayon")]
+    let references: Vec<Vec<f64>> = {
+        use rayon::prelude::*;
+        (0..config.references).into_par_iter().map(reference).collect::<Result<_, _>>()?
+    };
+    #[cfg(not(feature = "rayon"))]
+    let references: Vec<Vec<f64>> = (0..config.references).map(reference).collect::<Result<_, _>>()?;
+
+    let b = config.references as f64;
+    let scores: Vec<GapScore> = ks.iter().enumerate().map(|(i, &k)| {
+        let mean = references.iter().map(|logs| logs[i]).sum::<f64>() / b;
+        let variance = references.iter().map(|logs| (logs[i] - mean) * (logs[i] - mean)).sum::<f64>() / b;
+        GapScore {
+            k,
+            inertia: inertias[i],
+            gap: mean - log(inertias[i]),
+            standard_error: variance.sqrt() * (1.0 + 1.0 / b).sqrt()
+        }
+    }).collect();
+
+    let k = scores.windows(2)
+        .find(|pair| pair[0].gap >= pair[1].gap - pair[1].standard_error)
+        .map(|pair| &pair[0])
+        .or_else(|| scores.iter().max_by(|a, b| a.gap.total_cmp(&b.gap)))
+        .unwrap()
+        .k;
+
+    Ok(GapStatistic { k, scores })
+}
+
+/// The natural logarithm of an inertia, where a perfect fit is clamped to the smallest positive
+/// number so that the gap stays finite.
+fn log(inertia: f64) -> f64 {
+    inertia.max(f64::MIN_POSITIVE).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gap_statistic() {
+        // Two tight clusters
+        let corners = [[0.0, 0.0], [10.0, 9.0]];
+        let points: Vec<Point<2>> = (0..20)
+            .map(|i| Point([corners[i / 10][0] + (i % 5) as f64 * 0.1, corners[i / 10][1] + (i % 10 / 5) as f64 * 0.1]))
+            .collect();
+        let config = SelectionConfig::new().random_state(0);
+
+        let curve = inertia_curve(&points, 1..=4, &config).unwrap();
+        assert_eq!(curve.iter().map(|&(k, _)| k).collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert!(curve.windows(2).all(|pair| pair[1].1 <= pair[0].1));
+
+        let result = super::gap_statistic(&points, 1..=5, &config).unwrap();
+        assert_eq!(result.k, 2);
+        assert_eq!(result.scores.len(), 5);
+    }
+}
@@ -1,122 +1,506 @@
+//! The mrkd-tree of (Pelleg & Moore, 1999), which partitions the points into nested
+//! hyper-rectangles, see [`Tree`]. Besides the queries of [`SpatialIndex`], its nodes can be
+//! traversed from [`Tree::root`], for example to implement other tree-pruned algorithms with the
+//! building blocks of [`crate::geometry`].
+
 use crate::hyper_rectangle::HyperRectangle;
+use crate::index::SpatialIndex;
 use crate::point::{get_range, Point};
-use crate::quickselect::median;
+use crate::quickselect::{median, split};
+use crate::seeding::SeedSequence;
+use crate::summation::{CenterSums, CompensatedPoint, CompensatedSum};
 use rand::Rng;
+use std::ops::Deref;
+
+/// The maximum number of points in a leaf node, unless configured otherwise.
+pub const DEFAULT_LEAF_SIZE: usize = 8;
+
+/// Number of points in a node above which its children are built in parallel.
+#[cfg(feature = "rayon")]
+const PARALLEL_THRESHOLD: usize = 4096;
 
+/// A mrkd-tree (Pelleg & Moore, 1999): a kd-tree whose nodes cache the center of mass of their
+/// points. Besides accelerating k-means, it is a spatial index for nearest-neighbor and range
+/// queries with Euclidean distance.
+///
+/// It is stored as an arena: the nodes are kept in a single contiguous `Vec` in depth-first order,
+/// with the children referred to by index, and the points of the leaves in another, so that the
+/// repeated traversals while fitting touch memory in order.
+///
+/// # References
+///
+/// Pelleg, D., & Moore, A. (1999). Accelerating exact k-means algorithms with geometric reasoning.
+///     Proceedings of the Fifth ACM SIGKDD International Conference
+///     on Knowledge Discovery and Data Mining, 277–281. <https://doi.org/10.1145/312129.312248>
 #[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tree<const M: usize> {
+    /// The nodes, starting with the root. The left child of a non-leaf node directly follows it.
+    nodes: Vec<Node<M>>,
+    /// The points of the leaves, in the order of the leaves
+    points: Vec<Point<M>>,
+    /// The weights of the points
+    weights: Vec<f64>,
+    /// The indices of the points in the points the tree was built from
+    indices: Vec<usize>
+}
+
+#[derive(PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node<const M: usize> {
     /// Hyper-rectangle boundaries
     pub h: HyperRectangle<M>,
 
     /// Number of points in contained leaf nodes
     pub number_of_points: usize,
 
-    /// Center of mass of contained points
+    /// Total weight of contained points
+    pub weight: f64,
+
+    /// Weighted center of mass of contained points
     pub center_of_mass: Point<M>,
 
-    /// Sum of Euclidean norms of contained points
+    /// The difference between the weighted sum of the contained points and the center of mass
+    /// times the weight, which is lost to rounding, see [`Node::add_to`]
+    compensation: Point<M>,
+
+    /// Weighted sum of the squared Euclidean norms of contained points, from which the distortion
+    /// of the node is computed, see [`Node::distortion`]
     pub euclidean_norm_sum: f64,
 
     /// Node information
-    pub node: Box<Node<M>>
+    kind: NodeKind
 }
 
 #[derive(PartialEq, Debug)]
-pub enum Node<const M: usize> {
-    NonLeaf(NonLeaf<M>),
-    Leaf(Point<M>)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum NodeKind {
+    NonLeaf {
+        /// split dimension
+        d: usize,
+        /// split value
+        v: f64,
+        /// index of the left child
+        l: usize,
+        /// index of the right child
+        r: usize
+    },
+    /// The range of the points of the leaf in the points of the tree
+    Leaf(usize, usize)
 }
 
-impl<const M: usize> Node<M> {
-    pub fn get_points(&self) -> Box<dyn Iterator<Item = &Point<M>> + '_> {
-        match self {
-            Node::NonLeaf(node) => Box::new(node.l.get_points().chain(node.r.get_points())),
-            Node::Leaf(point) => Box::new(std::iter::once(point))
+/// A node of a [`Tree`], through which its children or points can be reached.
+#[derive(Clone, Copy)]
+pub struct NodeRef<'a, const M: usize> {
+    tree: &'a Tree<M>,
+    index: usize
+}
+
+/// The contents of a node: two child nodes, or the points of a leaf node.
+pub enum Children<'a, const M: usize> {
+    NonLeaf(NodeRef<'a, M>, NodeRef<'a, M>),
+    Leaf(Leaf<'a, M>)
+}
+
+/// The points in a leaf node, at most the maximum leaf size.
+pub struct Leaf<'a, const M: usize> {
+    pub points: &'a [Point<M>],
+    pub weights: &'a [f64],
+    /// The indices of the points in the points the tree was built from
+    pub indices: &'a [usize]
+}
+
+impl<'a, const M: usize> Leaf<'a, M> {
+    /// The points with their weights and indices.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a Point<M>, f64, usize)> + 'a {
+        self.points.iter().zip(self.weights).zip(self.indices).map(|((point, &w), &i)| (point, w, i))
+    }
+}
+
+impl<'a, const M: usize> NodeRef<'a, M> {
+    /// The child nodes, or the points of a leaf node.
+    pub fn children(&self) -> Children<'a, M> {
+        match self.tree.nodes[self.index].kind {
+            NodeKind::NonLeaf { l, r, .. } => Children::NonLeaf(self.tree.node(l), self.tree.node(r)),
+            NodeKind::Leaf(start, end) => Children::Leaf(Leaf {
+                points: &self.tree.points[start..end],
+                weights: &self.tree.weights[start..end],
+                indices: &self.tree.indices[start..end]
+            })
+        }
+    }
+
+    /// The position of the first point of a leaf node in the points of the tree.
+    fn leaf_start(&self) -> usize {
+        match self.kind {
+            NodeKind::NonLeaf { .. } => 0,
+            NodeKind::Leaf(start, _) => start
+        }
+    }
+
+    /// The split of a non-leaf node: its dimension and value.
+    fn split(&self) -> Option<(usize, f64)> {
+        match self.kind {
+            NodeKind::NonLeaf { d, v, .. } => Option::Some((d, v)),
+            NodeKind::Leaf(..) => Option::None
         }
     }
 }
 
-#[derive(PartialEq, Debug)]
-pub struct NonLeaf<const M: usize> {
-    /// split dimension
-    d: usize,
-    /// split value
-    v: f64,
+impl<const M: usize> Node<M> {
+    /// Add the weighted sum of the contained points to `sums` as center `k`, including the part
+    /// that the center of mass loses to rounding, such that the result is the same as adding the
+    /// points one by one.
+    ///
+    /// Time complexity: O(M)
+    pub(crate) fn add_to<const K: usize>(&self, sums: &mut CenterSums<K, M>, k: usize) {
+        sums.sums[k].add_with_error(&(self.center_of_mass * self.weight), &self.compensation);
+        sums.counts[k].add(self.weight);
+    }
+
+    /// The distortion of the contained points when they are all assigned to `center`: the
+    /// weighted sum of their squared distances to it, computed from the cached statistics without
+    /// visiting the points, as in Section 3 (p. 279). The sum is split into the scatter around the
+    /// center of mass and the distance of the center of mass to `center`, which loses less
+    /// precision than expanding the squares.
+    ///
+    /// Time complexity: O(M)
+    pub fn distortion(&self, center: &Point<M>) -> f64 {
+        let scatter = self.euclidean_norm_sum - self.weight * self.center_of_mass.squared_distance(&Point::default());
+        scatter.max(0.0) + self.weight * self.center_of_mass.squared_distance(center)
+    }
+}
 
-    /// left child
-    pub l: Tree<M>,
-    /// right child
-    pub r: Tree<M>
+impl<'a, const M: usize> Deref for NodeRef<'a, M> {
+    type Target = Node<M>;
+
+    fn deref(&self) -> &Node<M> {
+        &self.tree.nodes[self.index]
+    }
+}
+
+/// A node that is still to be built: the range of its points in the index buffer, its
+/// hyper-rectangle, the dimension in which to try to split it first, and the non-leaf node it is
+/// the left or right child of.
+struct Task<const M: usize> {
+    start: usize,
+    end: usize,
+    h: HyperRectangle<M>,
+    d: usize,
+    parent: Option<(usize, bool)>
 }
 
 impl<const M: usize> Tree<M> {
+    /// Build a tree over `points`, with a fixed random state.
+    ///
+    /// Time complexity: O(r * log(r) * M)
+    pub fn new(points: &[Point<M>]) -> Self {
+        Self::initialize(points, &mut SeedSequence::new(0).rng())
+    }
+
+    /// Build a tree over `points`, using `rng` to pick the pivots while splitting.
     pub fn initialize(points: &[Point<M>], rng: &mut impl Rng) -> Self {
-        let (min, max) = get_range(points);
-        let h = HyperRectangle(min, max);
-        let d = 0;
+        Self::initialize_weighted(points, &vec![1.0; points.len()], rng)
+    }
 
-        Self::make_node(points, h, d, rng)
+    /// Build a tree where each point has a weight in the center of mass, with leaves of at most 8
+    /// points.
+    pub fn initialize_weighted(points: &[Point<M>], weights: &[f64], rng: &mut impl Rng) -> Self {
+        Self::with_leaf_size(points, weights, DEFAULT_LEAF_SIZE, rng)
     }
 
-    fn make_node(points: &[Point<M>], h: HyperRectangle<M>, d: usize, rng: &mut impl Rng) -> Self {
-        // Determine the cached information that makes this a mrkd-tree instead of a kd-tree
-        let number_of_points = points.len();
-        let mut euclidean_norm_sum = 0.0;
-        let mut center_of_mass = Point::default();
-        for point in points {
-            euclidean_norm_sum += point.distance(&Point::default());
-            center_of_mass = center_of_mass + *point;
+    /// Build a tree where each point has a weight in the center of mass, splitting nodes until they
+    /// have at most `max_leaf_size` points. Larger leaves make the tree smaller and faster to
+    /// build, but their points are assigned one by one.
+    ///
+    /// The points are not copied while splitting: a single buffer of indices is partitioned in
+    /// place, and the points are copied once, in the order of the leaves, when it is done.
+    ///
+    /// Time complexity: O(r * log(r) * M)
+    pub fn with_leaf_size(points: &[Point<M>], weights: &[f64], max_leaf_size: usize, rng: &mut impl Rng) -> Self {
+        let (min, max) = get_range(points);
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        let nodes = Self::build(points, weights, &mut order, HyperRectangle(min, max), 0, max_leaf_size.max(1), rng);
+        Self {
+            nodes,
+            points: order.iter().map(|&i| points[i]).collect(),
+            weights: order.iter().map(|&i| weights[i]).collect(),
+            indices: order
         }
-        center_of_mass = center_of_mass / number_of_points;
-
-        let node = if points.len() == 1 {
-            // If only one point remains, make a simple leaf node
-            Node::Leaf(points[0])
-        } else {
-            // If more points remain, determine the split value
-            let v = median(points, d, rng);
+    }
+
+    /// The root node, whose hyper-rectangle is the bounding box of all points. Every non-leaf node
+    /// splits its hyper-rectangle in two at a value in one dimension, and every point belongs to
+    /// exactly one leaf.
+    pub fn root(&self) -> NodeRef<'_, M> {
+        self.node(0)
+    }
+
+    fn node(&self, index: usize) -> NodeRef<'_, M> {
+        NodeRef { tree: self, index }
+    }
+
+    /// Build the nodes over the points at the indices in `order`, reordering it so that the points
+    /// of every node are contiguous. The ranges of the leaves are relative to the start of `order`.
+    ///
+    /// The nodes are built depth-first with an explicit stack instead of recursion, so that the
+    /// depth of the tree is not limited by the size of the call stack.
+    fn build(points: &[Point<M>], weights: &[f64], order: &mut [usize], h: HyperRectangle<M>, d: usize, max_leaf_size: usize, rng: &mut impl Rng) -> Vec<Node<M>> {
+        let mut nodes: Vec<Node<M>> = Vec::with_capacity(2 * order.len().div_ceil(max_leaf_size));
+        let mut tasks = vec![Task { start: 0, end: order.len(), h, d, parent: Option::None }];
+
+        while let Option::Some(Task { start, end, h, d, parent }) = tasks.pop() {
+            // Link the node to its parent
+            let index = nodes.len();
+            if let Option::Some((parent, left)) = parent {
+                if let NodeKind::NonLeaf { l, r, .. } = &mut nodes[parent].kind {
+                    *(if left { l } else { r }) = index;
+                }
+            }
+
+            // Determine the cached information that makes this a mrkd-tree instead of a kd-tree
+            let indices = &mut order[start..end];
+            let mut weight = CompensatedSum::default();
+            let mut euclidean_norm_sum = 0.0;
+            let mut sum = CompensatedPoint::default();
+            for &i in indices.iter() {
+                euclidean_norm_sum += points[i].squared_distance(&Point::default()) * weights[i];
+                sum.add(&(points[i] * weights[i]));
+                weight.add(weights[i]);
+            }
+            let weight = weight.value();
+            let center_of_mass = sum.value() / weight;
+            let mut node = Node {
+                h: HyperRectangle(h.0, h.1),
+                number_of_points: indices.len(),
+                weight,
+                center_of_mass,
+                compensation: sum.difference(&(center_of_mass * weight)),
+                euclidean_norm_sum,
+                kind: NodeKind::Leaf(start, end)
+            };
+
+            // If few enough points remain, make a leaf node
+            if indices.len() <= max_leaf_size {
+                nodes.push(node);
+                continue;
+            }
+
+            // If more points remain, skip dimensions in which all points are equal
+            let first = points[indices[0]];
+            let d = (0..M).map(|i| (d + i) % M)
+                .find(|&d| indices.iter().any(|&i| points[i].0[d] != first.0[d]))
+                .unwrap_or(d);
+            // Determine the split value
+            let v = median(points, indices, d, rng);
             // And split the points accordingly
-            let (l, r) = Self::split_points(points, &h, d, v, rng);
-            Node::NonLeaf(NonLeaf { d, v, l, r})
-        };
+            let mid = start + split(points, indices, d, v);
+            let (h1, h2) = h.split(d, v);
+            let new_d = (d + 1) % M;
+            node.kind = NodeKind::NonLeaf { d, v, l: 0, r: 0 };
+            nodes.push(node);
 
-        Self {
-            h,
-            number_of_points,
-            center_of_mass,
-            euclidean_norm_sum,
-            node: Box::new(node)
+            // Build the children of large nodes in parallel, and append them. The median does not
+            // depend on the pivots chosen, so the tree is the same either way.
+            #[cfg(feature = "rayon")]
+            if end - start >= PARALLEL_THRESHOLD {
+                let seeds = SeedSequence::new(rng.gen());
+                let mut rng1 = seeds.child(0).rng();
+                let mut rng2 = seeds.child(1).rng();
+                let (left, right) = order[start..end].split_at_mut(mid - start);
+                let (l, r) = rayon::join(
+                    || Self::build(points, weights, left, h1, new_d, max_leaf_size, &mut rng1),
+                    || Self::build(points, weights, right, h2, new_d, max_leaf_size, &mut rng2)
+                );
+                let l = Self::append(&mut nodes, l, start);
+                let r = Self::append(&mut nodes, r, mid);
+                nodes[index].kind = NodeKind::NonLeaf { d, v, l, r };
+                continue;
+            }
+
+            // Build the left child first, then the right one
+            tasks.push(Task { start: mid, end, h: h2, d: new_d, parent: Option::Some((index, false)) });
+            tasks.push(Task { start, end: mid, h: h1, d: new_d, parent: Option::Some((index, true)) });
+        }
+
+        nodes
+    }
+
+    /// Append the nodes of a subtree whose points start at `offset`, and return the index of its
+    /// root.
+    #[cfg(feature = "rayon")]
+    fn append(nodes: &mut Vec<Node<M>>, subtree: Vec<Node<M>>, offset: usize) -> usize {
+        let root = nodes.len();
+        nodes.extend(subtree.into_iter().map(|mut node| {
+            node.kind = match node.kind {
+                NodeKind::NonLeaf { d, v, l, r } => NodeKind::NonLeaf { d, v, l: l + root, r: r + root },
+                NodeKind::Leaf(start, end) => NodeKind::Leaf(start + offset, end + offset)
+            };
+            node
+        }));
+        root
+    }
+
+    /// The points in the tree, grouped by leaf.
+    pub fn get_points(&self) -> &[Point<M>] {
+        &self.points
+    }
+
+    /// The number of points in the tree.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Whether the tree has no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The number of nodes in the tree, including the leaves.
+    pub fn number_of_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The number of leaf nodes in the tree.
+    ///
+    /// Time complexity: O(n) for n nodes
+    pub fn number_of_leaves(&self) -> usize {
+        self.nodes.iter().filter(|node| matches!(node.kind, NodeKind::Leaf(..))).count()
+    }
+
+    /// The number of edges on the longest path from the root to a leaf, which is 0 if the root
+    /// is a leaf.
+    ///
+    /// Time complexity: O(n) for n nodes
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut stack = vec![(0, 0)];
+        while let Option::Some((index, d)) = stack.pop() {
+            depth = depth.max(d);
+            if let NodeKind::NonLeaf { l, r, .. } = self.nodes[index].kind {
+                stack.push((l, d + 1));
+                stack.push((r, d + 1));
+            }
         }
+        depth
     }
 
-    fn split_points(points: &[Point<M>], h: &HyperRectangle<M>, d: usize, v: f64, rng: &mut impl Rng) -> (Self, Self) {
-        // Determine the next split dimension
-        let new_d = (d + 1) % M;
-        let len = points.len();
+    /// The point in the tree closest to `point`, and its distance.
+    ///
+    /// Time complexity: O(log(r) * M) on average
+    ///
+    /// # Panics
+    ///
+    /// If the tree has no points.
+    pub fn nearest(&self, point: &Point<M>) -> (Point<M>, f64) {
+        let (j, d) = self.k_nearest_positions(point, 1)[0];
+        (self.points[j], d)
+    }
 
-        // Split the hyper-rectangle
-        let (h1, h2) = h.split(d, v);
-        let mut p1 = Vec::with_capacity(len / 2 + 1);
-        let mut p2 = Vec::with_capacity(len / 2 + 1);
+    /// The indices of the `n` points in the tree closest to `point`, in the points the tree was
+    /// built from, with their distances, in ascending order of distance. If `point` is in the tree,
+    /// it is included with distance 0.
+    ///
+    /// Time complexity: O(log(r) * n * M) on average
+    pub fn k_nearest(&self, point: &Point<M>, n: usize) -> Vec<(usize, f64)> {
+        self.k_nearest_positions(point, n).into_iter().map(|(j, d)| (self.indices[j], d)).collect()
+    }
 
-        // Divide the points
-        for point in points {
-            if point.0[d] <= v {
-                p1.push(point.to_owned())
-            } else {
-                p2.push(point.to_owned())
+    /// Distances from `point` to its `n` nearest points in the tree, in ascending order. If `point`
+    /// is in the tree, the first distance is 0.
+    ///
+    /// Time complexity: O(log(r) * n * M) on average
+    pub fn k_nearest_distances(&self, point: &Point<M>, n: usize) -> Vec<f64> {
+        self.k_nearest_positions(point, n).into_iter().map(|(_, d)| d).collect()
+    }
+
+    /// The positions in the points of the tree of the `n` points closest to `point`, with their
+    /// distances.
+    fn k_nearest_positions(&self, point: &Point<M>, n: usize) -> Vec<(usize, f64)> {
+        let mut nearest = Vec::with_capacity(n + 1);
+        Self::k_nearest_search(self.root(), point, n, &mut nearest);
+        nearest
+    }
+
+    /// Indices of the points in the tree within distance `radius` of `point`, in no particular
+    /// order.
+    ///
+    /// Time complexity: O(log(r) + n * M) on average for n points within the radius
+    pub fn within_radius(&self, point: &Point<M>, radius: f64) -> Vec<usize> {
+        let mut indices = Vec::new();
+        Self::radius_search(self.root(), point, radius, &mut indices);
+        indices
+    }
+
+    fn radius_search(node: NodeRef<M>, point: &Point<M>, radius: f64, indices: &mut Vec<usize>) {
+        // Skip hyper-rectangles that are entirely outside the radius
+        if node.h.distance(point) > radius {
+            return;
+        }
+
+        match node.children() {
+            Children::NonLeaf(l, r) => {
+                Self::radius_search(l, point, radius, indices);
+                Self::radius_search(r, point, radius, indices);
+            },
+            Children::Leaf(leaf) => {
+                for (leaf_point, _, index) in leaf.iter() {
+                    if leaf_point.distance(point) <= radius {
+                        indices.push(index);
+                    }
+                }
             }
         }
+    }
+
+    fn k_nearest_search(node: NodeRef<M>, point: &Point<M>, n: usize, nearest: &mut Vec<(usize, f64)>) {
+        // Skip hyper-rectangles that cannot contain a point closer than the current n-th nearest
+        if nearest.len() == n && nearest.last().is_none_or(|&(_, max)| node.h.distance(point) >= max) {
+            return;
+        }
+
+        match node.children() {
+            Children::NonLeaf(l, r) => {
+                // Visit the side of the split containing the point first
+                let (d, v) = node.split().unwrap();
+                let (first, second) = if point.0[d] <= v { (l, r) } else { (r, l) };
+                Self::k_nearest_search(first, point, n, nearest);
+                Self::k_nearest_search(second, point, n, nearest);
+            },
+            Children::Leaf(leaf) => {
+                let start = node.leaf_start();
+                for (j, leaf_point) in leaf.points.iter().enumerate() {
+                    let d = leaf_point.distance(point);
+                    let index = nearest.partition_point(|&(_, x)| x <= d);
+                    if index < n {
+                        nearest.insert(index, (start + j, d));
+                        nearest.truncate(n);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<const M: usize> SpatialIndex<M> for Tree<M> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn nearest(&self, point: &Point<M>) -> (Point<M>, f64) {
+        self.nearest(point)
+    }
 
-        // Make nodes for the two new hyper-rectangles
-        (
-            Self::make_node(&p1, h1, new_d, rng),
-            Self::make_node(&p2, h2, new_d, rng)
-        )
+    fn k_nearest(&self, point: &Point<M>, n: usize) -> Vec<(usize, f64)> {
+        self.k_nearest(point, n)
     }
 
-    pub fn get_points(&self) -> Box<dyn Iterator<Item = &Point<M>> + '_> {
-        self.node.get_points()
+    fn k_nearest_distances(&self, point: &Point<M>, n: usize) -> Vec<f64> {
+        self.k_nearest_distances(point, n)
+    }
+
+    fn within_radius(&self, point: &Point<M>, radius: f64) -> Vec<usize> {
+        self.within_radius(point, radius)
     }
 }
 
@@ -137,65 +521,98 @@ mod tests {
             Point([0.5, 1.5]),
             Point([1.5, 1.5])
         ];
-        let tree = Tree::initialize(&points, &mut rng);
+        let tree = Tree::with_leaf_size(&points, &[1.0; 4], 1, &mut rng);
 
+        let node = |h: (Point<2>, Point<2>), number_of_points, center_of_mass, euclidean_norm_sum, kind| Node {
+            h: HyperRectangle(h.0, h.1),
+            number_of_points,
+            weight: number_of_points as f64,
+            center_of_mass,
+            compensation: Point::default(),
+            euclidean_norm_sum,
+            kind
+        };
         assert_eq!(tree, Tree {
-            h: HyperRectangle(Point([0.5, 0.5]), Point([1.5, 1.5])),
-            number_of_points: 4,
-            center_of_mass: Point([1.0, 1.0]),
-            euclidean_norm_sum: 5.99070478491457,
-            node: Box::new(Node::NonLeaf(NonLeaf {
-                d: 0,
-                v: 0.5,
-                l: Tree {
-                    h: HyperRectangle(Point([0.5, 0.5]), Point([0.5, 1.5])),
-                    number_of_points: 2,
-                    center_of_mass: Point([0.5, 1.0]),
-                    euclidean_norm_sum: 2.2882456112707374,
-                    node: Box::new(Node::NonLeaf(NonLeaf {
-                        d: 1,
-                        v: 0.5,
-                        l: Tree {
-                            h: HyperRectangle(Point([0.5, 0.5]), Point([0.5, 0.5])),
-                            number_of_points: 1,
-                            center_of_mass: Point([0.5, 0.5]),
-                            euclidean_norm_sum: 0.7071067811865476,
-                            node: Box::new(Node::Leaf(Point([0.5, 0.5])))
-                        },
-                        r: Tree {
-                            h: HyperRectangle(Point([0.5, 0.5]), Point([0.5, 1.5])),
-                            number_of_points: 1,
-                            center_of_mass: Point([0.5, 1.5]),
-                            euclidean_norm_sum: 1.5811388300841898,
-                            node: Box::new(Node::Leaf(Point([0.5, 1.5])))
-                        }
-                    }))
-                },
-                r: Tree {
-                    h: HyperRectangle(Point([0.5, 0.5]), Point([1.5, 1.5])),
-                    number_of_points: 2,
-                    center_of_mass: Point([1.5, 1.0]),
-                    euclidean_norm_sum: 3.702459173643832,
-                    node: Box::new(Node::NonLeaf(NonLeaf {
-                        d: 1,
-                        v: 0.5,
-                        l: Tree {
-                            h: HyperRectangle(Point([0.5, 0.5]), Point([1.5, 0.5])),
-                            number_of_points: 1,
-                            center_of_mass: Point([1.5, 0.5]),
-                            euclidean_norm_sum: 1.5811388300841898,
-                            node: Box::new(Node::Leaf(Point([1.5, 0.5])))
-                        },
-                        r: Tree {
-                            h: HyperRectangle(Point([0.5, 0.5]), Point([1.5, 1.5])),
-                            number_of_points: 1,
-                            center_of_mass: Point([1.5, 1.5]),
-                            euclidean_norm_sum: 2.1213203435596424,
-                            node: Box::new(Node::Leaf(Point([1.5, 1.5])))
-                        }
-                    }))
-                }
-            }))
+            nodes: vec![
+                node((Point([0.5, 0.5]), Point([1.5, 1.5])), 4, Point([1.0, 1.0]), 10.0,
+                    NodeKind::NonLeaf { d: 0, v: 0.5, l: 1, r: 4 }),
+                node((Point([0.5, 0.5]), Point([0.5, 1.5])), 2, Point([0.5, 1.0]), 3.0,
+                    NodeKind::NonLeaf { d: 1, v: 0.5, l: 2, r: 3 }),
+                node((Point([0.5, 0.5]), Point([0.5, 0.5])), 1, Point([0.5, 0.5]), 0.5,
+                    NodeKind::Leaf(0, 1)),
+                node((Point([0.5, 0.5]), Point([0.5, 1.5])), 1, Point([0.5, 1.5]), 2.5,
+                    NodeKind::Leaf(1, 2)),
+                node((Point([0.5, 0.5]), Point([1.5, 1.5])), 2, Point([1.5, 1.0]), 7.0,
+                    NodeKind::NonLeaf { d: 1, v: 0.5, l: 5, r: 6 }),
+                node((Point([0.5, 0.5]), Point([1.5, 0.5])), 1, Point([1.5, 0.5]), 2.5,
+                    NodeKind::Leaf(2, 3)),
+                node((Point([0.5, 0.5]), Point([1.5, 1.5])), 1, Point([1.5, 1.5]), 4.5,
+                    NodeKind::Leaf(3, 4))
+            ],
+            points: vec![Point([0.5, 0.5]), Point([0.5, 1.5]), Point([1.5, 0.5]), Point([1.5, 1.5])],
+            weights: vec![1.0; 4],
+            indices: vec![0, 2, 1, 3]
         });
+        assert_eq!(tree.number_of_nodes(), 7);
+        assert_eq!(tree.number_of_leaves(), 4);
+        assert_eq!(tree.depth(), 2);
+    }
+
+    #[test]
+    fn leaf_size() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let points: Vec<Point<2>> = (0..100).map(|i| Point([(i % 13) as f64, (i % 3) as f64])).collect();
+        let tree = Tree::with_leaf_size(&points, &[1.0; 100], 5, &mut rng);
+
+        fn leaves<'a>(node: NodeRef<'a, 2>, found: &mut Vec<Leaf<'a, 2>>) {
+            match node.children() {
+                Children::NonLeaf(l, r) => {
+                    leaves(l, found);
+                    leaves(r, found);
+                },
+                Children::Leaf(leaf) => found.push(leaf)
+            }
+        }
+        let mut found = Vec::new();
+        leaves(tree.root(), &mut found);
+        assert!(found.iter().all(|leaf| !leaf.points.is_empty() && leaf.points.len() <= 5));
+
+        let mut indices: Vec<usize> = found.iter().flat_map(|leaf| leaf.iter().map(|(point, _, i)| {
+            assert_eq!(point, &points[i]);
+            i
+        })).collect();
+        indices.sort();
+        assert_eq!(indices, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn nearest_neighbors() {
+        let points: Vec<Point<2>> = (0..50).map(|i| Point([(i * 7 % 11) as f64, (i * 3 % 13) as f64 + 0.1 * i as f64])).collect();
+        let tree = Tree::new(&points);
+        let query = Point([4.2, 6.1]);
+
+        let mut expected: Vec<(usize, f64)> = points.iter().map(|point| point.distance(&query)).enumerate().collect();
+        expected.sort_by(|a, b| a.1.total_cmp(&b.1));
+        assert_eq!(tree.k_nearest(&query, 5), expected[..5]);
+        assert_eq!(tree.nearest(&query), (points[expected[0].0], expected[0].1));
+
+        let mut within = tree.within_radius(&query, expected[9].1);
+        within.sort();
+        let mut expected: Vec<usize> = expected[..10].iter().map(|&(i, _)| i).collect();
+        expected.sort();
+        assert_eq!(within, expected);
+    }
+
+    #[test]
+    fn k_nearest_distances() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let points = vec![
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5])
+        ];
+        let tree = Tree::initialize(&points, &mut rng);
+        assert_eq!(tree.k_nearest_distances(&Point([0.5, 0.0]), 3), vec![0.5, 1.118033988749895, 1.5]);
     }
 }
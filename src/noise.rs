@@ -0,0 +1,209 @@
+use crate::centers::Centers;
+use crate::error::{check_point_rows, ClusterError};
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::initialization::random_points;
+use crate::point::Point;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Maximum number of iterations, as the learned threshold may keep changing the assignments.
+const MAX_ITER: usize = 300;
+
+/// Distance beyond which a point is assigned to the noise cluster.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoiseThreshold {
+    /// A fixed distance.
+    Fixed(f64),
+    /// A multiple of the mean distance of the non-noise points to their center in the previous
+    /// iteration.
+    Learned(f64)
+}
+
+/// Implements k-means with an additional noise cluster (Davé, 1991): points that are farther than
+/// a threshold from every center are assigned to noise and do not affect the centers.
+///
+/// # References
+///
+/// Davé, R. N. (1991). Characterization and detection of noise in clustering. Pattern Recognition
+///     Letters, 12(11), 657–664. <https://doi.org/10.1016/0167-8655(91)90002-4>
+pub struct NoiseKMeans<const K: usize, const M: usize> {
+    /// The coordinates of the k means.
+    pub centers: [Point<M>; K],
+    /// The index of the center that each point belongs to, or `None` for noise.
+    pub point_centers: Vec<Option<usize>>,
+    /// The threshold distance in the final iteration.
+    pub threshold: f64
+}
+
+impl<const K: usize, const M: usize> NoiseKMeans<K, M> {
+    /// Get k clusters and noise based on `points`, or an error if they have NaN or infinite
+    /// coordinates or there are fewer than k distinct points.
+    pub fn fit(points: &[Point<M>], threshold: NoiseThreshold) -> Result<Self, ClusterError> {
+        Self::new(points, threshold, Option::None)
+    }
+
+    /// Get k clusters and noise based on `points` with a pre-determined random state, or an error,
+    /// see [`NoiseKMeans::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], threshold: NoiseThreshold, random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, threshold, Option::Some(random_state))
+    }
+
+    fn new(points: &[Point<M>], threshold: NoiseThreshold, random_state: Option<u64>) -> Result<Self, ClusterError> {
+        check_point_rows(points.iter().map(|point| point.0), M, K)?;
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        // Initialize centers; a learned threshold starts out including every point
+        let mut centers = random_points::<K, M>(points, &mut rng);
+        let mut point_centers = vec![Option::None; points.len()];
+        let mut distance = match threshold {
+            NoiseThreshold::Fixed(distance) => distance,
+            NoiseThreshold::Learned(_) => f64::INFINITY
+        };
+
+        for _ in 0..MAX_ITER {
+            let mut new_centers = [Point::default(); K];
+            let mut new_counts = [0; K];
+            let mut total_distance = 0.0;
+            let mut different = false;
+
+            // For each data point
+            for (i, point) in points.iter().enumerate() {
+                // Find the closest center
+                let mut min_d = f64::INFINITY;
+                let mut min_c = 0;
+                for (k, center) in centers.iter().enumerate() {
+                    let d = center.squared_distance(point);
+                    if d < min_d {
+                        min_d = d;
+                        min_c = k;
+                    }
+                }
+
+                // Assign the point to noise if it is too far away from every center
+                let min_d = min_d.sqrt();
+                let c = if min_d > distance { Option::None } else { Option::Some(min_c) };
+                if point_centers[i] != c {
+                    point_centers[i] = c;
+                    different = true;
+                }
+
+                // Update the center of mass
+                if let Option::Some(c) = c {
+                    new_centers[c] += *point;
+                    new_counts[c] += 1;
+                    total_distance += min_d;
+                }
+            }
+
+            // Finalize updating the centers of mass, keeping empty centers in place
+            for k in 0..K {
+                if new_counts[k] > 0 {
+                    let new_center = new_centers[k] / new_counts[k];
+                    if centers[k] != new_center {
+                        different = true;
+                    }
+                    centers[k] = new_center;
+                }
+            }
+
+            // Update the learned threshold
+            if let NoiseThreshold::Learned(factor) = threshold {
+                let count: usize = new_counts.iter().sum();
+                let new_distance = if count == 0 { f64::INFINITY } else { factor * total_distance / count as f64 };
+                if new_distance != distance {
+                    different = true;
+                }
+                distance = new_distance;
+            }
+
+            // If nothing changed, return
+            if !different {
+                break;
+            }
+        }
+
+        Ok(NoiseKMeans { centers, point_centers, threshold: distance })
+    }
+}
+
+/// The parameters of [`NoiseKMeans::fit`], to fit it through the [`Fit`] trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoiseKMeansParams<const K: usize> {
+    threshold: NoiseThreshold,
+    random_state: Option<u64>
+}
+
+impl<const K: usize> NoiseKMeansParams<K> {
+    /// Assign the points beyond `threshold` from every center to noise.
+    pub fn new(threshold: NoiseThreshold) -> Self {
+        Self { threshold, random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for NoiseKMeansParams<K> {
+    type Model = NoiseKMeans<K, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<NoiseKMeans<K, M>, ClusterError> {
+        NoiseKMeans::new(points, self.threshold, self.random_state)
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for NoiseKMeans<K, M> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.clone()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.to_vec())
+    }
+}
+
+impl<const K: usize, const M: usize> Predict<M> for NoiseKMeans<K, M> {
+    /// The index of the closest center, or `None` if `point` is farther than the final threshold
+    /// from every center.
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        let k = Centers::new(self.centers).closest(point);
+        if point.distance(&self.centers[k]) > self.threshold { Option::None } else { Option::Some(k) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([1.0, 0.0]),
+            Point([1.0, 1.0]),
+            Point([50.0, 50.0])
+        ];
+        let model = NoiseKMeans::<1, 2>::fit_with_random_state(&points, NoiseThreshold::Fixed(5.0), 2).unwrap();
+        assert_eq!(model.point_centers[4], Option::None);
+        assert_eq!(model.centers, [Point([0.5, 0.5])]);
+
+        let model = NoiseKMeans::<1, 2>::fit_with_random_state(&points, NoiseThreshold::Learned(2.0), 2).unwrap();
+        assert_eq!(model.point_centers[4], Option::None);
+        assert_eq!(model.centers, [Point([0.5, 0.5])]);
+
+        assert_eq!(NoiseKMeans::<6, 2>::fit(&points, NoiseThreshold::Fixed(5.0)).err(), Option::Some(ClusterError::TooFewPoints { clusters: 6, distinct: 5 }));
+    }
+}
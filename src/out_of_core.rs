@@ -0,0 +1,188 @@
+use crate::centers::Centers;
+use crate::error::ClusterError;
+use crate::io::source::PointSource;
+use crate::point::Point;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use std::fmt;
+
+/// The error returned when a model cannot be fitted on a [`PointSource`].
+#[derive(Debug)]
+pub enum OutOfCoreError<E> {
+    /// Reading the source failed.
+    Source(E),
+    /// The points cannot be clustered.
+    Cluster(ClusterError)
+}
+
+impl<E: fmt::Display> fmt::Display for OutOfCoreError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutOfCoreError::Source(error) => error.fmt(f),
+            OutOfCoreError::Cluster(error) => error.fmt(f)
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for OutOfCoreError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OutOfCoreError::Source(error) => Option::Some(error),
+            OutOfCoreError::Cluster(error) => Option::Some(error)
+        }
+    }
+}
+
+/// Implements Lloyd's algorithm (Lloyd, 1982) on points that are read from a [`PointSource`] in
+/// chunks, so only the chunk being read and the k sums of the points of each center are held in
+/// memory. Every iteration reads the whole source once. The first pass also checks the points and
+/// picks k random points as the initial centers with reservoir sampling (Vitter, 1985).
+///
+/// As the labels of the points are not stored, iterations stop once the centers no longer move,
+/// which happens exactly when the labels no longer change.
+///
+/// # References
+///
+/// Lloyd, S. (1982). Least squares quantization in PCM. IEEE Transactions on Information Theory,
+///     28(2), 129–137. <https://doi.org/10.1109/TIT.1982.1056489>
+///
+/// Vitter, J. S. (1985). Random sampling with a reservoir. ACM Transactions on Mathematical
+///     Software, 11(1), 37–57. <https://doi.org/10.1145/3147.3165>
+pub struct OutOfCoreKMeans<const K: usize, const M: usize> {
+    /// The coordinates of the k means.
+    pub centers: [Point<M>; K],
+    /// The number of points in each cluster in the final iteration.
+    pub counts: [usize; K],
+    /// The sum of squared distances of the points to the centers they were assigned to in the
+    /// final iteration, before the centers were updated.
+    pub inertia: f64,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize
+}
+
+impl<const K: usize, const M: usize> OutOfCoreKMeans<K, M> {
+    /// Get k clusters based on the points of `source`, reading it at most `max_iter` + 1 times.
+    ///
+    /// Time complexity: O(r * k * M * i) for i iterations, with O(c * M + k * M) memory for
+    /// chunks of c points
+    pub fn fit<S: PointSource<M> + ?Sized>(source: &S, max_iter: usize) -> Result<Self, OutOfCoreError<S::Error>> {
+        Self::new(source, max_iter, Option::None)
+    }
+
+    /// Get k clusters based on the points of `source` with a pre-determined random state.
+    pub fn fit_with_random_state<S: PointSource<M> + ?Sized>(source: &S, max_iter: usize, random_state: u64) -> Result<Self, OutOfCoreError<S::Error>> {
+        Self::new(source, max_iter, Option::Some(random_state))
+    }
+
+    fn new<S: PointSource<M> + ?Sized>(source: &S, max_iter: usize, random_state: Option<u64>) -> Result<Self, OutOfCoreError<S::Error>> {
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        // Check the points and sample the initial centers in a single pass
+        let mut r = 0;
+        let mut reservoir = [Point::default(); K];
+        let mut error = Option::None;
+        source.for_each_chunk(&mut |chunk| {
+            for point in chunk {
+                if error.is_some() {
+                    return;
+                }
+                if let Option::Some(dimension) = point.0.iter().position(|x| !x.is_finite()) {
+                    error = Option::Some(ClusterError::NonFinite { point: r, dimension });
+                    return;
+                }
+                if r < K {
+                    reservoir[r] = *point;
+                } else {
+                    let i = rng.gen_range(0..=r);
+                    if i < K {
+                        reservoir[i] = *point;
+                    }
+                }
+                r += 1;
+            }
+        }).map_err(OutOfCoreError::Source)?;
+        if let Option::Some(error) = error {
+            return Err(OutOfCoreError::Cluster(error))
+        }
+        if r < K {
+            return Err(OutOfCoreError::Cluster(ClusterError::TooFewPoints { clusters: K, distinct: r }))
+        }
+
+        let mut model = Self { centers: reservoir, counts: [0; K], inertia: 0.0, iterations: 0 };
+        while model.iterations < max_iter {
+            model.iterations += 1;
+            let centers = Centers::new(model.centers);
+            let mut sums = [Point::<M>::default(); K];
+            let mut counts = [0; K];
+            let mut inertia = 0.0;
+            source.for_each_chunk(&mut |chunk| {
+                for point in chunk {
+                    let k = centers.closest(point);
+                    sums[k] += *point;
+                    counts[k] += 1;
+                    inertia += point.squared_distance(&model.centers[k]);
+                }
+            }).map_err(OutOfCoreError::Source)?;
+
+            // Centers without points stay where they are
+            let previous = model.centers;
+            for k in 0..K {
+                if counts[k] > 0 {
+                    model.centers[k] = sums[k] / counts[k];
+                }
+            }
+            model.counts = counts;
+            model.inertia = inertia;
+            if model.centers == previous {
+                break;
+            }
+        }
+
+        Ok(model)
+    }
+
+    /// Index of the center closest to `point`.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn predict(&self, point: &Point<M>) -> usize {
+        Centers::new(self.centers).closest(point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clusterer::{Algorithm, KMeans};
+    use crate::io::csv::{CsvError, CsvReader};
+    use crate::io::source::CsvSource;
+
+    #[test]
+    fn matches_in_memory() {
+        let points = crate::datasets::gaussian_blobs(2000, &[Point([0.0, 0.0]), Point([20.0, 0.0]), Point([0.0, 20.0])], 1.0, 0).0;
+        let model = OutOfCoreKMeans::<3, 2>::fit_with_random_state(&points[..], 100, 0).unwrap();
+        assert!(model.iterations < 100);
+
+        // The converged centers are a fixed point of Lloyd's algorithm in memory
+        let reference = KMeans::<3, 2>::fit_with_centers(&points, model.centers, Algorithm::Naive).unwrap();
+        for (a, b) in reference.centers.iter().zip(&model.centers) {
+            assert!(a.distance(b) < 1e-9);
+        }
+        assert_eq!(model.counts.iter().sum::<usize>(), 2000);
+
+        // Streaming the same points from a file in chunks gives the same centers
+        let path = std::env::temp_dir().join("kmeans_out_of_core.csv");
+        let csv: String = points.iter().map(|point| format!("{},{}\n", point.0[0], point.0[1])).collect();
+        std::fs::write(&path, csv).unwrap();
+        let source = CsvSource::<2>::new(&path, CsvReader::new().header(false)).chunk_size(128);
+        let streamed = OutOfCoreKMeans::<3, 2>::fit_with_random_state(&source, 100, 0);
+        std::fs::write(&path, "1,2\n3,x\n").unwrap();
+        let invalid = OutOfCoreKMeans::<3, 2>::fit(&source, 100);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(streamed.unwrap().centers, model.centers);
+        assert!(matches!(invalid, Err(OutOfCoreError::Source(CsvError::InvalidValue { line: 2, .. }))));
+    }
+}
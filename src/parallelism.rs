@@ -0,0 +1,43 @@
+#[cfg(feature = "rayon")]
+use std::sync::Arc;
+
+/// The threads that a fit may use, with the `rayon` feature, for example to constrain the CPU
+/// usage of a library in a shared service. Without the feature, fits always run on the calling
+/// thread. The fitted model does not depend on the number of threads.
+#[derive(Clone, Debug, Default)]
+pub enum Parallelism {
+    /// Use the thread pool that the fit is called from: the global rayon pool, unless the fit is
+    /// called within [`rayon::ThreadPool::install`].
+    #[default]
+    Default,
+    /// Run on a single thread.
+    Sequential,
+    /// Run on a new thread pool with this number of threads for every fit, or as many threads as
+    /// rayon uses by default for 0.
+    Threads(usize),
+    /// Run on an existing thread pool, shared with the rest of the application.
+    #[cfg(feature = "rayon")]
+    Pool(Arc<rayon::ThreadPool>)
+}
+
+impl Parallelism {
+    /// Run `f` with the parallel work in it on the configured threads. If a new thread pool cannot
+    /// be created, `f` runs on the current one instead.
+    pub(crate) fn install<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        #[cfg(feature = "rayon")]
+        {
+            let threads = match self {
+                Parallelism::Default => return f(),
+                Parallelism::Pool(pool) => return pool.install(f),
+                Parallelism::Sequential => 1,
+                Parallelism::Threads(threads) => *threads
+            };
+            match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+                Ok(pool) => pool.install(f),
+                Err(_) => f()
+            }
+        }
+        #[cfg(not(feature = "rayon"))]
+        f()
+    }
+}
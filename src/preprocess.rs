@@ -0,0 +1,185 @@
+//! Preprocessing of points before clustering: handling missing values and scaling dimensions.
+//! k-means compares dimensions by their distances, so dimensions in large units dominate unless
+//! the points are scaled first.
+
+use crate::point::{get_range, Point};
+use std::borrow::Cow;
+
+/// What to do with points that have missing (NaN) coordinates when fitting, see
+/// [`crate::KMeansBuilder::missing_policy`]. Infinite coordinates are always an error.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum MissingPolicy {
+    /// Refuse to fit, with [`crate::ClusterError::NonFinite`].
+    #[default]
+    Error,
+    /// Leave out points with missing coordinates, so that [`crate::KMeans::point_centers`] only
+    /// covers the remaining points.
+    DropRow,
+    /// Replace missing coordinates by the mean of the dimension, see [`impute`].
+    ImputeMean
+}
+
+/// Replace missing (NaN) coordinates by the mean of the coordinates that are present in the same
+/// dimension, or by 0 if the dimension has no coordinates at all.
+///
+/// Time complexity: O(r * M)
+pub fn impute<const M: usize>(points: &[Point<M>]) -> Vec<Point<M>> {
+    let means = column_means(points);
+    points.iter()
+        .map(|point| Point(std::array::from_fn(|d| if point.0[d].is_nan() { means[d] } else { point.0[d] })))
+        .collect()
+}
+
+/// The mean of every dimension, skipping missing coordinates.
+///
+/// Time complexity: O(r * M)
+pub fn column_means<const M: usize>(points: &[Point<M>]) -> [f64; M] {
+    let mut sums = [0.0; M];
+    let mut counts = [0usize; M];
+    for point in points {
+        for d in 0..M {
+            if !point.0[d].is_nan() {
+                sums[d] += point.0[d];
+                counts[d] += 1;
+            }
+        }
+    }
+    std::array::from_fn(|d| if counts[d] > 0 { sums[d] / counts[d] as f64 } else { 0.0 })
+}
+
+/// Scales every dimension to zero mean and unit variance, so that dimensions with large units do
+/// not dominate the distances.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StandardScaler<const M: usize> {
+    /// The mean of every dimension.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::array"))]
+    pub means: [f64; M],
+    /// The standard deviation of every dimension. Constant dimensions have a deviation of 0 and
+    /// are scaled to 0.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::array"))]
+    pub deviations: [f64; M]
+}
+
+impl<const M: usize> StandardScaler<M> {
+    /// Compute the mean and standard deviation of every dimension of `points`.
+    ///
+    /// Time complexity: O(r * M)
+    pub fn fit(points: &[Point<M>]) -> Self {
+        let r = points.len().max(1) as f64;
+        let means = std::array::from_fn(|d| points.iter().map(|point| point.0[d]).sum::<f64>() / r);
+        let deviations = std::array::from_fn(|d| {
+            (points.iter().map(|point| (point.0[d] - means[d]).powi(2)).sum::<f64>() / r).sqrt()
+        });
+        Self { means, deviations }
+    }
+
+    /// Scale `points`.
+    ///
+    /// Time complexity: O(r * M)
+    pub fn transform(&self, points: &[Point<M>]) -> Vec<Point<M>> {
+        points.iter().map(|point| Point(std::array::from_fn(|d| {
+            if self.deviations[d] > 0.0 { (point.0[d] - self.means[d]) / self.deviations[d] } else { 0.0 }
+        }))).collect()
+    }
+
+    /// Scale `points` back to their original units, for example to interpret fitted centers.
+    ///
+    /// Time complexity: O(r * M)
+    pub fn inverse_transform(&self, points: &[Point<M>]) -> Vec<Point<M>> {
+        points.iter().map(|point| Point(std::array::from_fn(|d| point.0[d] * self.deviations[d] + self.means[d]))).collect()
+    }
+}
+
+/// Scales every dimension to the range from 0 to 1, so that dimensions with large units do not
+/// dominate the distances.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinMaxScaler<const M: usize> {
+    /// The smallest value of every dimension.
+    pub min: Point<M>,
+    /// The largest value of every dimension. Constant dimensions are scaled to 0.
+    pub max: Point<M>
+}
+
+impl<const M: usize> MinMaxScaler<M> {
+    /// Compute the range of every dimension of `points`.
+    ///
+    /// Time complexity: O(r * M)
+    pub fn fit(points: &[Point<M>]) -> Self {
+        let (min, max) = get_range(points);
+        Self { min, max }
+    }
+
+    /// Scale `points`. Points outside the fitted range are scaled outside of the range from 0 to 1.
+    ///
+    /// Time complexity: O(r * M)
+    pub fn transform(&self, points: &[Point<M>]) -> Vec<Point<M>> {
+        points.iter().map(|point| Point(std::array::from_fn(|d| {
+            let range = self.max.0[d] - self.min.0[d];
+            if range > 0.0 { (point.0[d] - self.min.0[d]) / range } else { 0.0 }
+        }))).collect()
+    }
+
+    /// Scale `points` back to their original units, for example to interpret fitted centers.
+    ///
+    /// Time complexity: O(r * M)
+    pub fn inverse_transform(&self, points: &[Point<M>]) -> Vec<Point<M>> {
+        points.iter().map(|point| Point(std::array::from_fn(|d| {
+            point.0[d] * (self.max.0[d] - self.min.0[d]) + self.min.0[d]
+        }))).collect()
+    }
+}
+
+/// Apply `policy` to weighted points, borrowing them when nothing is missing.
+///
+/// Time complexity: O(r * M)
+pub(crate) fn handle_missing<'a, const M: usize>(points: &'a [Point<M>], weights: &'a [f64], policy: MissingPolicy) -> (Cow<'a, [Point<M>]>, Cow<'a, [f64]>) {
+    let missing = |point: &Point<M>| point.0.iter().any(|x| x.is_nan());
+    // Mismatched weights are reported when the points are checked
+    if policy == MissingPolicy::Error || points.len() != weights.len() || !points.iter().any(missing) {
+        return (Cow::Borrowed(points), Cow::Borrowed(weights))
+    }
+
+    match policy {
+        MissingPolicy::DropRow => {
+            let (points, weights) = points.iter()
+                .zip(weights)
+                .filter(|(point, _)| !missing(point))
+                .unzip();
+            (Cow::Owned(points), Cow::Owned(weights))
+        },
+        _ => (Cow::Owned(impute(points)), Cow::Borrowed(weights))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_values() {
+        let points = [Point([1.0, f64::NAN]), Point([3.0, 2.0]), Point([f64::NAN, f64::NAN])];
+        assert_eq!(impute(&points), [Point([1.0, 2.0]), Point([3.0, 2.0]), Point([2.0, 2.0])]);
+
+        let (kept, weights) = handle_missing(&points, &[1.0, 2.0, 3.0], MissingPolicy::DropRow);
+        assert_eq!(kept[..], [Point([3.0, 2.0])]);
+        assert_eq!(weights[..], [2.0]);
+    }
+
+    #[test]
+    fn scalers() {
+        let points = [Point([1.0, 10.0]), Point([3.0, 10.0]), Point([5.0, 10.0])];
+        let standard = StandardScaler::fit(&points);
+        let scaled = standard.transform(&points);
+        assert_eq!(scaled[0].0[1], 0.0);
+        assert!((scaled[2].0[0] - 1.5f64.sqrt()).abs() < 1e-12);
+        for (point, original) in standard.inverse_transform(&scaled).iter().zip(&points) {
+            assert!(point.distance(original) < 1e-12);
+        }
+
+        let min_max = MinMaxScaler::fit(&points);
+        assert_eq!(min_max.transform(&points), [Point([0.0, 0.0]), Point([0.5, 0.0]), Point([1.0, 0.0])]);
+        assert_eq!(min_max.inverse_transform(&min_max.transform(&points)), points);
+    }
+}
@@ -0,0 +1,387 @@
+use crate::error::{check_point_rows, ClusterError};
+use crate::estimator::{Clusterer, Fit};
+use crate::point::Point;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+/// Multiplier of k for the number of points sampled in the initialization phase.
+const SAMPLE_FACTOR: usize = 30;
+/// Multiplier of k for the number of candidate medoids kept after the initialization phase.
+const CANDIDATE_FACTOR: usize = 5;
+/// Clusters with fewer than this fraction of r / k points have a bad medoid.
+const MIN_DEVIATION: f64 = 0.1;
+/// Number of iterations without improvement after which the iterative phase stops.
+const MAX_STALE_ITERATIONS: usize = 10;
+
+/// Implements the PROCLUS projected clustering algorithm (Aggarwal et al., 1999), which finds k
+/// clusters each with their own subset of relevant dimensions.
+///
+/// # References
+///
+/// Aggarwal, C. C., Wolf, J. L., Yu, P. S., Procopiuc, C., & Park, J. S. (1999). Fast algorithms
+///     for projected clustering. Proceedings of the 1999 ACM SIGMOD International Conference on
+///     Management of Data, 61–72. <https://doi.org/10.1145/304182.304188>
+pub struct Proclus<const K: usize, const M: usize> {
+    /// The points used as the center of each cluster.
+    pub medoids: [Point<M>; K],
+    /// The relevant dimensions of each cluster, in ascending order.
+    pub dimensions: [Vec<usize>; K],
+    /// The index of the cluster that each point belongs to, or `None` for outliers.
+    pub point_centers: Vec<Option<usize>>
+}
+
+impl<const K: usize, const M: usize> Proclus<K, M> {
+    /// Get k clusters based on `points`, with on average `l` dimensions per cluster, or an error if
+    /// the points have NaN or infinite coordinates or there are fewer than k distinct points.
+    ///
+    /// # Panics
+    ///
+    /// If `l` is not between 2 and M.
+    pub fn fit(points: &[Point<M>], l: usize) -> Result<Self, ClusterError> {
+        Self::new(points, l, Option::None)
+    }
+
+    /// Get k clusters based on `points` with a pre-determined random state, or an error, see
+    /// [`Proclus::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], l: usize, random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, l, Option::Some(random_state))
+    }
+
+    fn new(points: &[Point<M>], l: usize, random_state: Option<u64>) -> Result<Self, ClusterError> {
+        let r = points.len();
+        assert!((2..=M).contains(&l), "average number of dimensions must be between 2 and {}", M);
+        check_point_rows(points.iter().map(|point| point.0), M, K)?;
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        // Initialization phase: pick well-separated candidate medoids from a random sample
+        let sample = rand::seq::index::sample(&mut rng, r, (SAMPLE_FACTOR * K).min(r)).into_vec();
+        let candidates = Self::greedy(points, &sample, (CANDIDATE_FACTOR * K).min(sample.len()), &mut rng);
+
+        // Iterative phase: replace bad medoids until the objective stops improving
+        let mut medoids = [0; K];
+        for (k, &i) in candidates.choose_multiple(&mut rng, K).enumerate() {
+            medoids[k] = i;
+        }
+        let mut best = medoids;
+        let mut best_objective = f64::INFINITY;
+        let mut best_labels = Vec::new();
+        let mut stale = 0;
+
+        while stale < MAX_STALE_ITERATIONS {
+            let localities = Self::localities(points, &medoids);
+            let dimensions = Self::find_dimensions(points, &medoids, &localities, l);
+            let labels = Self::assign(points, &medoids, &dimensions);
+            let objective = Self::evaluate(points, &labels, &dimensions);
+
+            if objective < best_objective {
+                best = medoids;
+                best_objective = objective;
+                best_labels = labels;
+                stale = 0;
+            } else {
+                stale += 1;
+            }
+
+            // Replace the bad medoids of the best set with random unused candidates
+            let unused: Vec<usize> = candidates.iter().copied().filter(|i| !best.contains(i)).collect();
+            if unused.is_empty() {
+                break;
+            }
+            medoids = best;
+            for (k, &i) in Self::bad_medoids(&best_labels).iter().zip(unused.choose_multiple(&mut rng, K)) {
+                medoids[*k] = i;
+            }
+        }
+
+        // Refinement phase: recompute the dimensions from the clusters and detect outliers
+        let clusters: [Vec<usize>; K] = Self::members(&best_labels);
+        let dimensions = Self::find_dimensions(points, &best, &clusters, l);
+        let labels = Self::assign(points, &best, &dimensions);
+        let point_centers = Self::remove_outliers(points, &best, &dimensions, labels);
+
+        Ok(Proclus {
+            medoids: best.map(|i| points[i]),
+            dimensions,
+            point_centers
+        })
+    }
+
+    /// Greedy(S, k) as described in Section 2.1: repeatedly pick the point farthest from the points
+    /// picked so far.
+    ///
+    /// Time complexity: O(s * n * M)
+    fn greedy(points: &[Point<M>], sample: &[usize], n: usize, rng: &mut impl Rng) -> Vec<usize> {
+        let first = sample[rng.gen_range(0..sample.len())];
+        let mut picked = vec![first];
+        let mut distances: Vec<f64> = sample.iter().map(|&i| points[i].distance(&points[first])).collect();
+
+        while picked.len() < n {
+            let (j, _) = distances.iter().enumerate().fold((0, f64::NEG_INFINITY), |max, (j, &d)| {
+                if d > max.1 { (j, d) } else { max }
+            });
+            let next = sample[j];
+            picked.push(next);
+            for (j, &i) in sample.iter().enumerate() {
+                distances[j] = distances[j].min(points[i].distance(&points[next]));
+            }
+        }
+
+        picked
+    }
+
+    /// The localities L_i: the points within the distance to the nearest other medoid.
+    ///
+    /// Time complexity: O(r * k * M)
+    fn localities(points: &[Point<M>], medoids: &[usize; K]) -> [Vec<usize>; K] {
+        let all: Vec<usize> = (0..M).collect();
+        let mut localities = [(); K].map(|_| Vec::new());
+        for k in 0..K {
+            let delta = (0..K)
+                .filter(|&j| j != k)
+                .map(|j| segmental_distance(&points[medoids[k]], &points[medoids[j]], &all))
+                .fold(f64::INFINITY, f64::min);
+            for (i, point) in points.iter().enumerate() {
+                if segmental_distance(point, &points[medoids[k]], &all) <= delta {
+                    localities[k].push(i);
+                }
+            }
+        }
+        localities
+    }
+
+    /// FindDimensions as described in Section 2.2: pick the k * l dimensions along which the points
+    /// are closest to their medoid relative to the other dimensions, with at least 2 per medoid.
+    ///
+    /// Time complexity: O(r * M + k * M * log(k * M))
+    fn find_dimensions(points: &[Point<M>], medoids: &[usize; K], groups: &[Vec<usize>; K], l: usize) -> [Vec<usize>; K] {
+        let mut z = Vec::with_capacity(K * M);
+        for k in 0..K {
+            // X_ij: the average distance to the medoid along dimension j
+            let mut x = [0.0; M];
+            for &i in &groups[k] {
+                for ((x, a), b) in x.iter_mut().zip(points[i].0).zip(points[medoids[k]].0) {
+                    *x += (a - b).abs();
+                }
+            }
+            let size = groups[k].len().max(1) as f64;
+            x = x.map(|x| x / size);
+
+            // Z_ij: the standardized X_ij
+            let y = x.iter().sum::<f64>() / M as f64;
+            let sigma = (x.iter().map(|x| (x - y).powi(2)).sum::<f64>() / (M - 1) as f64).sqrt();
+            for (d, &x) in x.iter().enumerate() {
+                z.push((if sigma > 0.0 { (x - y) / sigma } else { 0.0 }, k, d));
+            }
+        }
+        z.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Pick the two lowest dimensions per medoid, then the lowest dimensions overall
+        let mut dimensions = [(); K].map(|_| Vec::new());
+        let mut picked = vec![false; z.len()];
+        for (j, &(_, k, d)) in z.iter().enumerate() {
+            if dimensions[k].len() < 2 {
+                dimensions[k].push(d);
+                picked[j] = true;
+            }
+        }
+        let mut remaining = K * l - 2 * K;
+        for (j, &(_, k, d)) in z.iter().enumerate() {
+            if remaining == 0 {
+                break;
+            }
+            if !picked[j] {
+                dimensions[k].push(d);
+                remaining -= 1;
+            }
+        }
+
+        for dims in dimensions.iter_mut() {
+            dims.sort_unstable();
+        }
+        dimensions
+    }
+
+    /// AssignPoints as described in Section 2.2.
+    ///
+    /// Time complexity: O(r * k * M)
+    fn assign(points: &[Point<M>], medoids: &[usize; K], dimensions: &[Vec<usize>; K]) -> Vec<usize> {
+        points.iter().map(|point| {
+            let mut min_d = f64::INFINITY;
+            let mut min_c = 0;
+            for k in 0..K {
+                let d = segmental_distance(point, &points[medoids[k]], &dimensions[k]);
+                if d < min_d {
+                    min_d = d;
+                    min_c = k;
+                }
+            }
+            min_c
+        }).collect()
+    }
+
+    /// EvaluateClusters as described in Section 2.2: the average distance of the points to their
+    /// centroid along the dimensions of their cluster.
+    ///
+    /// Time complexity: O(r * M)
+    fn evaluate(points: &[Point<M>], labels: &[usize], dimensions: &[Vec<usize>; K]) -> f64 {
+        let mut sums = [Point::default(); K];
+        let mut counts = [0; K];
+        for (point, &k) in points.iter().zip(labels) {
+            sums[k] += *point;
+            counts[k] += 1;
+        }
+
+        let mut deviations = [[0.0; M]; K];
+        for (point, &k) in points.iter().zip(labels) {
+            let centroid = sums[k] / counts[k];
+            for ((deviation, x), c) in deviations[k].iter_mut().zip(point.0).zip(centroid.0) {
+                *deviation += (x - c).abs();
+            }
+        }
+
+        let mut total = 0.0;
+        for k in 0..K {
+            if counts[k] > 0 {
+                let w: f64 = dimensions[k].iter().map(|&d| deviations[k][d]).sum::<f64>() / dimensions[k].len() as f64;
+                total += w;
+            }
+        }
+        total / points.len() as f64
+    }
+
+    /// The medoid with the smallest cluster, and any medoid whose cluster is smaller than
+    /// `MIN_DEVIATION * r / k`.
+    fn bad_medoids(labels: &[usize]) -> Vec<usize> {
+        let mut counts = [0; K];
+        for &k in labels {
+            counts[k] += 1;
+        }
+        let threshold = MIN_DEVIATION * labels.len() as f64 / K as f64;
+        let smallest = (0..K).min_by_key(|&k| counts[k]).unwrap_or(0);
+        (0..K).filter(|&k| k == smallest || (counts[k] as f64) < threshold).collect()
+    }
+
+    fn members(labels: &[usize]) -> [Vec<usize>; K] {
+        let mut members = [(); K].map(|_| Vec::new());
+        for (i, &k) in labels.iter().enumerate() {
+            members[k].push(i);
+        }
+        members
+    }
+
+    /// Points outside the sphere of influence of their medoid are outliers. The sphere of influence
+    /// of a medoid is the distance to the nearest other medoid along the dimensions of its cluster.
+    ///
+    /// Time complexity: O(r * M + k^2 * M)
+    fn remove_outliers(points: &[Point<M>], medoids: &[usize; K], dimensions: &[Vec<usize>; K], labels: Vec<usize>) -> Vec<Option<usize>> {
+        let mut influence = [f64::INFINITY; K];
+        for k in 0..K {
+            for j in 0..K {
+                if j != k {
+                    let d = segmental_distance(&points[medoids[k]], &points[medoids[j]], &dimensions[k]);
+                    influence[k] = influence[k].min(d);
+                }
+            }
+        }
+
+        points.iter().zip(labels).map(|(point, k)| {
+            if segmental_distance(point, &points[medoids[k]], &dimensions[k]) <= influence[k] {
+                Option::Some(k)
+            } else {
+                Option::None
+            }
+        }).collect()
+    }
+}
+
+/// Manhattan segmental distance as defined in Section 1.3: the Manhattan distance along
+/// `dimensions`, divided by the number of dimensions.
+///
+/// Time complexity: O(M)
+fn segmental_distance<const M: usize>(a: &Point<M>, b: &Point<M>, dimensions: &[usize]) -> f64 {
+    dimensions.iter().map(|&d| (a.0[d] - b.0[d]).abs()).sum::<f64>() / dimensions.len() as f64
+}
+
+/// The parameters of [`Proclus::fit`], to fit it through the [`Fit`] trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProclusParams<const K: usize> {
+    l: usize,
+    random_state: Option<u64>
+}
+
+impl<const K: usize> ProclusParams<K> {
+    /// Find clusters with on average `l` dimensions per cluster.
+    ///
+    /// # Panics
+    ///
+    /// If `l` is not between 2 and M, when fitting.
+    pub fn new(l: usize) -> Self {
+        Self { l, random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for ProclusParams<K> {
+    type Model = Proclus<K, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<Proclus<K, M>, ClusterError> {
+        Proclus::new(points, self.l, self.random_state)
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for Proclus<K, M> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.clone()
+    }
+
+    /// The medoids.
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.medoids.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        // Two clusters that are only compact in dimensions {0, 1} and {2, 3} respectively
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut points = Vec::new();
+        for _ in 0..50 {
+            points.push(Point([1.0 + rng.gen::<f64>() * 0.1, 1.0 + rng.gen::<f64>() * 0.1, rng.gen::<f64>() * 10.0, rng.gen::<f64>() * 10.0]));
+        }
+        for _ in 0..50 {
+            points.push(Point([rng.gen::<f64>() * 10.0, rng.gen::<f64>() * 10.0, 5.0 + rng.gen::<f64>() * 0.1, 5.0 + rng.gen::<f64>() * 0.1]));
+        }
+
+        let model = Proclus::<2, 4>::fit_with_random_state(&points, 2, 0).unwrap();
+        let first = model.point_centers[0].unwrap();
+        let second = 1 - first;
+        assert_eq!(model.dimensions[first], vec![0, 1]);
+        assert_eq!(model.dimensions[second], vec![2, 3]);
+        assert!(model.point_centers[..50].iter().all(|&k| k == Option::Some(first)));
+        assert!(model.point_centers[50..].iter().all(|&k| k != Option::Some(first)));
+
+        assert_eq!(Proclus::<2, 4>::fit(&points[..1], 2).err(), Option::Some(ClusterError::TooFewPoints { clusters: 2, distinct: 1 }));
+    }
+}
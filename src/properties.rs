@@ -0,0 +1,53 @@
+//! Invariants of fitted models, checked on random synthetic datasets. Failing datasets are shrunk
+//! to a minimal number of points, blobs and spread before they are reported.
+
+use crate::clusterer::{Algorithm, KMeans};
+use crate::datasets::{gaussian_blobs, rings};
+use crate::hyper_rectangle::HyperRectangle;
+use crate::point::{get_range, Point};
+use proptest::prelude::*;
+
+/// Number of random datasets per invariant.
+const CASES: u32 = 32;
+
+/// Gaussian blobs with a random number of points, random centers and a random spread, and the
+/// random state to fit them with.
+fn blobs<const M: usize>() -> impl Strategy<Value = (Vec<Point<M>>, u64)> {
+    let centers = prop::collection::vec(prop::array::uniform::<_, M>(-50.0..50.0).prop_map(Point), 1..6);
+    (10..500_usize, centers, 0.1..10.0, any::<u64>())
+        .prop_map(|(n, centers, std_dev, random_state)| (gaussian_blobs(n, &centers, std_dev, random_state).0, random_state))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+    #[test]
+    fn labels_in_range((points, random_state) in blobs::<3>()) {
+        for algorithm in [Algorithm::Naive, Algorithm::Simple, Algorithm::Blacklist, Algorithm::Elkan, Algorithm::Hamerly, Algorithm::BallTree] {
+            let model = KMeans::<4, 3>::fit_with_random_state(&points, algorithm, random_state).unwrap();
+            prop_assert_eq!(model.point_centers.len(), points.len());
+            prop_assert!(model.point_centers.iter().all(|&k| k < 4), "{:?}", algorithm);
+            prop_assert_eq!(model.predict_many(&points), model.point_centers, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn centers_in_bounds(n in 200..400_usize, noise in 0.0..0.5, random_state in any::<u64>()) {
+        let (points, _) = rings(n, &[1.0, 3.0], noise, random_state);
+        let (min, max) = get_range(&points);
+        // Allow for rounding in the centers of mass
+        let bounds = HyperRectangle(min - Point([1e-9; 2]), max + Point([1e-9; 2]));
+        let model = KMeans::<5, 2>::fit_with_random_state(&points, Algorithm::Simple, random_state).unwrap();
+        prop_assert!(model.centers.iter().all(|center| bounds.contains(center)));
+    }
+
+    #[test]
+    fn simple_matches_naive((points, random_state) in blobs::<2>()) {
+        let naive = KMeans::<3, 2>::fit_with_random_state(&points, Algorithm::Naive, random_state).unwrap();
+        let simple = KMeans::<3, 2>::fit_with_random_state(&points, Algorithm::Simple, random_state).unwrap();
+        prop_assert_eq!(&simple.point_centers, &naive.point_centers);
+        for (a, b) in simple.centers.iter().zip(&naive.centers) {
+            prop_assert!(a.distance(b) < 1e-9);
+        }
+    }
+}
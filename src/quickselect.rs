@@ -2,17 +2,18 @@ use crate::point::Point;
 use rand::Rng;
 
 fn partition<const M: usize>(
-    list: &mut Vec<Point<M>>,
+    points: &[Point<M>],
+    list: &mut [usize],
     left: usize,
     right: usize,
     pivot_index: usize,
     d: usize
 ) -> usize {
-    let pivot_value = list[pivot_index].0[d];
+    let pivot_value = points[list[pivot_index]].0[d];
     list.swap(pivot_index, right);
     let mut store_index = left;
     for i in left..right {
-        if list[i].0[d] < pivot_value {
+        if points[list[i]].0[d] < pivot_value {
             list.swap(store_index, i);
             store_index += 1;
         }
@@ -21,24 +22,25 @@ fn partition<const M: usize>(
     store_index
 }
 
-/// Implementation of the quickselect algorithm for determining the median.
+/// Implementation of the quickselect algorithm for determining the median in dimension `d` of the
+/// points at `indices`, which are reordered in place instead of copying the points.
 /// Adapted from the pseudo-code on Wikipedia (<https://en.wikipedia.org/wiki/Quickselect>)
-pub fn median<const M: usize>(points: &[Point<M>], d: usize, rng: &mut impl Rng) -> f64 {
-    let mut list = points.to_vec();
-
-    let length = list.len();
+///
+/// Time complexity: O(n) on average
+pub fn median<const M: usize>(points: &[Point<M>], indices: &mut [usize], d: usize, rng: &mut impl Rng) -> f64 {
+    let length = indices.len();
     let mut left = 0;
     let mut right = length - 1;
     let k = (length - 1) / 2;
 
     loop {
         if left == right {
-            return list[left].0[d];
+            return points[indices[left]].0[d];
         }
         let pivot_index = left + rand::seq::index::sample(rng, right - left, 1).index(0);
-        let sorted_pivot_index = partition(&mut list, left, right, pivot_index, d);
+        let sorted_pivot_index = partition(points, indices, left, right, pivot_index, d);
         if k == sorted_pivot_index {
-            return list[k].0[d];
+            return points[indices[k]].0[d];
         } else if k < sorted_pivot_index {
             right = sorted_pivot_index - 1;
         } else {
@@ -47,6 +49,28 @@ pub fn median<const M: usize>(points: &[Point<M>], d: usize, rng: &mut impl Rng)
     }
 }
 
+/// Partition `indices` in place into the points left and right of the split, and return the
+/// number of points on the left.
+pub fn split<const M: usize>(points: &[Point<M>], indices: &mut [usize], d: usize, v: f64) -> usize {
+    // When all points are identical, divide them in two halves
+    let first = points[indices[0]];
+    if indices.iter().all(|&i| points[i] == first) {
+        return indices.len() / 2
+    }
+
+    // When the median is also the maximum, put the points equal to it on the right instead
+    let maximum = indices.iter().all(|&i| points[i].0[d] <= v);
+    let mut left = 0;
+    for j in 0..indices.len() {
+        let x = points[indices[j]].0[d];
+        if if maximum { x < v } else { x <= v } {
+            indices.swap(left, j);
+            left += 1;
+        }
+    }
+    left
+}
+
 #[cfg(test)]
 mod tests {
     use crate::point::Point;
@@ -69,7 +93,7 @@ mod tests {
             Point([7.0]),
             Point([3.0]),
         ];
-        assert_eq!(median(&points, 0, &mut rng), 5.0);
+        assert_eq!(median(&points, &mut (0..points.len()).collect::<Vec<_>>(), 0, &mut rng), 5.0);
     }
 
     #[test]
@@ -86,6 +110,6 @@ mod tests {
             Point([7.0]),
             Point([3.0]),
         ];
-        assert_eq!(median(&points, 0, &mut rng), 5.0);
+        assert_eq!(median(&points, &mut (0..points.len()).collect::<Vec<_>>(), 0, &mut rng), 5.0);
     }
 }
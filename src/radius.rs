@@ -0,0 +1,203 @@
+use crate::error::{check_point_rows, ClusterError};
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::point::Point;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+/// Maximum number of Lloyd iterations after each addition of a center.
+const MAX_ITER: usize = 300;
+
+/// Implements k-means where no point may be farther than a maximum radius from its center. Fitting
+/// starts with a single center, and whenever the converged clustering violates the radius, the
+/// point farthest from its center becomes a new center, up to at most `K` centers.
+pub struct RadiusConstrainedKMeans<const K: usize, const M: usize> {
+    /// The coordinates of the means, at most k.
+    pub centers: Vec<Point<M>>,
+    /// The index of the center that each point belongs to.
+    pub point_centers: Vec<usize>,
+    /// Whether every point is within the maximum radius of its center. This is only false when
+    /// `K` centers were not enough.
+    pub satisfied: bool
+}
+
+impl<const K: usize, const M: usize> RadiusConstrainedKMeans<K, M> {
+    /// Get at most k clusters based on `points` with a radius of at most `radius`, or an error if
+    /// k is 0, there are no points, or they have NaN or infinite coordinates.
+    pub fn fit(points: &[Point<M>], radius: f64) -> Result<Self, ClusterError> {
+        Self::new(points, radius, Option::None)
+    }
+
+    /// Get at most k clusters based on `points` with a pre-determined random state, or an error,
+    /// see [`RadiusConstrainedKMeans::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], radius: f64, random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, radius, Option::Some(random_state))
+    }
+
+    fn new(points: &[Point<M>], radius: f64, random_state: Option<u64>) -> Result<Self, ClusterError> {
+        check_point_rows(points.iter().map(|point| point.0), M, K.min(1))?;
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        // Start with a single random center
+        let mut centers = vec![points[rng.gen_range(0..points.len())]];
+
+        loop {
+            let point_centers = Self::lloyd(points, &mut centers);
+
+            // Find the point farthest from its center
+            let (farthest, distance) = points.iter()
+                .zip(&point_centers)
+                .map(|(point, &k)| point.distance(&centers[k]))
+                .enumerate()
+                .fold((0, 0.0), |max, (i, d)| if d > max.1 { (i, d) } else { max });
+
+            // Stop once the radius is satisfied or no more centers can be added
+            if distance <= radius || centers.len() == K {
+                return Ok(RadiusConstrainedKMeans { centers, point_centers, satisfied: distance <= radius })
+            }
+
+            centers.push(points[farthest]);
+        }
+    }
+
+    /// Lloyd's algorithm for a variable number of centers.
+    ///
+    /// Time complexity: O(i * r * k * M)
+    fn lloyd(points: &[Point<M>], centers: &mut [Point<M>]) -> Vec<usize> {
+        let mut point_centers = vec![0; points.len()];
+
+        for _ in 0..MAX_ITER {
+            let mut new_centers = vec![Point::default(); centers.len()];
+            let mut new_counts = vec![0; centers.len()];
+
+            // Assign each point to the closest center
+            for (i, point) in points.iter().enumerate() {
+                let mut min_d = f64::INFINITY;
+                for (k, center) in centers.iter().enumerate() {
+                    let d = center.squared_distance(point);
+                    if d < min_d {
+                        min_d = d;
+                        point_centers[i] = k;
+                    }
+                }
+                new_centers[point_centers[i]] += *point;
+                new_counts[point_centers[i]] += 1;
+            }
+
+            // Update the centers of mass, keeping empty centers in place
+            let mut different = false;
+            for k in 0..centers.len() {
+                if new_counts[k] > 0 {
+                    let new_center = new_centers[k] / new_counts[k];
+                    if centers[k] != new_center {
+                        different = true;
+                    }
+                    centers[k] = new_center;
+                }
+            }
+
+            if !different {
+                break;
+            }
+        }
+
+        point_centers
+    }
+
+    /// Index of the center closest to `point`. When multiple centers are equally close, the one
+    /// with the lowest index is returned.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn predict(&self, point: &Point<M>) -> usize {
+        let mut min_d = f64::INFINITY;
+        let mut min_c = 0;
+        for (k, center) in self.centers.iter().enumerate() {
+            let d = center.squared_distance(point);
+            if d < min_d {
+                min_d = d;
+                min_c = k;
+            }
+        }
+        min_c
+    }
+}
+
+/// The parameters of [`RadiusConstrainedKMeans::fit`], to fit it through the [`Fit`] trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RadiusConstrainedKMeansParams<const K: usize> {
+    radius: f64,
+    random_state: Option<u64>
+}
+
+impl<const K: usize> RadiusConstrainedKMeansParams<K> {
+    /// Keep every point within `radius` of its center, if at most k centers allow it.
+    pub fn new(radius: f64) -> Self {
+        Self { radius, random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for RadiusConstrainedKMeansParams<K> {
+    type Model = RadiusConstrainedKMeans<K, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<RadiusConstrainedKMeans<K, M>, ClusterError> {
+        RadiusConstrainedKMeans::new(points, self.radius, self.random_state)
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for RadiusConstrainedKMeans<K, M> {
+    /// The number of centers that were needed, at most k.
+    fn n_clusters(&self) -> usize {
+        self.centers.len()
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.iter().map(|&k| Option::Some(k)).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.clone())
+    }
+}
+
+impl<const K: usize, const M: usize> Predict<M> for RadiusConstrainedKMeans<K, M> {
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        Option::Some(RadiusConstrainedKMeans::predict(self, point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([1.0, 0.0]),
+            Point([10.0, 0.0]),
+            Point([11.0, 0.0]),
+            Point([30.0, 0.0])
+        ];
+        let model = RadiusConstrainedKMeans::<5, 2>::fit_with_random_state(&points, 1.0, 0).unwrap();
+        assert!(model.satisfied);
+        assert_eq!(model.centers.len(), 3);
+
+        let model = RadiusConstrainedKMeans::<2, 2>::fit_with_random_state(&points, 1.0, 0).unwrap();
+        assert!(!model.satisfied);
+        assert_eq!(model.centers.len(), 2);
+
+        assert_eq!(RadiusConstrainedKMeans::<2, 2>::fit(&[], 1.0).err(), Option::Some(ClusterError::TooFewPoints { clusters: 1, distinct: 0 }));
+        assert_eq!(RadiusConstrainedKMeans::<0, 2>::fit(&points, 1.0).err(), Option::Some(ClusterError::NoClusters));
+    }
+}
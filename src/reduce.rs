@@ -0,0 +1,324 @@
+//! Dimensionality reduction of points before clustering. The mrkd-tree prunes less as the number
+//! of dimensions grows, so mapping points with many dimensions M to points with fewer dimensions
+//! P first makes the accelerated algorithms effective again. Centers fitted on the reduced points
+//! can be mapped back with the inverse transform.
+
+use crate::point::Point;
+use crate::seeding::{standard_normal, SeedSequence};
+
+/// The largest number of iterations to find the principal components.
+const MAX_ITER: usize = 1000;
+
+/// The relative change in the captured variance below which the principal components are
+/// considered converged.
+const TOLERANCE: f64 = 1e-12;
+
+/// Principal component analysis (Pearson, 1901): projects the points onto the P orthogonal
+/// directions along which they vary the most.
+///
+/// The directions are the eigenvectors of the covariance matrix with the largest eigenvalues,
+/// which are found with subspace iteration and a Rayleigh–Ritz step (Golub & Van Loan, 2013,
+/// Section 8.2.4), so that only P vectors of M dimensions are iterated instead of decomposing the
+/// whole covariance matrix.
+///
+/// # References
+///
+/// Pearson, K. (1901). On lines and planes of closest fit to systems of points in space. The
+///     London, Edinburgh, and Dublin Philosophical Magazine and Journal of Science, 2(11),
+///     559–572. <https://doi.org/10.1080/14786440109462720>
+///
+/// Golub, G. H., & Van Loan, C. F. (2013). Matrix computations (4th ed.). Johns Hopkins University
+///     Press.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pca<const M: usize, const P: usize> {
+    /// The mean of the points, which is subtracted before projecting.
+    pub mean: Point<M>,
+    /// The principal axes as unit vectors, in order of decreasing variance.
+    pub components: [Point<M>; P],
+    /// The variance of the points along each of the principal axes.
+    pub explained_variance: [f64; P]
+}
+
+impl<const M: usize, const P: usize> Pca<M, P> {
+    /// Find the P principal components of `points`.
+    ///
+    /// Time complexity: O(r * M^2 + i * P * M^2) for i iterations
+    ///
+    /// # Panics
+    ///
+    /// If P is larger than M.
+    #[allow(clippy::needless_range_loop)]
+    pub fn fit(points: &[Point<M>]) -> Self {
+        assert!(P <= M, "cannot find {} principal components in {} dimensions", P, M);
+        let r = points.len().max(1) as f64;
+        let mean = points.iter().sum::<Point<M>>() / r;
+
+        // The covariance matrix
+        let mut covariance = vec![[0.0; M]; M];
+        for point in points {
+            let x = *point - mean;
+            for i in 0..M {
+                for j in i..M {
+                    covariance[i][j] += x.0[i] * x.0[j] / r;
+                }
+            }
+        }
+        for i in 0..M {
+            for j in 0..i {
+                covariance[i][j] = covariance[j][i];
+            }
+        }
+        let multiply = |v: &Point<M>| Point(std::array::from_fn(|i| covariance[i].iter().zip(&v.0).map(|(a, b)| a * b).sum()));
+
+        // Start from the axes of the dimensions with the largest variance, slightly tilted so that
+        // they are not orthogonal to any of the principal axes
+        let mut order: Vec<usize> = (0..M).collect();
+        order.sort_by(|&a, &b| covariance[b][b].total_cmp(&covariance[a][a]));
+        let mut components: [Point<M>; P] = std::array::from_fn(|p| {
+            let mut axis = Point([1e-3 / (p + 1) as f64; M]);
+            axis[order[p]] = 1.0;
+            axis
+        });
+        orthonormalize(&mut components);
+
+        // Subspace iteration, until the variance captured by the components stops increasing
+        let mut variance = 0.0;
+        for _ in 0..MAX_ITER {
+            let mut next = components.map(|v| multiply(&v));
+            let captured: f64 = components.iter().zip(&next).map(|(v, w)| v.dot(w)).sum();
+            orthonormalize(&mut next);
+            components = next;
+            if (captured - variance).abs() <= TOLERANCE * captured.abs() {
+                break;
+            }
+            variance = captured;
+        }
+
+        // Rotate the components within their span to the eigenvectors, with the Rayleigh–Ritz
+        // step on the projected covariance matrix
+        let projected: Vec<Vec<f64>> = components.iter()
+            .map(|v| {
+                let w = multiply(v);
+                components.iter().map(|u| u.dot(&w)).collect()
+            })
+            .collect();
+        let (values, vectors) = symmetric_eigen(projected);
+        let mut order: Vec<usize> = (0..P).collect();
+        order.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+        let components = std::array::from_fn(|p| {
+            (0..P).map(|q| components[q] * vectors[q][order[p]]).sum()
+        });
+        let explained_variance = std::array::from_fn(|p| values[order[p]].max(0.0));
+
+        Self { mean, components, explained_variance }
+    }
+
+    /// Project `points` onto the principal axes.
+    ///
+    /// Time complexity: O(r * P * M)
+    pub fn transform(&self, points: &[Point<M>]) -> Vec<Point<P>> {
+        points.iter()
+            .map(|point| {
+                let x = *point - self.mean;
+                Point(self.components.map(|component| component.dot(&x)))
+            })
+            .collect()
+    }
+
+    /// Map reduced `points` back to M dimensions, for example to interpret fitted centers. Points
+    /// are mapped to the closest point in the span of the principal axes, so the variance in the
+    /// other directions is lost.
+    ///
+    /// Time complexity: O(r * P * M)
+    pub fn inverse_transform(&self, points: &[Point<P>]) -> Vec<Point<M>> {
+        points.iter()
+            .map(|point| self.components.iter().zip(&point.0).fold(self.mean, |x, (component, &y)| x + *component * y))
+            .collect()
+    }
+}
+
+/// Gaussian random projection: multiplies the points by a random P × M matrix with independent
+/// normally distributed entries with variance 1 / P. By the Johnson–Lindenstrauss lemma (Dasgupta
+/// & Gupta, 2003), distances are preserved up to a factor 1 ± ε with high probability when P is
+/// of the order of log(r) / ε^2, regardless of M. Unlike [`Pca`], this does not look at the
+/// points.
+///
+/// # References
+///
+/// Dasgupta, S., & Gupta, A. (2003). An elementary proof of a theorem of Johnson and
+///     Lindenstrauss. Random Structures & Algorithms, 22(1), 60–65.
+///     <https://doi.org/10.1002/rsa.10073>
+#[derive(Clone, Debug, PartialEq)]
+pub struct GaussianRandomProjection<const M: usize, const P: usize> {
+    /// The rows of the projection matrix.
+    pub components: [Point<M>; P],
+    /// The columns of the pseudo-inverse of the projection matrix, used by
+    /// [`GaussianRandomProjection::inverse_transform`].
+    pub inverse: [Point<M>; P]
+}
+
+impl<const M: usize, const P: usize> GaussianRandomProjection<M, P> {
+    /// Draw a projection matrix with a pre-determined random state.
+    ///
+    /// Time complexity: O(P * M + P^3)
+    pub fn new(random_state: u64) -> Self {
+        let mut rng = SeedSequence::new(random_state).rng();
+        let scale = 1.0 / (P as f64).sqrt();
+        let components: [Point<M>; P] = std::array::from_fn(|_| Point(std::array::from_fn(|_| scale * standard_normal(&mut rng))));
+
+        // The pseudo-inverse is R^T (R R^T)^-1, where R R^T is invertible if R has full rank
+        let gram: Vec<Vec<f64>> = components.iter().map(|a| components.iter().map(|b| a.dot(b)).collect()).collect();
+        let gram_inverse = invert(gram);
+        let inverse = std::array::from_fn(|p| (0..P).map(|q| components[q] * gram_inverse[q][p]).sum());
+        Self { components, inverse }
+    }
+
+    /// Project `points`.
+    ///
+    /// Time complexity: O(r * P * M)
+    pub fn transform(&self, points: &[Point<M>]) -> Vec<Point<P>> {
+        points.iter().map(|point| Point(self.components.map(|component| component.dot(point)))).collect()
+    }
+
+    /// Map projected `points` back to M dimensions with the pseudo-inverse of the projection, for
+    /// example to interpret fitted centers: this gives the point with the smallest norm that
+    /// projects to each point.
+    ///
+    /// Time complexity: O(r * P * M)
+    pub fn inverse_transform(&self, points: &[Point<P>]) -> Vec<Point<M>> {
+        points.iter()
+            .map(|point| self.inverse.iter().zip(&point.0).map(|(column, &y)| *column * y).sum())
+            .collect()
+    }
+}
+
+/// Make `vectors` orthonormal with the modified Gram–Schmidt process. Vectors that are linearly
+/// dependent on the previous ones become 0.
+///
+/// Time complexity: O(P^2 * M)
+fn orthonormalize<const M: usize>(vectors: &mut [Point<M>]) {
+    for i in 0..vectors.len() {
+        for j in 0..i {
+            let projection = vectors[j].dot(&vectors[i]);
+            let previous = vectors[j];
+            vectors[i] -= previous * projection;
+        }
+        let norm = vectors[i].dot(&vectors[i]).sqrt();
+        if norm > 0.0 {
+            vectors[i] /= norm;
+        }
+    }
+}
+
+/// The eigenvalues and eigenvectors of a symmetric matrix with the cyclic Jacobi eigenvalue
+/// algorithm (Golub & Van Loan, 2013, Section 8.5). The eigenvectors are the columns of the
+/// returned matrix.
+///
+/// Time complexity: O(s * n^3) for s sweeps over an n × n matrix
+#[allow(clippy::needless_range_loop)]
+fn symmetric_eigen(mut a: Vec<Vec<f64>>) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let n = a.len();
+    let mut v: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+    for _ in 0..100 {
+        let off: f64 = (0..n).flat_map(|i| (0..n).filter(move |&j| j != i).map(move |j| (i, j))).map(|(i, j)| a[i][j] * a[i][j]).sum();
+        let total: f64 = a.iter().flatten().map(|x| x * x).sum();
+        if off <= f64::EPSILON * f64::EPSILON * total {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q] == 0.0 {
+                    continue;
+                }
+                // Rotate to zero a[p][q]
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for k in 0..n {
+                    let (akp, akq) = (a[k][p], a[k][q]);
+                    a[k][p] = c * akp - s * akq;
+                    a[k][q] = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let (apk, aqk) = (a[p][k], a[q][k]);
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+                for row in v.iter_mut() {
+                    let (vkp, vkq) = (row[p], row[q]);
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    ((0..n).map(|i| a[i][i]).collect(), v)
+}
+
+/// The inverse of a square matrix with Gauss–Jordan elimination with partial pivoting. Singular
+/// matrices give infinite or NaN entries.
+///
+/// Time complexity: O(n^3)
+fn invert(mut a: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    let n = a.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs())).unwrap();
+        a.swap(column, pivot);
+        inverse.swap(column, pivot);
+        let divisor = a[column][column];
+        for j in 0..n {
+            a[column][j] /= divisor;
+            inverse[column][j] /= divisor;
+        }
+        for i in 0..n {
+            if i != column {
+                let factor = a[i][column];
+                for j in 0..n {
+                    a[i][j] -= factor * a[column][j];
+                    inverse[i][j] -= factor * inverse[column][j];
+                }
+            }
+        }
+    }
+    inverse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pca() {
+        // Points on a line in 3 dimensions, with a little noise in another direction, which tilts
+        // the first principal axis slightly
+        let points: Vec<Point<3>> = (0..50)
+            .map(|i| Point([1.0, 2.0, -1.0]) * (i as f64 - 25.0) + Point([1.0, 0.0, 1.0]) * ((i % 3) as f64 * 0.1) + Point([5.0, 0.0, 0.0]))
+            .collect();
+        let pca = Pca::<3, 2>::fit(&points);
+        let axis = Point([1.0, 2.0, -1.0]) / 6f64.sqrt();
+        assert!((pca.components[0].dot(&axis).abs() - 1.0).abs() < 1e-6);
+        assert!(pca.components[0].dot(&pca.components[1]).abs() < 1e-12);
+        assert!(pca.explained_variance[0] > pca.explained_variance[1]);
+        assert!(pca.explained_variance[1] > 0.0);
+
+        // The points lie in the span of the 2 components, so they are recovered
+        for (point, original) in pca.inverse_transform(&pca.transform(&points)).iter().zip(&points) {
+            assert!(point.distance(original) < 1e-9);
+        }
+    }
+
+    #[test]
+    fn random_projection() {
+        let points = [Point([1.0, 2.0, 3.0, 4.0]), Point([-1.0, 0.5, 0.0, 2.0])];
+        let projection = GaussianRandomProjection::<4, 2>::new(0);
+        assert_eq!(projection, GaussianRandomProjection::new(0));
+
+        // Projecting the inverse gives back the projected points
+        let projected = projection.transform(&points);
+        for (point, original) in projection.transform(&projection.inverse_transform(&projected)).iter().zip(&projected) {
+            assert!(point.distance(original) < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::builder::KMeansBuilder;
+use crate::clusterer::KMeans;
+use crate::error::ClusterError;
+use crate::metrics::adjusted_rand_index;
+use crate::point::Point;
+use crate::seeding::SeedSequence;
+use crate::sweep::kmeans_plus_plus_point;
+use std::collections::VecDeque;
+
+/// How the clusters changed when a [`RollingKMeans`] model was refitted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Drift<const K: usize> {
+    /// The distance every center moved while refitting from the previous centers, before drifted
+    /// clusters were reseeded.
+    pub displacement: [f64; K],
+    /// The indices of the clusters that moved more than the drift threshold and were reseeded.
+    pub drifted: Vec<usize>,
+    /// The adjusted Rand index between the labels of the window under the previous and the
+    /// refitted model, see [`crate::metrics::adjusted_rand_index`].
+    pub agreement: f64
+}
+
+/// Maintains a [`KMeans`] model over a sliding window of the most recent points, for streams whose
+/// distribution changes over time.
+///
+/// Every update refits the model on the window, warm-started from the previous centers, so that
+/// stable clusters keep their index and converge in few iterations. Clusters whose center moved
+/// more than the drift threshold are reseeded with k-means++ seeding (Arthur & Vassilvitskii, 2007)
+/// relative to the stable centers, and the model is refitted once more.
+///
+/// # References
+///
+/// Arthur, D., & Vassilvitskii, S. (2007). k-means++: The advantages of careful seeding.
+///     Proceedings of the Eighteenth Annual ACM-SIAM Symposium on Discrete Algorithms, 1027–1035.
+pub struct RollingKMeans<const K: usize, const M: usize> {
+    builder: KMeansBuilder<K, M>,
+    window: usize,
+    drift_threshold: f64,
+    points: VecDeque<Point<M>>,
+    model: Option<KMeans<K, M>>,
+    updates: u64
+}
+
+impl<const K: usize, const M: usize> RollingKMeans<K, M> {
+    /// Maintain a model of the last `window` points, fitted with the settings of `builder`.
+    /// Clusters whose center moves more than `drift_threshold` in one update are reseeded.
+    pub fn new(builder: KMeansBuilder<K, M>, window: usize, drift_threshold: f64) -> Self {
+        Self {
+            builder,
+            window,
+            drift_threshold,
+            points: VecDeque::with_capacity(window),
+            model: Option::None,
+            updates: 0
+        }
+    }
+
+    /// The current model, if it has been fitted.
+    pub fn model(&self) -> Option<&KMeans<K, M>> {
+        self.model.as_ref()
+    }
+
+    /// The points in the window, from oldest to newest.
+    pub fn window(&self) -> impl Iterator<Item = &Point<M>> {
+        self.points.iter()
+    }
+
+    /// Add `points` to the window, dropping the oldest points beyond its size, and refit the model.
+    /// Returns how the clusters changed, or `None` for the first fit.
+    ///
+    /// If the refit fails, for example because the window has fewer than k distinct points, the
+    /// previous model is kept.
+    ///
+    /// Time complexity: O(i * w * k * M) for a window of w points
+    pub fn update(&mut self, points: &[Point<M>]) -> Result<Option<Drift<K>>, ClusterError> {
+        self.points.extend(points);
+        let excess = self.points.len().saturating_sub(self.window);
+        self.points.drain(..excess);
+        let window: Vec<Point<M>> = self.points.iter().copied().collect();
+
+        // Every update reseeds with its own random stream
+        let seeds = SeedSequence::from_random_state(self.builder.random_state).child(self.updates);
+        self.updates += 1;
+
+        let previous = match self.model.take() {
+            Option::Some(previous) => previous,
+            None => {
+                self.model = Option::Some(self.builder.fit(&window)?);
+                return Ok(Option::None)
+            }
+        };
+
+        // Refit from the previous centers
+        let model = match self.builder.clone().initial_centers(previous.centers).fit(&window) {
+            Ok(model) => model,
+            Err(error) => {
+                self.model = Option::Some(previous);
+                return Err(error)
+            }
+        };
+        let displacement: [f64; K] = std::array::from_fn(|k| model.centers[k].distance(&previous.centers[k]));
+        let drifted: Vec<usize> = (0..K).filter(|&k| displacement[k] > self.drift_threshold || displacement[k].is_nan()).collect();
+
+        // Reseed the drifted clusters around the stable centers and refit
+        let model = if drifted.is_empty() {
+            model
+        } else {
+            let mut centers: Vec<Point<M>> = (0..K).filter(|k| !drifted.contains(k)).map(|k| model.centers[k]).collect();
+            let mut rng = seeds.rng();
+            let mut initial_centers = model.centers;
+            for &k in &drifted {
+                initial_centers[k] = kmeans_plus_plus_point(&window, &centers, &mut rng);
+                centers.push(initial_centers[k]);
+            }
+            self.builder.clone().initial_centers(initial_centers).fit(&window).unwrap_or(model)
+        };
+
+        let agreement = adjusted_rand_index(&previous.predict_many(&window), &model.point_centers);
+        self.model = Option::Some(model);
+        Ok(Option::Some(Drift { displacement, drifted, agreement }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update() {
+        let a = [Point([0.0, 0.0]), Point([1.0, 0.0]), Point([10.0, 0.0]), Point([11.0, 0.0])];
+        let b = [Point([0.0, 0.0]), Point([1.0, 0.0]), Point([30.0, 0.0]), Point([31.0, 0.0])];
+        let mut rolling = RollingKMeans::new(KMeans::<2, 2>::builder().random_state(0), 4, 5.0);
+        assert_eq!(rolling.update(&a), Ok(Option::None));
+
+        // The same distribution does not drift
+        let drift = rolling.update(&a).unwrap().unwrap();
+        assert_eq!(drift.displacement, [0.0, 0.0]);
+        assert_eq!(drift.agreement, 1.0);
+
+        // The cluster around x = 10.5 moves to x = 30.5
+        let drift = rolling.update(&b).unwrap().unwrap();
+        let moved = rolling.model().unwrap().predict(&Point([30.0, 0.0]));
+        assert_eq!(drift.drifted, [moved]);
+        assert_eq!(rolling.model().unwrap().centers[moved], Point([30.5, 0.0]));
+        assert_eq!(rolling.window().count(), 4);
+    }
+}
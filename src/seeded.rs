@@ -0,0 +1,228 @@
+use crate::centers::Centers;
+use crate::error::{check_points, ClusterError};
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::point::Point;
+use crate::sweep::kmeans_plus_plus_point;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Maximum number of iterations.
+const MAX_ITER: usize = 300;
+
+/// Implements constrained k-means (Basu, Banerjee & Mooney, 2002), a semi-supervised variant in
+/// which some points are labeled with the cluster they belong to. The labeled points are pinned
+/// to their clusters, and only the unlabeled points are reassigned every iteration. Every point
+/// has a weight in the centers of mass.
+///
+/// The centers start at the weighted means of the labeled points of each cluster. Clusters
+/// without labeled points start at points sampled with k-means++ seeding (Arthur &
+/// Vassilvitskii, 2007), given the other centers.
+///
+/// # References
+///
+/// Basu, S., Banerjee, A., & Mooney, R. J. (2002). Semi-supervised clustering by seeding.
+///     Proceedings of the Nineteenth International Conference on Machine Learning, 27–34.
+///
+/// Arthur, D., & Vassilvitskii, S. (2007). k-means++: The advantages of careful seeding.
+///     Proceedings of the Eighteenth Annual ACM-SIAM Symposium on Discrete Algorithms, 1027–1035.
+pub struct SeededKMeans<const K: usize, const M: usize> {
+    /// The coordinates of the k means.
+    pub centers: [Point<M>; K],
+    /// The index of the center that each point belongs to.
+    pub point_centers: Vec<usize>,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the centers converged within the maximum number of iterations.
+    pub converged: bool
+}
+
+impl<const K: usize, const M: usize> SeededKMeans<K, M> {
+    /// Get k clusters based on weighted and partially labeled `points`, or an error if the number
+    /// of weights or labels differs from the number of points, a weight is negative, NaN or
+    /// infinite, a label is not below k, the points have NaN or infinite coordinates, or there are
+    /// fewer than k distinct points.
+    pub fn fit(points: &[Point<M>], weights: &[f64], labels: &[Option<usize>]) -> Result<Self, ClusterError> {
+        Self::new(points, weights, labels, Option::None)
+    }
+
+    /// Get k clusters based on `points` with a pre-determined random state, or an error, see
+    /// [`SeededKMeans::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], weights: &[f64], labels: &[Option<usize>], random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, weights, labels, Option::Some(random_state))
+    }
+
+    fn new(points: &[Point<M>], weights: &[f64], labels: &[Option<usize>], random_state: Option<u64>) -> Result<Self, ClusterError> {
+        check_points(points, weights, K)?;
+        if labels.len() != points.len() {
+            return Err(ClusterError::LabelMismatch { points: points.len(), labels: labels.len() })
+        }
+        if let Option::Some(point) = labels.iter().position(|label| label.is_some_and(|k| k >= K)) {
+            return Err(ClusterError::InvalidLabel { point })
+        }
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        // Start the labeled clusters at the means of their labeled points
+        let (mut centers, totals) = Self::means(points, weights, labels.iter().copied(), [Point::default(); K]);
+        let mut seeded: Vec<Point<M>> = (0..K).filter(|&k| totals[k] > 0.0).map(|k| centers[k]).collect();
+        for k in 0..K {
+            if totals[k] == 0.0 {
+                centers[k] = kmeans_plus_plus_point(points, &seeded, &mut rng);
+                seeded.push(centers[k]);
+            }
+        }
+
+        let mut point_centers: Vec<usize> = labels.iter().map(|label| label.unwrap_or(usize::MAX)).collect();
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < MAX_ITER {
+            iterations += 1;
+
+            // Reassign the unlabeled points to their closest center
+            let mut different = false;
+            for ((point, label), point_center) in points.iter().zip(labels).zip(point_centers.iter_mut()) {
+                if label.is_some() {
+                    continue;
+                }
+                let mut min_d = f64::INFINITY;
+                let mut min_c = 0;
+                for (k, center) in centers.iter().enumerate() {
+                    let d = point.squared_distance(center);
+                    if d < min_d {
+                        min_d = d;
+                        min_c = k;
+                    }
+                }
+                if *point_center != min_c {
+                    *point_center = min_c;
+                    different = true;
+                }
+            }
+
+            // Move the centers to the weighted means of all their points
+            centers = Self::means(points, weights, point_centers.iter().map(|&k| Option::Some(k)), centers).0;
+
+            if !different {
+                converged = true;
+                break;
+            }
+        }
+
+        Ok(SeededKMeans { centers, point_centers, iterations, converged })
+    }
+
+    /// The weighted means of the points per cluster, keeping the centers of clusters without
+    /// weight at `previous`, and the total weight per cluster.
+    ///
+    /// Time complexity: O(r * M)
+    fn means(points: &[Point<M>], weights: &[f64], clusters: impl Iterator<Item = Option<usize>>, previous: [Point<M>; K]) -> ([Point<M>; K], [f64; K]) {
+        let mut sums = [Point::default(); K];
+        let mut totals = [0.0; K];
+        for ((point, &w), cluster) in points.iter().zip(weights).zip(clusters) {
+            if let Option::Some(k) = cluster {
+                sums[k] += *point * w;
+                totals[k] += w;
+            }
+        }
+        let means = std::array::from_fn(|k| if totals[k] > 0.0 { sums[k] / totals[k] } else { previous[k] });
+        (means, totals)
+    }
+}
+
+/// The parameters of [`SeededKMeans::fit`], to fit it through the [`Fit`] trait: the labels of
+/// the points it will be fitted on, and optionally their weights.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeededKMeansParams<const K: usize> {
+    labels: Vec<Option<usize>>,
+    weights: Option<Vec<f64>>,
+    random_state: Option<u64>
+}
+
+impl<const K: usize> SeededKMeansParams<K> {
+    /// Pin the points with a label to their cluster. Fitting fails unless there is a label for
+    /// every point.
+    pub fn new(labels: Vec<Option<usize>>) -> Self {
+        Self { labels, weights: Option::None, random_state: Option::None }
+    }
+
+    /// Give every point a weight. Defaults to 1 for every point.
+    pub fn weights(mut self, weights: Vec<f64>) -> Self {
+        self.weights = Option::Some(weights);
+        self
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for SeededKMeansParams<K> {
+    type Model = SeededKMeans<K, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<SeededKMeans<K, M>, ClusterError> {
+        match &self.weights {
+            Option::Some(weights) => SeededKMeans::new(points, weights, &self.labels, self.random_state),
+            None => SeededKMeans::new(points, &vec![1.0; points.len()], &self.labels, self.random_state)
+        }
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for SeededKMeans<K, M> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.iter().map(|&k| Option::Some(k)).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.to_vec())
+    }
+}
+
+impl<const K: usize, const M: usize> Predict<M> for SeededKMeans<K, M> {
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        Option::Some(Centers::new(self.centers).closest(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 0.4]),
+            Point([0.0, 2.0]),
+            Point([10.0, 0.0]),
+            Point([10.0, 1.6]),
+            Point([10.0, 2.0])
+        ];
+        // Split the points by height instead of the obvious vertical gap
+        let labels = [Option::Some(0), Option::None, Option::Some(1), Option::Some(0), Option::None, Option::Some(1)];
+        let model = SeededKMeans::<2, 2>::fit_with_random_state(&points, &[1.0; 6], &labels, 0).unwrap();
+        assert_eq!(model.point_centers, [0, 0, 1, 0, 1, 1]);
+        assert_eq!(model.centers[1], Point([20.0 / 3.0, 5.6 / 3.0]));
+        assert!(model.converged);
+
+        let mut weights = [1.0; 6];
+        weights[4] = -1.0;
+        assert_eq!(SeededKMeans::<2, 2>::fit(&points, &weights, &labels).err(), Option::Some(ClusterError::InvalidWeight { point: 4 }));
+        weights[4] = f64::NAN;
+        assert_eq!(SeededKMeans::<2, 2>::fit(&points, &weights, &labels).err(), Option::Some(ClusterError::InvalidWeight { point: 4 }));
+        assert_eq!(SeededKMeans::<2, 2>::fit(&points, &[1.0; 6], &labels[..5]).err(), Option::Some(ClusterError::LabelMismatch { points: 6, labels: 5 }));
+        let mut labels = labels;
+        labels[1] = Option::Some(2);
+        assert_eq!(SeededKMeans::<2, 2>::fit(&points, &[1.0; 6], &labels).err(), Option::Some(ClusterError::InvalidLabel { point: 1 }));
+    }
+}
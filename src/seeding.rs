@@ -0,0 +1,86 @@
+//! Derivation of independent random streams from a single seed, so that stochastic routines give
+//! the same results regardless of the order or the threads in which their parts are run.
+
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Draw a sample from the standard normal distribution with the Box–Muller transform (Box &
+/// Muller, 1958).
+///
+/// # References
+///
+/// Box, G. E. P., & Muller, M. E. (1958). A note on the generation of random normal deviates. The
+///     Annals of Mathematical Statistics, 29(2), 610–611. <https://doi.org/10.1214/aoms/1177706645>
+///
+/// Time complexity: O(1)
+pub(crate) fn standard_normal(rng: &mut impl Rng) -> f64 {
+    // 1 - u lies in (0, 1], so the logarithm is finite
+    let u: f64 = rng.gen();
+    let v: f64 = rng.gen();
+    (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+}
+
+/// The SplitMix64 finalizer (Steele, Lea & Flood, 2014), which maps consecutive inputs to
+/// statistically independent outputs.
+///
+/// # References
+///
+/// Steele, G. L., Lea, D., & Flood, C. H. (2014). Fast splittable pseudorandom number generators.
+///     Proceedings of the 2014 ACM International Conference on Object Oriented Programming Systems
+///     Languages & Applications, 453–472. <https://doi.org/10.1145/2660193.2660195>
+///
+/// Time complexity: O(1)
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(GOLDEN_GAMMA);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// A seed from which a tree of child seeds can be derived, for example one per restart, and one
+/// per chunk within each restart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SeedSequence (pub u64);
+
+impl SeedSequence {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// A seed sequence from system entropy.
+    pub fn from_entropy() -> Self {
+        Self(rand::random())
+    }
+
+    /// Use `random_state` if it is given, and system entropy otherwise.
+    pub fn from_random_state(random_state: Option<u64>) -> Self {
+        random_state.map_or_else(Self::from_entropy, Self::new)
+    }
+
+    /// The `i`th child sequence, which does not depend on any of the other children.
+    ///
+    /// Time complexity: O(1)
+    pub fn child(&self, i: u64) -> Self {
+        Self(splitmix64(self.0 ^ splitmix64(i.wrapping_mul(GOLDEN_GAMMA))))
+    }
+
+    /// A random number generator seeded by this sequence.
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn children() {
+        let seeds = SeedSequence::new(0);
+        assert_eq!(seeds.child(1), SeedSequence::new(0).child(1));
+        assert_ne!(seeds.child(0), seeds.child(1));
+        assert_ne!(seeds.child(0).child(1), seeds.child(1).child(0));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::clusterer::KMeans;
+use serde::de::{self, DeserializeOwned, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeTuple, Serializer};
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// (De)serialize arrays of any length as tuples, since serde only implements arrays up to 32
+/// elements. Use with `#[serde(with = "crate::serialization::array")]`.
+pub(crate) mod array {
+    use super::*;
+
+    pub fn serialize<S: Serializer, T: Serialize, const N: usize>(array: &[T; N], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(N)?;
+        for element in array {
+            tuple.serialize_element(element)?;
+        }
+        tuple.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, T: Deserialize<'de>, const N: usize>(deserializer: D) -> Result<[T; N], D::Error> {
+        deserializer.deserialize_tuple(N, ArrayVisitor(PhantomData))
+    }
+
+    struct ArrayVisitor<T, const N: usize>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>, const N: usize> Visitor<'de> for ArrayVisitor<T, N> {
+        type Value = [T; N];
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(formatter, "an array of length {}", N)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut elements: Vec<T> = Vec::with_capacity(N);
+            for i in 0..N {
+                elements.push(seq.next_element()?.ok_or_else(|| de::Error::invalid_length(i, &self))?);
+            }
+            elements.try_into().map_err(|_| de::Error::invalid_length(N, &self))
+        }
+    }
+}
+
+impl<const K: usize, const M: usize, D: Serialize + DeserializeOwned> KMeans<K, M, D> {
+    /// Write the fitted model to `path` in a compact binary format.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(writer, self).map_err(|error| into_io_error(*error))
+    }
+
+    /// Read a model written by [`KMeans::save`] from `path`. The number of clusters, dimensions
+    /// and the metric have to match those of the saved model.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        bincode::deserialize_from(reader).map_err(|error| into_io_error(*error))
+    }
+}
+
+fn into_io_error(error: bincode::ErrorKind) -> io::Error {
+    match error {
+        bincode::ErrorKind::Io(error) => error,
+        error => io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clusterer::Algorithm;
+    use crate::data::{Encoding, FrequencyEncoder, MeanEmbedding, OneHotEncoder};
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn save_load() {
+        let points = [
+            Point([0.5, 0.5]),
+            Point([1.5, 0.5]),
+            Point([0.5, 1.5]),
+            Point([1.5, 1.5])
+        ];
+        let model = KMeans::<2, 2>::fit_with_random_state(&points, Algorithm::Simple, 0).unwrap();
+
+        let path = std::env::temp_dir().join("kmeans_save_load.bin");
+        model.save(&path).unwrap();
+        let loaded = KMeans::<2, 2>::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.centers, model.centers);
+        assert_eq!(loaded.point_centers, model.point_centers);
+        assert_eq!(loaded.inertia(), model.inertia());
+        assert_eq!(loaded.predict(&Point([1.4, 0.6])), model.predict(&Point([1.4, 0.6])));
+    }
+
+    #[test]
+    fn encoder_round_trip() {
+        let encoder = FrequencyEncoder::fit(&["a", "b", "a"], Encoding::Frequency);
+        let bytes = bincode::serialize(&encoder).unwrap();
+        let loaded: FrequencyEncoder = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(loaded, encoder);
+        assert_eq!(loaded.transform_many(&["a", "c"]), encoder.transform_many(&["a", "c"]));
+
+        let encoder = OneHotEncoder::fit(&["a", "b", "a"], 1.0);
+        let loaded: OneHotEncoder = bincode::deserialize(&bincode::serialize(&encoder).unwrap()).unwrap();
+        assert_eq!(loaded, encoder);
+
+        let embedding = MeanEmbedding::fit(&["a", "b", "a"], &[Point([0.0]), Point([1.0]), Point([2.0])]).unwrap();
+        let loaded: MeanEmbedding<1> = bincode::deserialize(&bincode::serialize(&embedding).unwrap()).unwrap();
+        assert_eq!(loaded, embedding);
+    }
+}
@@ -0,0 +1,111 @@
+//! Distance kernels that use AVX instructions when the `simd` feature is enabled and the CPU
+//! supports them, and fall back to scalar loops otherwise.
+
+use crate::point::Point;
+
+/// Number of dimensions from which a single squared distance is computed 4 dimensions at a time.
+/// This changes the order in which the terms are added, so it can round differently from the
+/// scalar sum.
+pub(crate) const SIMD_DIMENSIONS: usize = 16;
+
+/// Whether the AVX kernels can be used.
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn avx() -> bool {
+    is_x86_feature_detected!("avx")
+}
+
+/// The squared distance between `a` and `b`, summed in 4 lanes over the dimensions.
+///
+/// Time complexity: O(M)
+pub(crate) fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if avx() {
+        // Safety: the CPU supports AVX
+        return unsafe { avx::squared_distance(a, b) }
+    }
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+/// The squared distances from `point` to each of `others`, written to `distances`.
+///
+/// With AVX, 4 of the others are handled at once, one in each lane, so every distance is still
+/// summed in the order of the dimensions, and the results are identical to those of
+/// [`Point::squared_distance`] for fewer than [`SIMD_DIMENSIONS`] dimensions.
+///
+/// Time complexity: O(k * M) for k others
+pub(crate) fn squared_distances<const M: usize>(point: &Point<M>, others: &[Point<M>], distances: &mut [f64]) {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if M < SIMD_DIMENSIONS && avx() {
+        // Safety: the CPU supports AVX
+        return unsafe { avx::squared_distances(point, others, distances) }
+    }
+    for (distance, other) in distances.iter_mut().zip(others) {
+        *distance = point.squared_distance(other);
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod avx {
+    use crate::point::Point;
+    use std::arch::x86_64::*;
+
+    #[target_feature(enable = "avx")]
+    pub(super) unsafe fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+        let n = a.len().min(b.len());
+        let mut sum = _mm256_setzero_pd();
+        for i in (0..n / 4).map(|i| 4 * i) {
+            let difference = _mm256_sub_pd(_mm256_loadu_pd(a.as_ptr().add(i)), _mm256_loadu_pd(b.as_ptr().add(i)));
+            sum = _mm256_add_pd(sum, _mm256_mul_pd(difference, difference));
+        }
+        let mut lanes = [0.0; 4];
+        _mm256_storeu_pd(lanes.as_mut_ptr(), sum);
+
+        // Add the remaining dimensions one by one
+        let mut total = (lanes[0] + lanes[1]) + (lanes[2] + lanes[3]);
+        for i in n / 4 * 4..n {
+            total += (a[i] - b[i]) * (a[i] - b[i]);
+        }
+        total
+    }
+
+    #[target_feature(enable = "avx")]
+    pub(super) unsafe fn squared_distances<const M: usize>(point: &Point<M>, others: &[Point<M>], distances: &mut [f64]) {
+        let groups = others.chunks_exact(4);
+        let rest = groups.remainder();
+        for (group, output) in groups.zip(distances.chunks_exact_mut(4)) {
+            let mut sum = _mm256_setzero_pd();
+            for d in 0..M {
+                let coordinates = _mm256_set_pd(group[3].0[d], group[2].0[d], group[1].0[d], group[0].0[d]);
+                let difference = _mm256_sub_pd(_mm256_set1_pd(point.0[d]), coordinates);
+                sum = _mm256_add_pd(sum, _mm256_mul_pd(difference, difference));
+            }
+            _mm256_storeu_pd(output.as_mut_ptr(), sum);
+        }
+
+        let start = others.len() - rest.len();
+        for (distance, other) in distances[start..].iter_mut().zip(rest) {
+            *distance = point.squared_distance(other);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squared_distances() {
+        let point = Point([0.5, -1.0, 2.0]);
+        let others: Vec<Point<3>> = (0..7).map(|i| Point([i as f64 * 0.3, 1.0 / (i + 1) as f64, -(i as f64)])).collect();
+        let mut distances = [0.0; 7];
+        super::squared_distances(&point, &others, &mut distances);
+        for (distance, other) in distances.iter().zip(&others) {
+            assert_eq!(*distance, point.squared_distance(other));
+        }
+
+        let a: Vec<f64> = (0..19).map(|i| i as f64 * 0.5).collect();
+        let b: Vec<f64> = (0..19).map(|i| 1.0 - i as f64).collect();
+        let expected: f64 = a.iter().zip(&b).map(|(x, y)| (x - y) * (x - y)).sum();
+        assert!((squared_distance(&a, &b) - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::error::{check_point_rows, ClusterError};
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::point::Point;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+/// The learning rate at the start of training, which decreases linearly to 0.
+const LEARNING_RATE: f64 = 0.5;
+
+/// Implements a self-organizing map (Kohonen, 1982): a grid of ROWS × COLS nodes with a weight
+/// vector each, trained such that nodes that are close on the grid have similar weight vectors.
+/// Like k-means, every point belongs to the node with the closest weight vector, but the grid
+/// also preserves the topology of the points, for example to visualize high-dimensional data in
+/// 2 dimensions.
+///
+/// The points are presented one by one, in a random order in every epoch. Each moves the
+/// closest node, and to a lesser extent its neighbors on the grid, towards itself, weighted by
+/// a Gaussian of the distance on the grid. Both the learning rate and the width of the Gaussian
+/// decrease over time: the learning rate linearly from 0.5 to 0, and the width exponentially from
+/// half the size of the grid to 1.
+///
+/// # References
+///
+/// Kohonen, T. (1982). Self-organized formation of topologically correct feature maps.
+///     Biological Cybernetics, 43(1), 59–69. <https://doi.org/10.1007/BF00337288>
+pub struct Som<const ROWS: usize, const COLS: usize, const M: usize> {
+    /// The weight vector of every node, by row and column.
+    pub nodes: [[Point<M>; COLS]; ROWS],
+    /// The row and column of the node that each point belongs to.
+    pub point_nodes: Vec<(usize, usize)>
+}
+
+impl<const ROWS: usize, const COLS: usize, const M: usize> Som<ROWS, COLS, M> {
+    /// Train a map on `points`, or get an error if the grid has no nodes, there are no points or
+    /// they have NaN or infinite coordinates.
+    pub fn fit(points: &[Point<M>], epochs: usize) -> Result<Self, ClusterError> {
+        Self::new(points, epochs, Option::None)
+    }
+
+    /// Train a map on `points` with a pre-determined random state, or get an error, see
+    /// [`Som::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], epochs: usize, random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, epochs, Option::Some(random_state))
+    }
+
+    fn new(points: &[Point<M>], epochs: usize, random_state: Option<u64>) -> Result<Self, ClusterError> {
+        // Every node starts at a point, so a single distinct point suffices
+        check_point_rows(points.iter().map(|point| point.0), M, (ROWS * COLS).min(1))?;
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        // Start every node at a random point
+        let mut nodes = [[Point::default(); COLS]; ROWS];
+        for node in nodes.iter_mut().flatten() {
+            *node = points[rng.gen_range(0..points.len())];
+        }
+
+        let initial_width = ROWS.max(COLS) as f64 / 2.0;
+        let steps = (epochs * points.len()) as f64;
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        let mut t = 0.0;
+        for _ in 0..epochs {
+            order.shuffle(&mut rng);
+            for &i in &order {
+                let progress = t / steps;
+                let learning_rate = LEARNING_RATE * (1.0 - progress);
+                let width = initial_width.powf(1.0 - progress).max(1.0);
+
+                // Move the best matching node and its neighbors towards the point
+                let point = points[i];
+                let (row, col) = Self::closest(&nodes, &point);
+                for (r, nodes) in nodes.iter_mut().enumerate() {
+                    for (c, node) in nodes.iter_mut().enumerate() {
+                        let grid_d = (r as f64 - row as f64).powi(2) + (c as f64 - col as f64).powi(2);
+                        let neighborhood = (-grid_d / (2.0 * width * width)).exp();
+                        *node += (point - *node) * (learning_rate * neighborhood);
+                    }
+                }
+                t += 1.0;
+            }
+        }
+
+        let point_nodes = points.iter().map(|point| Self::closest(&nodes, point)).collect();
+        Ok(Som { nodes, point_nodes })
+    }
+
+    /// The row and column of the node closest to `point`.
+    ///
+    /// Time complexity: O(ROWS * COLS * M)
+    pub fn predict(&self, point: &Point<M>) -> (usize, usize) {
+        Self::closest(&self.nodes, point)
+    }
+
+    /// The mean distance of `points` to their closest nodes, which measures how well the map
+    /// represents them.
+    ///
+    /// Time complexity: O(r * ROWS * COLS * M)
+    pub fn quantization_error(&self, points: &[Point<M>]) -> f64 {
+        let total: f64 = points.iter()
+            .map(|point| {
+                let (row, col) = self.predict(point);
+                point.distance(&self.nodes[row][col])
+            })
+            .sum();
+        total / points.len() as f64
+    }
+
+    fn closest(nodes: &[[Point<M>; COLS]; ROWS], point: &Point<M>) -> (usize, usize) {
+        let mut min_d = f64::INFINITY;
+        let mut min_node = (0, 0);
+        for (r, row) in nodes.iter().enumerate() {
+            for (c, node) in row.iter().enumerate() {
+                let d = point.squared_distance(node);
+                if d < min_d {
+                    min_d = d;
+                    min_node = (r, c);
+                }
+            }
+        }
+        min_node
+    }
+}
+
+/// The parameters of [`Som::fit`], to train a map through the [`Fit`] trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SomParams<const ROWS: usize, const COLS: usize> {
+    epochs: usize,
+    random_state: Option<u64>
+}
+
+impl<const ROWS: usize, const COLS: usize> SomParams<ROWS, COLS> {
+    /// Present every point `epochs` times.
+    pub fn new(epochs: usize) -> Self {
+        Self { epochs, random_state: Option::None }
+    }
+
+    /// Train with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize, const M: usize> Fit<M> for SomParams<ROWS, COLS> {
+    type Model = Som<ROWS, COLS, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<Som<ROWS, COLS, M>, ClusterError> {
+        Som::new(points, self.epochs, self.random_state)
+    }
+}
+
+/// The clusters of a map are its nodes, numbered row by row: node (row, col) is cluster
+/// row * COLS + col.
+impl<const ROWS: usize, const COLS: usize, const M: usize> Clusterer<M> for Som<ROWS, COLS, M> {
+    fn n_clusters(&self) -> usize {
+        ROWS * COLS
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_nodes.iter().map(|&(row, col)| Option::Some(row * COLS + col)).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.nodes.iter().flatten().copied().collect())
+    }
+}
+
+impl<const ROWS: usize, const COLS: usize, const M: usize> Predict<M> for Som<ROWS, COLS, M> {
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        let (row, col) = Som::predict(self, point);
+        Option::Some(row * COLS + col)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        // Points on a line are mapped to a chain of nodes in order
+        let points: Vec<Point<2>> = (0..100).map(|i| Point([i as f64, 0.0])).collect();
+        let som = Som::<1, 5, 2>::fit_with_random_state(&points, 20, 0).unwrap();
+        let xs: Vec<f64> = som.nodes[0].iter().map(|node| node.0[0]).collect();
+        assert!(xs.windows(2).all(|pair| pair[0] < pair[1]) || xs.windows(2).all(|pair| pair[0] > pair[1]));
+        assert_eq!(som.point_nodes[0], (0, if xs[0] < xs[4] { 0 } else { 4 }));
+        assert!(som.quantization_error(&points) < 10.0);
+    }
+
+    #[test]
+    fn fit() {
+        let points = [Point([0.0, 0.0]), Point([1.0, f64::NAN])];
+        assert_eq!(Som::<2, 2, 2>::fit(&points, 1).err(), Option::Some(ClusterError::NonFinite { point: 1, dimension: 1 }));
+        assert_eq!(Som::<2, 2, 2>::fit(&[], 1).err(), Option::Some(ClusterError::TooFewPoints { clusters: 1, distinct: 0 }));
+        assert_eq!(Som::<0, 2, 2>::fit(&points[..1], 1).err(), Option::Some(ClusterError::NoClusters));
+    }
+}
@@ -0,0 +1,199 @@
+use crate::error::{check_point_rows, ClusterError};
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::initialization::random_points;
+use crate::point::Point;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Maximum number of iterations.
+const MAX_ITER: usize = 300;
+
+/// Implements spherical k-means (Dhillon & Modha, 2001), which clusters points by direction, for
+/// example text embeddings. The points are assigned to the center with the largest cosine
+/// similarity, and every center is the sum of its points renormalized to unit length.
+///
+/// The points are normalized before fitting; points at the origin have no direction, and are
+/// assigned to the first center without moving it.
+///
+/// # References
+///
+/// Dhillon, I. S., & Modha, D. S. (2001). Concept decompositions for large sparse text data using
+///     clustering. Machine Learning, 42(1), 143–175. <https://doi.org/10.1023/A:1007612920971>
+pub struct SphericalKMeans<const K: usize, const M: usize> {
+    /// The unit-length centers.
+    pub centers: [Point<M>; K],
+    /// The index of the center that each point belongs to.
+    pub point_centers: Vec<usize>,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the centers converged within the maximum number of iterations.
+    pub converged: bool
+}
+
+impl<const K: usize, const M: usize> SphericalKMeans<K, M> {
+    /// Get k clusters based on the directions of `points`, or an error if they have NaN or
+    /// infinite coordinates or there are fewer than k distinct points.
+    pub fn fit(points: &[Point<M>]) -> Result<Self, ClusterError> {
+        Self::new(points, Option::None)
+    }
+
+    /// Get k clusters based on the directions of `points` with a pre-determined random state, or
+    /// an error, see [`SphericalKMeans::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, Option::Some(random_state))
+    }
+
+    fn new(points: &[Point<M>], random_state: Option<u64>) -> Result<Self, ClusterError> {
+        check_point_rows(points.iter().map(|point| point.0), M, K)?;
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        let points: Vec<Point<M>> = points.iter().map(normalize).collect();
+        let mut centers = random_points::<K, M>(&points, &mut rng);
+        let mut point_centers = vec![usize::MAX; points.len()];
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < MAX_ITER {
+            iterations += 1;
+
+            // Assign each point to the most similar center
+            let mut different = false;
+            for (point, point_center) in points.iter().zip(point_centers.iter_mut()) {
+                let k = Self::most_similar(&centers, point);
+                if *point_center != k {
+                    *point_center = k;
+                    different = true;
+                }
+            }
+
+            // Move the centers to the directions of the sums of their points, keeping empty
+            // centers in place
+            let mut sums = [Point::default(); K];
+            for (point, &k) in points.iter().zip(&point_centers) {
+                sums[k] += *point;
+            }
+            for k in 0..K {
+                if sums[k] != Point::default() {
+                    centers[k] = normalize(&sums[k]);
+                }
+            }
+
+            if !different {
+                converged = true;
+                break;
+            }
+        }
+
+        Ok(SphericalKMeans { centers, point_centers, iterations, converged })
+    }
+
+    /// Index of the center with the largest cosine similarity to a unit-length point.
+    ///
+    /// Time complexity: O(k * M)
+    fn most_similar(centers: &[Point<M>; K], point: &Point<M>) -> usize {
+        let mut max_similarity = f64::NEG_INFINITY;
+        let mut max_c = 0;
+        for (k, center) in centers.iter().enumerate() {
+            let similarity: f64 = point.0.iter().zip(center.0).map(|(x, c)| x * c).sum();
+            if similarity > max_similarity {
+                max_similarity = similarity;
+                max_c = k;
+            }
+        }
+        max_c
+    }
+
+    /// Index of the center with the largest cosine similarity to `point`.
+    ///
+    /// Time complexity: O(k * M)
+    pub fn predict(&self, point: &Point<M>) -> usize {
+        Self::most_similar(&self.centers, &normalize(point))
+    }
+}
+
+/// The point scaled to unit length, or the origin itself.
+///
+/// Time complexity: O(M)
+fn normalize<const M: usize>(point: &Point<M>) -> Point<M> {
+    let norm = point.distance(&Point::default());
+    if norm > 0.0 { *point / norm } else { *point }
+}
+
+/// The parameters of [`SphericalKMeans::fit`], to fit it through the [`Fit`] trait.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SphericalKMeansParams<const K: usize> {
+    random_state: Option<u64>
+}
+
+impl<const K: usize> SphericalKMeansParams<K> {
+    /// Fit spherical k-means with k = K.
+    pub fn new() -> Self {
+        Self { random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for SphericalKMeansParams<K> {
+    type Model = SphericalKMeans<K, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<SphericalKMeans<K, M>, ClusterError> {
+        SphericalKMeans::new(points, self.random_state)
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for SphericalKMeans<K, M> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.iter().map(|&k| Option::Some(k)).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.to_vec())
+    }
+}
+
+impl<const K: usize, const M: usize> Predict<M> for SphericalKMeans<K, M> {
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        Option::Some(SphericalKMeans::predict(self, point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        // Unit vectors in two directions, at different distances from each other
+        let points: Vec<Point<3>> = (0..10).map(|i| {
+            let angle = 0.05 * (i % 5) as f64 + if i < 5 { 0.0 } else { 1.5 };
+            Point([angle.cos(), angle.sin(), 0.0])
+        }).collect();
+        let model = SphericalKMeans::<2, 3>::fit_with_random_state(&points, 0).unwrap();
+        assert!(model.point_centers[..5].iter().all(|&k| k == model.point_centers[0]));
+        assert!(model.point_centers[5..].iter().all(|&k| k == model.point_centers[5]));
+        assert_ne!(model.point_centers[0], model.point_centers[5]);
+        assert!(model.converged);
+        for center in &model.centers {
+            assert!((center.distance(&Point::default()) - 1.0).abs() < 1e-12);
+        }
+
+        // Only the direction of a point matters
+        assert_eq!(model.predict(&(points[7] * 100.0)), model.point_centers[7]);
+
+        assert_eq!(SphericalKMeans::<2, 3>::fit(&[Point([1.0, f64::NAN, 0.0])]).err(), Option::Some(ClusterError::NonFinite { point: 0, dimension: 1 }));
+    }
+}
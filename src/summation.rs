@@ -0,0 +1,156 @@
+use crate::point::Point;
+
+/// A sum with Neumaier's compensated summation (Neumaier, 1974), a variant of Kahan summation:
+/// the rounding error of every addition is accumulated separately and added back at the end. The
+/// error of the sum is then independent of the number of terms to first order, so sums of the
+/// same numbers in different orders, or partial sums merged in different ways, almost always
+/// round to the same value.
+///
+/// # References
+///
+/// Neumaier, A. (1974). Rundungsfehleranalyse einiger Verfahren zur Summation endlicher Summen.
+///     ZAMM - Journal of Applied Mathematics and Mechanics, 54(1), 39–51.
+///     <https://doi.org/10.1002/zamm.19740540106>
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct CompensatedSum {
+    sum: f64,
+    compensation: f64
+}
+
+impl CompensatedSum {
+    /// Add `x`, keeping track of the rounding error.
+    pub fn add(&mut self, x: f64) {
+        let t = self.sum + x;
+        self.compensation += if self.sum.abs() >= x.abs() { (self.sum - t) + x } else { (x - t) + self.sum };
+        self.sum = t;
+    }
+
+    /// Add `x`, which is known to be a rounded value with error `error`.
+    pub fn add_with_error(&mut self, x: f64, error: f64) {
+        self.add(x);
+        self.compensation += error;
+    }
+
+    /// Add another sum.
+    pub fn merge(&mut self, other: &Self) {
+        self.add_with_error(other.sum, other.compensation);
+    }
+
+    /// The sum, rounded once.
+    pub fn value(&self) -> f64 {
+        self.sum + self.compensation
+    }
+
+    /// The difference between the sum and `x`, which is exact if `x` is close to the sum.
+    pub fn difference(&self, x: f64) -> f64 {
+        (self.sum - x) + self.compensation
+    }
+}
+
+/// A [`CompensatedSum`] in every dimension.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct CompensatedPoint<const M: usize>([CompensatedSum; M]);
+
+impl<const M: usize> Default for CompensatedPoint<M> {
+    fn default() -> Self {
+        Self([CompensatedSum::default(); M])
+    }
+}
+
+impl<const M: usize> CompensatedPoint<M> {
+    /// Add `point`.
+    pub fn add(&mut self, point: &Point<M>) {
+        for d in 0..M {
+            self.0[d].add(point.0[d]);
+        }
+    }
+
+    /// Add `point`, which is known to be a rounded value with error `error`.
+    pub fn add_with_error(&mut self, point: &Point<M>, error: &Point<M>) {
+        for d in 0..M {
+            self.0[d].add_with_error(point.0[d], error.0[d]);
+        }
+    }
+
+    /// Add another sum.
+    pub fn merge(&mut self, other: &Self) {
+        for d in 0..M {
+            self.0[d].merge(&other.0[d]);
+        }
+    }
+
+    /// The sum, rounded once.
+    pub fn value(&self) -> Point<M> {
+        Point(self.0.map(|sum| sum.value()))
+    }
+
+    /// The difference between the sum and `point`, see [`CompensatedSum::difference`].
+    pub fn difference(&self, point: &Point<M>) -> Point<M> {
+        Point(std::array::from_fn(|d| self.0[d].difference(point.0[d])))
+    }
+}
+
+/// The compensated sums of `points` and `weights` per center, whose values are the weighted sums
+/// and the total weights.
+pub(crate) struct CenterSums<const K: usize, const M: usize> {
+    pub sums: [CompensatedPoint<M>; K],
+    pub counts: [CompensatedSum; K]
+}
+
+impl<const K: usize, const M: usize> Default for CenterSums<K, M> {
+    fn default() -> Self {
+        Self { sums: [CompensatedPoint::default(); K], counts: [CompensatedSum::default(); K] }
+    }
+}
+
+impl<const K: usize, const M: usize> CenterSums<K, M> {
+    /// Add `point` with weight `w` to center `k`.
+    pub fn add(&mut self, k: usize, point: &Point<M>, w: f64) {
+        self.sums[k].add(&(*point * w));
+        self.counts[k].add(w);
+    }
+
+    /// Add other sums.
+    pub fn merge(&mut self, other: &Self) {
+        for k in 0..K {
+            self.sums[k].merge(&other.sums[k]);
+            self.counts[k].merge(&other.counts[k]);
+        }
+    }
+
+    /// The weighted sums and the total weights.
+    pub fn values(&self) -> ([Point<M>; K], [f64; K]) {
+        (self.sums.map(|sum| sum.value()), self.counts.map(|count| count.value()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_independent() {
+        let xs = [1e16, 1.0, -1e16, 3.0, 0.5, 2.5e15, -2.5e15];
+        let mut forward = CompensatedSum::default();
+        for &x in &xs {
+            forward.add(x);
+        }
+        let mut backward = CompensatedSum::default();
+        for &x in xs.iter().rev() {
+            backward.add(x);
+        }
+        assert_eq!(forward.value(), 4.5);
+        assert_eq!(backward.value(), 4.5);
+
+        // Merging a rounded partial sum with its error is as good as merging the partial sum
+        let mut merged = CompensatedSum::default();
+        let mut parts = [CompensatedSum::default(); 2];
+        for (i, &x) in xs.iter().enumerate() {
+            parts[i % 2].add(x);
+        }
+        let value = parts[1].value();
+        merged.merge(&parts[0]);
+        merged.add_with_error(value, parts[1].difference(value));
+        assert_eq!(merged.value(), 4.5);
+    }
+}
@@ -0,0 +1,335 @@
+use crate::error::{check_finite_points, check_points, ClusterError};
+use crate::geometry::dominates;
+use crate::initialization::random_indices;
+use crate::metric::SquaredEuclidean;
+use crate::mrkd::{Children, NodeRef, Tree};
+use crate::point::Point;
+use crate::seeding::SeedSequence;
+use rand::Rng;
+
+/// The model for one number of clusters `k` in a [`Sweep`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SweepModel<const M: usize> {
+    /// The number of clusters.
+    pub k: usize,
+    /// The coordinates of the k means.
+    pub centers: Vec<Point<M>>,
+    /// The index of the center that each point belongs to.
+    pub point_centers: Vec<usize>,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the centers converged within the maximum number of iterations.
+    pub converged: bool,
+    /// The sum of the squared distances of the points to their centers.
+    pub inertia: f64
+}
+
+/// Fits k-means for several numbers of clusters at once, for example to pick k with the elbow
+/// method or a validation metric.
+///
+/// All models share a single mrkd-tree, and their iterations are interleaved: every pass updates
+/// each model that has not converged yet using the blacklisting algorithm of (Pelleg & Moore,
+/// 1999), so the cost of building the tree is paid once instead of once per k.
+///
+/// # References
+///
+/// Pelleg, D., & Moore, A. (1999). Accelerating exact k-means algorithms with geometric reasoning.
+///     Proceedings of the Fifth ACM SIGKDD International Conference
+///     on Knowledge Discovery and Data Mining, 277–281. <https://doi.org/10.1145/312129.312248>
+#[derive(Clone, Debug)]
+pub struct Sweep {
+    ks: Vec<usize>,
+    random_state: Option<u64>,
+    max_iter: usize,
+    warm_start: bool
+}
+
+impl Sweep {
+    /// Sweep over the numbers of clusters in `ks`.
+    pub fn new(ks: impl IntoIterator<Item = usize>) -> Self {
+        Self {
+            ks: ks.into_iter().collect(),
+            random_state: Option::None,
+            max_iter: 300,
+            warm_start: false
+        }
+    }
+
+    /// Set a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+
+    /// Set the maximum number of iterations per model. Defaults to 300.
+    pub fn max_iter(mut self, max_iter: usize) -> Self {
+        self.max_iter = max_iter;
+        self
+    }
+
+    /// Initialize each model from the centers of the model with the next smaller k, adding the
+    /// missing centers with k-means++ seeding (Arthur & Vassilvitskii, 2007). The models are then
+    /// fitted one after another in increasing order of k instead of being interleaved, but each
+    /// starts close to convergence, and the solutions are nested across k. Defaults to false.
+    ///
+    /// # References
+    ///
+    /// Arthur, D., & Vassilvitskii, S. (2007). k-means++: The advantages of careful seeding.
+    ///     Proceedings of the Eighteenth Annual ACM-SIAM Symposium on Discrete Algorithms, 1027–1035.
+    pub fn warm_start(mut self, warm_start: bool) -> Self {
+        self.warm_start = warm_start;
+        self
+    }
+
+    /// Fit a model for every k, in the order in which they were given, or an error if a k is 0,
+    /// the points have NaN or infinite coordinates, or there are fewer distinct points than the
+    /// largest k.
+    pub fn fit<const M: usize>(&self, points: &[Point<M>]) -> Result<Vec<SweepModel<M>>, ClusterError> {
+        let r = points.len();
+        if self.ks.contains(&0) {
+            return Err(ClusterError::NoClusters)
+        }
+        match self.ks.iter().copied().max() {
+            Option::Some(k) => check_points(points, &vec![1.0; r], k)?,
+            None => check_finite_points(points, &vec![1.0; r])?
+        }
+
+        let seeds = SeedSequence::from_random_state(self.random_state);
+        let tree = Tree::initialize_weighted(points, &vec![1.0; r], &mut seeds.rng());
+
+        // Initialize every model with its own random stream
+        let mut models: Vec<SweepModel<M>> = self.ks.iter().enumerate().map(|(i, &k)| {
+            let mut rng = seeds.child(i as u64).rng();
+            SweepModel {
+                k,
+                centers: if self.warm_start { Vec::new() } else { random_indices(r, k, &mut rng).into_iter().map(|i| points[i]).collect() },
+                point_centers: Vec::new(),
+                iterations: 0,
+                converged: false,
+                inertia: 0.0
+            }
+        }).collect();
+
+        if self.warm_start {
+            // Fit the models in increasing order of k, each starting from the previous centers
+            let mut order: Vec<usize> = (0..models.len()).collect();
+            order.sort_by_key(|&i| models[i].k);
+            let mut centers = Vec::new();
+            for i in order {
+                let mut rng = seeds.child(i as u64).rng();
+                let model = &mut models[i];
+                centers.truncate(model.k);
+                while centers.len() < model.k {
+                    let center = kmeans_plus_plus_point(points, &centers, &mut rng);
+                    centers.push(center);
+                }
+                model.centers = centers;
+                Self::run(vec![model], &tree, self.max_iter);
+                centers = model.centers.clone();
+            }
+        } else {
+            Self::run(models.iter_mut().collect(), &tree, self.max_iter);
+        }
+
+        // Assign the points to the final centers
+        for model in &mut models {
+            model.point_centers = points.iter().map(|point| closest(&model.centers, point)).collect();
+            model.inertia = points.iter()
+                .zip(&model.point_centers)
+                .map(|(point, &c)| point.squared_distance(&model.centers[c]))
+                .sum();
+        }
+
+        Ok(models)
+    }
+}
+
+impl Sweep {
+    /// Interleave the iterations of the models that have not converged yet, running them
+    /// concurrently when parallelism is enabled.
+    fn run<const M: usize>(mut models: Vec<&mut SweepModel<M>>, tree: &Tree<M>, max_iter: usize) {
+        for _ in 0..max_iter {
+            models.retain(|model| !model.converged);
+            if models.is_empty() {
+                break;
+            }
+
+            #[cfg(feature = "rayon")]
+            {
+                use rayon::prelude::*;
+                models.par_iter_mut().for_each(|model| model.step(tree));
+            }
+            #[cfg(not(feature = "rayon"))]
+            for model in models.iter_mut() {
+                model.step(tree);
+            }
+        }
+    }
+}
+
+impl<const M: usize> SweepModel<M> {
+    /// Perform one iteration, keeping empty centers in place.
+    ///
+    /// Time complexity: worst case O(r * k * M)
+    fn step(&mut self, tree: &Tree<M>) {
+        let mut sums = vec![Point::default(); self.k];
+        let mut counts = vec![0.0; self.k];
+        let candidates: Vec<usize> = (0..self.k).collect();
+        blacklist(tree.root(), &self.centers, &candidates, &mut sums, &mut counts);
+
+        self.iterations += 1;
+        self.converged = true;
+        for c in 0..self.k {
+            if counts[c] > 0.0 {
+                let center = sums[c] / counts[c];
+                if center != self.centers[c] {
+                    self.converged = false;
+                    self.centers[c] = center;
+                }
+            }
+        }
+    }
+}
+
+/// Update(h, C) with blacklisting for a number of centers that is only known at runtime, see
+/// [`crate::centers::Centers::update_blacklist`].
+///
+/// Time complexity: worst case O(r * k * M)
+pub(crate) fn blacklist<const M: usize>(tree: NodeRef<M>, centers: &[Point<M>], candidates: &[usize], sums: &mut [Point<M>], counts: &mut [f64]) {
+    let remaining: Vec<usize>;
+    let candidates = if tree.number_of_points > 1 {
+        // Find the candidate closest to the hyper-rectangle
+        let mut min_d = f64::INFINITY;
+        let mut c1 = candidates[0];
+        for &c in candidates {
+            let d = tree.h.squared_distance(&centers[c]);
+            if d < min_d {
+                min_d = d;
+                c1 = c;
+            }
+        }
+
+        // Remove the candidates it dominates
+        remaining = candidates.iter()
+            .copied()
+            .filter(|&c2| c2 == c1 || !dominates(&centers[c1], &centers[c2], &tree.h, &SquaredEuclidean))
+            .collect();
+
+        // If a single candidate remains, it owns every point in the node
+        if remaining.len() == 1 {
+            sums[c1] += tree.center_of_mass * tree.weight;
+            counts[c1] += tree.weight;
+            return;
+        }
+        &remaining[..]
+    } else {
+        candidates
+    };
+
+    match tree.children() {
+        // Else, descend in the child nodes with the remaining candidates
+        Children::NonLeaf(l, r) => {
+            blacklist(l, centers, candidates, sums, counts);
+            blacklist(r, centers, candidates, sums, counts);
+        },
+        // Or, in a leaf node, update the closest candidate as normal
+        Children::Leaf(leaf) => {
+            for (point, w, _) in leaf.iter() {
+                let c = candidates[closest_of(centers, candidates, point)];
+                sums[c] += *point * w;
+                counts[c] += w;
+            }
+        }
+    }
+}
+
+/// Sample a point with a probability proportional to its squared distance to the closest of
+/// `centers` (Arthur & Vassilvitskii, 2007), or uniformly if there are no centers yet or every point
+/// coincides with a center.
+///
+/// Time complexity: O(r * k * M)
+pub(crate) fn kmeans_plus_plus_point<const M: usize>(points: &[Point<M>], centers: &[Point<M>], rng: &mut impl Rng) -> Point<M> {
+    let weights: Vec<f64> = points.iter()
+        .map(|point| centers.iter().map(|center| point.squared_distance(center)).fold(f64::INFINITY, f64::min))
+        .collect();
+    let total: f64 = weights.iter().sum();
+    if centers.is_empty() || total == 0.0 {
+        return points[rng.gen_range(0..points.len())]
+    }
+
+    let mut target = rng.gen::<f64>() * total;
+    for (point, &w) in points.iter().zip(&weights) {
+        if target < w {
+            return *point
+        }
+        target -= w;
+    }
+    // Guard against rounding errors in the sum
+    *points.iter().zip(&weights).rev().find(|(_, &w)| w > 0.0).unwrap().0
+}
+
+/// Index of the center closest to a point.
+///
+/// Time complexity: O(k * M)
+pub(crate) fn closest<const M: usize>(centers: &[Point<M>], point: &Point<M>) -> usize {
+    let candidates: Vec<usize> = (0..centers.len()).collect();
+    closest_of(centers, &candidates, point)
+}
+
+/// Position in `candidates` of the candidate center closest to a point.
+///
+/// Time complexity: O(k * M)
+fn closest_of<const M: usize>(centers: &[Point<M>], candidates: &[usize], point: &Point<M>) -> usize {
+    let mut min_d = f64::INFINITY;
+    let mut min_i = 0;
+    for (i, &c) in candidates.iter().enumerate() {
+        let d = point.squared_distance(&centers[c]);
+        if d < min_d {
+            min_d = d;
+            min_i = i;
+        }
+    }
+    min_i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([5.0, 0.0]),
+            Point([5.0, 1.0]),
+            Point([10.0, 0.0]),
+            Point([10.0, 1.0])
+        ];
+        let models = Sweep::new([1, 3, 6]).random_state(0).fit(&points).unwrap();
+        assert_eq!(models.iter().map(|model| model.k).collect::<Vec<_>>(), [1, 3, 6]);
+        assert_eq!(models[0].centers, [Point([5.0, 0.5])]);
+        assert_eq!(models[2].inertia, 0.0);
+        assert!(models.iter().all(|model| model.converged));
+
+        assert_eq!(Sweep::new([0]).fit(&points).err(), Option::Some(ClusterError::NoClusters));
+        assert_eq!(Sweep::new([2, 7]).fit(&points).err(), Option::Some(ClusterError::TooFewPoints { clusters: 7, distinct: 6 }));
+        assert_eq!(Sweep::new([2]).fit(&[points[0], Point([f64::NAN, 0.0])]).err(), Option::Some(ClusterError::NonFinite { point: 1, dimension: 0 }));
+    }
+
+    #[test]
+    fn warm_start() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([5.0, 0.0]),
+            Point([5.0, 1.0]),
+            Point([10.0, 0.0]),
+            Point([10.0, 1.0])
+        ];
+        let models = Sweep::new([3, 1, 2]).random_state(0).warm_start(true).fit(&points).unwrap();
+        assert_eq!(models[1].centers, [Point([5.0, 0.5])]);
+        assert_eq!(models.iter().map(|model| model.centers.len()).collect::<Vec<_>>(), [3, 1, 2]);
+        assert!(models[0].inertia <= models[2].inertia && models[2].inertia <= models[1].inertia);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::centers::Centers;
+use crate::error::{check_point_rows, ClusterError};
+use crate::estimator::{Clusterer, Fit, Predict};
+use crate::initialization::random_points;
+use crate::point::Point;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+/// Maximum number of iterations.
+const MAX_ITER: usize = 300;
+
+/// Number of random initializations, as a center that starts at an outlier can keep it from
+/// being trimmed.
+const N_INIT: usize = 10;
+
+/// Implements trimmed k-means (Cuesta-Albertos, Gordaliza & Matrán, 1997), which is robust to
+/// outliers: in every iteration, the fraction alpha of the points that are farthest from their
+/// centers is trimmed, and only the remaining points move the centers. The fit is repeated from
+/// several random initializations, keeping the one with the lowest sum of squared distances of
+/// the untrimmed points to their centers.
+///
+/// # References
+///
+/// Cuesta-Albertos, J. A., Gordaliza, A., & Matrán, C. (1997). Trimmed k-means: An attempt to
+///     robustify quantizers. The Annals of Statistics, 25(2), 553–576.
+///     <https://doi.org/10.1214/aos/1031833664>
+pub struct TrimmedKMeans<const K: usize, const M: usize> {
+    /// The coordinates of the k means.
+    pub centers: [Point<M>; K],
+    /// The index of the center that each point is closest to, including trimmed points.
+    pub point_centers: Vec<usize>,
+    /// Whether each point was trimmed in the final iteration.
+    pub trimmed: Vec<bool>,
+    /// The number of iterations performed while fitting.
+    pub iterations: usize,
+    /// Whether the centers converged within the maximum number of iterations.
+    pub converged: bool
+}
+
+impl<const K: usize, const M: usize> TrimmedKMeans<K, M> {
+    /// Get k clusters based on `points`, ignoring the fraction `alpha` of the points that are
+    /// farthest from their centers, or an error if the points have NaN or infinite coordinates or
+    /// there are fewer than k distinct points, or fewer than k points remain after trimming.
+    ///
+    /// # Panics
+    ///
+    /// If alpha is not in [0, 1).
+    pub fn fit(points: &[Point<M>], alpha: f64) -> Result<Self, ClusterError> {
+        Self::new(points, alpha, Option::None)
+    }
+
+    /// Get k clusters based on `points` with a pre-determined random state, or an error, see
+    /// [`TrimmedKMeans::fit`].
+    pub fn fit_with_random_state(points: &[Point<M>], alpha: f64, random_state: u64) -> Result<Self, ClusterError> {
+        Self::new(points, alpha, Option::Some(random_state))
+    }
+
+    fn new(points: &[Point<M>], alpha: f64, random_state: Option<u64>) -> Result<Self, ClusterError> {
+        assert!((0.0..1.0).contains(&alpha), "alpha must be in [0, 1)");
+        let r = points.len();
+        let trim = (alpha * r as f64).floor() as usize;
+        check_point_rows(points.iter().map(|point| point.0), M, K)?;
+        if r - trim < K {
+            return Err(ClusterError::TooFewPoints { clusters: K, distinct: r - trim })
+        }
+
+        // Initialize randomness
+        let mut rng = match random_state {
+            Option::Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy()
+        };
+
+        let mut best: Option<(Self, f64)> = Option::None;
+        for _ in 0..N_INIT {
+            let centers = random_points::<K, M>(points, &mut rng);
+            let (model, inertia) = Self::run(points, trim, centers);
+            if best.as_ref().is_none_or(|(_, best_inertia)| inertia < *best_inertia) {
+                best = Option::Some((model, inertia));
+            }
+        }
+        Ok(best.unwrap().0)
+    }
+
+    /// Fit from the initial `centers`, trimming `trim` points, and return the model with the sum
+    /// of squared distances of the untrimmed points to their centers.
+    ///
+    /// Time complexity: O(r * k * M) per iteration
+    fn run(points: &[Point<M>], trim: usize, mut centers: [Point<M>; K]) -> (Self, f64) {
+        let r = points.len();
+        let mut point_centers = vec![usize::MAX; r];
+        let mut trimmed = vec![false; r];
+        let mut distances = vec![0.0; r];
+        let mut order: Vec<usize> = (0..r).collect();
+        let mut iterations = 0;
+        let mut converged = false;
+        while iterations < MAX_ITER {
+            iterations += 1;
+
+            // Assign each point to the closest center
+            let mut different = false;
+            for i in 0..r {
+                let mut min_d = f64::INFINITY;
+                let mut min_c = 0;
+                for (k, center) in centers.iter().enumerate() {
+                    let d = points[i].squared_distance(center);
+                    if d < min_d {
+                        min_d = d;
+                        min_c = k;
+                    }
+                }
+                distances[i] = min_d;
+                if point_centers[i] != min_c {
+                    point_centers[i] = min_c;
+                    different = true;
+                }
+            }
+
+            // Trim the points farthest from their centers, breaking ties by index
+            if trim > 0 {
+                order.select_nth_unstable_by(r - trim, |&a, &b| distances[a].total_cmp(&distances[b]).then(a.cmp(&b)));
+            }
+            for (position, &i) in order.iter().enumerate() {
+                let trim_point = position >= r - trim;
+                if trimmed[i] != trim_point {
+                    trimmed[i] = trim_point;
+                    different = true;
+                }
+            }
+
+            // Move the centers to the means of their untrimmed points, keeping empty centers in
+            // place
+            let mut sums = [Point::default(); K];
+            let mut counts = [0; K];
+            for i in (0..r).filter(|&i| !trimmed[i]) {
+                sums[point_centers[i]] += points[i];
+                counts[point_centers[i]] += 1;
+            }
+            for k in 0..K {
+                if counts[k] > 0 {
+                    centers[k] = sums[k] / counts[k];
+                }
+            }
+
+            if !different {
+                converged = true;
+                break;
+            }
+        }
+
+        let inertia = (0..r)
+            .filter(|&i| !trimmed[i])
+            .map(|i| points[i].squared_distance(&centers[point_centers[i]]))
+            .sum();
+        (TrimmedKMeans { centers, point_centers, trimmed, iterations, converged }, inertia)
+    }
+}
+
+/// The parameters of [`TrimmedKMeans::fit`], to fit it through the [`Fit`] trait.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrimmedKMeansParams<const K: usize> {
+    alpha: f64,
+    random_state: Option<u64>
+}
+
+impl<const K: usize> TrimmedKMeansParams<K> {
+    /// Trim the fraction `alpha` of the points in every iteration.
+    ///
+    /// # Panics
+    ///
+    /// If alpha is not in [0, 1), when fitting.
+    pub fn new(alpha: f64) -> Self {
+        Self { alpha, random_state: Option::None }
+    }
+
+    /// Fit with a pre-determined random state.
+    pub fn random_state(mut self, random_state: u64) -> Self {
+        self.random_state = Option::Some(random_state);
+        self
+    }
+}
+
+impl<const K: usize, const M: usize> Fit<M> for TrimmedKMeansParams<K> {
+    type Model = TrimmedKMeans<K, M>;
+
+    fn fit(&self, points: &[Point<M>]) -> Result<TrimmedKMeans<K, M>, ClusterError> {
+        TrimmedKMeans::new(points, self.alpha, self.random_state)
+    }
+}
+
+impl<const K: usize, const M: usize> Clusterer<M> for TrimmedKMeans<K, M> {
+    fn n_clusters(&self) -> usize {
+        K
+    }
+
+    /// The index of the center that each point belongs to, or `None` for trimmed points.
+    fn labels(&self) -> Vec<Option<usize>> {
+        self.point_centers.iter().zip(&self.trimmed).map(|(&k, &trimmed)| if trimmed { Option::None } else { Option::Some(k) }).collect()
+    }
+
+    fn centers(&self) -> Option<Vec<Point<M>>> {
+        Option::Some(self.centers.to_vec())
+    }
+}
+
+/// Assigns new points to the closest center; only the points the model was fitted on are trimmed.
+impl<const K: usize, const M: usize> Predict<M> for TrimmedKMeans<K, M> {
+    fn predict(&self, point: &Point<M>) -> Option<usize> {
+        Option::Some(Centers::new(self.centers).closest(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::point::Point;
+    use super::*;
+
+    #[test]
+    fn fit_with_random_state() {
+        let points = [
+            Point([0.0, 0.0]),
+            Point([0.0, 1.0]),
+            Point([1.0, 0.0]),
+            Point([1.0, 1.0]),
+            Point([10.0, 0.0]),
+            Point([10.0, 1.0]),
+            Point([11.0, 0.0]),
+            Point([11.0, 1.0]),
+            Point([500.0, -500.0]),
+            Point([-500.0, 500.0])
+        ];
+        let model = TrimmedKMeans::<2, 2>::fit_with_random_state(&points, 0.2, 0).unwrap();
+        assert_eq!(model.trimmed, [false, false, false, false, false, false, false, false, true, true]);
+        let mut centers = model.centers.to_vec();
+        centers.sort_by(|a, b| a.0[0].total_cmp(&b.0[0]));
+        assert_eq!(centers, [Point([0.5, 0.5]), Point([10.5, 0.5])]);
+        assert!(model.converged);
+
+        assert_eq!(TrimmedKMeans::<2, 2>::fit(&points[..2], 0.5).err(), Option::Some(ClusterError::TooFewPoints { clusters: 2, distinct: 1 }));
+    }
+}
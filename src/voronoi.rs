@@ -0,0 +1,82 @@
+use crate::hyper_rectangle::HyperRectangle;
+use crate::point::Point;
+
+/// The Voronoi cell of every center within `bounds` under the weighted squared Euclidean distance
+/// sum_d w_d * (x_d - c_d)^2, as a closed ring of vertices in counter-clockwise order, where the
+/// last vertex repeats the first, or an empty list if the cell does not intersect the bounds.
+///
+/// Every cell starts as the bounds, and is clipped (Sutherland & Hodgman, 1974) by the half-plane
+/// of the points closer to its center than to each other center.
+///
+/// # References
+///
+/// Sutherland, I. E., & Hodgman, G. W. (1974). Reentrant polygon clipping. Communications of the
+///     ACM, 17(1), 32–42. <https://doi.org/10.1145/360767.360802>
+///
+/// Time complexity: O(k^2 * v) for at most v vertices per cell
+pub(crate) fn cells(centers: &[Point<2>], feature_weights: &[f64; 2], bounds: &HyperRectangle<2>) -> Vec<Vec<[f64; 2]>> {
+    let HyperRectangle(min, max) = bounds;
+    let rectangle = vec![[min.0[0], min.0[1]], [max.0[0], min.0[1]], [max.0[0], max.0[1]], [min.0[0], max.0[1]]];
+
+    centers.iter().enumerate().map(|(i, a)| {
+        let mut cell = rectangle.clone();
+        for (j, b) in centers.iter().enumerate() {
+            if i == j || cell.is_empty() {
+                continue;
+            }
+
+            // The points closer to a than to b satisfy normal . x <= offset
+            let normal: [f64; 2] = std::array::from_fn(|d| 2.0 * feature_weights[d] * (b.0[d] - a.0[d]));
+            let offset: f64 = (0..2).map(|d| feature_weights[d] * (b.0[d] * b.0[d] - a.0[d] * a.0[d])).sum();
+            if normal == [0.0, 0.0] {
+                // Coinciding centers share their cell with the first of them
+                if j < i {
+                    cell.clear();
+                }
+                continue;
+            }
+            cell = clip(&cell, normal, offset);
+        }
+
+        if let Option::Some(&first) = cell.first() {
+            cell.push(first);
+        }
+        cell
+    }).collect()
+}
+
+/// Clip a convex polygon to the half-plane normal . x <= offset.
+///
+/// Time complexity: O(v) for v vertices
+fn clip(polygon: &[[f64; 2]], normal: [f64; 2], offset: f64) -> Vec<[f64; 2]> {
+    let side = |p: &[f64; 2]| normal[0] * p[0] + normal[1] * p[1] - offset;
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (n, p) in polygon.iter().enumerate() {
+        let q = &polygon[(n + 1) % polygon.len()];
+        let (sp, sq) = (side(p), side(q));
+        if sp <= 0.0 {
+            clipped.push(*p);
+        }
+        // Add the intersection of an edge that crosses the boundary
+        if (sp < 0.0 && sq > 0.0) || (sp > 0.0 && sq < 0.0) {
+            let t = sp / (sp - sq);
+            clipped.push([p[0] + t * (q[0] - p[0]), p[1] + t * (q[1] - p[1])]);
+        }
+    }
+    clipped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells() {
+        let centers = [Point([0.0, 0.0]), Point([2.0, 0.0]), Point([10.0, 10.0])];
+        let bounds = HyperRectangle(Point([-1.0, -1.0]), Point([3.0, 1.0]));
+        let cells = super::cells(&centers, &[1.0, 1.0], &bounds);
+        assert_eq!(cells[0], [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0], [-1.0, -1.0]]);
+        assert_eq!(cells[1], [[1.0, -1.0], [3.0, -1.0], [3.0, 1.0], [1.0, 1.0], [1.0, -1.0]]);
+        assert!(cells[2].is_empty());
+    }
+}